@@ -0,0 +1,371 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum StreamHealth {
+    Running,
+    Stalled,
+    Dead
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum AudioStreamHealth {
+    Running,
+    NoData,
+    Degraded,
+    Dead
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ComparisonResult {
+    pub stream1: String,
+    pub stream2: String,
+    pub similarity_percent: f32,
+    pub is_within_channel: bool,
+    pub is_error: bool,
+    pub offset_seconds: Option<f32>, // Time offset between streams (only for within-channel)
+    #[serde(default)]
+    pub whitelisted: Option<String>, // Set when a cross-channel collision falls inside a whitelist window or scheduled dead air
+    #[serde(default)]
+    pub is_silence_check: bool, // Against the silence reference channel, an error means the stream is silent
+}
+
+impl ComparisonResult {
+    /// The label metrics and exports use for this kind of comparison
+    pub fn comparison_type(&self) -> &'static str {
+        if self.is_silence_check {
+            "silence_check"
+        } else if self.is_within_channel {
+            "within_channel"
+        } else {
+            "cross_channel"
+        }
+    }
+}
+
+/// A comparison result as it stood at one point in time, kept for history export
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ComparisonSample {
+    pub timestamp: DateTime<Utc>,
+    #[serde(flatten)]
+    pub result: ComparisonResult,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AlertTransition {
+    Failing,
+    Cleared,
+}
+
+/// Stable machine-readable alert type, for automation that shouldn't parse alert prose
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum AlertReason {
+    Silence,
+    Divergence, // streams of one channel stopped matching
+    Collision, // streams of different channels carry the same audio
+    ProcessDead, // a stream's command died and could not be respawned
+    SdrDown, // no IQ samples at all from an SDR
+    BerHigh, // nrsc5 bit error rate too high
+    IqDrops,
+    StereoDead,
+    StereoSwap,
+    DeadAirBudget,
+    OriginDown,
+    DnsUnexpected,
+    FormatMismatch,
+    BeaconMissing,
+    SlackDisconnected,
+    OnBackup, // stream is playing from its backup source
+    ClockSkew, // system clock drifted from NTP
+    EncoderRestart, // repeated jumps in a stream's audio timeline
+    Dropout, // repeated short near-silent runs between volume samples
+    DecodeArtifacts, // HD Radio decoder concealing errors: repeated frames, clicks, mutes
+    ChannelDegraded, // fewer healthy streams in a channel than its configured minimum
+    ProcessStalled, // a stream's command went without output long enough to be respawned
+    AudioFrozen, // a running stream keeps repeating the same audio, e.g. a source replaying its last buffer
+    DeliveryFailing, // a notification sink (Slack, health webhook, email) keeps failing to deliver
+}
+
+/// How urgently an alert needs someone: critical ones mean listeners are likely affected
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertSeverity {
+    Critical,
+    #[default]
+    Warning,
+}
+
+impl AlertReason {
+    pub fn severity(&self) -> AlertSeverity {
+        match self {
+            AlertReason::Silence
+            | AlertReason::Divergence
+            | AlertReason::Collision
+            | AlertReason::ProcessDead
+            | AlertReason::SdrDown
+            | AlertReason::StereoDead
+            | AlertReason::OriginDown
+            | AlertReason::BeaconMissing
+            | AlertReason::ChannelDegraded
+            | AlertReason::AudioFrozen => AlertSeverity::Critical,
+            _ => AlertSeverity::Warning,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AlertReason::Silence => "SILENCE",
+            AlertReason::Divergence => "DIVERGENCE",
+            AlertReason::Collision => "COLLISION",
+            AlertReason::ProcessDead => "PROCESS_DEAD",
+            AlertReason::SdrDown => "SDR_DOWN",
+            AlertReason::BerHigh => "BER_HIGH",
+            AlertReason::IqDrops => "IQ_DROPS",
+            AlertReason::StereoDead => "STEREO_DEAD",
+            AlertReason::StereoSwap => "STEREO_SWAP",
+            AlertReason::DeadAirBudget => "DEAD_AIR_BUDGET",
+            AlertReason::OriginDown => "ORIGIN_DOWN",
+            AlertReason::DnsUnexpected => "DNS_UNEXPECTED",
+            AlertReason::FormatMismatch => "FORMAT_MISMATCH",
+            AlertReason::BeaconMissing => "BEACON_MISSING",
+            AlertReason::SlackDisconnected => "SLACK_DISCONNECTED",
+            AlertReason::OnBackup => "ON_BACKUP",
+            AlertReason::ClockSkew => "CLOCK_SKEW",
+            AlertReason::EncoderRestart => "ENCODER_RESTART",
+            AlertReason::Dropout => "DROPOUT",
+            AlertReason::DecodeArtifacts => "DECODE_ARTIFACTS",
+            AlertReason::ChannelDegraded => "CHANNEL_DEGRADED",
+            AlertReason::ProcessStalled => "PROCESS_STALLED",
+            AlertReason::AudioFrozen => "AUDIO_FROZEN",
+            AlertReason::DeliveryFailing => "DELIVERY_FAILING",
+        }
+    }
+}
+
+/// A currently failing alert, served at `/api/alerts`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ActiveAlert {
+    pub alert: String,
+    pub reason: AlertReason,
+    pub streams: Vec<String>,
+    pub message: String,
+    pub failing_since: DateTime<Utc>,
+    #[serde(default)]
+    pub severity: AlertSeverity,
+    #[serde(default)]
+    pub announced: bool, // past its grace period and sent to Slack
+    #[serde(default)]
+    pub announce_at: Option<DateTime<Utc>>, // while held by the grace period, when it will be sent
+    #[serde(default)]
+    pub acknowledged_by: Option<String>, // someone is on it, reminders stop until it clears
+}
+
+/// Maintenance in progress, new alerts and reminders are held until it ends; served at `/api/maintenance`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct MaintenanceStatus {
+    pub reason: String, // the scheduled window's name, or who started it
+    pub until: DateTime<Utc>,
+    #[serde(default)]
+    pub started_by: Option<String>, // set when started by hand instead of by a scheduled window
+}
+
+/// An alert starting or stopping failing
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct AlertEvent {
+    pub timestamp: DateTime<Utc>,
+    pub alert: String,
+    pub reason: AlertReason,
+    pub streams: Vec<String>,
+    pub transition: AlertTransition,
+    pub message: String,
+}
+
+/// One entry of the live feed served as server-sent events at `/api/events`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum LiveEvent {
+    /// A stream's command or audio health changed, or its state when the feed connected
+    Health {
+        timestamp: DateTime<Utc>,
+        stream: String,
+        command_health: StreamHealth,
+        audio_health: AudioStreamHealth,
+    },
+    /// A stream pair was compared for the first time or flipped between passing and failing
+    Comparison {
+        timestamp: DateTime<Utc>,
+        result: ComparisonResult,
+    },
+    Alert(AlertEvent),
+}
+
+/// A stream's health at one moment, as sent by the health webhook
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct HealthState {
+    pub command_health: StreamHealth,
+    pub audio_health: AudioStreamHealth,
+    pub usable: bool, // process and audio both running, the leg can be put on air
+}
+
+/// Body of the health webhook, posted on every change of a stream's health
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct HealthTransition {
+    pub timestamp: DateTime<Utc>,
+    pub stream: String,
+    pub channel: Option<String>,
+    pub before: HealthState,
+    pub after: HealthState,
+}
+
+/// A stream pair temporarily compared more often, served at `/api/pins`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PinnedComparison {
+    pub stream1: String,
+    pub stream2: String,
+    pub interval_seconds: u64,
+    pub window_seconds: u64,
+    pub until: DateTime<Utc>,
+    pub samples: u64,
+    pub similarity_percent: Option<f32>, // latest result
+    pub offset_seconds: Option<f32>,
+}
+
+/// Rough severity of a child process stderr line
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum StderrClass {
+    Error,
+    Warning,
+    Info
+}
+
+/// A distinct stderr line and how often it has repeated
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StderrSummary {
+    pub line: String,
+    pub class: StderrClass,
+    pub count: u64,
+    pub last_seen: DateTime<Utc>,
+}
+
+/// Last raw stderr lines of one child process feeding a stream
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ProcessLog {
+    pub process: String,
+    pub lines: Vec<String>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StreamStatus {
+    pub name: String,
+    pub channel: String,
+    pub command_health: StreamHealth,
+    pub audio_health: AudioStreamHealth,
+    pub uptime_seconds: Option<i64>,
+    pub mean_volume_db: Option<f32>,
+    pub max_volume_db: Option<f32>,
+    pub recent_stderr: Vec<StderrSummary>,
+    #[serde(default)]
+    pub buffer: Option<BufferStatus>,
+    #[serde(default)]
+    pub not_sampled: bool, // scanning SDR is tuned to another station
+    #[serde(default)]
+    pub on_backup: bool, // primary source is down, playing from the backup source
+    #[serde(default)]
+    pub discontinuities_last_hour: usize, // jumps in the audio timeline, e.g. the origin encoder restarting
+    #[serde(default)]
+    pub restarts_total: Option<u64>, // respawns across watchdog restarts, when a stats database is configured
+    #[serde(default)]
+    pub dropouts_last_hour: usize, // short near-silent runs, too brief for the periodic volume check
+    #[serde(default)]
+    pub startup_error: Option<String>, // set when the stream could not be started at all
+}
+
+/// Fingerprint buffer fill of a stream, comparisons need `items_needed` before they include it
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+pub struct BufferStatus {
+    pub items: usize,
+    pub items_needed: usize,
+    pub ready_in_seconds: f32,
+}
+
+impl BufferStatus {
+    pub fn is_ready(&self) -> bool {
+        self.items >= self.items_needed
+    }
+}
+
+/// Payload served at `/api/status`
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StatusPayload {
+    pub generated_at: DateTime<Utc>,
+    pub streams: Vec<StreamStatus>,
+    pub comparisons: Vec<ComparisonResult>,
+    #[serde(default)]
+    pub warming_up: bool, // no comparisons yet because streams are still filling their buffers
+}
+
+/// Entry in the `/recordings` index
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordingEntry {
+    pub stream: String,
+    pub file: String,
+    pub size_bytes: u64,
+    pub recorded_at: DateTime<Utc>,
+}
+
+/// Decode glitches seen in an HD Radio stream's audio, served at `/api/artifacts`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ArtifactStats {
+    pub rate_per_minute: f32, // over the configured window
+    pub repeated_frames: u64,
+    pub clicks: u64,
+    pub mutes: u64,
+}
+
+/// Outcome of a direct HTTP check of a web stream's origin
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ProbeResult {
+    pub status_code: Option<u16>,
+    pub content_type: Option<String>,
+    pub icy_name: Option<String>,
+    pub icy_bitrate: Option<String>,
+    pub latency_ms: Option<u64>,
+    pub error: Option<String>, // set when no HTTP response was received at all
+    pub checked_at: DateTime<Utc>,
+}
+
+impl ProbeResult {
+    pub fn is_up(&self) -> bool {
+        self.status_code.is_some_and(|code| (200..300).contains(&code))
+    }
+}
+
+/// Resolved addresses of a stream origin and how often they've changed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DnsRecord {
+    pub host: String,
+    pub addresses: Vec<String>,
+    pub previous_addresses: Vec<String>,
+    pub changes: u64,
+    pub last_changed: Option<DateTime<Utc>>,
+    pub checked_at: DateTime<Utc>,
+}
+
+/// Codec parameters of a web stream as reported by ffprobe
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StreamFormat {
+    pub codec: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub bitrate_kbps: Option<u32>,
+    pub mismatches: Vec<String>, // differences from the expected format in config
+    pub checked_at: DateTime<Utc>,
+}
+
+/// One volume detection pass for a stream
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct VolumeSample {
+    pub timestamp: DateTime<Utc>,
+    pub mean_volume: f32,
+    pub max_volume: f32,
+}
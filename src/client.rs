@@ -0,0 +1,115 @@
+use serde::de::DeserializeOwned;
+
+use std::collections::HashMap;
+
+use crate::api::{ActiveAlert, ComparisonResult, DnsRecord, LiveEvent, ProbeResult, ProcessLog, RecordingEntry, StatusPayload, StreamFormat};
+
+/// Minimal client for a running watchdog's web server
+pub struct WatchdogClient {
+    base_url: String,
+    http: reqwest::Client,
+    token: Option<String>,
+}
+
+impl WatchdogClient {
+    /// `base_url` is the web server root, e.g. `http://watchdog.local:3000`
+    pub fn new(base_url: &str) -> Self {
+        WatchdogClient {
+            base_url: base_url.trim_end_matches('/').to_string(),
+            http: reqwest::Client::new(),
+            token: None,
+        }
+    }
+
+    /// Sends `token` as a bearer token, for watchdogs that require one on the API
+    pub fn with_token(mut self, token: &str) -> Self {
+        self.token = Some(token.to_string());
+        self
+    }
+
+    fn get(&self, url: &str) -> reqwest::RequestBuilder {
+        let request = self.http.get(url);
+        match self.token {
+            Some(ref token) => request.bearer_auth(token),
+            None => request,
+        }
+    }
+
+    async fn get_json<T: DeserializeOwned>(&self, path: &str) -> Result<T, String> {
+        let url = format!("{}{}", self.base_url, path);
+        let response = self.get(&url)
+            .send()
+            .await
+            .map_err(|e| format!("HTTP request to {} failed: {}", url, e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("HTTP {} response from {}", response.status(), url));
+        }
+
+        response.json::<T>()
+            .await
+            .map_err(|e| format!("Failed to parse JSON from {}: {}", url, e))
+    }
+
+    pub async fn status(&self) -> Result<StatusPayload, String> {
+        self.get_json("/api/status").await
+    }
+
+    pub async fn comparisons(&self) -> Result<Vec<ComparisonResult>, String> {
+        Ok(self.status().await?.comparisons)
+    }
+
+    pub async fn stream_stderr(&self, stream: &str) -> Result<Vec<ProcessLog>, String> {
+        self.get_json(&format!("/api/streams/{}/stderr", stream)).await
+    }
+
+    pub async fn probes(&self) -> Result<HashMap<String, ProbeResult>, String> {
+        self.get_json("/api/probes").await
+    }
+
+    pub async fn dns(&self) -> Result<HashMap<String, DnsRecord>, String> {
+        self.get_json("/api/dns").await
+    }
+
+    pub async fn formats(&self) -> Result<HashMap<String, StreamFormat>, String> {
+        self.get_json("/api/formats").await
+    }
+
+    pub async fn alerts(&self) -> Result<Vec<ActiveAlert>, String> {
+        self.get_json("/api/alerts").await
+    }
+
+    pub async fn recordings(&self) -> Result<Vec<RecordingEntry>, String> {
+        self.get_json("/recordings").await
+    }
+
+    /// Follows the live event feed, calling `on_event` for each event until the server closes the connection
+    pub async fn events<F: FnMut(LiveEvent)>(&self, mut on_event: F) -> Result<(), String> {
+        let url = format!("{}/api/events", self.base_url);
+        let mut response = self.get(&url)
+            .header(reqwest::header::ACCEPT, "text/event-stream")
+            .send()
+            .await
+            .map_err(|e| format!("HTTP request to {} failed: {}", url, e))?;
+
+        if !response.status().is_success() {
+            return Err(format!("HTTP {} response from {}", response.status(), url));
+        }
+
+        let mut pending: Vec<u8> = Vec::new();
+        while let Some(chunk) = response.chunk().await.map_err(|e| format!("Event stream from {} failed: {}", url, e))? {
+            pending.extend_from_slice(&chunk);
+            while let Some(end) = pending.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = pending.drain(..=end).collect();
+                let line = String::from_utf8_lossy(&line);
+                // Keep-alive comments and blank separators carry no event
+                let Some(data) = line.trim_end().strip_prefix("data:") else { continue };
+                match serde_json::from_str::<LiveEvent>(data.trim_start()) {
+                    Ok(event) => on_event(event),
+                    Err(e) => return Err(format!("Failed to parse event from {}: {}", url, e)),
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,3 @@
+//! Types shared with the watchdog HTTP API, plus a small typed client for downstream tooling.
+pub mod api;
+pub mod client;
@@ -1,110 +1,1226 @@
-use std::{collections::HashMap, fs};
+use std::{collections::{HashMap, HashSet}, fs, path::{Path, PathBuf}, time::SystemTime};
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
+use schemars::JsonSchema;
 use serde::Deserialize;
 use std::sync::Arc;
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
 use tracing::{debug, error, info, warn, Level};
-use utils::{audiorouter::AudioRouter, commandprocessor::CommandHolder, comparator::StreamComparator, slack::SlackMessageSender, slacklistener::SlackListener, webserver::WebServer, alertmanager::AlertManager, nrsc::NrscManager, sdr::SdrManager};
+use utils::recorder::{RecordingSchedule, RecordingScheduler};
+use utils::deadair::DeadAirTracker;
+use utils::volumedetect::DropoutConfig;
+use utils::artifacts::{ArtifactConfig, ArtifactDetector};
+use utils::uptimestore::UptimeStore;
+use utils::fingerprintcache::FingerprintCache;
+use utils::prober::StreamProber;
+use utils::dnswatch::{host_from_url, DnsTarget, DnsWatcher};
+use utils::formatcheck::{ExpectedFormat, FormatChecker, FormatTarget};
+use utils::configdir::{scan_config_dir, ConfigDirEvent, ConfigDirWatcher};
+use utils::beacon::{Beacon, BeaconConfig, BeaconTarget, BeaconWatcher};
+use utils::sdr::{TunnelConfig, NRSC5_SAMPLE_RATE};
+use utils::scanner::{ScanConfig, SdrScanner};
+use utils::gaincontrol::{GainControlConfig, GainController};
+use utils::email::{EmailConfig, EmailSender};
+use utils::report::{WeeklyReportConfig, WeeklyReporter};
+use utils::timesync::{TimeSyncChecker, TimeSyncConfig};
+use utils::pushgateway::PushgatewayConfig;
+use utils::statsd::{StatsdConfig, StatsdEmitter};
+use utils::healthhook::{HealthWebhook, HealthWebhookConfig};
+use utils::frozen::FrozenDetector;
+use utils::delivery::DeliveryStats;
+use utils::tuning::ThresholdTuner;
+use utils::thresholdschedule::ThresholdSchedule;
+use utils::fastmatch::FastMatchConfig;
+use utils::overrides::{OverridesStore, RuntimeOverrides};
+use utils::webauth::{WebToken, WebUser};
+use utils::proxy::ProxyConfig;
+use utils::outbound::{HttpIdentity, WebFetch};
+use utils::slack::DEFAULT_USER_AGENT;
+use utils::branding::Branding;
+use utils::configformat::ConfigFormat;
+use utils::pin::PairPinner;
+use utils::slackhome::HomeTab;
+use utils::spectrum::parse_frequency;
+use watchdog::api::{AlertReason, AlertTransition, AudioStreamHealth, LiveEvent, StreamHealth};
+use watchdog::client::WatchdogClient;
+use utils::{audiostream::FingerprintSource, audiorouter::{AudioRouter, SupervisorConfig, VolumeLimits}, commandprocessor::{CommandHolder, RestartPolicy, TransientEofConfig}, comparator::{CollisionWhitelist, ComparisonOverrides, StreamComparator}, slack::SlackMessageSender, slacklistener::SlackListener, webserver::WebServer, alertmanager::{AlertFormat, AlertManager, MaintenanceWindow}, nrsc::NrscManager, sdr::SdrManager};
 mod utils;
 
 #[derive(Parser, Debug)]
 #[command(name = "watchdog")]
 #[command(about = "Audio stream monitoring and comparison tool", long_about = None)]
 struct Args {
-    /// Path to the configuration file
-    #[arg(short, long, default_value = "config.yaml")]
+    /// Path to the configuration file; channels and comparison thresholds are reloaded on SIGHUP or when it changes
+    #[arg(short, long, default_value = "config.yaml", global = true)]
     config: String,
 
-    /// Dry run mode - don't send Slack messages, print to terminal instead
+    /// Syntax of the configuration file; by default taken from its extension (.toml, .json), YAML otherwise
+    #[arg(long, value_enum, global = true)]
+    format: Option<ConfigFormat>,
+
+    /// Directory of per-channel YAML files named `<channel>.yaml`, watched for added and removed channels
+    #[arg(long, global = true)]
+    config_dir: Option<String>,
+
+    /// Dry run mode - don't send Slack messages, print to terminal instead; implies --no-spawn
     #[arg(long, default_value = "false")]
     dry_run: bool,
+
+    /// Don't start rtl_tcp, tunnels or SDR decoding, and only log the respawns the supervisor would do, to trial configs without SDR hardware
+    #[arg(long, default_value = "false")]
+    no_spawn: bool,
+
+    #[command(subcommand)]
+    command: Option<Commands>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Commands {
+    /// Print the JSON Schema for the configuration file and exit
+    Schema,
+    /// Write an example config to --config listing every field with its default, commented, and exit
+    Init {
+        /// Overwrite the file if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+    /// Query a running watchdog and exit with a Nagios/Icinga status code (0=OK, 1=WARNING, 2=CRITICAL, 3=UNKNOWN)
+    CheckStatus {
+        /// Web server root of the watchdog to check
+        #[arg(long, default_value = "http://localhost:3000")]
+        url: String,
+        /// Bearer token, for watchdogs with api_requires_token set
+        #[arg(long)]
+        token: Option<String>,
+    },
+    /// Sweep a configured SDR and print a power-vs-frequency report, to choose gain and check the antenna
+    Scan {
+        /// Name of the SDR in the config file
+        #[arg(long)]
+        sdr: String,
+        /// Start frequency, e.g. 88M
+        #[arg(long, value_parser = parse_frequency)]
+        from: u32,
+        /// End frequency, e.g. 108M
+        #[arg(long, value_parser = parse_frequency)]
+        to: u32,
+        /// Tuner gain in dB, automatic gain when unset
+        #[arg(long)]
+        gain: Option<f32>,
+        /// Width of each report row, e.g. 100k
+        #[arg(long, value_parser = parse_frequency, default_value = "100k")]
+        step: u32,
+    },
+    /// Parse the config (and --config-dir) and run the startup checks without spawning anything, exiting nonzero on errors
+    Validate,
+    /// Show or clear the runtime overrides file (tuned thresholds, acknowledgements); stop the watchdog first, it rewrites the file
+    Overrides {
+        #[command(subcommand)]
+        action: OverridesAction,
+        /// Overrides file, overrides_file from the config when unset
+        #[arg(long)]
+        file: Option<String>,
+    },
+    /// Follow a running watchdog and print health transitions, comparison verdicts and alerts as they happen
+    Tail {
+        /// Web server root of the watchdog to follow
+        #[arg(long, default_value = "http://localhost:3000")]
+        url: String,
+        /// Bearer token, for watchdogs with api_requires_token set
+        #[arg(long)]
+        token: Option<String>,
+    },
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Subcommand, Debug)]
+enum OverridesAction {
+    /// Print the overrides as JSON
+    Show,
+    /// Drop overrides so the config applies again; everything when no kind is given
+    Clear {
+        /// Only the tuned thresholds
+        #[arg(long)]
+        thresholds: bool,
+        /// Only the acknowledged alerts
+        #[arg(long)]
+        acknowledgements: bool,
+    },
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 struct Config {
     slack_channel: String,
-    slack_auth: String, // Bot token (xoxb-...)
-    slack_app_token: Option<String>, // App-level token for Socket Mode (xapp-...)
-    slack_bot_user_id: Option<String>, // Bot's user ID (U0829LK8DFE)
+    /// Bot token (xoxb-...)
+    slack_auth: String,
+    /// App-level token for Socket Mode (xapp-...)
+    slack_app_token: Option<String>,
+    /// Bot's user ID (U0829LK8DFE)
+    #[serde(alias = "bot_user_id")]
+    slack_bot_user_id: Option<String>,
+    /// Outbound proxy for Slack and web streams, for sites without direct internet access
+    http_proxy: Option<ProxyConfig>,
+    /// User-Agent (wrek-watchdog/1.0 when unset) and extra headers on Slack API calls
+    #[serde(default)]
+    slack_http: HttpIdentity,
+    /// User-Agent (ffmpeg's own when unset) and extra headers on web stream fetches
+    #[serde(default)]
+    stream_http: HttpIdentity,
     silence: SilenceDetectType,
+    /// What the silence reference channel plays in Match mode
+    #[serde(default)]
+    silence_reference: SilenceReference,
     sdrs: Option<HashMap<String, SDR>>,
+    /// May be empty when channels come from --config-dir
+    #[serde(default)]
     channels: HashMap<String, Channel>,
     #[serde(default = "default_buffer_duration")]
     buffer_duration: f32,
+    /// Total for all PCM and fingerprint buffers; past it every buffer shrinks proportionally instead of the box running out of memory
+    buffer_memory_cap_mb: Option<usize>,
     #[serde(default = "default_comparison_duration")]
     comparison_duration: f32,
     #[serde(default = "default_min_buffer_duration")]
     min_buffer_duration: f32,
+    /// Percentage (0-100) for within-channel matching
     #[serde(default = "default_match_threshold")]
-    match_threshold: f32, // Percentage (0-100) for within-channel matching
+    match_threshold: f32,
+    /// Percentage (0-100) for cross-channel divergence
     #[serde(default = "default_divergence_threshold")]
-    divergence_threshold: f32, // Percentage (0-100) for cross-channel divergence
+    divergence_threshold: f32,
+    /// Port for web status server
     #[serde(default = "default_web_port")]
-    web_port: u16, // Port for web status server
+    web_port: u16,
+    /// e.g. 127.0.0.1 or ::1 to keep the dashboard local, :: for IPv6 (and usually IPv4) on all interfaces
+    #[serde(default = "default_web_bind_address")]
+    web_bind_address: IpAddr,
+    /// Serve every route and link under this prefix, e.g. /watchdog behind a path-routed reverse proxy
+    web_base_path: Option<String>,
+    /// Title, logo, language and key labels of the web pages
+    #[serde(default)]
+    branding: Branding,
+    /// Alert when a running stream keeps repeating the same audio this long, 0 disables
+    #[serde(default = "default_frozen_after_seconds")]
+    frozen_after_seconds: u64,
+    /// Alert when a notification sink (Slack, health webhook, email) fails this many deliveries in a row, 0 disables
+    #[serde(default = "default_delivery_failure_alert")]
+    delivery_failure_alert: u64,
+    /// Start every SDR and stream that can be started, alerting on the rest instead of exiting
+    #[serde(default)]
+    fail_soft: bool,
+    /// Bearer tokens with a Viewer, Operator or Admin role; when set, control endpoints require one
+    #[serde(default)]
+    web_tokens: Vec<WebToken>,
+    /// Dashboard logins with argon2-hashed passwords; when set, the dashboard pages require a login
+    #[serde(default)]
+    web_users: Vec<WebUser>,
+    /// Every /api/*, /graphql and /metrics request needs a web token or login, not only the control endpoints
+    #[serde(default)]
+    api_requires_token: bool,
+    /// Browser origins allowed to call /api/*, e.g. https://status.example.org, or "*"
+    #[serde(default)]
+    cors_origins: Vec<String>,
+    /// Staging only: POST /api/debug/inject?stream=<name>&fault=silence|kill|lag[&seconds=60][&delay=5] to fire-drill alerting
+    #[serde(default)]
+    fault_injection: bool,
+    /// IANA name like America/New_York for the status page and Slack fallbacks, defaults to the system timezone
+    display_timezone: Option<String>,
+    /// Grace period before sending new failure alerts
     #[serde(default = "default_grace_period")]
-    grace_period_seconds: i64, // Grace period before sending new failure alerts
-    #[serde(default = "default_volume_detection_interval")]
-    volume_detection_interval: u64, // Interval in seconds for volume detection
-    #[serde(default = "default_minimum_max_volume")]
-    volume_minimum_max_volume: f32
+    grace_period_seconds: i64,
+    /// Full posts every alert message; Compact posts a one-line summary with the details in its thread
+    #[serde(default)]
+    alert_format: AlertFormat,
+    /// Recurring maintenance, e.g. Wednesday morning transmitter work, holding new alerts and reminders; see also `maintenance on`
+    #[serde(default)]
+    maintenance_windows: Vec<MaintenanceWindow>,
+    /// How often volume is sampled, in seconds
+    #[serde(default = "default_volume_sample_interval", alias = "volume_detection_interval")]
+    volume_sample_interval: u64,
+    /// How much audio each sample analyzes, in seconds; defaults to buffer_duration
+    volume_analysis_window: Option<f32>,
+    /// Short silences caught continuously between volume samples
+    #[serde(default)]
+    dropouts: DropoutConfig,
+    /// Watch NRSC streams for decoder glitches: repeated frames, clicks and mutes
+    artifacts: Option<ArtifactConfig>,
+    /// Only drives alerts when silence is Volume; Match mode relies on the silence reference channel instead
+    #[serde(default = "default_minimum_max_volume", alias = "minimum_max_volume")]
+    volume_minimum_max_volume: f32,
+    /// Alert on dead L/R channels and swapped stereo pairs
+    #[serde(default)]
+    stereo_detection: bool,
+    /// Scheduled clip capture, e.g. legal IDs
+    recordings: Option<RecordingConfig>,
+    /// Alert when a stream's silent time for the day exceeds this
+    dead_air_budget_seconds: Option<u64>,
+    /// SQLite file keeping stream uptime and restart counts across watchdog restarts
+    stats_database: Option<String>,
+    /// JSON file keeping runtime changes (tuned thresholds, acknowledgements) across watchdog restarts; see `watchdog overrides`
+    overrides_file: Option<String>,
+    /// Directory fingerprint buffers are saved to, so comparisons resume within seconds of a watchdog restart
+    fingerprint_cache_dir: Option<String>,
+    /// Interval for HTTP origin probes of web streams with probe enabled
+    #[serde(default = "default_probe_interval")]
+    probe_interval_seconds: u64,
+    /// Interval for resolving origins of web streams with watch_dns enabled
+    #[serde(default = "default_dns_watch_interval")]
+    dns_watch_interval_seconds: u64,
+    /// Interval for ffprobe checks of web streams with expected_format
+    #[serde(default = "default_format_check_interval")]
+    format_check_interval_seconds: u64,
+    /// Longer intervals, in-process volume levels and capped comparisons for small boards like a Pi Zero 2
+    #[serde(default)]
+    low_power: bool,
+    /// XOR/popcount matching for large buffers, for installs where rusty_chromaprint dominates CPU
+    fast_match: Option<FastMatchConfig>,
+    /// Time windows where two channels are expected to carry the same audio
+    #[serde(default)]
+    collision_whitelist: Vec<CollisionWhitelist>,
+    /// Thresholds and expected dead air by time of day and day of week, e.g. quieter overnight automation
+    #[serde(default)]
+    threshold_schedules: Vec<ThresholdSchedule>,
+    /// Respawn checks, backoff and streams that need confirmation to respawn
+    #[serde(default)]
+    supervisor: SupervisorConfig,
+    /// SMTP server for emailed reports, and for the Slack link-down alert that Slack itself may not deliver
+    email: Option<EmailConfig>,
+    /// Weekly availability summary, sent via email
+    weekly_report: Option<WeeklyReportConfig>,
+    /// NTP offset check, clock skew breaks grace periods, uptimes and offsets
+    #[serde(default)]
+    time_sync: TimeSyncConfig,
+    /// Push metrics on an interval, for sites behind NAT that Prometheus can't scrape
+    pushgateway: Option<PushgatewayConfig>,
+    /// Emit core gauges and counters to statsd/DogStatsD, for Datadog-based infrastructure
+    statsd: Option<StatsdConfig>,
+    /// POST every stream health change, for audio switchers that fail over to backup program audio
+    health_webhook: Option<HealthWebhookConfig>,
 }
 
+const LOW_POWER_INTERVAL_FACTOR: u64 = 3;
+const LOW_POWER_COMPARISON_INTERVAL: u64 = 15;
+const LOW_POWER_MAX_CROSS_PAIRS: usize = 3;
+const CONFIG_DIR_POLL_SECONDS: u64 = 10;
+const PRIORITY_TIER_WAIT_SECONDS: u64 = 30; // longest a priority tier may hold up the next one at startup
+const STARTUP_STEP_TIMEOUT_SECONDS: u64 = 60; // longest one SDR, NRSC connection or NRSC program may take to come up
+
 fn default_buffer_duration() -> f32 { 120.0 }
+fn default_frozen_after_seconds() -> u64 { 30 }
+fn default_delivery_failure_alert() -> u64 { 3 }
 fn default_comparison_duration() -> f32 { 5.0 }
 fn default_min_buffer_duration() -> f32 { 30.0 }
 fn default_match_threshold() -> f32 { 85.0 }
 fn default_divergence_threshold() -> f32 { 50.0 }
 fn default_web_port() -> u16 { 3000 }
+fn default_web_bind_address() -> IpAddr { IpAddr::V4(Ipv4Addr::UNSPECIFIED) }
 fn default_grace_period() -> i64 { 60 } // Default 60 second grace period
-fn default_volume_detection_interval() -> u64 { 10 } // Default 10 seconds
+fn default_volume_sample_interval() -> u64 { 10 } // Default 10 seconds
 fn default_minimum_max_volume() -> f32 { -70.0 } // Default -70dB
+fn default_probe_interval() -> u64 { 60 }
+fn default_dns_watch_interval() -> u64 { 300 }
+fn default_format_check_interval() -> u64 { 600 }
 
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 struct Channel {
-    streams: HashMap<String, Stream>
+    streams: HashMap<String, Stream>,
+    /// Overrides the global grace period for every stream in this channel
+    grace_period_seconds: Option<i64>,
+    /// Overrides the global buffer_duration (seconds of audio kept) for every stream in this channel
+    buffer_duration: Option<f32>,
+    /// Action hint appended to failure alerts of every stream in this channel, e.g. "check Barix at rack 3, see wiki/transmitter-a"
+    runbook: Option<String>,
+    /// With silence: Match, compare this channel's streams against the silence reference channel
+    #[serde(default)]
+    silence_check: bool,
+    /// Alert on the channel when fewer of its streams have running command and audio
+    min_healthy_streams: Option<usize>,
+    /// Overrides the global match_threshold for comparisons within this channel, e.g. looser for talk
+    match_threshold: Option<f32>,
+    /// Overrides the global divergence_threshold; a pair of channels uses the higher of their two
+    divergence_threshold: Option<f32>,
+    /// Overrides the global comparison_duration; a pair of channels uses the longer of their two
+    comparison_duration: Option<f32>,
+    /// With silence: Volume, overrides volume_minimum_max_volume for every stream in this channel, e.g. lower for a quiet classical channel
+    min_max_volume_db: Option<f32>,
+    /// With silence: Volume, also alert when a stream's mean level drops below this
+    min_mean_volume_db: Option<f32>,
+    /// Station ID clips every stream should air, to catch wrong-station audio
+    beacons: Option<BeaconConfig>
 }
 
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, PartialEq, JsonSchema)]
 enum StreamType {
-    Web, // FFmpeg-compatible stream
-    NRSC, // stream via nrsc, which needs an input from an RTL-SDR
-    FM // TODO, however it is just an input from an RTL-SDR
+    /// FFmpeg-compatible stream
+    Web,
+    /// stream via nrsc, which needs an input from an RTL-SDR
+    NRSC,
+    /// TODO, however it is just an input from an RTL-SDR
+    FM
 }
 
-#[derive(Debug, Clone, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Deserialize, PartialEq, JsonSchema)]
 enum SilenceDetectType {
-    None, // dont silence detect
-    Match, // use stream matching using fingerprinting
-    Volume, // use the volumedetect module, helpful to determine volume_minimum_max_db
+    /// dont silence detect
+    None,
+    /// use stream matching using fingerprinting
+    Match,
+    /// use the volumedetect module, helpful to determine volume_minimum_max_db
+    Volume,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 struct Stream {
     r#type: StreamType,
     host: String,
-    path: String
+    path: String,
+    /// Mono, Left or Right, for per-channel comparisons of a stereo pair
+    #[serde(default)]
+    fingerprint_source: FingerprintSource,
+    /// Disabled streams stay documented in config but are never spawned
+    #[serde(default = "default_stream_enabled")]
+    enabled: bool,
+    /// Overrides the channel and global grace periods
+    grace_period_seconds: Option<i64>,
+    /// Overrides the channel and global buffer_duration, e.g. longer for HD streams that match slowly
+    buffer_duration: Option<f32>,
+    /// Overrides the global min_buffer_duration, e.g. longer for NRSC streams that ride out sync losses
+    min_buffer_duration: Option<f32>,
+    /// Overrides the channel's runbook hint for this stream's failure alerts
+    runbook: Option<String>,
+    /// Overrides the channel's min_max_volume_db and the global volume_minimum_max_volume
+    min_max_volume_db: Option<f32>,
+    /// Overrides the channel's min_mean_volume_db
+    min_mean_volume_db: Option<f32>,
+    /// Web only: also check the origin URL over HTTP, so origin outages are told apart from decoder trouble
+    #[serde(default)]
+    probe: bool,
+    /// Web only: record the origin's resolved IPs and alert when they change
+    #[serde(default)]
+    watch_dns: bool,
+    /// With watch_dns, only alert when the origin resolves outside this list, not on every change
+    expected_ips: Option<Vec<String>>,
+    /// Web only: alert when ffprobe reports a different codec/rate/bitrate
+    expected_format: Option<ExpectedFormat>,
+    /// Web only: EOFs to restart straight away instead of treating as a dead stream
+    transient_eof: Option<TransientEofConfig>,
+    /// Web only: standby source played while the primary has been dead past the grace period
+    backup_url: Option<String>,
+    /// NRSC on a scanning SDR: which scan frequency (Hz) carries this station
+    frequency: Option<u32>,
+    /// Seconds without output before the stream counts as stalled, overriding supervisor.stall_timeout_seconds
+    stall_timeout_seconds: Option<u64>,
+    /// Respawn the stream once stalled this much longer, overriding supervisor.stalled_respawn_seconds
+    stalled_dead_after_seconds: Option<u64>,
+    /// Consecutive respawns before giving up until a `restart`, overriding supervisor.max_restarts
+    max_restarts: Option<u32>,
+    /// Delay before each consecutive respawn, overriding supervisor.backoff_seconds, e.g. longer for a flaky satellite feed
+    backoff_seconds: Option<Vec<u64>>,
+    /// Higher priorities are spawned, buffered and respawned first, e.g. the main transmitter feed
+    #[serde(default)]
+    priority: i32
+}
+
+fn default_stream_enabled() -> bool { true }
+
+/// Source of the silence reference channel that Match mode compares silence_check channels against
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+struct SilenceReference {
+    /// Off drops the reference channel, leaving silence_check channels with nothing to compare against
+    #[serde(default = "default_silence_reference_enabled")]
+    enabled: bool,
+    #[serde(default)]
+    source: SilenceReferenceSource,
+    /// Generated at this rate, then resampled to 44100 like every stream
+    #[serde(default = "default_silence_sample_rate")]
+    sample_rate: u32,
+    /// ffmpeg layout of the generated signal, e.g. stereo or mono
+    #[serde(default = "default_silence_channel_layout")]
+    channel_layout: String,
+    /// PinkNoise only, 0.0-1.0; low enough to pass for silence, loud enough to fingerprint
+    #[serde(default = "default_pink_noise_amplitude")]
+    amplitude: f32,
+    /// Url only, e.g. a station-specific recording of its dead air
+    url: Option<String>,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, JsonSchema)]
+enum SilenceReferenceSource {
+    /// digital silence from anullsrc
+    #[default]
+    Null,
+    /// low-level pink noise from anoisesrc
+    PinkNoise,
+    /// any ffmpeg input, played in real time
+    Url,
+}
+
+fn default_silence_reference_enabled() -> bool { true }
+fn default_silence_sample_rate() -> u32 { 44100 }
+fn default_silence_channel_layout() -> String { "stereo".to_string() }
+fn default_pink_noise_amplitude() -> f32 { 0.001 }
+
+impl Default for SilenceReference {
+    fn default() -> Self {
+        SilenceReference {
+            enabled: default_silence_reference_enabled(),
+            source: SilenceReferenceSource::default(),
+            sample_rate: default_silence_sample_rate(),
+            channel_layout: default_silence_channel_layout(),
+            amplitude: default_pink_noise_amplitude(),
+            url: None,
+        }
+    }
+}
+
+impl SilenceReference {
+    /// ffmpeg arguments producing the reference as s16le stereo at 44.1 kHz on stdout
+    fn ffmpeg_args(&self) -> Result<Vec<String>, String> {
+        let input = match self.source {
+            SilenceReferenceSource::Null => vec![
+                "-f".to_string(), "lavfi".to_string(),
+                "-i".to_string(), format!("anullsrc=r={}:cl={}", self.sample_rate, self.channel_layout),
+            ],
+            SilenceReferenceSource::PinkNoise => {
+                if !(0.0..=1.0).contains(&self.amplitude) {
+                    return Err(format!("silence_reference amplitude must be between 0 and 1, got {}", self.amplitude));
+                }
+                vec![
+                    "-f".to_string(), "lavfi".to_string(),
+                    "-i".to_string(), format!("anoisesrc=r={}:c=pink:a={}", self.sample_rate, self.amplitude),
+                ]
+            }
+            SilenceReferenceSource::Url => match self.url {
+                Some(ref url) => vec!["-i".to_string(), url.clone()],
+                None => return Err("silence_reference source Url needs a url".to_string()),
+            },
+        };
+
+        let mut args = vec!["-loglevel".to_string(), "error".to_string(), "-re".to_string()];
+        args.extend(input);
+        args.extend(["-ar", "44100", "-ac", "2", "-f", "s16le", "-"].map(String::from));
+        Ok(args)
+    }
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+struct RecordingConfig {
+    #[serde(default = "default_recording_directory")]
+    directory: String,
+    #[serde(default = "default_recording_retention_days")]
+    retention_days: u64,
+    schedules: Vec<RecordingSchedule>
+}
+
+fn default_recording_directory() -> String { "recordings".to_string() }
+fn default_recording_retention_days() -> u64 { 30 }
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 struct SDR {
-    host: String, // could be local, or could be something we netcat in to
+    /// could be local, or could be something we netcat in to
+    host: String,
     port: u16,
-    spawn: Option<SDRSpawnArgs>
+    spawn: Option<SDRSpawnArgs>,
+    /// Reach a remote rtl_tcp through SSH or a pre-connect command; host/port are then the local end
+    tunnel: Option<TunnelConfig>,
+    /// Share this SDR between several stations by cycling through frequencies
+    scan: Option<ScanConfig>,
+    /// Let the watchdog adjust gain from signal level and nrsc5 BER
+    gain_control: Option<GainControlConfig>
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
 struct SDRSpawnArgs {
     // rtl_tcp -a 0.0.0.0 -f 91.1M -s 1488375 -g -15.0
     frequency: u32,
+    /// IQ sample rate, must be 1488375 for SDRs feeding NRSC streams
     size: u32,
-    gain: f32
+    gain: f32,
+    /// Replace a wrong size with the rate nrsc5 expects instead of refusing to start
+    #[serde(default)]
+    auto_correct_rate: bool
+}
+
+/// Checks requested by a channel's streams, collected so the checkers can be started once
+#[derive(Default)]
+struct StreamTargets {
+    probes: Vec<(String, String)>, // (stream name, url)
+    dns: Vec<DnsTarget>,
+    formats: Vec<FormatTarget>,
+    scan_members: Vec<(String, u32, String)>, // (SDR name, frequency, stream name) for scanning SDRs
+    decoded: Vec<String>, // NRSC streams, watched for decode artifacts
+    failed: Vec<(String, String)>, // (stream name, error) of streams that could not be started
+}
+
+impl StreamTargets {
+    fn is_empty(&self) -> bool {
+        self.probes.is_empty() && self.dns.is_empty() && self.formats.is_empty() && self.scan_members.is_empty() && self.decoded.is_empty()
+    }
+
+    fn merge(&mut self, other: StreamTargets) {
+        self.probes.extend(other.probes);
+        self.dns.extend(other.dns);
+        self.formats.extend(other.formats);
+        self.scan_members.extend(other.scan_members);
+        self.decoded.extend(other.decoded);
+        self.failed.extend(other.failed);
+    }
+}
+
+/// Stops the nrsc5 decoders that only fed the given, now removed, streams
+async fn release_nrsc_programs(nrsc_managers: &HashMap<String, Arc<NrscManager>>, streams: &[String]) {
+    for manager in nrsc_managers.values() {
+        for stream_name in streams {
+            manager.remove_program(stream_name).await;
+        }
+    }
+}
+
+/// Runs named startup steps concurrently, logging progress as each finishes; results come back in input order.
+/// Steps given a timeout fail once it passes instead of holding up the rest of startup
+async fn run_startup_steps<T, F>(what: &str, steps: Vec<(String, F)>, timeout: Option<std::time::Duration>) -> Vec<Result<T, String>>
+where
+    F: std::future::Future<Output = Result<T, String>>,
+{
+    let total = steps.len();
+    if total == 0 {
+        return Vec::new();
+    }
+    let started = std::time::Instant::now();
+    let finished = std::sync::atomic::AtomicUsize::new(0);
+    let finished = &finished;
+    let results = futures_util::future::join_all(steps.into_iter().map(|(name, step)| async move {
+        let result = match timeout {
+            Some(limit) => tokio::time::timeout(limit, step).await
+                .unwrap_or_else(|_| Err(format!("{} {} did not come up within {}s", what, name, limit.as_secs()))),
+            None => step.await,
+        };
+        let done = finished.fetch_add(1, std::sync::atomic::Ordering::Relaxed) + 1;
+        match result {
+            Ok(_) => info!("Startup: {} {} ready ({}/{})", what, name, done, total),
+            Err(ref e) => warn!("Startup: {} {} failed ({}/{}): {}", what, name, done, total, e),
+        }
+        result
+    })).await;
+    let ready = results.iter().filter(|result| result.is_ok()).count();
+    info!("Startup: {} of {} {} steps ready after {:.1}s", ready, total, what, started.elapsed().as_secs_f64());
+    results
+}
+
+/// Spawns every enabled stream of a channel into the router, returning the names of the streams added;
+/// streams that can't be started are skipped and listed in `targets.failed`
+#[allow(clippy::too_many_arguments)]
+async fn add_channel(
+    router: &AudioRouter,
+    channel_name: &String,
+    channel: Channel,
+    buffer_duration: f32,
+    default_grace_period: i64,
+    sdrs: &Option<HashMap<String, SDR>>,
+    nrsc_managers: &HashMap<String, Arc<NrscManager>>,
+    sdr_managers: &HashMap<String, Arc<SdrManager>>,
+    web_fetch: &WebFetch,
+    targets: &mut StreamTargets,
+) -> Vec<String> {
+    let mut added = Vec::new();
+    router.set_silence_check(channel_name, channel.silence_check);
+    router.set_min_healthy_streams(channel_name, channel.min_healthy_streams);
+    router.set_comparison_overrides(channel_name, ComparisonOverrides {
+        match_threshold: channel.match_threshold,
+        divergence_threshold: channel.divergence_threshold,
+        comparison_duration: channel.comparison_duration,
+    });
+    let mut streams: Vec<(String, Stream)> = channel.streams.into_iter().collect();
+    streams.sort_by_key(|(_, stream)| std::cmp::Reverse(stream.priority));
+    for (name, stream) in streams {
+        if !stream.enabled {
+            info!("Channel {} stream {} is disabled, skipping", channel_name, name);
+            continue;
+        }
+        let stream_name = format!("{}-{}", channel_name, name);
+        let buffer_duration = stream.buffer_duration.or(channel.buffer_duration).unwrap_or(buffer_duration);
+        debug!("Stream {} keeps {}s of audio", stream_name, buffer_duration);
+        let stall_timeout = stream.stall_timeout_seconds.map(std::time::Duration::from_secs);
+        let stalled_dead_after = stream.stalled_dead_after_seconds.map(std::time::Duration::from_secs);
+        let restart_policy = RestartPolicy { max_restarts: stream.max_restarts, backoff_seconds: stream.backoff_seconds.clone() };
+        router.set_volume_limits(&stream_name, VolumeLimits {
+            min_max_volume_db: stream.min_max_volume_db.or(channel.min_max_volume_db),
+            min_mean_volume_db: stream.min_mean_volume_db.or(channel.min_mean_volume_db),
+        });
+        match stream.r#type {
+            StreamType::FM => {
+                error!("FM stream type is not currently supported");
+            },
+            StreamType::NRSC => {
+                let Some(sdrs) = sdrs else {
+                    targets.failed.push((stream_name, format!("Channel {} stream {} needs an SDR yet none are defined!", channel_name, name)));
+                    continue;
+                };
+                let Some(sdr) = sdrs.get(&stream.host) else {
+                    targets.failed.push((stream_name, format!("Channel {} stream {} needs an SDR yet {} is not defined!", channel_name, name, stream.host)));
+                    continue;
+                };
+
+                // On a scanning SDR each decoder is tied to the frequency its station is on
+                let frequency = match sdr.scan {
+                    None => None,
+                    Some(ref scan) => match stream.frequency {
+                        Some(frequency) if scan.frequencies.contains(&frequency) => Some(frequency),
+                        _ => {
+                            targets.failed.push((stream_name, format!("Channel {} stream {} is on scanning SDR {} and needs a frequency from its scan list",
+                                channel_name, name, stream.host)));
+                            continue;
+                        }
+                    },
+                };
+                if router.spawns_disabled() {
+                    info!("Would add NRSC stream {} for program {} via SDR {}", stream_name, stream.path, stream.host);
+                    continue;
+                }
+                debug!("Adding NRSC stream {} for program {} via SDR {}", stream_name, stream.path, stream.host);
+
+                // Get the NRSC manager for this SDR
+                let Some(manager) = nrsc_managers.get(&stream.host) else {
+                    targets.failed.push((stream_name, format!("SDR {} is not running", stream.host)));
+                    continue;
+                };
+
+                // Add program to the manager and get the output receiver
+                let limit = std::time::Duration::from_secs(STARTUP_STEP_TIMEOUT_SECONDS);
+                let receiver = match tokio::time::timeout(limit, manager.add_program(&stream.path, frequency, &stream_name)).await {
+                    Ok(Ok(receiver)) => receiver,
+                    Ok(Err(e)) => {
+                        targets.failed.push((stream_name.clone(), format!("Failed to add NRSC program {} for stream {}: {}", stream.path, stream_name, e)));
+                        continue;
+                    }
+                    Err(_) => {
+                        targets.failed.push((stream_name.clone(), format!("NRSC program {} for stream {} did not start within {}s", stream.path, stream_name, STARTUP_STEP_TIMEOUT_SECONDS)));
+                        continue;
+                    }
+                };
+
+                // nrsc5 writes WAV; the relay checks its format and converts it in-process if needed
+                let command = CommandHolder::relay(&stream_name, receiver)
+                    .with_stall_limits(stall_timeout, stalled_dead_after)
+                    .with_restart_policy(restart_policy);
+                router.add_stream(&stream_name, channel_name, buffer_duration, stream.min_buffer_duration, stream.fingerprint_source, stream.priority, command).await;
+                if let Some(log) = manager.get_program_stderr(&stream.path, frequency).await {
+                    router.attach_upstream_log(&stream_name, &format!("nrsc5 program {}", stream.path), log);
+                }
+                if let Some(sdr_manager) = sdr_managers.get(&stream.host) {
+                    router.attach_upstream_log(&stream_name, sdr_manager.process_name(), sdr_manager.get_stderr_log());
+                }
+                if let Some(frequency) = frequency {
+                    targets.scan_members.push((stream.host.clone(), frequency, stream_name.clone()));
+                }
+                targets.decoded.push(stream_name.clone());
+                info!("Added NRSC stream {} successfully", stream_name);
+                added.push(stream_name);
+            },
+            StreamType::Web => {
+                let url = format!("{}/{}", stream.host, stream.path);
+                debug!("Adding web stream {} for {}", stream_name, url);
+                if stream.probe {
+                    targets.probes.push((stream_name.clone(), url.clone()));
+                }
+                if let Some(ref expected) = stream.expected_format {
+                    targets.formats.push(FormatTarget {
+                        stream_name: stream_name.clone(),
+                        url: url.clone(),
+                        expected: expected.clone(),
+                    });
+                }
+                if stream.watch_dns {
+                    match host_from_url(&url) {
+                        Some(host) => targets.dns.push(DnsTarget {
+                            stream_name: stream_name.clone(),
+                            host,
+                            expected_ips: stream.expected_ips.clone(),
+                        }),
+                        None => warn!("Could not find a hostname in {} for DNS watch of {}", url, stream_name),
+                    }
+                }
+                let fetch_args = web_fetch.ffmpeg_args(&url);
+                let mut args = vec!["-loglevel", "error", "-re"];
+                args.extend(fetch_args.iter().map(String::as_str));
+                args.extend(["-i", &url, "-ar", "44100", "-ac", "2", "-f", "s16le", "-"]);
+                let mut command = CommandHolder::new(&stream_name, "ffmpeg", args, None)
+                    .with_stall_limits(stall_timeout, stalled_dead_after)
+                    .with_restart_policy(restart_policy);
+                if let Some(ref transient_eof) = stream.transient_eof {
+                    command = command.with_transient_eof(transient_eof.clone());
+                }
+                if let Some(ref backup_url) = stream.backup_url {
+                    let grace = stream.grace_period_seconds.or(channel.grace_period_seconds).unwrap_or(default_grace_period);
+                    let fetch_args = web_fetch.ffmpeg_args(backup_url);
+                    let mut args = vec!["-loglevel", "error", "-re"];
+                    args.extend(fetch_args.iter().map(String::as_str));
+                    args.extend(["-i", backup_url, "-ar", "44100", "-ac", "2", "-f", "s16le", "-"]);
+                    command = command.with_backup(args, chrono::Duration::seconds(grace));
+                }
+                router.add_stream(&stream_name, channel_name, buffer_duration, stream.min_buffer_duration, stream.fingerprint_source, stream.priority, command).await;
+                added.push(stream_name);
+            }
+        }
+    }
+    added
+}
+
+/// Shows streams that could not be started as errored and alerts on each, instead of leaving them silently missing
+async fn report_failed_streams(router: &AudioRouter, alert_manager: &AlertManager, channel_name: &str, failed: Vec<(String, String)>) {
+    for (stream_name, error) in failed {
+        error!("{}", error);
+        router.mark_failed(channel_name, &stream_name, &error);
+        alert_manager.update_alert(format!("{}_startup", stream_name), AlertReason::ProcessDead, &[stream_name.as_str()], true,
+            format!("Stream `{}` could not be started: {}", stream_name, error)).await;
+    }
+}
+
+/// Alerts on an SDR that could not be brought up; its streams then fail to start and are reported on their own
+async fn report_failed_sdr(alert_manager: &AlertManager, sdr_name: &str, error: String) {
+    error!("{}", error);
+    alert_manager.update_alert(format!("{}_startup", sdr_name), AlertReason::SdrDown, &[], true,
+        format!("SDR `{}` could not be started: {}", sdr_name, error)).await;
+}
+
+/// Canonical rendering of a channel's settings, to spot channels a config reload changed; the config
+/// types have no PartialEq and a HashMap's Debug output is in no particular order
+fn channel_fingerprint(channel: &Channel) -> String {
+    let mut streams: Vec<String> = channel.streams.iter().map(|(name, stream)| format!("{}={:?}", name, stream)).collect();
+    streams.sort();
+    let settings = Channel { streams: HashMap::new(), ..channel.clone() };
+    format!("{:?} {:?}", settings, streams)
+}
+
+/// Reads the main config file in `format`, or the one its extension names, with `${NAME}` references resolved from the environment
+fn read_config(path: &str, format: Option<ConfigFormat>) -> Result<Config, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Error reading config file {}: {}", path, e))?;
+    let format = format.unwrap_or_else(|| ConfigFormat::from_path(Path::new(path)));
+    format.parse(&text).map_err(|e| format!("Error parsing {} as {:?}: {}", path, format, e))
+}
+
+/// Reads one channel definition from a config directory file
+fn load_channel_file(path: &std::path::Path) -> Result<Channel, String> {
+    let text = fs::read_to_string(path).map_err(|e| format!("Error reading channel file {}: {}", path.display(), e))?;
+    ConfigFormat::Yaml.parse(&text).map_err(|e| format!("Error parsing channel file {}: {}", path.display(), e))
+}
+
+fn watchdog_client(url: &str, token: Option<&str>) -> WatchdogClient {
+    let client = WatchdogClient::new(url);
+    match token {
+        Some(token) => client.with_token(token),
+        None => client,
+    }
+}
+
+/// Nagios plugin check of a running watchdog, returning the exit code and the summary line with perfdata
+async fn check_status(url: &str, token: Option<&str>) -> (i32, String) {
+    let client = watchdog_client(url, token);
+    let status = match client.status().await {
+        Ok(status) => status,
+        Err(e) => return (3, format!("WATCHDOG UNKNOWN - {}", e)),
+    };
+    // Older watchdogs have no alerts endpoint, the status alone still makes a useful check
+    let alerts = client.alerts().await.unwrap_or_default();
+
+    let sampled: Vec<_> = status.streams.iter().filter(|s| !s.not_sampled).collect();
+    let dead = sampled.iter()
+        .filter(|s| s.command_health == StreamHealth::Dead || s.audio_health == AudioStreamHealth::Dead)
+        .count();
+    let degraded = sampled.iter()
+        .filter(|s| s.command_health == StreamHealth::Stalled || s.audio_health == AudioStreamHealth::Degraded)
+        .count();
+    let failing_comparisons = status.comparisons.iter().filter(|c| c.is_error).count();
+    let on_backup = sampled.iter().filter(|s| s.on_backup).count();
+
+    let mut problems = Vec::new();
+    if dead > 0 { problems.push(format!("{} dead", dead)); }
+    if failing_comparisons > 0 { problems.push(format!("{} failing comparisons", failing_comparisons)); }
+    if degraded > 0 { problems.push(format!("{} stalled or degraded", degraded)); }
+    if on_backup > 0 { problems.push(format!("{} on backup", on_backup)); }
+    if !alerts.is_empty() { problems.push(format!("{} active alerts", alerts.len())); }
+
+    let (code, state) = if dead > 0 || failing_comparisons > 0 {
+        (2, "CRITICAL")
+    } else if !problems.is_empty() {
+        (1, "WARNING")
+    } else {
+        (0, "OK")
+    };
+    let detail = if problems.is_empty() {
+        let warming = if status.warming_up { ", warming up" } else { "" };
+        format!("{} streams healthy{}", status.streams.len(), warming)
+    } else {
+        problems.join(", ")
+    };
+    let perfdata = format!("streams={} dead={} degraded={} failing_comparisons={} alerts={}",
+        status.streams.len(), dead, degraded, failing_comparisons, alerts.len());
+    (code, format!("WATCHDOG {} - {} | {}", state, detail, perfdata))
+}
+
+/// One colorized line of `watchdog tail` output
+fn format_live_event(event: &LiveEvent, color: bool) -> String {
+    const GREEN: &str = "\x1b[32m";
+    const YELLOW: &str = "\x1b[33m";
+    const RED: &str = "\x1b[31m";
+    const RESET: &str = "\x1b[0m";
+
+    let (timestamp, tint, text) = match event {
+        LiveEvent::Health { timestamp, stream, command_health, audio_health } => {
+            let tint = if *command_health == StreamHealth::Dead || *audio_health == AudioStreamHealth::Dead {
+                RED
+            } else if *command_health == StreamHealth::Running && *audio_health == AudioStreamHealth::Running {
+                GREEN
+            } else {
+                YELLOW
+            };
+            (timestamp, tint, format!("HEALTH   {}: command {:?}, audio {:?}", stream, command_health, audio_health))
+        }
+        LiveEvent::Comparison { timestamp, result } => {
+            let verdict = if result.is_error { "FAILING" } else { "ok" };
+            let offset = result.offset_seconds.map(|o| format!(", offset {:.2}s", o)).unwrap_or_default();
+            (timestamp, if result.is_error { RED } else { GREEN }, format!("COMPARE  {} <-> {} ({}): {:.1}% {}{}",
+                result.stream1, result.stream2, result.comparison_type(), result.similarity_percent, verdict, offset))
+        }
+        LiveEvent::Alert(event) => {
+            let (tint, transition) = match event.transition {
+                AlertTransition::Failing => (RED, "FAILING"),
+                AlertTransition::Cleared => (GREEN, "cleared"),
+            };
+            (&event.timestamp, tint, format!("ALERT    {} {} [{}]: {}", event.alert, transition, event.reason.as_str(), event.message))
+        }
+    };
+
+    let time = timestamp.with_timezone(&chrono::Local).format("%H:%M:%S");
+    if color {
+        format!("{} {}{}{}", time, tint, text, RESET)
+    } else {
+        format!("{} {}", time, text)
+    }
+}
+
+/// Prints a running watchdog's live feed until interrupted, reconnecting whenever it drops
+async fn tail(url: &str, token: Option<&str>) -> ! {
+    use std::io::IsTerminal;
+    let color = std::io::stdout().is_terminal() && std::env::var_os("NO_COLOR").is_none();
+    let client = watchdog_client(url, token);
+
+    loop {
+        let result = client.events(|event| println!("{}", format_live_event(&event, color))).await;
+        match result {
+            Ok(()) => eprintln!("Event stream from {} closed, reconnecting", url),
+            Err(e) => eprintln!("{}, retrying in 5s", e),
+        }
+        tokio::time::sleep(std::time::Duration::from_secs(5)).await;
+    }
+}
+
+/// Merges the channels of a config directory into `config`, returning the files they came from
+fn merge_config_dir(config: &mut Config, config_path: &str, dir: &str) -> Result<HashMap<String, (PathBuf, SystemTime)>, String> {
+    let files = scan_config_dir(Path::new(dir))?;
+    for (channel_name, (path, _)) in &files {
+        if config.channels.contains_key(channel_name) {
+            return Err(format!("Channel {} from {} is also defined in {}", channel_name, path.display(), config_path));
+        }
+        let channel = load_channel_file(path)?;
+        info!("Loaded channel {} from {}", channel_name, path.display());
+        config.channels.insert(channel_name.clone(), channel);
+    }
+    Ok(files)
+}
+
+/// Problems startup would run into with `config`, as (errors, warnings); errors stop startup
+fn check_config(config: &Config) -> (Vec<String>, Vec<String>) {
+    let mut errors = Vec::new();
+    let mut warnings = Vec::new();
+
+    if let Some(ref name) = config.display_timezone {
+        if let Err(e) = name.parse::<chrono_tz::Tz>() {
+            errors.push(format!("Unknown display_timezone {}: {}", name, e));
+        }
+    }
+    if let Err(e) = config.slack_http.client(DEFAULT_USER_AGENT, config.http_proxy.as_ref()) {
+        errors.push(format!("slack_http: {}", e));
+    }
+    if let Err(e) = config.stream_http.client(DEFAULT_USER_AGENT, None) {
+        errors.push(format!("stream_http: {}", e));
+    }
+    let silence_match = config.silence == SilenceDetectType::Match && config.silence_reference.enabled;
+    if silence_match {
+        if let Err(e) = config.silence_reference.ffmpeg_args() {
+            errors.push(e);
+        }
+        if !config.channels.is_empty() && !config.channels.values().any(|channel| channel.silence_check) {
+            warnings.push("silence is Match but no channel sets silence_check, nothing will be compared against the silence reference".to_string());
+        }
+    }
+    if let Some(window) = config.volume_analysis_window {
+        if window < config.volume_sample_interval as f32 {
+            warnings.push(format!("volume_analysis_window ({}s) is shorter than volume_sample_interval ({}s), audio between samples is never analyzed",
+                window, config.volume_sample_interval));
+        }
+    }
+
+    // Router stream names join channel and stream, so different pairs can end up with the same name
+    let mut stream_names: HashMap<String, String> = HashMap::new();
+    if silence_match {
+        stream_names.insert("silence".to_string(), "the silence reference".to_string());
+    }
+    let mut channel_names: Vec<&String> = config.channels.keys().collect();
+    channel_names.sort();
+    for channel_name in channel_names {
+        let channel = &config.channels[channel_name];
+        let mut names: Vec<&String> = channel.streams.keys().collect();
+        names.sort();
+        if names.iter().all(|name| !channel.streams[*name].enabled) {
+            warnings.push(format!("Channel {} has no enabled streams", channel_name));
+        }
+        let volume_limits = channel.min_max_volume_db.is_some() || channel.min_mean_volume_db.is_some()
+            || channel.streams.values().any(|stream| stream.min_max_volume_db.is_some() || stream.min_mean_volume_db.is_some());
+        if volume_limits && config.silence != SilenceDetectType::Volume {
+            warnings.push(format!("Channel {} sets volume levels but silence is not Volume, they are ignored", channel_name));
+        }
+        for name in names {
+            let stream = &channel.streams[name];
+            let stream_name = format!("{}-{}", channel_name, name);
+            let owner = format!("channel {} stream {}", channel_name, name);
+            if let Some(other) = stream_names.insert(stream_name.clone(), owner.clone()) {
+                errors.push(format!("Stream name {} is used by both {} and {}", stream_name, other, owner));
+            }
+            if !stream.enabled {
+                continue;
+            }
+            match stream.r#type {
+                StreamType::FM => errors.push(format!("Channel {} stream {} is FM, which is not currently supported", channel_name, name)),
+                StreamType::NRSC => match config.sdrs.as_ref().and_then(|sdrs| sdrs.get(&stream.host)) {
+                    None => errors.push(format!("Channel {} stream {} needs SDR {}, which is not defined", channel_name, name, stream.host)),
+                    Some(sdr) => if let Some(ref scan) = sdr.scan {
+                        if !stream.frequency.is_some_and(|frequency| scan.frequencies.contains(&frequency)) {
+                            errors.push(format!("Channel {} stream {} is on scanning SDR {} and needs a frequency from its scan list",
+                                channel_name, name, stream.host));
+                        }
+                    },
+                },
+                StreamType::Web => {}
+            }
+        }
+    }
+
+    if let Some(ref fast_match) = config.fast_match {
+        if fast_match.min_buffer_duration > config.buffer_duration {
+            warnings.push(format!("fast_match.min_buffer_duration ({}s) is longer than buffer_duration ({}s), the fast path is never used",
+                fast_match.min_buffer_duration, config.buffer_duration));
+        }
+    }
+    for maintenance in &config.maintenance_windows {
+        if let Err(e) = maintenance.window.validate() {
+            errors.push(format!("Maintenance window {}: {}", maintenance.label(), e));
+        }
+    }
+    for schedule in &config.threshold_schedules {
+        if let Err(e) = schedule.validate() {
+            errors.push(e);
+        }
+        for channel_name in schedule.channels.iter().flatten() {
+            if !config.channels.contains_key(channel_name) {
+                warnings.push(format!("Threshold schedule {} names channel {}, which is not in the config file", schedule.label(), channel_name));
+            }
+        }
+        if schedule.silence_threshold.is_some() && config.silence != SilenceDetectType::Volume {
+            warnings.push(format!("Threshold schedule {} sets a silence threshold but silence is not Volume, it is ignored", schedule.label()));
+        }
+    }
+
+    // nrsc5 silently fails to sync at any other rate
+    if let Some(ref sdrs) = config.sdrs {
+        let mut sdr_names: Vec<&String> = sdrs.keys().collect();
+        sdr_names.sort();
+        for sdr_name in sdr_names {
+            let sdr = &sdrs[sdr_name];
+            if sdr.scan.as_ref().is_some_and(|scan| scan.frequencies.is_empty()) {
+                warnings.push(format!("SDR {} has a scan schedule without frequencies, it will not scan", sdr_name));
+            }
+            let Some(ref spawn_args) = sdr.spawn else { continue };
+            let feeds_nrsc = config.channels.values()
+                .flat_map(|channel| channel.streams.values())
+                .any(|stream| stream.r#type == StreamType::NRSC && stream.host == *sdr_name);
+            if !feeds_nrsc || spawn_args.size == NRSC5_SAMPLE_RATE {
+                continue;
+            }
+            let problem = format!("SDR {} feeds NRSC streams but its size is {}; nrsc5 needs {}", sdr_name, spawn_args.size, NRSC5_SAMPLE_RATE);
+            if spawn_args.auto_correct_rate {
+                warnings.push(format!("{}, auto_correct_rate will correct it", problem));
+            } else {
+                errors.push(format!("{} (set size or auto_correct_rate: true)", problem));
+            }
+        }
+    }
+
+    (errors, warnings)
+}
+
+/// The `validate` subcommand: prints a report of the config's problems and returns the exit code
+fn validate_config(config_path: &str, format: Option<ConfigFormat>, config_dir: Option<&str>) -> i32 {
+    let mut config = match read_config(config_path, format) {
+        Ok(config) => config,
+        Err(e) => {
+            println!("ERROR    {}", e);
+            return 1;
+        }
+    };
+    if let Some(dir) = config_dir {
+        if let Err(e) = merge_config_dir(&mut config, config_path, dir) {
+            println!("ERROR    {}", e);
+            return 1;
+        }
+    }
+
+    let (errors, warnings) = check_config(&config);
+    for error in &errors {
+        println!("ERROR    {}", error);
+    }
+    for warning in &warnings {
+        println!("WARNING  {}", warning);
+    }
+    let streams: usize = config.channels.values().map(|channel| channel.streams.len()).sum();
+    println!("{}: {} channels, {} streams, {} errors, {} warnings",
+        config_path, config.channels.len(), streams, errors.len(), warnings.len());
+    if errors.is_empty() { 0 } else { 1 }
+}
+
+/// The `overrides` subcommand, working on the file directly so it also works while the watchdog is stopped
+/// Writes the example config for `watchdog init`, generated from the schema so it follows the serde defaults
+fn init_config(path: &str, format: Option<ConfigFormat>, force: bool) -> i32 {
+    if format.unwrap_or_else(|| ConfigFormat::from_path(Path::new(path))) != ConfigFormat::Yaml {
+        eprintln!("watchdog init writes YAML, pass a --config path ending in .yaml");
+        return 1;
+    }
+    if !force && Path::new(path).exists() {
+        eprintln!("{} already exists, pass --force to overwrite it", path);
+        return 1;
+    }
+    let schema = serde_json::to_value(schemars::schema_for!(Config)).expect("Could not serialize config schema");
+    match fs::write(path, utils::configexample::render(&schema)) {
+        Ok(()) => {
+            println!("Wrote an example config to {}, fill in the required fields and check it with `watchdog validate`", path);
+            0
+        }
+        Err(e) => {
+            eprintln!("Could not write {}: {}", path, e);
+            1
+        }
+    }
+}
+
+fn manage_overrides(action: &OverridesAction, file: Option<&str>, config_path: &str, format: Option<ConfigFormat>) -> i32 {
+    let path = match file {
+        Some(file) => file.to_string(),
+        None => match read_config(config_path, format).map(|config| config.overrides_file) {
+            Ok(Some(path)) => path,
+            Ok(None) => {
+                eprintln!("{} sets no overrides_file, pass --file", config_path);
+                return 1;
+            }
+            Err(e) => {
+                eprintln!("{}", e);
+                return 1;
+            }
+        },
+    };
+    let mut overrides = match RuntimeOverrides::read(&path) {
+        Ok(overrides) => overrides,
+        Err(e) => {
+            eprintln!("{}", e);
+            return 1;
+        }
+    };
+
+    match action {
+        OverridesAction::Show => {
+            println!("{}", serde_json::to_string_pretty(&overrides).expect("Could not serialize overrides"));
+            0
+        }
+        OverridesAction::Clear { thresholds, acknowledgements } => {
+            let all = !thresholds && !acknowledgements;
+            if all || *thresholds {
+                overrides.thresholds = Default::default();
+            }
+            if all || *acknowledgements {
+                overrides.acknowledged.clear();
+            }
+            match overrides.write(&path) {
+                Ok(()) => {
+                    let cleared = match (all, *thresholds, *acknowledgements) {
+                        (true, _, _) | (_, true, true) => "all overrides",
+                        (_, true, false) => "tuned thresholds",
+                        _ => "acknowledgements",
+                    };
+                    println!("Cleared {} in {}", cleared, path);
+                    0
+                }
+                Err(e) => {
+                    eprintln!("{}", e);
+                    1
+                }
+            }
+        }
+    }
+}
+
+/// Sweeps one SDR for the `scan` subcommand, bringing up its rtl_tcp or tunnel for the duration when configured
+async fn scan_sdr(config: &Config, sdr_name: &str, from: u32, to: u32, gain: Option<f32>, step: u32) -> Result<String, String> {
+    let sdr = config.sdrs.as_ref()
+        .and_then(|sdrs| sdrs.get(sdr_name))
+        .ok_or_else(|| format!("SDR {} is not in the config", sdr_name))?;
+
+    let manager = match (&sdr.spawn, &sdr.tunnel) {
+        (Some(spawn_args), _) => Some(SdrManager::new(sdr.host.clone(), sdr.port, spawn_args.frequency, spawn_args.size, spawn_args.gain)),
+        (None, Some(tunnel)) => Some(SdrManager::tunneled(sdr.host.clone(), sdr.port, tunnel.clone())),
+        (None, None) => None,
+    };
+    let mut spawned = false;
+    if let Some(ref manager) = manager {
+        match manager.spawn().await {
+            Ok(_) => spawned = true,
+            Err(e) if e.contains("already in use") => info!("{}:{} is already reachable, scanning through it", sdr.host, sdr.port),
+            Err(e) => return Err(format!("Failed to start {} for {}: {}", manager.process_name(), sdr_name, e)),
+        }
+    }
+
+    let result = utils::spectrum::sweep(&sdr.host, sdr.port, from, to, gain).await;
+    if let (true, Some(manager)) = (spawned, manager) {
+        manager.stop().await.ok();
+    }
+    Ok(utils::spectrum::report(&result?, step))
 }
 
 #[tokio::main]
 async fn main() {
     let args = Args::parse();
 
+    if let Some(Commands::Schema) = args.command {
+        let schema = schemars::schema_for!(Config);
+        println!("{}", serde_json::to_string_pretty(&schema).expect("Could not serialize config schema"));
+        return;
+    }
+    if let Some(Commands::CheckStatus { ref url, ref token }) = args.command {
+        let (code, summary) = check_status(url, token.as_deref()).await;
+        println!("{}", summary);
+        std::process::exit(code);
+    }
+    if let Some(Commands::Tail { ref url, ref token }) = args.command {
+        tail(url, token.as_deref()).await;
+    }
+    if let Some(Commands::Init { force }) = args.command {
+        std::process::exit(init_config(&args.config, args.format, force));
+    }
+    if let Some(Commands::Validate) = args.command {
+        std::process::exit(validate_config(&args.config, args.format, args.config_dir.as_deref()));
+    }
+    if let Some(Commands::Overrides { ref action, ref file }) = args.command {
+        std::process::exit(manage_overrides(action, file.as_deref(), &args.config, args.format));
+    }
+
     let subscriber_level = match std::env::var("LOGLEVEL").unwrap_or("INFO".to_string()).to_ascii_uppercase().as_str() {
         "TRACE" => Level::TRACE,
         "DEBUG" => Level::DEBUG,
@@ -118,56 +1234,179 @@ async fn main() {
 
     info!("Loading configuration from: {}", args.config);
 
-    let config_text = fs::read_to_string(&args.config);
-    if config_text.is_err() {
-        error!("Error reading config file: {}", args.config);
-        return;
-    }
-    let config: Config = match serde_yaml::from_str(&config_text.expect("Could not decode YAML to string")) {
+    let mut config = match read_config(&args.config, args.format) {
         Ok(config) => config,
         Err(e) => {
-            error!("Error parsing config.yaml: {}", e);
+            error!("{}", e);
             return;
         }
     };
 
+    if let Err(e) = utils::timefmt::set_display_timezone(config.display_timezone.as_deref()) {
+        error!("{}", e);
+        return;
+    }
+
+    if let Some(Commands::Scan { ref sdr, from, to, gain, step }) = args.command {
+        match scan_sdr(&config, sdr, from, to, gain, step).await {
+            Ok(report) => println!("{}", report),
+            Err(e) => {
+                error!("{}", e);
+                std::process::exit(1);
+            }
+        }
+        return;
+    }
+
+    // Fingerprinting already uses preset_test1, the cheapest chromaprint preset, so low power only stretches the schedule
+    if config.low_power {
+        config.volume_sample_interval *= LOW_POWER_INTERVAL_FACTOR;
+        config.probe_interval_seconds *= LOW_POWER_INTERVAL_FACTOR;
+        config.dns_watch_interval_seconds *= LOW_POWER_INTERVAL_FACTOR;
+        config.format_check_interval_seconds *= LOW_POWER_INTERVAL_FACTOR;
+        info!("Low power mode: intervals x{}, native volume analysis, comparisons every {}s capped at {} cross-channel pairs",
+              LOW_POWER_INTERVAL_FACTOR, LOW_POWER_COMPARISON_INTERVAL, LOW_POWER_MAX_CROSS_PAIRS);
+    }
+
+    // What the main file's channels looked like at startup, to tell which ones a reload changes
+    let file_channels: HashMap<String, String> = config.channels.iter()
+        .map(|(name, channel)| (name.clone(), channel_fingerprint(channel)))
+        .collect();
+
+    // Channels from the config directory are merged in as if they were in the main file
+    let mut config_dir_files = HashMap::new();
+    if let Some(ref dir) = args.config_dir {
+        config_dir_files = match merge_config_dir(&mut config, &args.config, dir) {
+            Ok(files) => files,
+            Err(e) => {
+                error!("{}", e);
+                return;
+            }
+        };
+    }
+
+    // The same checks as `watchdog validate`, so a config it rejects never half starts
+    let (errors, warnings) = check_config(&config);
+    for warning in &warnings {
+        warn!("{}", warning);
+    }
+    if !errors.is_empty() {
+        for e in &errors {
+            error!("{}", e);
+        }
+        error!("{} has {} errors, not starting", args.config, errors.len());
+        std::process::exit(1);
+    }
+
+    // check_config refused NRSC SDRs at any other rate unless they asked for auto_correct_rate
+    let nrsc_sdrs: HashSet<String> = config.channels.values()
+        .flat_map(|channel| channel.streams.values())
+        .filter(|stream| stream.r#type == StreamType::NRSC)
+        .map(|stream| stream.host.clone())
+        .collect();
+    if let Some(ref mut sdrs) = config.sdrs {
+        for (sdr_name, sdr_config) in sdrs.iter_mut() {
+            let Some(ref mut spawn_args) = sdr_config.spawn else { continue };
+            if nrsc_sdrs.contains(sdr_name) && spawn_args.auto_correct_rate {
+                spawn_args.size = NRSC5_SAMPLE_RATE;
+            }
+        }
+    }
+
     debug!("Using config: {:?}", config);
 
     // lets set up slack
-    let slack = Arc::new(SlackMessageSender::new(config.slack_auth, config.slack_channel, args.dry_run));
+    let slack_client = config.slack_http.client(DEFAULT_USER_AGENT, config.http_proxy.as_ref()).expect("slack_http was checked at startup");
+    if let Some(ref proxy) = config.http_proxy {
+        info!("Sending Slack and web stream traffic through proxy {} (bypassed for {:?})", proxy.url, proxy.no_proxy);
+    }
+    let web_fetch = WebFetch { identity: config.stream_http.clone(), proxy: config.http_proxy.clone() };
+    let delivery_stats = Arc::new(DeliveryStats::new());
+    let slack = Arc::new(SlackMessageSender::new(config.slack_auth, config.slack_channel, args.dry_run)
+        .with_client(slack_client.clone())
+        .with_delivery_stats(&delivery_stats));
+
+    // Per-stream grace periods and runbook hints, keyed by the router's stream name
+    let mut grace_overrides = HashMap::new();
+    let mut runbooks = HashMap::new();
+    for (channel_name, channel) in &config.channels {
+        for (stream_name, stream) in &channel.streams {
+            if let Some(grace) = stream.grace_period_seconds.or(channel.grace_period_seconds) {
+                grace_overrides.insert(format!("{}-{}", channel_name, stream_name), grace);
+            }
+            if let Some(runbook) = stream.runbook.as_ref().or(channel.runbook.as_ref()) {
+                runbooks.insert(format!("{}-{}", channel_name, stream_name), runbook.clone());
+            }
+        }
+    }
 
     // Set up alert manager
     let alert_manager = Arc::new(AlertManager::new(
         slack.clone(),
         10, // 10 minute reminders
         config.grace_period_seconds
-    ));
+    ).with_grace_overrides(grace_overrides)
+        .with_runbooks(runbooks)
+        .with_format(config.alert_format)
+        .with_maintenance_windows(config.maintenance_windows.clone()));
     alert_manager.clone().start_alert_loop().await;
 
+    let no_spawn = args.no_spawn || args.dry_run;
     let mut router = AudioRouter::new();
+    if no_spawn {
+        warn!("Not spawning SDR processes or respawning streams, intended actions are only logged");
+        router = router.with_no_spawn();
+    }
+    if let Some(cap_mb) = config.buffer_memory_cap_mb {
+        router = router.with_buffer_cap(cap_mb * 1024 * 1024);
+    }
+    router = router.with_dropout_config(config.dropouts.clone())
+        // Before any channel is added, streams take their default stall timeout from it
+        .with_supervisor_config(config.supervisor.clone());
+    if let Some(window) = config.volume_analysis_window {
+        router = router.with_volume_window(window);
+    }
+    // Saved buffers are loaded as streams are added, so the cache has to be in place first
+    let mut fingerprint_cache = None;
+    if let Some(ref directory) = config.fingerprint_cache_dir {
+        match FingerprintCache::open(directory) {
+            Ok(cache) => {
+                let cache = Arc::new(cache);
+                router = router.with_fingerprint_cache(cache.clone());
+                fingerprint_cache = Some(cache);
+            }
+            Err(e) => warn!("{}, streams will fill their buffers from scratch", e),
+        }
+    }
 
     info!("Configuration: buffer_duration={}s, comparison_duration={}s, min_buffer_duration={}s",
           config.buffer_duration, config.comparison_duration, config.min_buffer_duration);
     info!("Thresholds: match_threshold={:.1}%, divergence_threshold={:.1}%",
           config.match_threshold, config.divergence_threshold);
+    if !config.threshold_schedules.is_empty() {
+        info!("{} threshold schedules by time of day", config.threshold_schedules.len());
+    }
+    router.set_threshold_schedules(config.threshold_schedules.clone());
 
     // Add silence detection channel if enabled
     match config.silence {
         SilenceDetectType::Match => {
-            info!("Silence detection enabled, adding silence reference channel");
-            router.add_stream(
-                &"silence".to_string(),
-                &"silence".to_string(),
-                config.buffer_duration,
-                CommandHolder::new("ffmpeg", vec![
-                    "-loglevel", "error",
-                    "-re",
-                    "-f", "lavfi",
-                    "-i", "anullsrc=r=44100:cl=stereo",
-                    "-f", "s16le",
-                    "-"
-                ], None)
-            ).await;
+            let checked: Vec<&String> = config.channels.iter().filter(|(_, c)| c.silence_check).map(|(name, _)| name).collect();
+            if !config.silence_reference.enabled {
+                warn!("silence is Match but the silence reference is disabled, silence_check channels {:?} are not compared against anything", checked);
+            } else {
+                info!("Silence detection enabled, adding {:?} silence reference channel (checked against: {:?})", config.silence_reference.source, checked);
+                let args = config.silence_reference.ffmpeg_args().expect("silence_reference was checked at startup");
+                router.add_stream(
+                    &"silence".to_string(),
+                    &"silence".to_string(),
+                    config.buffer_duration,
+                    None,
+                    FingerprintSource::Mono,
+                    0,
+                    CommandHolder::new("silence", "ffmpeg", args.iter().map(String::as_str).collect(), None)
+                ).await;
+            }
         },
         SilenceDetectType::Volume => {
             info!("Using volume detection with level {:.1} dB warning level", config.volume_minimum_max_volume);
@@ -175,33 +1414,70 @@ async fn main() {
         SilenceDetectType::None => info!("No silence detection.")
     }
 
-    // Spawn rtl_tcp processes for SDRs that need them
+    // Spawn rtl_tcp processes, or tunnels to remote ones, for SDRs that need them
     let mut sdr_managers: HashMap<String, Arc<SdrManager>> = HashMap::new();
+    let mut failed_sdrs: HashSet<String> = HashSet::new(); // only with fail_soft, their streams then fail to start
 
     if let Some(ref sdrs) = config.sdrs {
+        let mut to_spawn = Vec::new();
         for (sdr_name, sdr_config) in sdrs {
-            if let Some(ref spawn_args) = sdr_config.spawn {
-                info!("Checking if rtl_tcp needs to be spawned for SDR {} at {}:{}", sdr_name, sdr_config.host, sdr_config.port);
-                let sdr_manager = Arc::new(SdrManager::new(
-                    sdr_config.host.clone(),
-                    sdr_config.port,
-                    spawn_args.frequency,
-                    spawn_args.size,
-                    spawn_args.gain,
-                ));
-
-                match sdr_manager.spawn().await {
-                    Ok(_) => {
-                        info!("Successfully spawned and verified rtl_tcp for {}", sdr_name);
-                        sdr_managers.insert(sdr_name.clone(), sdr_manager);
+            let sdr_manager = match (&sdr_config.spawn, &sdr_config.tunnel) {
+                (Some(_), Some(_)) => {
+                    let error = format!("SDR {} has both spawn and tunnel set; a spawned rtl_tcp is local and needs no tunnel", sdr_name);
+                    if !config.fail_soft {
+                        error!("{}", error);
+                        return;
                     }
-                    Err(e) => {
-                        if e.contains("already in use") {
-                            warn!("rtl_tcp already running for {}, continuing without spawning", sdr_name);
-                        } else {
-                            error!("Failed to spawn rtl_tcp for {}: {}", sdr_name, e);
+                    report_failed_sdr(&alert_manager, sdr_name, error).await;
+                    failed_sdrs.insert(sdr_name.clone());
+                    continue;
+                }
+                (Some(spawn_args), None) => {
+                    info!("Checking if rtl_tcp needs to be spawned for SDR {} at {}:{}", sdr_name, sdr_config.host, sdr_config.port);
+                    Arc::new(SdrManager::new(
+                        sdr_config.host.clone(),
+                        sdr_config.port,
+                        spawn_args.frequency,
+                        spawn_args.size,
+                        spawn_args.gain,
+                    ))
+                }
+                (None, Some(tunnel)) => {
+                    info!("Checking if a tunnel needs to be opened for SDR {} at {}:{}", sdr_name, sdr_config.host, sdr_config.port);
+                    Arc::new(SdrManager::tunneled(sdr_config.host.clone(), sdr_config.port, tunnel.clone()))
+                }
+                (None, None) => continue,
+            };
+            if no_spawn {
+                info!("Would start {} for {} at {}:{}", sdr_manager.process_name(), sdr_name, sdr_config.host, sdr_config.port);
+                continue;
+            }
+            to_spawn.push((sdr_name, sdr_config, sdr_manager));
+        }
+
+        // Each spawn retries with sleeps, so they run side by side rather than one after another
+        let steps = to_spawn.iter()
+            .map(|(sdr_name, _, sdr_manager)| (sdr_name.to_string(), sdr_manager.spawn()))
+            .collect();
+        let results = run_startup_steps("SDR", steps, Some(std::time::Duration::from_secs(STARTUP_STEP_TIMEOUT_SECONDS))).await;
+        for ((sdr_name, sdr_config, sdr_manager), result) in to_spawn.into_iter().zip(results) {
+            match result {
+                Ok(_) => {
+                    info!("Successfully spawned and verified {} for {}", sdr_manager.process_name(), sdr_name);
+                    sdr_managers.insert(sdr_name.clone(), sdr_manager);
+                }
+                Err(e) => {
+                    if e.contains("already in use") {
+                        warn!("{}:{} is already reachable for {}, continuing without spawning {}",
+                            sdr_config.host, sdr_config.port, sdr_name, sdr_manager.process_name());
+                    } else {
+                        let error = format!("Failed to spawn {} for {}: {}", sdr_manager.process_name(), sdr_name, e);
+                        if !config.fail_soft {
+                            error!("{}", error);
                             return;
                         }
+                        report_failed_sdr(&alert_manager, sdr_name, error).await;
+                        failed_sdrs.insert(sdr_name.clone());
                     }
                 }
             }
@@ -212,127 +1488,525 @@ async fn main() {
     let mut nrsc_managers: HashMap<String, Arc<NrscManager>> = HashMap::new();
 
     if let Some(ref sdrs) = config.sdrs {
+        let mut to_start = Vec::new();
         for (sdr_name, sdr_config) in sdrs {
+            if no_spawn {
+                info!("Would start NRSC decoding from SDR {} at {}:{}", sdr_name, sdr_config.host, sdr_config.port);
+                continue;
+            }
+            if failed_sdrs.contains(sdr_name) {
+                continue;
+            }
             info!("Initializing NRSC manager for SDR {} at {}:{}", sdr_name, sdr_config.host, sdr_config.port);
-            let nrsc_manager = Arc::new(NrscManager::new(sdr_config.host.clone(), sdr_config.port));
-            if let Err(e) = nrsc_manager.start().await {
-                error!("Failed to start NRSC manager for {}: {}", sdr_name, e);
-                return;
+            let nrsc_manager = Arc::new(NrscManager::new(sdr_name, sdr_config.host.clone(), sdr_config.port)
+                .with_alert_manager(alert_manager.clone()));
+            to_start.push((sdr_name, nrsc_manager));
+        }
+
+        let steps = to_start.iter()
+            .map(|(sdr_name, nrsc_manager)| (sdr_name.to_string(), async move { nrsc_manager.start().await.map_err(|e| e.to_string()) }))
+            .collect();
+        let results = run_startup_steps("NRSC", steps, Some(std::time::Duration::from_secs(STARTUP_STEP_TIMEOUT_SECONDS))).await;
+        for ((sdr_name, nrsc_manager), result) in to_start.into_iter().zip(results) {
+            if let Err(e) = result {
+                let error = format!("Failed to start NRSC manager for {}: {}", sdr_name, e);
+                if !config.fail_soft {
+                    error!("{}", error);
+                    return;
+                }
+                report_failed_sdr(&alert_manager, sdr_name, error).await;
+                continue;
             }
             nrsc_managers.insert(sdr_name.clone(), nrsc_manager);
         }
     }
 
-    // we need to do some sanity checks
-    for channel in config.channels {
-        for stream in channel.1.streams {
-            match stream.1.r#type {
-                StreamType::FM => {
-                    error!("FM stream type is not currently supported");
-                },
-                StreamType::NRSC => {
-                    match config.sdrs {
-                        None => {
-                            error!("Channel {} stream {} needs an SDR yet none are defined!", channel.0, stream.0);
-                            return;
-                        }
-                        Some(ref sdrs) => match sdrs.get(&stream.1.host) {
-                            None => {
-                                error!("Channel {} stream {} needs an SDR yet {} is not defined!", channel.0, stream.0, stream.1.host);
-                                return;
-                            }
-                            Some(_sdr) => {
-                                let stream_name = format!("{}-{}", channel.0, stream.0);
-                                debug!("Adding NRSC stream {} for program {} via SDR {}", stream_name, stream.1.path, stream.1.host);
-
-                                // Get the NRSC manager for this SDR
-                                if let Some(manager) = nrsc_managers.get(&stream.1.host) {
-                                    // Add program to the manager and get the output receiver
-                                    match manager.add_program(&stream.1.path).await {
-                                        Ok(receiver) => {
-                                            // Create a CommandHolder that uses the NRSC output
-                                            // We pipe this into ffmpeg to ensure proper audio format
-                                            router.add_stream(
-                                                &stream_name,
-                                                &channel.0,
-                                                config.buffer_duration,
-                                                CommandHolder::new("ffmpeg", vec![
-                                                    "-loglevel", "error",
-                                                    "-f", "s16le",
-                                                    "-ar", "44100",
-                                                    "-ac", "2",
-                                                    "-i", "-",
-                                                    "-ar", "44100",
-                                                    "-ac", "2",
-                                                    "-f", "s16le",
-                                                    "-"
-                                                ], Some(receiver))
-                                            ).await;
-                                            info!("Added NRSC stream {} successfully", stream_name);
-                                        }
-                                        Err(e) => {
-                                            error!("Failed to add NRSC program {} for stream {}: {}", stream.1.path, stream_name, e);
-                                            return;
-                                        }
-                                    }
-                                } else {
-                                    error!("NRSC manager not found for SDR {}", stream.1.host);
-                                    return;
-                                }
-                            }
-                        }
+    // Streams are started one priority tier at a time, highest first, so the main feeds
+    // buffer before the confidence feeds start competing for CPU and bandwidth
+    let mut targets = StreamTargets::default();
+    let beacon_configs: Vec<(String, BeaconConfig)> = config.channels.iter()
+        .filter_map(|(name, channel)| channel.beacons.clone().map(|beacons| (name.clone(), beacons)))
+        .collect();
+    let mut priorities: Vec<i32> = config.channels.values()
+        .flat_map(|channel| channel.streams.values().map(|stream| stream.priority))
+        .collect();
+    priorities.sort_unstable_by(|a, b| b.cmp(a));
+    priorities.dedup();
+    for (tier, priority) in priorities.iter().enumerate() {
+        let mut tier_streams = Vec::new();
+        let mut subsets = Vec::new();
+        for (channel_name, channel) in &config.channels {
+            let subset = Channel {
+                streams: channel.streams.iter()
+                    .filter(|(_, stream)| stream.priority == *priority)
+                    .map(|(name, stream)| (name.clone(), stream.clone()))
+                    .collect(),
+                beacons: None,
+                ..channel.clone()
+            };
+            if subset.streams.is_empty() {
+                continue;
+            }
+            subsets.push((channel_name, subset));
+        }
+
+        // The channels of a tier start together; slow NRSC programs are bounded inside add_channel
+        let channel_names: Vec<&String> = subsets.iter().map(|(channel_name, _)| *channel_name).collect();
+        let (buffer_duration, grace_period) = (config.buffer_duration, config.grace_period_seconds);
+        let steps = subsets.into_iter().map(|(channel_name, subset)| {
+            let (router, sdrs, nrsc_managers, sdr_managers, web_fetch) = (&router, &config.sdrs, &nrsc_managers, &sdr_managers, &web_fetch);
+            (channel_name.clone(), async move {
+                let mut channel_targets = StreamTargets::default();
+                let added = add_channel(router, channel_name, subset, buffer_duration, grace_period, sdrs, nrsc_managers, sdr_managers, web_fetch, &mut channel_targets).await;
+                Ok::<_, String>((added, channel_targets))
+            })
+        }).collect();
+        let results = run_startup_steps(&format!("priority {} channel", priority), steps, None).await;
+        for (channel_name, result) in channel_names.into_iter().zip(results) {
+            let Ok((added, mut channel_targets)) = result else { continue };
+            tier_streams.extend(added);
+            if !channel_targets.failed.is_empty() {
+                if !config.fail_soft {
+                    for (_, error) in &channel_targets.failed {
+                        error!("{}", error);
                     }
-                },
-                StreamType::Web => {
-                    let stream_name = format!("{}-{}", channel.0, stream.0);
-                    let url = format!("{}/{}", stream.1.host, stream.1.path);
-                    debug!("Adding web stream {} for {}", stream_name, url);
-                    router.add_stream(&stream_name, &channel.0, config.buffer_duration, CommandHolder::new("ffmpeg", vec![
-                        "-loglevel", "error",
-                        "-re",
-                        "-i", &url,
-                        "-ar", "44100",
-                        "-ac", "2",
-                        "-f", "s16le",
-                        "-"
-                    ], None)).await;
+                    return;
                 }
+                report_failed_streams(&router, &alert_manager, channel_name, std::mem::take(&mut channel_targets.failed)).await;
+            }
+            targets.merge(channel_targets);
+        }
+
+        if tier + 1 < priorities.len() && !tier_streams.is_empty() {
+            info!("Waiting for {} priority {} streams to buffer before starting lower priorities", tier_streams.len(), priority);
+            if !router.wait_for_audio(&tier_streams, std::time::Duration::from_secs(PRIORITY_TIER_WAIT_SECONDS)).await {
+                warn!("Priority {} streams not all producing audio after {}s, continuing", priority, PRIORITY_TIER_WAIT_SECONDS);
+            }
+        }
+    }
+
+    // Dead air is counted against the volume threshold regardless of the silence detection mode
+    let dead_air = Arc::new(DeadAirTracker::new(
+        config.volume_minimum_max_volume,
+        config.dead_air_budget_seconds,
+        alert_manager.clone()
+    ));
+    let mut router = router
+        .with_alert_manager(alert_manager.clone())
+        .with_dead_air_tracker(dead_air);
+
+    // Volume alerts are only wired in Volume mode. In Match mode silence already shows up as a
+    // silence check against the reference channel, so wiring both would double-alert.
+    if config.silence == SilenceDetectType::Volume {
+        info!("Wiring alert manager into volume detection (minimum max volume {:.1} dB)", config.volume_minimum_max_volume);
+        router = router.with_silence_threshold(config.volume_minimum_max_volume);
+    }
+
+    if config.stereo_detection {
+        info!("Stereo channel-swap and dead-channel detection enabled");
+        router = router.with_stereo_detection();
+    }
+
+    if config.low_power {
+        router = router.with_native_volume().with_volume_concurrency(1);
+    }
+
+    let mut uptime_store = None;
+    if let Some(ref path) = config.stats_database {
+        match UptimeStore::open(path) {
+            Ok(store) => {
+                let store = Arc::new(store);
+                router = router.with_uptime_store(store.clone());
+                uptime_store = Some(store);
             }
+            Err(e) => warn!("{}, uptime will reset with the watchdog", e),
         }
     }
 
     // Convert router to Arc for sharing across tasks
-    let router = if config.silence == SilenceDetectType::Volume {
-        Arc::new(router.with_alert_manager(alert_manager.clone(), config.volume_minimum_max_volume))
-    } else {
-        Arc::new(router)
-    };
+    let router = Arc::new(router);
+
+    // Runtime overrides from before a restart apply over the config from the first pass on
+    let mut tuner = ThresholdTuner::new(router.clone());
+    if let Some(ref path) = config.overrides_file {
+        let store = Arc::new(OverridesStore::new(path, router.clone(), alert_manager.clone()));
+        store.restore().await;
+        store.clone().start_save_loop().await;
+        tuner = tuner.with_store(store);
+    }
+    let tuner = Arc::new(tuner);
 
     // Start the supervisor to monitor stream health
     info!("Starting AudioRouter supervisor");
     router.start_supervisor().await;
+    if let Some(store) = uptime_store {
+        store.start_flush_loop().await;
+    }
+    if let Some(cache) = fingerprint_cache {
+        cache.start_save_loop(router.clone()).await;
+    }
 
     // Start the volume detection loop
     info!("Starting volume detection loop");
-    router.start_volume_detection_loop(config.volume_detection_interval).await;
+    router.start_volume_detection_loop(config.volume_sample_interval).await;
+
+    // Start frequency scanning for SDRs shared between stations
+    if let Some(ref sdrs) = config.sdrs {
+        for (sdr_name, sdr_config) in sdrs {
+            let Some(ref scan) = sdr_config.scan else { continue };
+            if scan.frequencies.is_empty() {
+                warn!("SDR {} has a scan schedule without frequencies, not scanning", sdr_name);
+                continue;
+            }
+            let Some(nrsc_manager) = nrsc_managers.get(sdr_name) else { continue };
+            let members: Vec<(u32, String)> = targets.scan_members.iter()
+                .filter(|(sdr, _, _)| sdr == sdr_name)
+                .map(|(_, frequency, stream_name)| (*frequency, stream_name.clone()))
+                .collect();
+            SdrScanner::new(sdr_name.clone(), nrsc_manager.clone(), router.clone(), scan.clone(), members)
+                .start_scan_loop().await;
+        }
+
+        // Start gain control for SDRs that asked for it
+        for (sdr_name, sdr_config) in sdrs {
+            let Some(ref gain_control) = sdr_config.gain_control else { continue };
+            if gain_control.min_gain > gain_control.max_gain {
+                warn!("SDR {} gain control has min_gain above max_gain, not adjusting gain", sdr_name);
+                continue;
+            }
+            let Some(nrsc_manager) = nrsc_managers.get(sdr_name) else {
+                warn!("SDR {} has gain control but no NRSC manager, not adjusting gain", sdr_name);
+                continue;
+            };
+            let initial_gain = sdr_config.spawn.as_ref().map(|s| s.gain);
+            GainController::new(sdr_name.clone(), nrsc_manager.clone(), gain_control.clone(), initial_gain)
+                .start_control_loop().await;
+        }
+    }
 
     // Start the comparator to check stream similarity
     info!("Starting StreamComparator");
-    let comparator = StreamComparator::new(
+    let mut comparator = StreamComparator::new(
         router.clone(),
         config.comparison_duration,
         config.min_buffer_duration,
         config.match_threshold,
         config.divergence_threshold
-    ).with_alert_manager(alert_manager.clone());
+    ).with_alert_manager(alert_manager.clone())
+        .with_collision_whitelist(config.collision_whitelist.clone());
+    if let Some(ref fast_match) = config.fast_match {
+        info!("Matching buffers of {}s and longer on the fast path, checking every {} against rusty_chromaprint",
+            fast_match.min_buffer_duration, fast_match.verify_every);
+        comparator = comparator.with_fast_match(fast_match.clone());
+    }
+    if config.low_power {
+        comparator = comparator
+            .with_interval(LOW_POWER_COMPARISON_INTERVAL)
+            .with_max_cross_pairs(LOW_POWER_MAX_CROSS_PAIRS);
+    }
     comparator.start_comparison_loop().await;
+    let comparator = Arc::new(comparator);
 
-    // Start the web server
-    info!("Starting web server on port {}", config.web_port);
-    let web_server = WebServer::new(router.clone(), comparator.get_results());
-    tokio::spawn(async move {
-        web_server.start(config.web_port).await;
-    });
+    // Focused comparisons of one pair on request, from Slack or the API
+    let pinner = Arc::new(PairPinner::new(router.clone(), slack.clone(), config.match_threshold));
+
+    let mut web_server = WebServer::new(router.clone(), comparator.get_results())
+        .with_min_buffer_size(comparator.get_min_buffer_size())
+        .with_comparison_history(comparator.get_history())
+        .with_alert_manager(alert_manager.clone())
+        .with_nrsc_managers(nrsc_managers.clone())
+        .with_comparison_counters(comparator.get_counters())
+        .with_slack_sender(slack.clone())
+        .with_delivery_stats(delivery_stats.clone())
+        .with_pinner(pinner.clone())
+        .with_tuner(tuner.clone())
+        .with_comparator(comparator.clone());
+    if let Some(ref statsd) = config.statsd {
+        let emitter = Arc::new(StatsdEmitter::new(statsd.clone(), router.clone(), comparator.get_results())
+            .with_comparison_counters(comparator.get_counters())
+            .with_alert_manager(alert_manager.clone())
+            .with_slack_sender(slack.clone())
+            .with_delivery_stats(delivery_stats.clone()));
+        emitter.start_emit_loop().await;
+    }
+    if config.frozen_after_seconds > 0 {
+        let detector = Arc::new(FrozenDetector::new(router.clone(), std::time::Duration::from_secs(config.frozen_after_seconds))
+            .with_alert_manager(alert_manager.clone()));
+        detector.start_check_loop().await;
+    }
+    if let Some(ref health_webhook) = config.health_webhook {
+        Arc::new(HealthWebhook::new(health_webhook.clone(), router.clone())
+            .with_delivery_stats(&delivery_stats)).start_watch_loop().await;
+    }
+    if config.delivery_failure_alert > 0 {
+        delivery_stats.clone().start_alert_loop(alert_manager.clone(), config.delivery_failure_alert).await;
+    }
+    if let Some(ref pushgateway) = config.pushgateway {
+        web_server = web_server.with_pushgateway(pushgateway.clone());
+    }
+    if let Some(ref base_path) = config.web_base_path {
+        web_server = web_server.with_base_path(base_path);
+    }
+    if !config.web_tokens.is_empty() || !config.web_users.is_empty() {
+        info!("Web control endpoints require a role token or login ({} tokens, {} users configured)", config.web_tokens.len(), config.web_users.len());
+        web_server = web_server.with_web_auth(config.web_tokens.clone(), config.web_users.clone());
+    }
+    if config.api_requires_token && config.web_tokens.is_empty() && config.web_users.is_empty() {
+        warn!("api_requires_token is set but no web_tokens or web_users are configured, every /api/*, /graphql and /metrics request will be refused");
+    }
+    web_server = web_server.with_api_access(config.cors_origins.clone(), config.api_requires_token)
+        .with_branding(config.branding.clone());
+    if config.fault_injection {
+        warn!("Fault injection is enabled, anyone reaching the web server can kill, mute or delay streams");
+        web_server = web_server.with_fault_injection();
+    }
+
+    // Start HTTP probing of web stream origins
+    if !targets.probes.is_empty() {
+        let prober = Arc::new(StreamProber::new(targets.probes, config.probe_interval_seconds)
+            .with_alert_manager(alert_manager.clone()));
+        prober.clone().start_probe_loop().await;
+        web_server = web_server.with_prober(prober);
+    }
+
+    // Start DNS watching of web stream origins
+    if !targets.dns.is_empty() {
+        let dns_watcher = Arc::new(DnsWatcher::new(targets.dns, config.dns_watch_interval_seconds)
+            .with_alert_manager(alert_manager.clone()));
+        dns_watcher.clone().start_watch_loop().await;
+        web_server = web_server.with_dns_watcher(dns_watcher);
+    }
+
+    // Start codec/format verification of web streams
+    if !targets.formats.is_empty() {
+        let format_checker = Arc::new(FormatChecker::new(targets.formats, config.format_check_interval_seconds)
+            .with_alert_manager(alert_manager.clone()));
+        format_checker.clone().start_check_loop().await;
+        web_server = web_server.with_format_checker(format_checker);
+    }
+
+    // Watch decoded HD Radio audio for decoder glitches
+    if let Some(artifact_config) = config.artifacts.as_ref().filter(|_| !targets.decoded.is_empty()) {
+        let detector = Arc::new(ArtifactDetector::new(artifact_config.clone())
+            .with_alert_manager(alert_manager.clone()));
+        for stream_name in &targets.decoded {
+            if let Some(reader) = router.get_stream_reader(stream_name).await {
+                detector.watch(stream_name.clone(), reader).await;
+            }
+        }
+        detector.clone().start_alert_loop().await;
+        web_server = web_server.with_artifact_detector(detector);
+    }
+
+    // Check the system clock against NTP at startup and periodically
+    if config.time_sync.enabled {
+        let time_sync = Arc::new(TimeSyncChecker::new(config.time_sync.clone())
+            .with_alert_manager(alert_manager.clone()));
+        time_sync.clone().start_check_loop().await;
+        web_server = web_server.with_time_sync(time_sync);
+    }
+
+    // Reload the main config file on SIGHUP or when it changes on disk. Added channels are started,
+    // removed ones torn down and changed ones re-created, and comparison thresholds are applied in
+    // place along with their time of day schedules; SDRs and everything else still need a restart
+    {
+        let path = args.config.clone();
+        let format = args.format;
+        let mut file_channels = file_channels;
+        let router = router.clone();
+        let alert_manager = alert_manager.clone();
+        let comparator = comparator.clone();
+        let sdrs = config.sdrs.clone();
+        let nrsc_managers = nrsc_managers.clone();
+        let sdr_managers = sdr_managers.clone();
+        tokio::spawn(async move {
+            let mut hangup = match tokio::signal::unix::signal(tokio::signal::unix::SignalKind::hangup()) {
+                Ok(hangup) => Some(hangup),
+                Err(e) => {
+                    warn!("Could not listen for SIGHUP, config reloads only follow file changes: {}", e);
+                    None
+                }
+            };
+            let modified = |path: &str| fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+            let mut last_modified = modified(&path);
+            let mut poll = tokio::time::interval(std::time::Duration::from_secs(CONFIG_DIR_POLL_SECONDS));
+            loop {
+                tokio::select! {
+                    _ = async {
+                        match hangup.as_mut() {
+                            Some(hangup) => { hangup.recv().await; }
+                            None => std::future::pending::<()>().await,
+                        }
+                    } => info!("SIGHUP received, reloading {}", path),
+                    _ = poll.tick() => {
+                        if modified(&path) == last_modified {
+                            continue;
+                        }
+                        info!("{} changed, reloading", path);
+                    }
+                }
+                last_modified = modified(&path);
+
+                let new_config = match read_config(&path, format) {
+                    Ok(new_config) => new_config,
+                    Err(e) => {
+                        warn!("Keeping the running configuration: {}", e);
+                        continue;
+                    }
+                };
+                comparator.set_thresholds(new_config.match_threshold, new_config.divergence_threshold);
+                router.set_threshold_schedules(new_config.threshold_schedules.clone());
+
+                let wanted: HashMap<String, String> = new_config.channels.iter()
+                    .map(|(name, channel)| (name.clone(), channel_fingerprint(channel)))
+                    .collect();
+                let stale: Vec<String> = file_channels.iter()
+                    .filter(|(name, fingerprint)| wanted.get(*name) != Some(*fingerprint))
+                    .map(|(name, _)| name.clone())
+                    .collect();
+                for channel_name in stale {
+                    file_channels.remove(&channel_name);
+                    let streams = router.remove_channel(&channel_name).await;
+                    release_nrsc_programs(&nrsc_managers, &streams).await;
+                    alert_manager.forget_streams(&streams).await;
+                    info!("Reload: stopped channel {} ({} streams)", channel_name, streams.len());
+                }
+
+                for (channel_name, channel) in new_config.channels {
+                    if file_channels.contains_key(&channel_name) {
+                        continue;
+                    }
+                    if router.get_channel_streams(&channel_name).is_some() {
+                        warn!("Reload: channel {} already exists from the config directory, ignoring it in {}", channel_name, path);
+                        continue;
+                    }
+                    for (name, stream) in &channel.streams {
+                        let grace = stream.grace_period_seconds.or(channel.grace_period_seconds);
+                        alert_manager.set_grace_override(&format!("{}-{}", channel_name, name), grace).await;
+                        let runbook = stream.runbook.clone().or(channel.runbook.clone());
+                        alert_manager.set_runbook(&format!("{}-{}", channel_name, name), runbook).await;
+                    }
+                    let fingerprint = channel_fingerprint(&channel);
+                    let beacons_requested = channel.beacons.is_some();
+                    let mut targets = StreamTargets::default();
+                    let streams = add_channel(&router, &channel_name, channel, new_config.buffer_duration, new_config.grace_period_seconds, &sdrs, &nrsc_managers, &sdr_managers, &WebFetch { identity: new_config.stream_http.clone(), proxy: new_config.http_proxy.clone() }, &mut targets).await;
+                    info!("Reload: started channel {} ({} streams)", channel_name, streams.len());
+                    report_failed_streams(&router, &alert_manager, &channel_name, std::mem::take(&mut targets.failed)).await;
+                    if !targets.is_empty() || beacons_requested {
+                        warn!("Channel {} asks for probe, DNS, format, beacon, scan or artifact handling, which only run for channels present at startup", channel_name);
+                    }
+                    file_channels.insert(channel_name, fingerprint);
+                }
+            }
+        });
+    }
+
+    // Hot-add and remove channels as files appear in and disappear from the config directory
+    if let Some(dir) = args.config_dir {
+        let mut events = ConfigDirWatcher::new(dir.as_str(), CONFIG_DIR_POLL_SECONDS).start_watch_loop(config_dir_files.clone());
+        let mut owned: HashSet<String> = config_dir_files.into_keys().collect();
+        let router = router.clone();
+        let alert_manager = alert_manager.clone();
+        let sdrs = config.sdrs.clone();
+        let web_fetch = web_fetch.clone();
+        let buffer_duration = config.buffer_duration;
+        let default_grace_period = config.grace_period_seconds;
+        tokio::spawn(async move {
+            while let Some(event) = events.recv().await {
+                match event {
+                    ConfigDirEvent::Added { channel: channel_name, path } => {
+                        if router.get_channel_streams(&channel_name).is_some() {
+                            warn!("Channel {} from {} already exists, ignoring the file", channel_name, path.display());
+                            continue;
+                        }
+                        let channel = match load_channel_file(&path) {
+                            Ok(channel) => channel,
+                            Err(e) => {
+                                warn!("{}", e);
+                                continue;
+                            }
+                        };
+                        let beacons_requested = channel.beacons.is_some();
+                        for (name, stream) in &channel.streams {
+                            let grace = stream.grace_period_seconds.or(channel.grace_period_seconds);
+                            alert_manager.set_grace_override(&format!("{}-{}", channel_name, name), grace).await;
+                            let runbook = stream.runbook.clone().or(channel.runbook.clone());
+                            alert_manager.set_runbook(&format!("{}-{}", channel_name, name), runbook).await;
+                        }
+
+                        owned.insert(channel_name.clone());
+                        let mut targets = StreamTargets::default();
+                        let streams = add_channel(&router, &channel_name, channel, buffer_duration, default_grace_period, &sdrs, &nrsc_managers, &sdr_managers, &web_fetch, &mut targets).await;
+                        info!("Hot-added channel {} from {} ({} streams)", channel_name, path.display(), streams.len());
+                        // A running watchdog never exits over a new file, hot-added channels are always fail-soft
+                        report_failed_streams(&router, &alert_manager, &channel_name, std::mem::take(&mut targets.failed)).await;
+                        if !targets.is_empty() || beacons_requested {
+                            warn!("Channel {} asks for probe, DNS, format, beacon, scan or artifact handling, which only run for channels present at startup", channel_name);
+                        }
+                    }
+                    ConfigDirEvent::Removed { channel: channel_name } => {
+                        // Only channels that came from the directory can be removed through it
+                        if !owned.remove(&channel_name) {
+                            continue;
+                        }
+                        let streams = router.remove_channel(&channel_name).await;
+                        release_nrsc_programs(&nrsc_managers, &streams).await;
+                        alert_manager.forget_streams(&streams).await;
+                    }
+                }
+            }
+        });
+    }
+
+    // Load station beacons and start watching for them
+    let mut beacon_targets = Vec::new();
+    for (channel_name, beacon_config) in &beacon_configs {
+        let mut beacons = Vec::new();
+        for file in &beacon_config.files {
+            match Beacon::load(file).await {
+                Ok(beacon) => beacons.push(beacon),
+                Err(e) => error!("Channel {}: {}", channel_name, e),
+            }
+        }
+        if beacons.is_empty() {
+            warn!("Channel {} has no usable beacons, not watching it", channel_name);
+            continue;
+        }
+        beacon_targets.push(BeaconTarget {
+            channel: channel_name.clone(),
+            beacons,
+            period_minutes: beacon_config.period_minutes,
+        });
+    }
+    if !beacon_targets.is_empty() {
+        let beacon_watcher = Arc::new(BeaconWatcher::new(router.clone(), beacon_targets, config.buffer_duration)
+            .with_alert_manager(alert_manager.clone()));
+        beacon_watcher.start_watch_loop().await;
+    }
+
+    // Start the recording scheduler
+    if let Some(recordings) = config.recordings {
+        let recorder = Arc::new(RecordingScheduler::new(
+            router.clone(),
+            recordings.directory,
+            recordings.retention_days,
+            recordings.schedules
+        ));
+        recorder.clone().start_schedule_loop().await;
+        web_server = web_server.with_recorder(recorder);
+    }
+
+    let email = config.email.map(|email_config| Arc::new(EmailSender::new(email_config, args.dry_run).with_delivery_stats(&delivery_stats)));
+
+    // Start the weekly availability report
+    match (config.weekly_report, email.clone()) {
+        (Some(report_config), Some(email)) => {
+            WeeklyReporter::new(router.clone(), alert_manager.clone(), email, report_config)
+                .start_report_loop().await;
+        }
+        (Some(_), None) => warn!("weekly_report is configured but email is not, no report will be sent"),
+        _ => {}
+    }
 
     // Start the Slack listener if app token is provided
     if let Some(app_token) = config.slack_app_token {
@@ -346,8 +2020,23 @@ async fn main() {
             bot_user_id,
             slack.clone(),
             router.clone(),
+            alert_manager.clone(),
             args.dry_run
-        );
+        ).with_pinner(pinner.clone())
+            .with_tuner(tuner.clone())
+            .with_comparator(comparator.clone());
+        slack_listener = slack_listener.with_client(slack_client);
+        if let Some(email) = email {
+            slack_listener = slack_listener.with_email(email);
+        }
+        if let Some(proxy) = config.http_proxy.clone() {
+            slack_listener = slack_listener.with_proxy(proxy);
+        }
+        // The tab only exists when Home is enabled in the app's settings and it is subscribed to app_home_opened
+        let home = Arc::new(HomeTab::new(slack.clone(), router.clone(), alert_manager.clone()));
+        home.clone().start_refresh_loop().await;
+        slack_listener = slack_listener.with_home_tab(home);
+        web_server = web_server.with_slack_listener_state(slack_listener.get_state());
         tokio::spawn(async move {
             slack_listener.start().await;
         });
@@ -355,9 +2044,16 @@ async fn main() {
         info!("Slack Socket Mode disabled (no app token provided)");
     }
 
+    // Start the web server
+    let web_address = SocketAddr::new(config.web_bind_address, config.web_port);
+    info!("Starting web server on {}", web_address);
+    tokio::spawn(async move {
+        web_server.start(web_address).await;
+    });
+
     // Keep the application running
     info!("Watchdog is now running. Press Ctrl+C to stop.");
-    info!("Web interface available at http://localhost:{}", config.web_port);
+    info!("Web interface available at http://{}{}", web_address, config.web_base_path.as_deref().unwrap_or(""));
     tokio::signal::ctrl_c().await.expect("Failed to listen for Ctrl+C");
     info!("Shutting down...");
 }
@@ -1,9 +1,134 @@
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::sync::Arc;
-use chrono::{DateTime, Duration, Utc};
+use chrono::{DateTime, Duration, Local, Utc};
+use schemars::JsonSchema;
+use serde::Deserialize;
 use tokio::sync::RwLock;
-use tracing::{info, warn, error};
+use tracing::{debug, info, warn, error};
+use watchdog::api::{ActiveAlert, AlertEvent, AlertReason, AlertTransition, MaintenanceStatus};
 use super::slack::SlackMessageSender;
+use super::timefmt;
+use super::weekly::{self, WeeklyWindow};
+
+// Alert transitions kept for history export
+const ALERT_HISTORY_DAYS: i64 = 8;
+// Compact summaries name at most this many streams, and are cut to this many characters
+const COMPACT_MAX_STREAMS: usize = 4;
+const COMPACT_MAX_CHARS: usize = 120;
+// How long maintenance started by hand lasts when no duration is given
+pub const DEFAULT_MAINTENANCE_MINUTES: i64 = 60;
+const MAX_MAINTENANCE_MINUTES: i64 = 7 * 24 * 60;
+
+/// How aggregated alerts are laid out in Slack
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, JsonSchema)]
+pub enum AlertFormat {
+    /// every alert's whole message in one post
+    #[default]
+    Full,
+    /// a one-line summary with a status emoji, the whole messages in its thread; readable in mobile notifications
+    Compact,
+}
+
+/// Recurring maintenance, e.g. weekly transmitter work, during which new alerts and reminders are held
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct MaintenanceWindow {
+    /// shown in Slack and the API, the time window when unset
+    pub name: Option<String>,
+    /// days, start and end
+    #[serde(flatten)]
+    pub window: WeeklyWindow,
+}
+
+impl MaintenanceWindow {
+    pub fn label(&self) -> String {
+        match self.name {
+            Some(ref name) => name.clone(),
+            None => self.window.describe(),
+        }
+    }
+}
+
+/// Maintenance started or ended by hand, overriding the windows until `until`
+#[derive(Debug, Clone)]
+struct MaintenanceToggle {
+    active: bool,
+    until: DateTime<Utc>,
+    by: String,
+}
+
+/// One kind of aggregated alert post
+#[derive(Clone, Copy)]
+enum Batch {
+    NewFailures,
+    Clears,
+    Reminders,
+}
+
+impl Batch {
+    fn full_header(&self, count: usize) -> String {
+        match (self, count) {
+            (Batch::NewFailures, 1) => "*Warning:* _A new issue has been detected!_".to_string(),
+            (Batch::NewFailures, n) => format!("*Warning:* _{} new issues detected!_", n),
+            (Batch::Clears, 1) => "*Success:* _Issue resolved!_".to_string(),
+            (Batch::Clears, n) => format!("*Success:* _{} issues resolved!_", n),
+            (Batch::Reminders, 1) => "*Reminder:* _Issue is still present!_".to_string(),
+            (Batch::Reminders, n) => format!("*Reminder:* _{} issues still present!_", n),
+        }
+    }
+
+    fn emoji(&self) -> &'static str {
+        match self {
+            Batch::NewFailures => ":red_circle:",
+            Batch::Clears => ":large_green_circle:",
+            Batch::Reminders => ":large_yellow_circle:",
+        }
+    }
+
+    fn compact_label(&self, count: usize) -> String {
+        match self {
+            Batch::NewFailures => format!("{} new issues", count),
+            Batch::Clears => format!("{} issues resolved", count),
+            Batch::Reminders => format!("{} issues still present", count),
+        }
+    }
+}
+
+/// The one-line summary of a batch of alerts, each given as (message, streams)
+fn compact_summary(batch: Batch, alerts: &[(String, Vec<String>)]) -> String {
+    let summary = match alerts {
+        [(message, _)] => message.lines().next().unwrap_or_default().to_string(),
+        _ => {
+            let mut streams: Vec<&str> = Vec::new();
+            for stream in alerts.iter().flat_map(|(_, streams)| streams) {
+                if !streams.contains(&stream.as_str()) {
+                    streams.push(stream);
+                }
+            }
+            let mut named: Vec<String> = streams.iter().take(COMPACT_MAX_STREAMS).map(|s| format!("`{}`", s)).collect();
+            if streams.len() > COMPACT_MAX_STREAMS {
+                named.push(format!("+{} more", streams.len() - COMPACT_MAX_STREAMS));
+            }
+            if named.is_empty() {
+                batch.compact_label(alerts.len())
+            } else {
+                format!("{}: {}", batch.compact_label(alerts.len()), named.join(", "))
+            }
+        }
+    };
+    let summary = match summary.char_indices().nth(COMPACT_MAX_CHARS) {
+        Some((cut, _)) => format!("{}…", &summary[..cut]),
+        None => summary,
+    };
+    format!("{} {}", batch.emoji(), summary)
+}
+
+fn numbered(alerts: &[(String, Vec<String>)]) -> String {
+    alerts.iter()
+        .enumerate()
+        .map(|(i, (message, _))| format!("{}. {}", i + 1, message))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum AlertState {
@@ -25,20 +150,30 @@ enum PendingAggregation {
 #[derive(Debug, Clone)]
 pub struct Alert {
     pub name: String,
+    pub reason: AlertReason,
     pub message: String,
     failing_since: Option<DateTime<Utc>>,
     last_sent_update: Option<DateTime<Utc>>,
     pending_aggregation: PendingAggregation,
+    grace_period_seconds: Option<i64>, // per-stream override, otherwise the manager's global value
+    streams: Vec<String>, // streams the alert concerns
+    acknowledged_by: Option<String>,
+    grace_bypassed: bool, // an operator asked for it to be sent without waiting out the grace period
 }
 
 impl Alert {
-    pub fn new(name: String, message: String) -> Self {
+    pub fn new(name: String, reason: AlertReason, message: String) -> Self {
         Alert {
             name,
+            reason,
             message,
             failing_since: None,
             last_sent_update: None,
             pending_aggregation: PendingAggregation::None,
+            grace_period_seconds: None,
+            streams: Vec::new(),
+            acknowledged_by: None,
+            grace_bypassed: false,
         }
     }
 
@@ -51,12 +186,19 @@ impl Alert {
 
     pub fn mark_passing(&mut self) {
         self.failing_since = None;
+        self.acknowledged_by = None;
+        self.grace_bypassed = false;
     }
 
     pub fn is_failing(&self) -> bool {
         self.failing_since.is_some()
     }
 
+    /// Failing and already sent, i.e. no longer held by the grace period
+    pub fn is_announced(&self) -> bool {
+        self.is_failing() && self.last_sent_update.is_some()
+    }
+
     pub fn alert_state(&self) -> AlertState {
         let reminder_interval = Duration::minutes(10);
         let now = Utc::now();
@@ -90,6 +232,14 @@ pub struct AlertManager {
     slack: Arc<SlackMessageSender>,
     reminder_interval_minutes: i64,
     grace_period_seconds: i64,
+    grace_overrides: RwLock<HashMap<String, i64>>, // stream name -> grace period
+    runbooks: RwLock<HashMap<String, String>>, // stream name -> hint appended to its failure alerts
+    history: RwLock<VecDeque<AlertEvent>>, // failing/cleared transitions, oldest first
+    fired: std::sync::Mutex<HashMap<AlertReason, u64>>, // alerts announced after their grace period, by reason
+    format: AlertFormat,
+    restored_acks: RwLock<HashMap<String, String>>, // acknowledgements from before a restart, applied if their alert is still failing
+    maintenance_windows: Vec<MaintenanceWindow>,
+    maintenance_toggle: RwLock<Option<MaintenanceToggle>>,
 }
 
 impl AlertManager {
@@ -99,25 +249,136 @@ impl AlertManager {
             slack,
             reminder_interval_minutes,
             grace_period_seconds,
+            grace_overrides: RwLock::new(HashMap::new()),
+            runbooks: RwLock::new(HashMap::new()),
+            history: RwLock::new(VecDeque::new()),
+            fired: std::sync::Mutex::new(HashMap::new()),
+            format: AlertFormat::Full,
+            restored_acks: RwLock::new(HashMap::new()),
+            maintenance_windows: Vec::new(),
+            maintenance_toggle: RwLock::new(None),
+        }
+    }
+
+    pub fn with_format(mut self, format: AlertFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_maintenance_windows(mut self, maintenance_windows: Vec<MaintenanceWindow>) -> Self {
+        self.maintenance_windows = maintenance_windows;
+        self
+    }
+
+    pub fn with_grace_overrides(mut self, grace_overrides: HashMap<String, i64>) -> Self {
+        self.grace_overrides = RwLock::new(grace_overrides);
+        self
+    }
+
+    pub fn with_runbooks(mut self, runbooks: HashMap<String, String>) -> Self {
+        self.runbooks = RwLock::new(runbooks);
+        self
+    }
+
+    /// Sets or clears a stream's runbook hint, for streams added after startup
+    pub async fn set_runbook(&self, stream: &str, runbook: Option<String>) {
+        let mut runbooks = self.runbooks.write().await;
+        match runbook {
+            Some(runbook) => { runbooks.insert(stream.to_string(), runbook); },
+            None => { runbooks.remove(stream); },
         }
     }
 
-    pub async fn update_alert(&self, alert_id: String, is_error: bool, message: String) {
+    /// Sets or clears a stream's grace period override, for streams added after startup
+    pub async fn set_grace_override(&self, stream: &str, grace_period_seconds: Option<i64>) {
+        let mut overrides = self.grace_overrides.write().await;
+        match grace_period_seconds {
+            Some(grace) => { overrides.insert(stream.to_string(), grace); },
+            None => { overrides.remove(stream); },
+        }
+    }
+
+    /// Drops every alert concerning any of these streams without announcing it, used when streams are removed
+    pub async fn forget_streams(&self, streams: &[String]) {
+        let mut alerts = self.alerts.write().await;
+        alerts.retain(|_, alert| !alert.streams.iter().any(|s| streams.contains(s)));
+        let mut overrides = self.grace_overrides.write().await;
+        let mut runbooks = self.runbooks.write().await;
+        for stream in streams {
+            overrides.remove(stream);
+            runbooks.remove(stream);
+        }
+    }
+
+    /// `streams` are the streams the alert concerns; the longest grace period override among them applies,
+    /// and their runbook hints are appended to failure messages
+    pub async fn update_alert(&self, alert_id: String, reason: AlertReason, streams: &[&str], is_error: bool, message: String) {
+        let grace_override = {
+            let overrides = self.grace_overrides.read().await;
+            streams.iter()
+                .filter_map(|stream| overrides.get(*stream).copied())
+                .max()
+        };
+        let message = if is_error {
+            let runbooks = self.runbooks.read().await;
+            let mut hints: Vec<&str> = Vec::new();
+            for hint in streams.iter().filter_map(|stream| runbooks.get(*stream)) {
+                if !hints.contains(&hint.as_str()) {
+                    hints.push(hint);
+                }
+            }
+            if hints.is_empty() {
+                message
+            } else {
+                format!("{}\n:book: {}", message, hints.join("; "))
+            }
+        } else {
+            message
+        };
+
         let mut alerts = self.alerts.write().await;
         let alert = alerts.entry(alert_id.clone()).or_insert_with(|| {
-            Alert::new(alert_id.clone(), message.clone())
+            Alert::new(alert_id.clone(), reason, message.clone())
         });
+        alert.reason = reason;
+        alert.grace_period_seconds = grace_override;
+        alert.streams = streams.iter().map(|s| s.to_string()).collect();
 
         let previous_state = alert.alert_state();
+        let was_failing = alert.is_failing();
 
         if is_error {
             alert.mark_failing(message.clone());
         } else {
             alert.mark_passing();
         }
+        // The first check after a restart decides whether an acknowledgement from before it still applies
+        if let Some(by) = self.restored_acks.write().await.remove(&alert_id) {
+            if is_error {
+                info!("Alert {} is still failing, keeping its acknowledgement by {}", alert_id, by);
+                alert.acknowledged_by = Some(by);
+            }
+        }
 
         let new_state = alert.alert_state();
 
+        if was_failing != alert.is_failing() {
+            let event = AlertEvent {
+                timestamp: Utc::now(),
+                alert: alert_id.clone(),
+                reason,
+                streams: alert.streams.clone(),
+                transition: if is_error { AlertTransition::Failing } else { AlertTransition::Cleared },
+                message: message.clone(),
+            };
+            let mut history = self.history.write().await;
+            history.push_back(event);
+            let cutoff = Utc::now() - Duration::days(ALERT_HISTORY_DAYS);
+            while history.front().is_some_and(|e| e.timestamp < cutoff) {
+                history.pop_front();
+            }
+        }
+
         // Mark alerts for aggregation instead of sending immediately
         match new_state {
             AlertState::NewFailing if previous_state != AlertState::NewFailing => {
@@ -132,11 +393,164 @@ impl AlertManager {
         }
     }
 
+    pub async fn get_failing_alerts(&self) -> Vec<Alert> {
+        let alerts = self.alerts.read().await;
+        let mut failing: Vec<Alert> = alerts.values().filter(|a| a.is_failing()).cloned().collect();
+        failing.sort_by(|a, b| a.name.cmp(&b.name));
+        failing
+    }
+
+    /// When a failing alert leaves its grace period, `None` once announced
+    fn announce_at(&self, alert: &Alert) -> Option<DateTime<Utc>> {
+        if alert.is_announced() {
+            return None;
+        }
+        let grace_period = Duration::seconds(alert.grace_period_seconds.unwrap_or(self.grace_period_seconds));
+        alert.failing_since.map(|since| since + grace_period)
+    }
+
+    /// Failing alerts in their machine-readable form
+    pub async fn get_active_alerts(&self) -> Vec<ActiveAlert> {
+        self.get_failing_alerts().await.into_iter()
+            .filter_map(|alert| Some(ActiveAlert {
+                announce_at: self.announce_at(&alert),
+                failing_since: alert.failing_since?,
+                severity: alert.reason.severity(),
+                announced: alert.is_announced(),
+                alert: alert.name,
+                reason: alert.reason,
+                streams: alert.streams,
+                message: alert.message,
+                acknowledged_by: alert.acknowledged_by,
+            }))
+            .collect()
+    }
+
+    /// Marks a failing alert as being handled by `by`, which stops its reminders until it clears
+    pub async fn acknowledge(&self, alert_id: &str, by: &str) -> Result<(), String> {
+        let mut alerts = self.alerts.write().await;
+        match alerts.get_mut(alert_id) {
+            Some(alert) if alert.is_failing() => {
+                info!("Alert {} acknowledged by {}", alert_id, by);
+                alert.acknowledged_by = Some(by.to_string());
+                Ok(())
+            }
+            _ => Err(format!("No failing alert `{}`", alert_id)),
+        }
+    }
+
+    /// Who acknowledged each failing alert, including acknowledgements restored but not yet confirmed by a check
+    pub async fn get_acknowledgements(&self) -> BTreeMap<String, String> {
+        let mut acknowledged: BTreeMap<String, String> = self.restored_acks.read().await.clone().into_iter().collect();
+        for (alert_id, alert) in self.alerts.read().await.iter() {
+            if let (true, Some(by)) = (alert.is_failing(), &alert.acknowledged_by) {
+                acknowledged.insert(alert_id.clone(), by.clone());
+            }
+        }
+        acknowledged
+    }
+
+    /// Acknowledgements saved before a restart, kept for alerts that turn out to be still failing
+    pub async fn restore_acknowledgements(&self, acknowledged: BTreeMap<String, String>) {
+        *self.restored_acks.write().await = acknowledged.into_iter().collect();
+    }
+
+    /// The maintenance in progress, from a scheduled window or started by hand
+    pub async fn get_maintenance(&self) -> Option<MaintenanceStatus> {
+        let now = Utc::now();
+        if let Some(toggle) = self.maintenance_toggle.read().await.clone().filter(|toggle| toggle.until > now) {
+            return toggle.active.then(|| MaintenanceStatus {
+                reason: format!("started by {}", toggle.by),
+                until: toggle.until,
+                started_by: Some(toggle.by),
+            });
+        }
+        let local = Local::now().naive_local();
+        self.maintenance_windows.iter().find_map(|maintenance| {
+            maintenance.window.active_until(local).map(|end| MaintenanceStatus {
+                reason: maintenance.label(),
+                until: weekly::to_utc(end),
+                started_by: None,
+            })
+        })
+    }
+
+    /// Holds new alerts and reminders for `minutes`, at most a week, replacing any maintenance already in progress
+    pub async fn start_maintenance(&self, minutes: i64, by: &str) -> Result<MaintenanceStatus, String> {
+        if minutes <= 0 {
+            return Err("Maintenance needs to last at least a minute".to_string());
+        }
+        let minutes = minutes.min(MAX_MAINTENANCE_MINUTES);
+        let until = Utc::now() + Duration::minutes(minutes);
+        info!("Maintenance started by {} for {} minutes", by, minutes);
+        *self.maintenance_toggle.write().await = Some(MaintenanceToggle { active: true, until, by: by.to_string() });
+        Ok(MaintenanceStatus { reason: format!("started by {}", by), until, started_by: Some(by.to_string()) })
+    }
+
+    /// Ends the maintenance in progress; a scheduled window stays off until it would have ended
+    pub async fn end_maintenance(&self, by: &str) -> Result<(), String> {
+        let Some(status) = self.get_maintenance().await else {
+            return Err("No maintenance in progress".to_string());
+        };
+        info!("Maintenance ({}) ended early by {}", status.reason, by);
+        let mut toggle = self.maintenance_toggle.write().await;
+        *toggle = None;
+        let local = Local::now().naive_local();
+        let scheduled_until = self.maintenance_windows.iter()
+            .filter_map(|maintenance| maintenance.window.active_until(local))
+            .max()
+            .map(weekly::to_utc);
+        if let Some(until) = scheduled_until {
+            *toggle = Some(MaintenanceToggle { active: false, until, by: by.to_string() });
+        }
+        Ok(())
+    }
+
+    /// Sends an alert still held by its grace period right away, for when an operator already knows it's real
+    pub async fn alert_now(&self, alert_id: &str) -> Result<(), String> {
+        {
+            let mut alerts = self.alerts.write().await;
+            match alerts.get_mut(alert_id) {
+                Some(alert) if alert.is_announced() => return Err(format!("Alert `{}` was already sent", alert_id)),
+                Some(alert) if alert.is_failing() => {
+                    info!("Skipping the rest of the grace period of alert {}", alert_id);
+                    alert.grace_bypassed = true;
+                }
+                _ => return Err(format!("No failing alert `{}`", alert_id)),
+            }
+        }
+        self.process_aggregated_alerts().await;
+        Ok(())
+    }
+
+    /// Alerts announced since startup, by reason
+    pub fn get_fired_counts(&self) -> HashMap<AlertReason, u64> {
+        self.fired.lock().expect("fired lock poisoned").clone()
+    }
+
+    /// Alert transitions between `from` and `to`, oldest first
+    pub async fn get_history(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<AlertEvent> {
+        self.history.read().await.iter()
+            .filter(|e| e.timestamp >= from && e.timestamp <= to)
+            .cloned()
+            .collect()
+    }
+
+    /// Send an informational summary straight through, bypassing alert state and aggregation
+    pub async fn send_digest(&self, message: String) {
+        self.slack.send(message).await;
+    }
+
+    /// Held during maintenance, so reminders go out once it ends for alerts still failing
     pub async fn process_alerts(&self) {
+        if self.get_maintenance().await.is_some() {
+            return;
+        }
         let mut alerts = self.alerts.write().await;
 
         for (_alert_id, alert) in alerts.iter_mut() {
             match alert.alert_state() {
+                AlertState::FailingReminderNeeded if alert.acknowledged_by.is_some() => {}
                 AlertState::FailingReminderNeeded => {
                     warn!("Alert reminder: {}", alert.message);
                     alert.pending_aggregation = PendingAggregation::Reminder;
@@ -151,9 +565,10 @@ impl AlertManager {
     }
 
     async fn process_aggregated_alerts(&self) {
+        // New failures are held during maintenance unless someone asked for them; clears of alerts sent before it still go out
+        let maintenance = self.get_maintenance().await.is_some();
         let mut alerts = self.alerts.write().await;
         let now = Utc::now();
-        let grace_period = Duration::seconds(self.grace_period_seconds);
 
         // Collect alerts by pending state
         let mut new_failures = Vec::new();
@@ -163,24 +578,28 @@ impl AlertManager {
         for alert in alerts.values_mut() {
             match alert.pending_aggregation {
                 PendingAggregation::NewFailure => {
-                    new_failures.push(alert.message.clone());
+                    new_failures.push((alert.message.clone(), alert.streams.clone()));
                     alert.pending_aggregation = PendingAggregation::None;
                 }
                 PendingAggregation::Cleared => {
-                    clears.push(alert.message.clone());
+                    clears.push((alert.message.clone(), alert.streams.clone()));
                     alert.pending_aggregation = PendingAggregation::None;
                 }
                 PendingAggregation::Reminder => {
-                    reminders.push(alert.message.clone());
+                    reminders.push((alert.message.clone(), alert.streams.clone()));
                     alert.pending_aggregation = PendingAggregation::None;
                 }
                 PendingAggregation::None => {
                     // Check if this is a new failure that has passed the grace period
                     if let AlertState::NewFailing = alert.alert_state() {
                         if let Some(failing_since) = alert.failing_since {
-                            if now - failing_since >= grace_period {
+                            let grace_period = Duration::seconds(alert.grace_period_seconds.unwrap_or(self.grace_period_seconds));
+                            if !alert.grace_bypassed && maintenance {
+                                debug!("Holding alert for maintenance: {}", alert.message);
+                            } else if alert.grace_bypassed || now - failing_since >= grace_period {
                                 error!("Alert passed grace period: {}", alert.message);
-                                new_failures.push(alert.message.clone());
+                                *self.fired.lock().expect("fired lock poisoned").entry(alert.reason).or_insert(0) += 1;
+                                new_failures.push((alert.message.clone(), alert.streams.clone()));
                                 alert.pending_aggregation = PendingAggregation::None;
                                 alert.register_sent();
                             }
@@ -194,57 +613,68 @@ impl AlertManager {
         drop(alerts);
 
         // Send aggregated messages
-        if !new_failures.is_empty() {
-            let message = if new_failures.len() == 1 {
-                format!("*Warning:* _A new issue has been detected!_\n{}", new_failures[0])
-            } else {
-                let issues = new_failures.iter()
-                    .enumerate()
-                    .map(|(i, msg)| format!("{}. {}", i + 1, msg))
-                    .collect::<Vec<_>>()
-                    .join("\n");
-                format!("*Warning:* _{} new issues detected!_\n{}", new_failures.len(), issues)
-            };
-            self.slack.send(message).await;
-        }
+        self.send_batch(Batch::NewFailures, &new_failures).await;
+        self.send_batch(Batch::Clears, &clears).await;
+        self.send_batch(Batch::Reminders, &reminders).await;
+    }
 
-        if !clears.is_empty() {
-            let message = if clears.len() == 1 {
-                format!("*Success:* _Issue resolved!_\n{}", clears[0])
-            } else {
-                let issues = clears.iter()
-                    .enumerate()
-                    .map(|(i, msg)| format!("{}. {}", i + 1, msg))
-                    .collect::<Vec<_>>()
-                    .join("\n");
-                format!("*Success:* _{} issues resolved!_\n{}", clears.len(), issues)
-            };
-            self.slack.send(message).await;
+    async fn send_batch(&self, batch: Batch, alerts: &[(String, Vec<String>)]) {
+        if alerts.is_empty() {
+            return;
         }
+        let details = match alerts {
+            [(message, _)] => message.clone(),
+            _ => numbered(alerts),
+        };
 
-        if !reminders.is_empty() {
-            let message = if reminders.len() == 1 {
-                format!("*Reminder:* _Issue is still present!_\n{}", reminders[0])
-            } else {
-                let issues = reminders.iter()
-                    .enumerate()
-                    .map(|(i, msg)| format!("{}. {}", i + 1, msg))
-                    .collect::<Vec<_>>()
-                    .join("\n");
-                format!("*Reminder:* _{} issues still present!_\n{}", reminders.len(), issues)
-            };
-            self.slack.send(message).await;
+        match self.format {
+            AlertFormat::Full => {
+                self.slack.send(format!("{}\n{}", batch.full_header(alerts.len()), details)).await;
+            }
+            AlertFormat::Compact => {
+                let summary = compact_summary(batch, alerts);
+                // A lone one-line alert is its own summary, there is nothing to put in a thread
+                let single_line = alerts.len() == 1 && !details.contains('\n') && details.chars().count() <= COMPACT_MAX_CHARS;
+                if let Some(ts) = self.slack.start_thread(summary).await {
+                    if !single_line {
+                        self.slack.send_in_thread(details, &ts).await;
+                    }
+                }
+            }
         }
     }
 
+    async fn announce_maintenance(&self, maintenance: Option<MaintenanceStatus>) {
+        let message = match maintenance {
+            Some(status) => {
+                info!("Maintenance ({}) started, holding new alerts and reminders", status.reason);
+                format!(":construction: Maintenance ({}) until {}: new alerts and reminders are held",
+                    status.reason, timefmt::slack(status.until, "{time}", "%H:%M"))
+            }
+            None => {
+                let held = self.get_failing_alerts().await.iter().filter(|alert| !alert.is_announced()).count();
+                info!("Maintenance over, {} held alerts still failing", held);
+                format!(":white_check_mark: Maintenance is over, {} alerts held during it are still failing and will be sent", held)
+            }
+        };
+        self.slack.send(message).await;
+    }
+
     pub async fn start_alert_loop(self: Arc<Self>) {
         info!("Starting alert manager with {}min reminder interval, 30s aggregation window, and {}s grace period",
               self.reminder_interval_minutes, self.grace_period_seconds);
 
         tokio::spawn(async move {
+            let mut in_maintenance = false;
             loop {
                 tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
 
+                let maintenance = self.get_maintenance().await;
+                if maintenance.is_some() != in_maintenance {
+                    in_maintenance = maintenance.is_some();
+                    self.announce_maintenance(maintenance).await;
+                }
+
                 // Check for reminders and mark them as pending
                 self.process_alerts().await;
 
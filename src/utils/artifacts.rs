@@ -0,0 +1,244 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tokio::sync::{broadcast::{error::RecvError, Receiver}, RwLock};
+use tracing::{debug, info, warn};
+
+use super::alertmanager::AlertManager;
+
+pub use watchdog::api::ArtifactStats;
+use watchdog::api::AlertReason;
+
+// HDC frames decode to 2048 samples per channel, a decoder concealing a lost frame replays the previous one
+const HDC_FRAME_SAMPLES: usize = 2048 * 2; // interleaved stereo
+const REPEAT_MIN_SAMPLES: usize = 2048; // identical run one frame back that counts as a repeated frame
+const CLICK_JUMP: i32 = 20_000; // sample-to-sample step (of 65536) that real audio at 44.1 kHz practically never makes
+const CLICK_HOLDOFF_SAMPLES: usize = 4410; // 50 ms stereo, one glitch often produces several steps
+const MUTE_MIN_SAMPLES: usize = 882; // 10 ms stereo of exact digital zero
+const MUTE_MAX_SAMPLES: usize = 44_100; // 500 ms, longer mutes are silence and alerted on as such
+const ARTIFACT_HISTORY_MINUTES: i64 = 60;
+const ALERT_CHECK_SECONDS: u64 = 30;
+
+/// Decode glitch detection for HD Radio streams
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ArtifactConfig {
+    /// Alert when a stream averages this many artifacts per minute over the window
+    #[serde(default = "default_artifacts_per_minute")]
+    pub alert_per_minute: f32,
+    /// Window the artifact rate is averaged over
+    #[serde(default = "default_artifact_window_minutes")]
+    pub window_minutes: i64,
+}
+
+fn default_artifacts_per_minute() -> f32 { 2.0 }
+fn default_artifact_window_minutes() -> i64 { 10 }
+
+#[derive(Clone, Copy)]
+enum Artifact {
+    RepeatedFrame,
+    Click,
+    Mute,
+}
+
+#[derive(Default)]
+struct ArtifactLog {
+    recent: VecDeque<DateTime<Utc>>,
+    repeated_frames: u64,
+    clicks: u64,
+    mutes: u64,
+}
+
+impl ArtifactLog {
+    fn push(&mut self, artifact: Artifact) {
+        let at = Utc::now();
+        self.recent.push_back(at);
+        while self.recent.front().is_some_and(|t| at - *t > chrono::Duration::minutes(ARTIFACT_HISTORY_MINUTES)) {
+            self.recent.pop_front();
+        }
+        match artifact {
+            Artifact::RepeatedFrame => self.repeated_frames += 1,
+            Artifact::Click => self.clicks += 1,
+            Artifact::Mute => self.mutes += 1,
+        }
+    }
+
+    fn rate(&self, window_minutes: i64) -> f32 {
+        let window_minutes = window_minutes.clamp(1, ARTIFACT_HISTORY_MINUTES);
+        let since = Utc::now() - chrono::Duration::minutes(window_minutes);
+        self.recent.iter().filter(|t| **t >= since).count() as f32 / window_minutes as f32
+    }
+}
+
+/// Scans decoded PCM sample by sample for the marks a struggling decoder leaves
+struct Scanner {
+    history: Vec<i16>, // last HDC frame of interleaved samples, as a ring
+    position: usize,
+    filled: bool,
+    repeat_run: usize,
+    previous: [i16; 2], // last sample per channel
+    since_click: usize,
+    zero_run: usize,
+    heard_audio: bool, // a mute only counts between stretches of audio
+    carry: Option<u8>,
+}
+
+impl Scanner {
+    fn new() -> Self {
+        Scanner {
+            history: vec![0; HDC_FRAME_SAMPLES],
+            position: 0,
+            filled: false,
+            repeat_run: 0,
+            previous: [0; 2],
+            since_click: CLICK_HOLDOFF_SAMPLES,
+            zero_run: 0,
+            heard_audio: false,
+            carry: None,
+        }
+    }
+
+    fn consume(&mut self, mut data: &[u8], found: &mut Vec<Artifact>) {
+        if let Some(low) = self.carry.take() {
+            let Some((&high, rest)) = data.split_first() else {
+                self.carry = Some(low);
+                return;
+            };
+            self.sample(i16::from_le_bytes([low, high]), found);
+            data = rest;
+        }
+        let mut samples = data.chunks_exact(2);
+        for sample in &mut samples {
+            self.sample(i16::from_le_bytes([sample[0], sample[1]]), found);
+        }
+        self.carry = samples.remainder().first().copied();
+    }
+
+    fn sample(&mut self, value: i16, found: &mut Vec<Artifact>) {
+        let channel = self.position % 2;
+
+        // Repeated frame: the same non-silent audio one frame later
+        if self.filled && value != 0 && self.history[self.position] == value {
+            self.repeat_run += 1;
+            if self.repeat_run == REPEAT_MIN_SAMPLES {
+                found.push(Artifact::RepeatedFrame);
+            }
+        } else {
+            self.repeat_run = 0;
+        }
+        self.history[self.position] = value;
+        self.position = (self.position + 1) % HDC_FRAME_SAMPLES;
+        self.filled |= self.position == 0;
+
+        // Click: a step no band-limited signal makes
+        self.since_click = self.since_click.saturating_add(1);
+        if (value as i32 - self.previous[channel] as i32).abs() > CLICK_JUMP && self.since_click > CLICK_HOLDOFF_SAMPLES {
+            found.push(Artifact::Click);
+            self.since_click = 0;
+        }
+        self.previous[channel] = value;
+
+        // Mute: a short stretch of exact zeros with audio on both sides
+        if value == 0 {
+            self.zero_run += 1;
+        } else {
+            if self.heard_audio && (MUTE_MIN_SAMPLES..=MUTE_MAX_SAMPLES).contains(&self.zero_run) {
+                found.push(Artifact::Mute);
+            }
+            self.zero_run = 0;
+            self.heard_audio = true;
+        }
+    }
+}
+
+/// Watches decoded HD Radio audio for repeated frames, clicks and mutes, and alerts when a stream's artifact rate climbs
+pub struct ArtifactDetector {
+    config: ArtifactConfig,
+    logs: RwLock<HashMap<String, ArtifactLog>>,
+    alert_manager: Option<Arc<AlertManager>>,
+}
+
+impl ArtifactDetector {
+    pub fn new(config: ArtifactConfig) -> Self {
+        ArtifactDetector {
+            config,
+            logs: RwLock::new(HashMap::new()),
+            alert_manager: None,
+        }
+    }
+
+    pub fn with_alert_manager(mut self, alert_manager: Arc<AlertManager>) -> Self {
+        self.alert_manager = Some(alert_manager);
+        self
+    }
+
+    /// Starts scanning one stream's decoded audio
+    pub async fn watch(self: &Arc<Self>, stream_name: String, mut input: Receiver<Vec<u8>>) {
+        self.logs.write().await.insert(stream_name.clone(), ArtifactLog::default());
+        let detector = self.clone();
+
+        tokio::spawn(async move {
+            let mut scanner = Scanner::new();
+            let mut found = Vec::new();
+            loop {
+                match input.recv().await {
+                    Ok(data) => {
+                        scanner.consume(&data, &mut found);
+                        if found.is_empty() {
+                            continue;
+                        }
+                        debug!("Stream {}: {} decode artifacts in the last chunk", stream_name, found.len());
+                        let mut logs = detector.logs.write().await;
+                        let log = logs.entry(stream_name.clone()).or_default();
+                        for artifact in found.drain(..) {
+                            log.push(artifact);
+                        }
+                    }
+                    // Skipped audio would read as a glitch of its own, start over clean
+                    Err(RecvError::Lagged(_)) => scanner = Scanner::new(),
+                    Err(RecvError::Closed) => {
+                        warn!("Artifact detection input for {} closed", stream_name);
+                        break;
+                    }
+                }
+            }
+        });
+    }
+
+    pub async fn start_alert_loop(self: Arc<Self>) {
+        info!("Starting decode artifact alerts (≥{:.1}/min over {} min)", self.config.alert_per_minute, self.config.window_minutes);
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(ALERT_CHECK_SECONDS)).await;
+                let Some(ref am) = self.alert_manager else { continue };
+
+                let rates: Vec<(String, f32)> = self.logs.read().await.iter()
+                    .map(|(name, log)| (name.clone(), log.rate(self.config.window_minutes)))
+                    .collect();
+                for (stream_name, rate) in rates {
+                    let is_error = rate >= self.config.alert_per_minute;
+                    let message = if is_error {
+                        format!("Stream `{}` decode quality degraded: {:.1} artifacts per minute (repeated frames, clicks, mutes)", stream_name, rate)
+                    } else {
+                        format!("Stream `{}` decodes cleanly again", stream_name)
+                    };
+                    am.update_alert(format!("{}_artifacts", stream_name), AlertReason::DecodeArtifacts, &[stream_name.as_str()], is_error, message).await;
+                }
+            }
+        });
+    }
+
+    pub async fn get_stats(&self) -> HashMap<String, ArtifactStats> {
+        self.logs.read().await.iter()
+            .map(|(name, log)| (name.clone(), ArtifactStats {
+                rate_per_minute: log.rate(self.config.window_minutes),
+                repeated_frames: log.repeated_frames,
+                clicks: log.clicks,
+                mutes: log.mutes,
+            }))
+            .collect()
+    }
+}
@@ -1,117 +1,553 @@
-use std::{collections::HashMap, sync::Arc, time::Duration};
-use tokio::sync::Mutex;
+use std::{collections::{HashMap, HashSet, VecDeque}, sync::{Arc, RwLock}, time::Duration};
+use tokio::sync::{Mutex, Semaphore};
+use tokio::task::JoinSet;
 use tracing::{info, warn, error, debug};
 use crate::utils::alertmanager::AlertManager;
 
-use super::commandprocessor::{CommandHolder, StreamHealth};
-use super::audiostream::{AudioStream, AudioStreamHealth};
-use super::volumedetect::VolumeMetrics;
+use super::commandprocessor::{CommandHolder, Fault, RestartPolicy, StderrSummary, StreamHealth};
+use super::audiostream::{AudioStream, AudioStreamHealth, FingerprintSource};
+use super::volumedetect::{DropoutConfig, StereoProfile, VolumeDetector, VolumeMetrics};
+use super::deadair::{DeadAirStats, DeadAirTracker};
+use super::stderrlog::StderrLog;
+use super::uptimestore::{PersistedStats, UptimeStore};
+use super::bufferbudget::BufferBudget;
+use super::fingerprintpool::FingerprintPool;
+use super::fingerprintcache::FingerprintCache;
+use super::comparator::ComparisonOverrides;
+use super::tuning::{ThresholdKind, TunedThresholds};
+use super::thresholdschedule::{ActiveSchedules, ThresholdSchedule};
+use rusty_chromaprint::Configuration;
+use watchdog::api::{AlertReason, BufferStatus, ProcessLog, VolumeSample};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+// Each analysis pipes a full buffer through its own ffmpeg, so keep the fan-out modest
+const DEFAULT_VOLUME_CONCURRENCY: usize = 4;
+
+// Volume samples kept per stream for history queries (an hour at the default 10s interval)
+const VOLUME_HISTORY_SAMPLES: usize = 360;
+
+// Stereo heuristics, all in dB
+const STEREO_MIN_ACTIVE_DB: f32 = -50.0; // louder channel must be above this before we judge the other
+const STEREO_DEAD_CHANNEL_DB: f32 = 30.0; // imbalance at which the quieter channel counts as dead
+// Stereo image comparison, as correlations
+const STEREO_MONO_CORRELATION: f32 = 0.98; // L/R more alike than this is mono, its image can't be told from a swapped one
+const STEREO_SAME_IMAGE: f32 = 0.5; // two streams' balance over time correlating at least this well (or inversely) decides
+
+/// How the supervisor reacts to dead streams
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct SupervisorConfig {
+    /// How often stream health is checked
+    #[serde(default = "default_supervisor_interval")]
+    pub interval_seconds: u64,
+    /// Also respawn when the command runs but its audio processing died
+    #[serde(default = "default_respawn_on_dead_audio")]
+    pub respawn_on_dead_audio: bool,
+    /// Delay before each consecutive respawn, the last entry repeating; unset adds 30s per attempt; streams override it
+    pub backoff_seconds: Option<Vec<u64>>,
+    /// Consecutive respawns before a stream is left dead until a `restart`, unset keeps trying; streams override it
+    pub max_restarts: Option<u32>,
+    /// Seconds without output before a stream counts as stalled; streams override it
+    #[serde(default = "default_stall_timeout")]
+    pub stall_timeout_seconds: u64,
+    /// Fragile streams only respawned after a `restart` via Slack or the API
+    #[serde(default)]
+    pub manual_streams: Vec<String>,
+    /// Alert when a stream's audio timeline jumps this often within an hour, 0 disables
+    #[serde(default = "default_discontinuities_per_hour")]
+    pub discontinuities_per_hour: usize,
+    /// Respawn and alert once a stall outlasts its timeout by this much, 0 leaves stalls alone; streams override it with stalled_dead_after_seconds
+    #[serde(default = "default_stalled_respawn")]
+    pub stalled_respawn_seconds: u64,
+}
+
+fn default_supervisor_interval() -> u64 { 10 }
+fn default_stalled_respawn() -> u64 { 120 }
+fn default_stall_timeout() -> u64 { 30 }
+fn default_respawn_on_dead_audio() -> bool { true }
+fn default_discontinuities_per_hour() -> usize { 3 }
+
+impl Default for SupervisorConfig {
+    fn default() -> Self {
+        SupervisorConfig {
+            interval_seconds: default_supervisor_interval(),
+            respawn_on_dead_audio: default_respawn_on_dead_audio(),
+            backoff_seconds: None,
+            max_restarts: None,
+            stall_timeout_seconds: default_stall_timeout(),
+            manual_streams: Vec::new(),
+            discontinuities_per_hour: default_discontinuities_per_hour(),
+            stalled_respawn_seconds: default_stalled_respawn(),
+        }
+    }
+}
+
+impl SupervisorConfig {
+    /// Delay before the next respawn, from the stream's own schedule when it has one
+    fn backoff(&self, policy: &RestartPolicy, restart_count: u32) -> Duration {
+        let seconds = match policy.backoff_seconds.as_ref().or(self.backoff_seconds.as_ref()) {
+            Some(schedule) if !schedule.is_empty() => schedule[(restart_count as usize).min(schedule.len() - 1)],
+            _ => 30 * restart_count as u64,
+        };
+        Duration::from_secs(seconds)
+    }
+
+    /// Whether a stream respawned `restart_count` times without recovering has used up its respawns
+    fn gave_up(&self, policy: &RestartPolicy, restart_count: u32) -> bool {
+        policy.max_restarts.or(self.max_restarts).is_some_and(|max| restart_count >= max)
+    }
+
+    fn stalled_respawn(&self) -> Option<Duration> {
+        (self.stalled_respawn_seconds > 0).then(|| Duration::from_secs(self.stalled_respawn_seconds))
+    }
+}
+
+/// Levels a stream alerts as silent below, replacing the global minimum max volume
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VolumeLimits {
+    pub min_max_volume_db: Option<f32>,
+    pub min_mean_volume_db: Option<f32>, // also alert when the average level drops this low, e.g. a rock channel gone quiet
+}
 
 pub struct StreamInfo {
     command: CommandHolder,
     audio: AudioStream,
+    priority: i32, // higher is respawned first
 }
 
 pub struct AudioRouter {
     streams: Arc<Mutex<HashMap<String, StreamInfo>>>,
-    channels: HashMap<String, Vec<String>>, // channel -> list of stream names
+    channels: Arc<RwLock<HashMap<String, Vec<String>>>>, // channel -> list of stream names, changes when channels are hot-added/removed
     volume_metrics: Arc<Mutex<HashMap<String, VolumeMetrics>>>, // stream name -> volume metrics
+    volume_history: Arc<Mutex<HashMap<String, VecDeque<VolumeSample>>>>, // stream name -> recent samples, oldest first
     alert_manager: Option<Arc<AlertManager>>,
-    minimum_max_volume_threshold: Option<f32>
+    minimum_max_volume_threshold: Option<f32>,
+    stereo_detection: bool,
+    native_volume: bool, // compute levels in-process instead of spawning ffmpeg volumedetect
+    volume_concurrency: usize, // max concurrent volume analyses
+    dead_air: Option<Arc<DeadAirTracker>>,
+    uptime_store: Option<Arc<UptimeStore>>, // persisted uptime and restarts, survives watchdog restarts
+    fingerprint_cache: Option<Arc<FingerprintCache>>, // saved fingerprint buffers new streams start from
+    buffer_budget: Arc<BufferBudget>, // bytes held by every stream's audio buffers, optionally capped
+    fingerprint_pool: Arc<FingerprintPool>, // threads every stream's fingerprinting runs on
+    volume_window: Option<f32>, // seconds of audio each volume check analyzes, the stream's buffer_duration when unset
+    dropout: DropoutConfig,
+    upstream_logs: RwLock<HashMap<String, Vec<(String, StderrLog)>>>, // stream name -> (process label, log) for nrsc5/rtl_tcp
+    unsampled: Arc<RwLock<HashSet<String>>>, // streams whose scanning SDR is currently tuned elsewhere
+    silence_checked: RwLock<HashSet<String>>, // channels compared against the silence reference channel
+    min_healthy: Arc<RwLock<HashMap<String, usize>>>, // channel -> streams that must be healthy before a channel alert
+    comparison_overrides: RwLock<HashMap<String, ComparisonOverrides>>,
+    tuned_thresholds: Arc<RwLock<TunedThresholds>>, // set at runtime from Slack or the API, over the config's
+    threshold_schedules: Arc<RwLock<Vec<ThresholdSchedule>>>, // thresholds and expected dead air by time of day
+    volume_limits: Arc<RwLock<HashMap<String, VolumeLimits>>>, // stream name -> its own silence levels
+    failed: RwLock<HashMap<String, (String, String)>>, // stream name -> (channel, why it could not be started)
+    supervisor: SupervisorConfig,
+    no_spawn: bool, // log the respawns the supervisor would do instead of doing them
+    awaiting_confirmation: Arc<RwLock<HashSet<String>>>, // manual streams that are down until someone restarts them
 }
 
 impl AudioRouter {
     pub fn new() -> Self {
         AudioRouter {
             streams: Arc::new(Mutex::new(HashMap::new())),
-            channels: HashMap::new(),
+            channels: Arc::new(RwLock::new(HashMap::new())),
             volume_metrics: Arc::new(Mutex::new(HashMap::new())),
+            volume_history: Arc::new(Mutex::new(HashMap::new())),
             alert_manager: None,
-            minimum_max_volume_threshold: None
+            minimum_max_volume_threshold: None,
+            stereo_detection: false,
+            native_volume: false,
+            volume_concurrency: DEFAULT_VOLUME_CONCURRENCY,
+            dead_air: None,
+            uptime_store: None,
+            fingerprint_cache: None,
+            buffer_budget: BufferBudget::new(None),
+            fingerprint_pool: FingerprintPool::new(std::thread::available_parallelism().map_or(2, |n| n.get())),
+            volume_window: None,
+            dropout: DropoutConfig::default(),
+            upstream_logs: RwLock::new(HashMap::new()),
+            unsampled: Arc::new(RwLock::new(HashSet::new())),
+            silence_checked: RwLock::new(HashSet::new()),
+            min_healthy: Arc::new(RwLock::new(HashMap::new())),
+            comparison_overrides: RwLock::new(HashMap::new()),
+            tuned_thresholds: Arc::new(RwLock::new(TunedThresholds::default())),
+            threshold_schedules: Arc::new(RwLock::new(Vec::new())),
+            volume_limits: Arc::new(RwLock::new(HashMap::new())),
+            failed: RwLock::new(HashMap::new()),
+            supervisor: SupervisorConfig::default(),
+            no_spawn: false,
+            awaiting_confirmation: Arc::new(RwLock::new(HashSet::new())),
         }
     }
 
-    pub fn with_alert_manager(mut self, alert_manager: Arc<AlertManager>, minimum_max_volume_threshold: f32) -> Self {
+    pub fn with_supervisor_config(mut self, supervisor: SupervisorConfig) -> Self {
+        self.supervisor = supervisor;
+        self
+    }
+
+    pub fn with_alert_manager(mut self, alert_manager: Arc<AlertManager>) -> Self {
         self.alert_manager = Some(alert_manager);
+        self
+    }
+
+    /// Alert when a stream's max volume drops below this level (dB)
+    pub fn with_silence_threshold(mut self, minimum_max_volume_threshold: f32) -> Self {
         self.minimum_max_volume_threshold = Some(minimum_max_volume_threshold);
         self
     }
 
-    pub async fn add_stream(&mut self, stream_name: &String, channel_name: &String, buffer_duration: f32, command_holder: CommandHolder) {
-        // Create channel if not exists
-        if !self.channels.contains_key(channel_name) {
-            self.channels.insert(channel_name.to_string(), vec![]);
-        }
+    /// Alert on dead stereo channels and on L/R swaps relative to the channel's reference stream
+    pub fn with_stereo_detection(mut self) -> Self {
+        self.stereo_detection = true;
+        self
+    }
 
-        // Add stream to channel
-        if let Some(streams) = self.channels.get_mut(channel_name) {
-            streams.push(stream_name.to_string());
-        }
+    pub fn with_native_volume(mut self) -> Self {
+        self.native_volume = true;
+        self
+    }
+
+    pub fn with_volume_concurrency(mut self, volume_concurrency: usize) -> Self {
+        self.volume_concurrency = volume_concurrency.max(1);
+        self
+    }
+
+    pub fn with_dead_air_tracker(mut self, tracker: Arc<DeadAirTracker>) -> Self {
+        self.dead_air = Some(tracker);
+        self
+    }
 
+    /// Caps the bytes all audio buffers may hold together; streams added later share the cap
+    pub fn with_buffer_cap(mut self, cap_bytes: usize) -> Self {
+        self.buffer_budget = BufferBudget::new(Some(cap_bytes));
+        self
+    }
+
+    /// Analyze only the last `seconds` of audio on each volume check instead of the whole buffer, so short silences show up
+    pub fn with_volume_window(mut self, seconds: f32) -> Self {
+        self.volume_window = Some(seconds);
+        self
+    }
+
+    /// Must be set before streams are added, each stream's PCM is watched for dropouts from the start
+    pub fn with_dropout_config(mut self, dropout: DropoutConfig) -> Self {
+        self.dropout = dropout;
+        self
+    }
+
+    pub fn get_buffer_budget(&self) -> &BufferBudget {
+        &self.buffer_budget
+    }
+
+    pub fn with_uptime_store(mut self, store: Arc<UptimeStore>) -> Self {
+        self.uptime_store = Some(store);
+        self
+    }
+
+    /// Trial mode: the supervisor logs the respawns it would do, and SDR streams aren't started
+    pub fn with_no_spawn(mut self) -> Self {
+        self.no_spawn = true;
+        self
+    }
+
+    pub fn spawns_disabled(&self) -> bool {
+        self.no_spawn
+    }
+
+    pub fn with_fingerprint_cache(mut self, cache: Arc<FingerprintCache>) -> Self {
+        self.fingerprint_cache = Some(cache);
+        self
+    }
+
+    pub async fn add_stream(&self, stream_name: &String, channel_name: &String, buffer_duration: f32, min_buffer_duration: Option<f32>, fingerprint_source: FingerprintSource, priority: i32, command_holder: CommandHolder) {
         // Create AudioStream from CommandHolder (uses a reader from it)
+        command_holder.set_default_stall_timeout(Duration::from_secs(self.supervisor.stall_timeout_seconds));
         let reader = command_holder.get_reader();
-        let audio = AudioStream::new(reader, buffer_duration);
+        let audio = AudioStream::new(reader, buffer_duration, min_buffer_duration, self.volume_window.unwrap_or(buffer_duration), &self.dropout, fingerprint_source, &self.buffer_budget, &self.fingerprint_pool);
+        if let Some(saved) = self.fingerprint_cache.as_ref().and_then(|cache| cache.load(stream_name)) {
+            info!("Stream {} resumes from {} saved fingerprint items", stream_name, saved.len());
+            audio.seed_fingerprint(saved).await;
+        }
         let stream_info = StreamInfo {
             command: command_holder,
             audio,
+            priority,
         };
 
-        // Store stream
+        // Store stream before listing it, so anything iterating channels can always look it up
         self.streams.lock().await.insert(stream_name.clone(), stream_info);
+
+        // Add stream to channel, creating the channel if needed
+        self.channels.write().expect("channels lock poisoned")
+            .entry(channel_name.to_string())
+            .or_default()
+            .push(stream_name.to_string());
+    }
+
+    /// Stops and forgets every stream in a channel, returning the removed stream names
+    pub async fn remove_channel(&self, channel_name: &str) -> Vec<String> {
+        // Streams that never started are returned too, so their startup alerts are forgotten with the rest
+        let mut never_started = vec![];
+        self.failed.write().expect("failed lock poisoned").retain(|stream, (channel, _)| {
+            let keep = channel != channel_name;
+            if !keep {
+                never_started.push(stream.clone());
+            }
+            keep
+        });
+        let Some(stream_names) = self.channels.write().expect("channels lock poisoned").remove(channel_name) else {
+            return never_started;
+        };
+
+        let removed: Vec<StreamInfo> = {
+            let mut streams = self.streams.lock().await;
+            stream_names.iter().filter_map(|name| streams.remove(name)).collect()
+        };
+        for stream_info in removed {
+            stream_info.command.stop().await;
+        }
+        self.set_silence_check(channel_name, false);
+        self.set_min_healthy_streams(channel_name, None);
+        self.comparison_overrides.write().expect("comparison overrides lock poisoned").remove(channel_name);
+
+        {
+            let mut upstream_logs = self.upstream_logs.write().expect("upstream logs lock poisoned");
+            for name in &stream_names {
+                upstream_logs.remove(name);
+            }
+        }
+        {
+            let mut volume_limits = self.volume_limits.write().expect("volume limits lock poisoned");
+            for name in &stream_names {
+                volume_limits.remove(name);
+            }
+        }
+        let mut volume_metrics = self.volume_metrics.lock().await;
+        let mut volume_history = self.volume_history.lock().await;
+        for name in &stream_names {
+            volume_metrics.remove(name);
+            volume_history.remove(name);
+        }
+
+        info!("Removed channel '{}' ({} streams)", channel_name, stream_names.len());
+        stream_names.into_iter().chain(never_started).collect()
     }
 
     pub async fn start_supervisor(&self) {
-        info!("Starting AudioRouter supervisor");
+        info!("Starting AudioRouter supervisor (interval: {}s, respawn on dead audio: {}, {} streams need confirmation)",
+            self.supervisor.interval_seconds, self.supervisor.respawn_on_dead_audio, self.supervisor.manual_streams.len());
         let streams = self.streams.clone();
+        let alert_manager = self.alert_manager.clone();
+        let config = self.supervisor.clone();
+        let awaiting_confirmation = self.awaiting_confirmation.clone();
+        let uptime_store = self.uptime_store.clone();
+        let channels = self.channels.clone();
+        let min_healthy = self.min_healthy.clone();
+        let unsampled = self.unsampled.clone();
+        let no_spawn = self.no_spawn;
 
         tokio::spawn(async move {
+            let mut stall_respawned: HashSet<String> = HashSet::new(); // streams respawned for a long stall, alerting until healthy
             loop {
-                tokio::time::sleep(Duration::from_secs(10)).await;
+                tokio::time::sleep(Duration::from_secs(config.interval_seconds)).await;
 
                 let mut streams_lock = streams.lock().await;
-                for (name, stream_info) in streams_lock.iter_mut() {
+
+                // Walk streams highest priority first so main feeds are back before confidence feeds
+                let mut names: Vec<String> = streams_lock.keys().cloned().collect();
+                names.sort_by_key(|name| std::cmp::Reverse(streams_lock[name].priority));
+                let mut healthy: HashSet<String> = HashSet::new();
+                stall_respawned.retain(|name| streams_lock.contains_key(name));
+
+                for name in names {
+                    let Some(stream_info) = streams_lock.get_mut(&name) else { continue };
+                    if stream_info.command.primary_recovered() {
+                        if no_spawn {
+                            info!("Would switch stream {} back to its primary source", name);
+                        } else {
+                            info!("Stream {} switching back to its primary source", name);
+                            stream_info.command.respawn(Duration::ZERO).await;
+                        }
+                    }
                     let cmd_health = stream_info.command.get_health().await;
                     let audio_health = stream_info.audio.get_health().await;
+                    if cmd_health == StreamHealth::Running && audio_health == AudioStreamHealth::Running {
+                        healthy.insert(name.clone());
+                    }
 
-                    match cmd_health {
+                    // Why the stream needs a respawn, if it does
+                    let cause = match cmd_health {
                         StreamHealth::Dead => {
-                            error!("Stream {} command is dead, attempting respawn", name);
-                            if stream_info.command.respawn().await {
-                                info!("Stream {} successfully respawned", name);
+                            error!("Stream {} command is dead", name);
+                            Some("dead command")
+                        },
+                        StreamHealth::Stalled if stream_info.command.is_soft_eof() => {
+                            if no_spawn {
+                                info!("Stream {} hit a transient EOF, would restart it", name);
                             } else {
-                                error!("Stream {} failed to respawn (max restarts exceeded)", name);
+                                info!("Stream {} hit a transient EOF, restarting it", name);
+                                stream_info.command.respawn(Duration::ZERO).await;
                             }
+                            None
                         },
-                        StreamHealth::Stalled => {
-                            warn!("Stream {} command is stalled", name);
+                        StreamHealth::Stalled => match stream_info.command.stalled_too_long(config.stalled_respawn()).await {
+                            Some(silent) => {
+                                error!("Stream {} has been stalled for {}s", name, silent.as_secs());
+                                // The stalled process is still running, it has to go before a new one starts
+                                if !no_spawn {
+                                    stream_info.command.kill();
+                                }
+                                stall_respawned.insert(name.clone());
+                                Some("long stall")
+                            }
+                            None => {
+                                warn!("Stream {} command is stalled", name);
+                                None
+                            }
                         },
                         StreamHealth::Running => {
                             match audio_health {
+                                AudioStreamHealth::Dead if config.respawn_on_dead_audio => {
+                                    error!("Stream {} audio processing is dead", name);
+                                    Some("dead audio")
+                                },
                                 AudioStreamHealth::Dead => {
-                                    error!("Stream {} audio processing is dead, attempting respawn", name);
-                                    if stream_info.command.respawn().await {
-                                        info!("Stream {} successfully respawned due to dead audio", name);
-                                    } else {
-                                        error!("Stream {} failed to respawn (max restarts exceeded)", name);
-                                    }
+                                    warn!("Stream {} audio processing is dead, respawning on dead audio is disabled", name);
+                                    None
                                 },
                                 AudioStreamHealth::Degraded => {
                                     warn!("Stream {} audio processing degraded", name);
+                                    None
                                 },
                                 AudioStreamHealth::NoData => {
                                     warn!("Stream {} has no audio data yet", name);
+                                    None
                                 },
                                 AudioStreamHealth::Running => {
                                     // All good
+                                    None
                                 }
                             }
                         }
+                    };
+
+                    let mut process_dead = false;
+                    let mut needs_confirmation = false;
+                    if let Some(cause) = cause {
+                        if config.manual_streams.contains(&name) {
+                            if awaiting_confirmation.write().expect("confirmation lock poisoned").insert(name.clone()) {
+                                warn!("Stream {} has a {} and needs confirmation before it is respawned", name, cause);
+                            }
+                            needs_confirmation = true;
+                        } else if config.gave_up(stream_info.command.get_restart_policy(), stream_info.command.get_restart_count().await) {
+                            error!("Stream {} failed to respawn (max restarts exceeded), waiting for a restart", name);
+                            process_dead = true;
+                        } else if no_spawn {
+                            let backoff = config.backoff(stream_info.command.get_restart_policy(), stream_info.command.get_restart_count().await);
+                            info!("Would respawn stream {} ({}) after {}s", name, cause, backoff.as_secs());
+                        } else {
+                            let backoff = config.backoff(stream_info.command.get_restart_policy(), stream_info.command.get_restart_count().await);
+                            info!("Respawning stream {} ({}) after {}s", name, cause, backoff.as_secs());
+                            if stream_info.command.respawn(backoff).await {
+                                info!("Stream {} successfully respawned", name);
+                            } else {
+                                error!("Stream {} failed to respawn (max restarts exceeded)", name);
+                                process_dead = true;
+                            }
+                        }
+                    } else if cmd_health == StreamHealth::Running {
+                        awaiting_confirmation.write().expect("confirmation lock poisoned").remove(&name);
+                    }
+
+                    if let Some(ref store) = uptime_store {
+                        let state = if process_dead { format!("{:?}", StreamHealth::Dead) } else { format!("{:?}", cmd_health) };
+                        store.record(&name, cause.is_none() && cmd_health == StreamHealth::Running,
+                            &state, stream_info.command.get_respawn_count());
+                    }
+
+                    // A stalled command may still recover, only a running one clears the alert
+                    if let Some(ref am) = alert_manager {
+                        let is_error = process_dead || needs_confirmation;
+                        if is_error || cmd_health == StreamHealth::Running {
+                            let message = if needs_confirmation {
+                                format!("Stream `{}` is down and waiting for confirmation to respawn: reply `restart {}` or POST /api/streams/{}/restart",
+                                    name, name, name)
+                            } else if process_dead {
+                                format!("Stream `{}` process is dead and could not be respawned", name)
+                            } else {
+                                format!("Stream `{}` process is running again", name)
+                            };
+                            am.update_alert(format!("{}_process", name), AlertReason::ProcessDead, &[name.as_str()], is_error, message).await;
+                        }
+
+                        if stall_respawned.contains(&name) {
+                            let recovered = healthy.contains(&name);
+                            let message = if recovered {
+                                format!("Stream `{}` is producing audio again after its stall", name)
+                            } else {
+                                format!("Stream `{}` stopped producing output and was respawned", name)
+                            };
+                            am.update_alert(format!("{}_stalled", name), AlertReason::ProcessStalled, &[name.as_str()], !recovered, message).await;
+                            if recovered {
+                                stall_respawned.remove(&name);
+                            }
+                        }
+
+                        if config.discontinuities_per_hour > 0 {
+                            let (recent, _) = stream_info.audio.get_discontinuities(chrono::Duration::hours(1));
+                            let is_error = recent >= config.discontinuities_per_hour;
+                            let message = if is_error {
+                                format!("Stream `{}` audio jumped {} times in the last hour, its origin encoder may be restarting", name, recent)
+                            } else {
+                                format!("Stream `{}` audio is continuous again", name)
+                            };
+                            am.update_alert(format!("{}_discontinuity", name), AlertReason::EncoderRestart, &[name.as_str()], is_error, message).await;
+                        }
+
+                        if stream_info.command.has_backup() {
+                            let on_backup = stream_info.command.is_on_backup();
+                            let message = if on_backup {
+                                format!("Stream `{}` primary source is down, playing from its backup", name)
+                            } else {
+                                format!("Stream `{}` is back on its primary source", name)
+                            };
+                            am.update_alert(format!("{}_backup", name), AlertReason::OnBackup, &[name.as_str()], on_backup, message).await;
+                        }
+                    }
+                }
+                drop(streams_lock);
+
+                // Channel minimums are judged on health alone, so they fire even when comparisons lack data
+                if let Some(ref am) = alert_manager {
+                    let thresholds = min_healthy.read().expect("min healthy lock poisoned").clone();
+                    // A stream whose scanning SDR is tuned elsewhere can't be judged, give it the benefit of the doubt
+                    let not_sampled = unsampled.read().expect("unsampled lock poisoned").clone();
+                    for (channel_name, min) in thresholds {
+                        let Some(stream_names) = channels.read().expect("channels lock poisoned").get(&channel_name).cloned() else { continue };
+                        let (up, down): (Vec<&String>, Vec<&String>) = stream_names.iter()
+                            .partition(|name| healthy.contains(*name) || not_sampled.contains(*name));
+                        let is_error = up.len() < min;
+                        let message = if is_error {
+                            let down: Vec<String> = down.iter().map(|name| format!("`{}`", name)).collect();
+                            format!("Channel `{}` has {} of {} streams healthy (need ≥{}), down: {}",
+                                channel_name, up.len(), stream_names.len(), min, down.join(", "))
+                        } else {
+                            format!("Channel `{}` has {} of {} streams healthy again", channel_name, up.len(), stream_names.len())
+                        };
+                        let streams: Vec<&str> = stream_names.iter().map(String::as_str).collect();
+                        am.update_alert(format!("{}_min_healthy", channel_name), AlertReason::ChannelDegraded, &streams, is_error, message).await;
                     }
                 }
             }
         });
     }
 
+    /// A fresh subscription to the stream's decoded PCM, for analyses that need every sample
+    pub async fn get_stream_reader(&self, stream_name: &str) -> Option<tokio::sync::broadcast::Receiver<Vec<u8>>> {
+        let streams = self.streams.lock().await;
+        streams.get(stream_name).map(|stream_info| stream_info.command.get_reader())
+    }
+
     pub async fn get_stream_fingerprint(&self, stream_name: &str) -> Option<Vec<u32>> {
         let streams = self.streams.lock().await;
         if let Some(stream_info) = streams.get(stream_name) {
@@ -121,6 +557,167 @@ impl AudioRouter {
         }
     }
 
+    pub async fn get_stream_versioned_fingerprint(&self, stream_name: &str) -> Option<(u64, Vec<u32>)> {
+        let streams = self.streams.lock().await;
+        match streams.get(stream_name) {
+            Some(stream_info) => Some(stream_info.audio.get_versioned_fingerprint().await),
+            None => None,
+        }
+    }
+
+    /// Fingerprint items a stream needs before it is compared, its own minimum or else `default`
+    pub async fn get_stream_min_buffer(&self, stream_name: &str, default: usize) -> usize {
+        let streams = self.streams.lock().await;
+        streams.get(stream_name).and_then(|stream_info| stream_info.audio.get_min_buffer_size()).unwrap_or(default)
+    }
+
+    /// Records a configured stream that could not be started, so it shows as errored instead of missing
+    pub fn mark_failed(&self, channel_name: &str, stream_name: &str, error: &str) {
+        self.failed.write().expect("failed lock poisoned")
+            .insert(stream_name.to_string(), (channel_name.to_string(), error.to_string()));
+    }
+
+    /// (stream, channel, error) of every stream that could not be started
+    pub fn get_failed_streams(&self) -> Vec<(String, String, String)> {
+        self.failed.read().expect("failed lock poisoned").iter()
+            .map(|(stream, (channel, error))| (stream.clone(), channel.clone(), error.clone()))
+            .collect()
+    }
+
+    /// Opts a channel in or out of comparisons against the silence reference channel
+    pub fn set_silence_check(&self, channel_name: &str, enabled: bool) {
+        let mut silence_checked = self.silence_checked.write().expect("silence check lock poisoned");
+        if enabled {
+            silence_checked.insert(channel_name.to_string());
+        } else {
+            silence_checked.remove(channel_name);
+        }
+    }
+
+    /// Alert on the channel as a whole when fewer of its streams than this have running command and audio
+    pub fn set_min_healthy_streams(&self, channel_name: &str, min_healthy: Option<usize>) {
+        let mut thresholds = self.min_healthy.write().expect("min healthy lock poisoned");
+        match min_healthy {
+            Some(min) => thresholds.insert(channel_name.to_string(), min),
+            None => thresholds.remove(channel_name),
+        };
+    }
+
+    /// Comparison settings the channel uses instead of the global ones
+    pub fn set_comparison_overrides(&self, channel_name: &str, overrides: ComparisonOverrides) {
+        let mut all = self.comparison_overrides.write().expect("comparison overrides lock poisoned");
+        all.insert(channel_name.to_string(), overrides);
+    }
+
+    pub fn get_comparison_overrides(&self, channel_name: &str) -> ComparisonOverrides {
+        self.comparison_overrides.read().expect("comparison overrides lock poisoned")
+            .get(channel_name).copied().unwrap_or_default()
+    }
+
+    pub fn set_tuned_thresholds(&self, tuned: TunedThresholds) {
+        *self.tuned_thresholds.write().expect("tuned thresholds lock poisoned") = tuned;
+    }
+
+    /// Sets a threshold at runtime, for one channel or all of them
+    pub fn tune_threshold(&self, kind: ThresholdKind, value: f32, channel_name: Option<&str>) {
+        self.tuned_thresholds.write().expect("tuned thresholds lock poisoned").set(kind, value, channel_name);
+    }
+
+    pub fn get_tuned_thresholds(&self) -> TunedThresholds {
+        self.tuned_thresholds.read().expect("tuned thresholds lock poisoned").clone()
+    }
+
+    /// Replaces the time-of-day schedules, at startup and on config reloads
+    pub fn set_threshold_schedules(&self, schedules: Vec<ThresholdSchedule>) {
+        *self.threshold_schedules.write().expect("threshold schedules lock poisoned") = schedules;
+    }
+
+    /// The schedules in effect right now, in local time
+    pub fn get_active_schedules(&self) -> ActiveSchedules {
+        let schedules = self.threshold_schedules.read().expect("threshold schedules lock poisoned");
+        ActiveSchedules::at(&schedules, chrono::Local::now().naive_local())
+    }
+
+    /// Silence levels of one stream, from its own or its channel's config
+    pub fn set_volume_limits(&self, stream_name: &str, limits: VolumeLimits) {
+        self.volume_limits.write().expect("volume limits lock poisoned").insert(stream_name.to_string(), limits);
+    }
+
+    /// Whether streams alert on their volume dropping below a level
+    pub fn has_silence_threshold(&self) -> bool {
+        self.minimum_max_volume_threshold.is_some()
+    }
+
+    pub fn has_silence_check(&self, channel_name: &str) -> bool {
+        self.silence_checked.read().expect("silence check lock poisoned").contains(channel_name)
+    }
+
+    /// Marks whether a stream's SDR is currently tuned to it; unsampled streams are left out of comparisons and alerts
+    pub fn set_stream_sampled(&self, stream_name: &str, sampled: bool) {
+        let mut unsampled = self.unsampled.write().expect("unsampled lock poisoned");
+        if sampled {
+            unsampled.remove(stream_name);
+        } else {
+            unsampled.insert(stream_name.to_string());
+        }
+    }
+
+    pub fn is_stream_sampled(&self, stream_name: &str) -> bool {
+        !self.unsampled.read().expect("unsampled lock poisoned").contains(stream_name)
+    }
+
+    /// How far a stream's fingerprint buffer is towards the items comparisons wait for, `items_needed` unless the stream sets its own
+    pub async fn get_stream_buffer(&self, stream_name: &str, items_needed: usize) -> Option<BufferStatus> {
+        let (items, items_needed) = {
+            let streams = self.streams.lock().await;
+            let audio = &streams.get(stream_name)?.audio;
+            (audio.get_fingerprint_len().await, audio.get_min_buffer_size().unwrap_or(items_needed))
+        };
+        let item_duration = Configuration::preset_test1().item_duration_in_seconds();
+        Some(BufferStatus {
+            items,
+            items_needed,
+            ready_in_seconds: items_needed.saturating_sub(items) as f32 * item_duration,
+        })
+    }
+
+    /// Subscribe to a stream's raw s16le 44.1kHz stereo output
+    pub async fn subscribe_stream(&self, stream_name: &str) -> Option<tokio::sync::broadcast::Receiver<Vec<u8>>> {
+        let streams = self.streams.lock().await;
+        streams.get(stream_name).map(|stream_info| stream_info.command.get_reader())
+    }
+
+    /// Attach the stderr log of an upstream process (nrsc5, rtl_tcp) feeding a stream
+    pub fn attach_upstream_log(&self, stream_name: &str, process: &str, log: StderrLog) {
+        self.upstream_logs.write().expect("upstream logs lock poisoned")
+            .entry(stream_name.to_string()).or_default().push((process.to_string(), log));
+    }
+
+    /// Raw stderr of every process feeding a stream, the stream's own ffmpeg first
+    pub async fn get_stream_logs(&self, stream_name: &str) -> Option<Vec<ProcessLog>> {
+        let ffmpeg_log = {
+            let streams = self.streams.lock().await;
+            streams.get(stream_name)?.command.get_stderr_log()
+        };
+
+        let upstream = self.upstream_logs.read().expect("upstream logs lock poisoned")
+            .get(stream_name).cloned().unwrap_or_default();
+
+        let mut logs = vec![ProcessLog { process: "ffmpeg".to_string(), lines: ffmpeg_log.get_lines().await }];
+        for (process, log) in upstream {
+            logs.push(ProcessLog { process, lines: log.get_lines().await });
+        }
+        Some(logs)
+    }
+
+    pub async fn get_stream_stderr(&self, stream_name: &str) -> Option<Vec<StderrSummary>> {
+        let streams = self.streams.lock().await;
+        match streams.get(stream_name) {
+            Some(stream_info) => Some(stream_info.command.get_recent_stderr().await),
+            None => None,
+        }
+    }
+
     pub async fn get_stream_health(&self, stream_name: &str) -> Option<(StreamHealth, AudioStreamHealth)> {
         let streams = self.streams.lock().await;
         if let Some(stream_info) = streams.get(stream_name) {
@@ -132,21 +729,75 @@ impl AudioRouter {
         }
     }
 
+    /// Waits until every listed stream is producing fingerprints, or the timeout passes
+    /// Returns whether they all came up in time
+    pub async fn wait_for_audio(&self, stream_names: &[String], timeout: Duration) -> bool {
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let mut all_running = true;
+            for name in stream_names {
+                if !matches!(self.get_stream_health(name).await, Some((_, AudioStreamHealth::Running))) {
+                    all_running = false;
+                    break;
+                }
+            }
+            if all_running {
+                return true;
+            }
+            if tokio::time::Instant::now() >= deadline {
+                return false;
+            }
+            tokio::time::sleep(Duration::from_secs(1)).await;
+        }
+    }
+
+    /// Audio timeline jumps of a stream within `window`, and in total
+    pub async fn get_stream_discontinuities(&self, stream_name: &str, window: chrono::Duration) -> Option<(usize, u64)> {
+        let streams = self.streams.lock().await;
+        streams.get(stream_name).map(|stream_info| stream_info.audio.get_discontinuities(window))
+    }
+
+    pub async fn get_stream_dropouts(&self, stream_name: &str, window: chrono::Duration) -> Option<(usize, u64)> {
+        let streams = self.streams.lock().await;
+        streams.get(stream_name).map(|stream_info| stream_info.audio.get_dropouts(window))
+    }
+
+    pub async fn is_stream_on_backup(&self, stream_name: &str) -> bool {
+        let streams = self.streams.lock().await;
+        streams.get(stream_name).is_some_and(|stream_info| stream_info.command.is_on_backup())
+    }
+
+    pub async fn get_stream_respawns(&self, stream_name: &str) -> Option<u64> {
+        let streams = self.streams.lock().await;
+        streams.get(stream_name).map(|stream_info| stream_info.command.get_respawn_count())
+    }
+
+    pub async fn get_stream_soft_eofs(&self, stream_name: &str) -> Option<u64> {
+        let streams = self.streams.lock().await;
+        streams.get(stream_name).map(|stream_info| stream_info.command.get_soft_eof_count())
+    }
+
+    /// Time the stream has been up; with a stats database this spans watchdog restarts and is zero while the stream is down
     pub async fn get_stream_uptime(&self, stream_name: &str) -> Option<chrono::Duration> {
         let streams = self.streams.lock().await;
-        if let Some(stream_info) = streams.get(stream_name) {
-            Some(stream_info.command.get_uptime())
-        } else {
-            None
+        let stream_info = streams.get(stream_name)?;
+        match self.get_persisted_stats(stream_name) {
+            Some(stats) => Some(stats.up_since.map_or(chrono::Duration::zero(), |since| chrono::Utc::now() - since)),
+            None => Some(stream_info.command.get_uptime()),
         }
     }
 
+    /// Cumulative uptime and restart count from the stats database, if one is configured and has seen the stream
+    pub fn get_persisted_stats(&self, stream_name: &str) -> Option<PersistedStats> {
+        self.uptime_store.as_ref()?.get(stream_name)
+    }
+
     pub fn get_channel_streams(&self, channel_name: &str) -> Option<Vec<String>> {
-        self.channels.get(channel_name).cloned()
+        self.channels.read().expect("channels lock poisoned").get(channel_name).cloned()
     }
 
     pub fn get_all_channels(&self) -> Vec<String> {
-        self.channels.keys().cloned().collect()
+        self.channels.read().expect("channels lock poisoned").keys().cloned().collect()
     }
 
     pub async fn get_stream_volume(&self, stream_name: &str) -> Option<VolumeMetrics> {
@@ -154,47 +805,178 @@ impl AudioRouter {
         metrics.get(stream_name).copied()
     }
 
+    pub async fn get_stream_volume_history(&self, stream_name: &str) -> Vec<VolumeSample> {
+        let history = self.volume_history.lock().await;
+        history.get(stream_name).map(|samples| samples.iter().copied().collect()).unwrap_or_default()
+    }
+
     pub async fn get_all_stream_volumes(&self) -> HashMap<String, VolumeMetrics> {
         self.volume_metrics.lock().await.clone()
     }
 
+    pub async fn get_dead_air_stats(&self) -> HashMap<String, DeadAirStats> {
+        match self.dead_air {
+            Some(ref tracker) => tracker.get_stats().await,
+            None => HashMap::new(),
+        }
+    }
+
     pub async fn start_volume_detection_loop(&self, interval_seconds: u64) {
-        info!("Starting volume detection loop (interval: {}s)", interval_seconds);
+        info!("Starting volume detection loop (interval: {}s, max {} concurrent analyses, {})",
+              interval_seconds, self.volume_concurrency, if self.native_volume { "native" } else { "ffmpeg" });
         let streams = self.streams.clone();
         let volume_metrics = self.volume_metrics.clone();
+        let volume_history = self.volume_history.clone();
         let alert_manager = self.alert_manager.clone();
         let minimum_max_volume_threshold = self.minimum_max_volume_threshold;
+        let tuned_thresholds = self.tuned_thresholds.clone();
+        let threshold_schedules = self.threshold_schedules.clone();
+        let volume_limits = self.volume_limits.clone();
+        let stereo_detection = self.stereo_detection;
+        let channels = self.channels.clone();
+        let dead_air = self.dead_air.clone();
+        let native_volume = self.native_volume;
+        let unsampled = self.unsampled.clone();
+        let dropouts_per_hour = self.dropout.alert_per_hour;
+        let semaphore = Arc::new(Semaphore::new(self.volume_concurrency));
         tokio::spawn(async move {
             loop {
                 tokio::time::sleep(Duration::from_secs(interval_seconds)).await;
 
-                let streams_lock = streams.lock().await;
-                let stream_names: Vec<String> = streams_lock.keys().cloned().collect();
-                drop(streams_lock);
+                // Grab detector handles under the lock, then release it before any ffmpeg work
+                let mut detectors: Vec<(String, VolumeDetector)> = Vec::new();
+                let mut healthy: HashSet<String> = HashSet::new();
+                {
+                    let streams_lock = streams.lock().await;
+                    for (name, stream_info) in streams_lock.iter() {
+                        detectors.push((name.clone(), stream_info.audio.get_volume_detector()));
+                        if stream_info.command.get_health().await == StreamHealth::Running
+                            && stream_info.audio.get_health().await == AudioStreamHealth::Running {
+                            healthy.insert(name.clone());
+                        }
+                    }
+                }
+
+                // Dropouts are counted continuously as audio arrives, the silence reference is silent by design
+                let dropout_counts: HashMap<String, usize> = detectors.iter()
+                    .filter(|(name, _)| name != "silence")
+                    .map(|(name, detector)| (name.clone(), detector.get_dropouts(chrono::Duration::hours(1)).0))
+                    .collect();
+
+                // Run the analyses concurrently, bounded by the semaphore
+                let mut tasks = JoinSet::new();
+                for (stream_name, detector) in detectors {
+                    let semaphore = semaphore.clone();
+                    tasks.spawn(async move {
+                        let _permit = semaphore.acquire_owned().await.expect("volume semaphore closed");
+                        let metrics = if native_volume {
+                            detector.get_native_metrics().await
+                        } else {
+                            detector.get_metrics().await
+                        };
+                        let profile = if stereo_detection { Some(detector.get_stereo_profile().await) } else { None };
+                        (stream_name, metrics, profile)
+                    });
+                }
+
+                // Silence thresholds set at runtime or by the time of day apply per channel
+                let tuned = tuned_thresholds.read().expect("tuned thresholds lock poisoned").clone();
+                let scheduled = ActiveSchedules::at(&threshold_schedules.read().expect("threshold schedules lock poisoned"),
+                    chrono::Local::now().naive_local());
+                let limits = volume_limits.read().expect("volume limits lock poisoned").clone();
+                let stream_channels: HashMap<String, String> = channels.read().expect("channels lock poisoned").iter()
+                    .flat_map(|(channel, streams)| streams.iter().map(move |stream| (stream.clone(), channel.clone())))
+                    .collect();
+
+                // Dead air is judged against the same thresholds as the silence alert, in every silence mode
+                let silence_threshold = minimum_max_volume_threshold.or(dead_air.as_ref().map(|tracker| tracker.silence_threshold()));
+                let mut dead_air_passes: HashMap<String, bool> = HashMap::new(); // stream -> silent this pass
 
                 // Collect volume metrics for all streams
                 let mut new_metrics = HashMap::new();
-                for stream_name in stream_names {
-                    let streams_lock = streams.lock().await;
-                    if let Some(stream_info) = streams_lock.get(&stream_name) {
-                        let metrics = stream_info.audio.get_volume_metrics().await;
-                        new_metrics.insert(stream_name.clone(), metrics);
-                        debug!("Stream '{}': mean={:.1} dB, max={:.1} dB",
-                            stream_name, metrics.mean_volume, metrics.max_volume);
-                        if let Some(ref am) = alert_manager {
-                        let alert_id = format!("{}_{}", stream_name, "silence");
-                        let is_error = metrics.max_volume < minimum_max_volume_threshold.unwrap();
+                let mut profiles = HashMap::new();
+                while let Some(joined) = tasks.join_next().await {
+                    let (stream_name, metrics, profile) = match joined {
+                        Ok(result) => result,
+                        Err(e) => {
+                            error!("Volume analysis task failed: {:?}", e);
+                            continue;
+                        }
+                    };
+                    debug!("Stream '{}': mean={:.1} dB, max={:.1} dB",
+                        stream_name, metrics.mean_volume, metrics.max_volume);
+                    let sampled = !unsampled.read().expect("unsampled lock poisoned").contains(&stream_name);
+                    if let (Some(am), Some(&recent), true) = (&alert_manager, dropout_counts.get(&stream_name), sampled && dropouts_per_hour > 0) {
+                        let is_error = recent >= dropouts_per_hour;
                         let message = if is_error {
+                            format!("Stream `{}` dropped out {} times in the last hour", stream_name, recent)
+                        } else {
+                            format!("Stream `{}` has no more short dropouts", stream_name)
+                        };
+                        am.update_alert(format!("{}_dropouts", stream_name), AlertReason::Dropout, &[stream_name.as_str()], is_error, message).await;
+                    }
+                    // The placeholder of a stream still buffering, or whose analysis failed, isn't silence
+                    if let (Some(configured), true) = (silence_threshold, sampled && metrics.analyzed) {
+                        // Runtime tuning, then the schedule, beat the config at each level: the channel's, then the global one
+                        let stream_limits = limits.get(&stream_name).copied().unwrap_or_default();
+                        let channel = stream_channels.get(&stream_name);
+                        let threshold = channel
+                            .and_then(|channel| tuned.channel(ThresholdKind::Silence, channel)
+                                .or(scheduled.channel(ThresholdKind::Silence, channel)))
+                            .or(stream_limits.min_max_volume_db)
+                            .or(tuned.global(ThresholdKind::Silence))
+                            .or(scheduled.global(ThresholdKind::Silence))
+                            .unwrap_or(configured);
+                        let too_quiet = stream_limits.min_mean_volume_db.filter(|min_mean| metrics.mean_volume < *min_mean);
+                        let quiet = metrics.max_volume < threshold || too_quiet.is_some();
+                        let expected = channel.and_then(|channel| scheduled.silence_expected(channel)).filter(|_| quiet);
+                        let is_error = quiet && expected.is_none();
+                        dead_air_passes.insert(stream_name.clone(), is_error);
+                        if let (Some(am), true) = (&alert_manager, minimum_max_volume_threshold.is_some()) {
+                            let message = if let Some(schedule) = expected {
+                                format!("Stream `{}` is quiet ({:.1} dB), as expected during {}",
+                                    stream_name, metrics.max_volume, schedule)
+                            } else if metrics.max_volume < threshold {
                                 format!("Stream `{}` is silent ({:.1} dB, need ≥{:.1} dB)",
-                                    stream_name, metrics.max_volume, minimum_max_volume_threshold.unwrap())
+                                    stream_name, metrics.max_volume, threshold)
+                            } else if let Some(min_mean) = too_quiet {
+                                format!("Stream `{}` is too quiet (mean {:.1} dB, need ≥{:.1} dB)",
+                                    stream_name, metrics.mean_volume, min_mean)
                             } else {
                                 format!("Stream `{}` is playing normally again ({:.1} dB)",
                                     stream_name, metrics.max_volume)
                             };
-                            am.update_alert(alert_id, is_error, message).await;
+                            am.update_alert(format!("{}_silence", stream_name), AlertReason::Silence, &[stream_name.as_str()], is_error, message).await;
+                        }
+                    }
+                    if let Some(profile) = profile {
+                        profiles.insert(stream_name.clone(), profile);
+                    }
+                    new_metrics.insert(stream_name, metrics);
+                }
+
+                if stereo_detection {
+                    if let Some(ref am) = alert_manager {
+                        let channels = channels.read().expect("channels lock poisoned").clone();
+                        Self::check_stereo(am, &channels, &new_metrics, &profiles, &healthy).await;
+                    }
+                }
+
+                if let Some(ref tracker) = dead_air {
+                    tracker.record(&dead_air_passes).await;
+                }
+
+                // Append to the per-stream history
+                {
+                    let now = chrono::Utc::now();
+                    let mut history = volume_history.lock().await;
+                    for (stream_name, metrics) in &new_metrics {
+                        let samples = history.entry(stream_name.clone()).or_default();
+                        samples.push_back(VolumeSample { timestamp: now, mean_volume: metrics.mean_volume, max_volume: metrics.max_volume });
+                        while samples.len() > VOLUME_HISTORY_SAMPLES {
+                            samples.pop_front();
                         }
                     }
-                    drop(streams_lock);
                 }
 
                 // Update stored metrics
@@ -203,6 +985,76 @@ impl AudioRouter {
         });
     }
 
+    async fn check_stereo(
+        alert_manager: &AlertManager,
+        channels: &HashMap<String, Vec<String>>,
+        metrics: &HashMap<String, VolumeMetrics>,
+        profiles: &HashMap<String, StereoProfile>,
+        healthy: &HashSet<String>
+    ) {
+        for (channel_name, stream_names) in channels {
+            if channel_name == "silence" {
+                continue;
+            }
+
+            // Single-channel-dead: one side far below the other while the other carries audio
+            let mut dead_channel: HashSet<&String> = HashSet::new();
+            for stream_name in stream_names {
+                let Some(m) = metrics.get(stream_name).filter(|m| m.analyzed) else { continue };
+                let louder = m.stereo.left_rms.max(m.stereo.right_rms);
+                let is_error = louder >= STEREO_MIN_ACTIVE_DB && m.stereo.balance().abs() >= STEREO_DEAD_CHANNEL_DB;
+                let message = if is_error {
+                    dead_channel.insert(stream_name);
+                    let dead_side = if m.stereo.balance() > 0.0 { "right" } else { "left" };
+                    format!("Stream `{}` has a dead {} channel (L {:.1} dB, R {:.1} dB)",
+                        stream_name, dead_side, m.stereo.left_rms, m.stereo.right_rms)
+                } else {
+                    format!("Stream `{}` has audio on both stereo channels again", stream_name)
+                };
+                alert_manager.update_alert(format!("{}_stereo_dead", stream_name), AlertReason::StereoDead, &[stream_name.as_str()], is_error, message).await;
+            }
+
+            // Channel swap: only healthy, analyzed streams with distinct audio on both channels can be judged
+            let mut judged: Vec<&String> = stream_names.iter()
+                .filter(|name| healthy.contains(*name) && !dead_channel.contains(name) && profiles.contains_key(*name))
+                .filter(|name| metrics.get(*name).is_some_and(|m| m.analyzed
+                    && m.stereo.left_rms.max(m.stereo.right_rms) >= STEREO_MIN_ACTIVE_DB
+                    && m.stereo.correlation < STEREO_MONO_CORRELATION))
+                .collect();
+            judged.sort();
+            if judged.len() < 2 {
+                continue;
+            }
+
+            // Each stream is compared with every other, and is swapped when it disagrees with most of them, so a
+            // swapped stream can't flag the rest; with only two, the first by name is taken as right
+            for (i, stream_name) in judged.iter().enumerate() {
+                let mut agree: Vec<&str> = Vec::new();
+                let mut disagree: Vec<(&str, f32)> = Vec::new();
+                for other in judged.iter().filter(|other| *other != stream_name) {
+                    match profiles[*stream_name].image_correlation(&profiles[*other]) {
+                        Some(correlation) if correlation >= STEREO_SAME_IMAGE => agree.push(other.as_str()),
+                        Some(correlation) if correlation <= -STEREO_SAME_IMAGE => disagree.push((other.as_str(), correlation)),
+                        _ => {}
+                    }
+                }
+                if agree.is_empty() && disagree.is_empty() {
+                    continue; // nothing decisive this pass, e.g. near-mono program material
+                }
+                let is_error = disagree.len() > agree.len() && !(judged.len() == 2 && i == 0);
+                let message = if is_error {
+                    let others: Vec<String> = disagree.iter()
+                        .map(|(other, correlation)| format!("`{}` ({:+.2})", other, correlation))
+                        .collect();
+                    format!("Stream `{}` appears to have L/R swapped relative to {}", stream_name, others.join(", "))
+                } else {
+                    format!("Stream `{}` stereo image matches the rest of channel `{}` again", stream_name, channel_name)
+                };
+                alert_manager.update_alert(format!("{}_stereo_swap", stream_name), AlertReason::StereoSwap, &[stream_name.as_str()], is_error, message).await;
+            }
+        }
+    }
+
     pub async fn get_all_streams(&self) -> Vec<(String, StreamHealth, super::audiostream::AudioStreamHealth)> {
         let streams = self.streams.lock().await;
         let mut result = Vec::new();
@@ -216,13 +1068,27 @@ impl AudioRouter {
         result
     }
 
+    /// Fire-drill fault on one stream, see `CommandHolder::inject_fault`
+    pub async fn inject_fault(&self, stream_name: &str, fault: Fault, duration: Duration, delay: Duration) -> Result<(), String> {
+        match self.streams.lock().await.get(stream_name) {
+            Some(stream_info) => {
+                stream_info.command.inject_fault(fault, duration, delay);
+                Ok(())
+            }
+            None => Err(format!("Stream '{}' not found", stream_name)),
+        }
+    }
+
+    /// Operator-requested restart, also the confirmation manual streams wait for; skips the backoff
     pub async fn restart_stream(&self, stream_name: &str) -> Result<(), String> {
         let mut streams = self.streams.lock().await;
 
         match streams.get_mut(stream_name) {
             Some(stream_info) => {
                 info!("Restarting stream '{}' via command", stream_name);
-                if stream_info.command.respawn().await {
+                self.awaiting_confirmation.write().expect("confirmation lock poisoned").remove(stream_name);
+                if stream_info.command.respawn(Duration::ZERO).await {
+                    stream_info.command.reset_restart_count().await;
                     Ok(())
                 } else {
                     Err("Max restarts exceeded".to_string())
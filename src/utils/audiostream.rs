@@ -1,113 +1,238 @@
+use std::collections::VecDeque;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Instant;
 
-use rusty_chromaprint::{Configuration, Fingerprinter};
+use rusty_chromaprint::Configuration;
 use tokio::sync::{broadcast::Receiver, Mutex};
-use tracing::warn;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tracing::{info, warn};
 use chrono::{DateTime, Utc};
-use super::volumedetect::{VolumeDetector, VolumeMetrics};
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum AudioStreamHealth {
-    Running,
-    NoData,
-    Degraded,
-    Dead
+use super::bufferbudget::BufferBudget;
+use super::fingerprintpool::FingerprintPool;
+use super::volumedetect::{DropoutConfig, VolumeDetector, VolumeMetrics};
+
+pub use watchdog::api::AudioStreamHealth;
+
+const SAMPLE_RATE: f64 = 44100.0;
+const BYTES_PER_FRAME: usize = 4; // s16le stereo
+const DISCONTINUITY_SECONDS: f64 = 3.0; // audio running this far ahead of or behind the wall clock was a buffer reset
+const CONTINUITY_REANCHOR_SECONDS: f64 = 600.0; // keeps slow encoder clock drift from adding up to a false jump
+const TIMELINE_WARMUP_SECONDS: f64 = 10.0; // skips the burst of buffered audio a fresh connection starts with
+const TIMELINE_OUTAGE_SECONDS: f64 = 10.0; // longer silences are outages or respawns, alerted on elsewhere
+const DISCONTINUITY_HISTORY_HOURS: i64 = 24;
+
+/// Jumps in a stream's audio timeline, such as an origin encoder restarting behind a still-connected ffmpeg
+#[derive(Default)]
+struct DiscontinuityLog {
+    recent: VecDeque<DateTime<Utc>>,
+    total: u64,
+}
+
+/// Which part of the stereo signal the fingerprinter consumes
+#[derive(Debug, Clone, Copy, Default, Deserialize, PartialEq, JsonSchema)]
+pub enum FingerprintSource {
+    /// stereo handed to chromaprint, which mixes it down
+    #[default]
+    Mono,
+    Left,
+    Right
+}
+
+impl FingerprintSource {
+    fn channels(&self) -> u32 {
+        match self {
+            FingerprintSource::Mono => 2,
+            FingerprintSource::Left | FingerprintSource::Right => 1,
+        }
+    }
 }
 
 pub struct AudioStream {
     output: Arc<Mutex<Vec<u32>>>, // fingerprint data
+    version: Arc<AtomicU64>, // bumped whenever the fingerprint data changes, under its lock
     health: Arc<Mutex<AudioStreamHealth>>,
     last_fingerprint_update: Arc<Mutex<DateTime<Utc>>>,
+    discontinuities: Arc<std::sync::Mutex<DiscontinuityLog>>,
+    seed: Arc<std::sync::Mutex<Vec<u32>>>, // saved fingerprint from before a restart, ahead of the live one until that fills the buffer
+    min_buffer_size: Option<usize>, // fingerprint items needed before comparisons, overriding the comparator's global minimum
     volume_detector: VolumeDetector
 }
 
 impl AudioStream {
-    pub fn new(mut input: Receiver<Vec<u8>>, buffer_duration: f32) -> Self {
+    pub fn new(mut input: Receiver<Vec<u8>>, buffer_duration: f32, min_buffer_duration: Option<f32>, analysis_window: f32, dropout: &DropoutConfig, source: FingerprintSource, budget: &Arc<BufferBudget>, pool: &FingerprintPool) -> Self {
         let output = Arc::new(Mutex::new(vec![]));
         let health = Arc::new(Mutex::new(AudioStreamHealth::NoData));
         let last_update = Arc::new(Mutex::new(Utc::now()));
 
-        let thread_out = output.clone();
-        let thread_health = health.clone();
-        let thread_last_update = last_update.clone();
+        let version = Arc::new(AtomicU64::new(0));
+        let task_out = output.clone();
+        let task_version = version.clone();
+        let task_health = health.clone();
+        let task_last_update = last_update.clone();
+        let discontinuities = Arc::new(std::sync::Mutex::new(DiscontinuityLog::default()));
+        let task_discontinuities = discontinuities.clone();
+        let seed = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let task_seed = seed.clone();
 
         // Create a second receiver for volume detection
         let volume_input = input.resubscribe();
-        let volume_detector = VolumeDetector::new(volume_input, buffer_duration);
+        let volume_detector = VolumeDetector::new(volume_input, analysis_window, dropout, budget);
 
         let stream = AudioStream {
             output,
+            version,
             health,
             last_fingerprint_update: last_update,
+            discontinuities,
+            seed,
+            min_buffer_size: min_buffer_duration.map(|duration| (duration / Configuration::preset_test1().item_duration_in_seconds()) as usize),
             volume_detector
         };
 
         // Calculate record size based on configured buffer duration
         // Each fingerprint item represents ~0.1238 seconds (from Configuration::preset_test1())
         let record_size = (buffer_duration / Configuration::preset_test1().item_duration_in_seconds()) as usize;
+        let handle = budget.register(record_size * std::mem::size_of::<u32>());
 
-        // Capture runtime handle before spawning thread
-        let rt = tokio::runtime::Handle::current();
+        let fingerprinter = pool.open(source.channels());
 
-        std::thread::spawn(move || {
-            let mut fingerprinter = Fingerprinter::new(&Configuration::preset_test1());
-            fingerprinter.start(44100, 2).unwrap();
+        tokio::spawn(async move {
+            let mut odd_sample_count = false; // chunks aren't guaranteed to end on a frame boundary
+            let mut timeline: Option<(Instant, usize)> = None; // (anchor, bytes received since)
+            let mut connected_at: Option<Instant> = None;
+            let mut last_chunk: Option<Instant> = None;
             loop {
-                let samples = match rt.block_on(input.recv()) {
-                    Ok(data) => {
-                        rt.block_on(async {
-                            *thread_health.lock().await = AudioStreamHealth::Running;
-                        });
-                        unsafe {
-                            std::slice::from_raw_parts(
-                                data.as_ptr() as *const i16,
-                                data.len() / 2
-                            )
-                        }
-                    },
+                let data = match input.recv().await {
+                    Ok(data) => data,
                     Err(e) => {
                         warn!("AudioStream input closed: {:?}", e);
-                        rt.block_on(async {
-                            *thread_health.lock().await = AudioStreamHealth::Dead;
-                        });
+                        *task_health.lock().await = AudioStreamHealth::Dead;
                         break;
                     }
                 };
 
-                fingerprinter.consume(samples);
-                let fingerprint = fingerprinter.fingerprint();
+                // Audio arrives in real time, so received audio and elapsed time only part ways on a gap or burst
+                let now = Instant::now();
+                if last_chunk.is_some_and(|last| now.duration_since(last).as_secs_f64() > TIMELINE_OUTAGE_SECONDS) {
+                    timeline = None;
+                    connected_at = None;
+                }
+                last_chunk = Some(now);
+                let connected = *connected_at.get_or_insert(now);
+                timeline = match timeline {
+                    Some((anchor, received)) => {
+                        let elapsed = now.duration_since(anchor).as_secs_f64();
+                        let drift = (received / BYTES_PER_FRAME) as f64 / SAMPLE_RATE - elapsed;
+                        if drift.abs() > DISCONTINUITY_SECONDS {
+                            info!("Audio timeline jumped by {:.1}s, the source likely reset its buffer", drift);
+                            let mut log = task_discontinuities.lock().expect("discontinuity lock poisoned");
+                            let at = Utc::now();
+                            log.recent.push_back(at);
+                            log.total += 1;
+                            while log.recent.front().is_some_and(|t| at - *t > chrono::Duration::hours(DISCONTINUITY_HISTORY_HOURS)) {
+                                log.recent.pop_front();
+                            }
+                            Some((now, data.len()))
+                        } else if elapsed > CONTINUITY_REANCHOR_SECONDS {
+                            Some((now, data.len()))
+                        } else {
+                            Some((anchor, received + data.len()))
+                        }
+                    }
+                    None if now.duration_since(connected).as_secs_f64() >= TIMELINE_WARMUP_SECONDS => Some((now, data.len())),
+                    None => None,
+                };
+                *task_health.lock().await = AudioStreamHealth::Running;
+
+                let interleaved: Vec<i16> = data.chunks_exact(2)
+                    .map(|sample| i16::from_le_bytes([sample[0], sample[1]]))
+                    .collect();
+                let samples = match source {
+                    FingerprintSource::Mono => interleaved,
+                    FingerprintSource::Left | FingerprintSource::Right => {
+                        let channel = if source == FingerprintSource::Left { 0 } else { 1 };
+                        let offset = (channel + odd_sample_count as usize) % 2;
+                        odd_sample_count ^= interleaved.len() % 2 == 1;
+                        interleaved.iter().skip(offset).step_by(2).copied().collect()
+                    }
+                };
+
+                let record_size = handle.limit() / std::mem::size_of::<u32>();
+                let Some(fingerprint) = fingerprinter.consume(samples, record_size).await else {
+                    warn!("Fingerprint worker for this stream is gone");
+                    *task_health.lock().await = AudioStreamHealth::Dead;
+                    break;
+                };
 
                 if fingerprint.is_empty() {
                     // Empty fingerprints are normal at startup while buffering
-                    rt.block_on(async {
-                        *thread_health.lock().await = AudioStreamHealth::NoData;
-                    });
+                    *task_health.lock().await = AudioStreamHealth::NoData;
                 } else {
-                    rt.block_on(async {
-                        *thread_health.lock().await = AudioStreamHealth::Running;
-                        *thread_last_update.lock().await = Utc::now();
-                    });
+                    *task_health.lock().await = AudioStreamHealth::Running;
+                    *task_last_update.lock().await = Utc::now();
                 }
 
-                rt.block_on(async {
-                    let mut fingerprint_content = thread_out.lock().await;
-                    fingerprint_content.clear();
-                    fingerprint_content.extend(fingerprint.to_vec());
-                    if fingerprint_content.len() > record_size {
-                        let start = fingerprint_content.len() - record_size;
-                        *fingerprint_content = fingerprint_content.split_off(start);
+                let mut updated = Vec::with_capacity(record_size);
+                {
+                    let mut seed = task_seed.lock().expect("seed lock poisoned");
+                    if fingerprint.len() >= record_size {
+                        seed.clear();
                     }
-                });
+                    let from_seed = record_size.saturating_sub(fingerprint.len()).min(seed.len());
+                    updated.extend_from_slice(&seed[seed.len() - from_seed..]);
+                }
+                updated.extend_from_slice(&fingerprint);
+                handle.set_held(updated.len() * std::mem::size_of::<u32>());
+
+                // Chunks too short to complete a fingerprint item leave the buffer as it was
+                let mut fingerprint_content = task_out.lock().await;
+                if *fingerprint_content != updated {
+                    *fingerprint_content = updated;
+                    task_version.fetch_add(1, Ordering::Relaxed);
+                }
             }
         });
 
         stream
     }
 
+    /// Starts the buffer from a fingerprint saved before a restart, live audio pushes it out as it arrives
+    pub async fn seed_fingerprint(&self, saved: Vec<u32>) {
+        let mut output = self.output.lock().await;
+        if output.is_empty() && !saved.is_empty() {
+            output.extend_from_slice(&saved);
+            self.version.fetch_add(1, Ordering::Relaxed);
+        }
+        *self.seed.lock().expect("seed lock poisoned") = saved;
+    }
+
     pub async fn get_fingerprint(&self) -> Vec<u32> {
         self.output.lock().await.clone()
     }
 
+    /// The fingerprint along with its version, which only changes when the fingerprint does
+    pub async fn get_versioned_fingerprint(&self) -> (u64, Vec<u32>) {
+        let output = self.output.lock().await;
+        (self.version.load(Ordering::Relaxed), output.clone())
+    }
+
+    /// Timeline jumps within `window`, and in total since startup
+    pub fn get_discontinuities(&self, window: chrono::Duration) -> (usize, u64) {
+        let log = self.discontinuities.lock().expect("discontinuity lock poisoned");
+        let since = Utc::now() - window;
+        (log.recent.iter().filter(|t| **t >= since).count(), log.total)
+    }
+
+    pub async fn get_fingerprint_len(&self) -> usize {
+        self.output.lock().await.len()
+    }
+
+    pub fn get_min_buffer_size(&self) -> Option<usize> {
+        self.min_buffer_size
+    }
+
     pub async fn get_health(&self) -> AudioStreamHealth {
         self.health.lock().await.clone()
     }
@@ -119,4 +244,14 @@ impl AudioStream {
     pub async fn get_volume_metrics(&self) -> VolumeMetrics {
         self.volume_detector.get_metrics().await
     }
+
+    /// Near-silent dropouts within `window`, and in total since startup
+    pub fn get_dropouts(&self, window: chrono::Duration) -> (usize, u64) {
+        self.volume_detector.get_dropouts(window)
+    }
+
+    /// Cheap handle to the volume buffer so analysis can run without holding the router lock
+    pub fn get_volume_detector(&self) -> VolumeDetector {
+        self.volume_detector.clone()
+    }
 }
\ No newline at end of file
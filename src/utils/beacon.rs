@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use rusty_chromaprint::{match_fingerprints, Configuration, Fingerprinter};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tokio::process::Command;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+use watchdog::api::AlertReason;
+
+use super::alertmanager::AlertManager;
+use super::audiorouter::AudioRouter;
+
+// Share of a beacon's length that has to line up with the stream before it counts as heard
+const BEACON_MIN_MATCH_RATIO: f32 = 0.6;
+
+/// Station IDs or jingles expected to air on every stream of a channel
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct BeaconConfig {
+    /// Short audio clips of the ID/jingle, anything ffmpeg can decode
+    pub files: Vec<String>,
+    /// Alert when none of the clips has been heard for this long
+    #[serde(default = "default_beacon_period")]
+    pub period_minutes: u64,
+}
+
+fn default_beacon_period() -> u64 { 65 } // hourly ID plus some slack
+
+pub struct Beacon {
+    name: String,
+    fingerprint: Vec<u32>,
+    duration: f32, // seconds
+}
+
+impl Beacon {
+    /// Decodes an audio file with ffmpeg and fingerprints it with the same preset the streams use
+    pub async fn load(path: &str) -> Result<Self, String> {
+        let output = Command::new("ffmpeg")
+            .args(["-loglevel", "error", "-i", path, "-ar", "44100", "-ac", "2", "-f", "s16le", "-"])
+            .stdin(Stdio::null())
+            .output()
+            .await
+            .map_err(|e| format!("Failed to run ffmpeg on beacon {}: {}", path, e))?;
+        if !output.status.success() {
+            return Err(format!("ffmpeg could not decode beacon {}: {}", path, String::from_utf8_lossy(&output.stderr).trim()));
+        }
+
+        let samples: Vec<i16> = output.stdout
+            .chunks_exact(2)
+            .map(|b| i16::from_le_bytes([b[0], b[1]]))
+            .collect();
+
+        let config = Configuration::preset_test1();
+        let mut fingerprinter = Fingerprinter::new(&config);
+        fingerprinter.start(44100, 2).map_err(|e| format!("Could not start fingerprinter for {}: {:?}", path, e))?;
+        fingerprinter.consume(&samples);
+        fingerprinter.finish();
+        let fingerprint = fingerprinter.fingerprint().to_vec();
+        if fingerprint.is_empty() {
+            return Err(format!("Beacon {} is too short to fingerprint", path));
+        }
+
+        Ok(Beacon {
+            name: path.to_string(),
+            duration: fingerprint.len() as f32 * config.item_duration_in_seconds(),
+            fingerprint,
+        })
+    }
+
+    /// Whether this beacon shows up anywhere in a stream's fingerprint buffer
+    fn heard_in(&self, stream_fingerprint: &[u32]) -> bool {
+        let config = Configuration::preset_test1();
+        let Ok(segments) = match_fingerprints(stream_fingerprint, &self.fingerprint, &config) else {
+            return false;
+        };
+        segments.iter().any(|s| s.duration(&config) >= self.duration * BEACON_MIN_MATCH_RATIO)
+    }
+}
+
+pub struct BeaconTarget {
+    pub channel: String,
+    pub beacons: Vec<Beacon>,
+    pub period_minutes: u64,
+}
+
+/// Watches that each stream keeps airing its channel's station ID, catching legs that agree
+/// with each other but carry the wrong station
+pub struct BeaconWatcher {
+    router: Arc<AudioRouter>,
+    targets: Vec<BeaconTarget>,
+    interval_seconds: u64, // kept below the fingerprint buffer length so no airing is missed
+    last_heard: RwLock<HashMap<String, DateTime<Utc>>>, // stream name -> last beacon match
+    alert_manager: Option<Arc<AlertManager>>,
+}
+
+impl BeaconWatcher {
+    pub fn new(router: Arc<AudioRouter>, targets: Vec<BeaconTarget>, buffer_duration: f32) -> Self {
+        BeaconWatcher {
+            router,
+            targets,
+            interval_seconds: ((buffer_duration / 2.0) as u64).clamp(5, 60),
+            last_heard: RwLock::new(HashMap::new()),
+            alert_manager: None,
+        }
+    }
+
+    pub fn with_alert_manager(mut self, alert_manager: Arc<AlertManager>) -> Self {
+        self.alert_manager = Some(alert_manager);
+        self
+    }
+
+    pub async fn start_watch_loop(self: Arc<Self>) {
+        info!("Starting station beacon watch for {} channels (interval: {}s)", self.targets.len(), self.interval_seconds);
+        let started = Utc::now();
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(self.interval_seconds)).await;
+                for target in &self.targets {
+                    self.check(target, started).await;
+                }
+            }
+        });
+    }
+
+    async fn check(&self, target: &BeaconTarget, started: DateTime<Utc>) {
+        let Some(stream_names) = self.router.get_channel_streams(&target.channel) else { return };
+        let now = Utc::now();
+
+        for stream_name in stream_names {
+            let Some(fingerprint) = self.router.get_stream_fingerprint(&stream_name).await else { continue };
+
+            if let Some(beacon) = target.beacons.iter().find(|b| b.heard_in(&fingerprint)) {
+                debug!("Stream {} aired beacon {}", stream_name, beacon.name);
+                self.last_heard.write().await.insert(stream_name.clone(), now);
+            }
+
+            // Streams that never aired a beacon are measured from watchdog startup
+            let last = self.last_heard.read().await.get(&stream_name).copied().unwrap_or(started);
+            let silent_minutes = (now - last).num_minutes();
+            let is_error = silent_minutes >= target.period_minutes as i64;
+            if is_error {
+                warn!("Stream {} has not aired a station beacon in {} minutes", stream_name, silent_minutes);
+            }
+
+            if let Some(ref am) = self.alert_manager {
+                let message = if is_error {
+                    format!("Stream `{}` has not aired its station ID in {} minutes, it may be carrying the wrong station",
+                        stream_name, silent_minutes)
+                } else {
+                    format!("Stream `{}` is airing its station ID again", stream_name)
+                };
+                am.update_alert(format!("{}_beacon", stream_name), AlertReason::BeaconMissing, &[stream_name.as_str()], is_error, message).await;
+            }
+        }
+    }
+}
@@ -0,0 +1,47 @@
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+/// Station branding and wording of the web pages, so each affiliate's monitor reads as their own
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct Branding {
+    /// Page title and heading, "Watchdog Status" when unset
+    pub title: Option<String>,
+    /// Image shown beside the heading, e.g. /static/logo.png or a full URL
+    pub logo_url: Option<String>,
+    /// BCP 47 code for the pages' lang attribute, "en" when unset
+    pub language: Option<String>,
+    #[serde(default)]
+    pub labels: Labels,
+}
+
+/// Replacements for the pages' key labels, the English text is kept for any left unset
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct Labels {
+    pub last_updated: Option<String>,
+    pub active_alerts: Option<String>,
+    pub no_active_alerts: Option<String>,
+    pub recently_cleared: Option<String>,
+    pub comparisons: Option<String>,
+    pub within_channel: Option<String>,
+    pub cross_channel: Option<String>,
+    pub silence_checks: Option<String>,
+    pub stream_status: Option<String>,
+    pub channel: Option<String>,
+    pub back_to_status: Option<String>,
+    pub log_in: Option<String>,
+}
+
+impl Branding {
+    pub fn title(&self) -> &str {
+        self.title.as_deref().unwrap_or("Watchdog Status")
+    }
+
+    pub fn language(&self) -> &str {
+        self.language.as_deref().unwrap_or("en")
+    }
+}
+
+/// `label` when configured, else the built-in English `default`
+pub fn label<'a>(label: &'a Option<String>, default: &'a str) -> &'a str {
+    label.as_deref().unwrap_or(default)
+}
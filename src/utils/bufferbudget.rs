@@ -0,0 +1,103 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use tracing::{debug, info, warn};
+
+struct Registered {
+    requested: usize,
+    limit: Arc<AtomicUsize>,
+    held: Arc<AtomicUsize>,
+}
+
+/// Accounts for the bytes held by every stream's PCM and fingerprint buffers, and shrinks them all
+/// proportionally when what they ask for would exceed the configured cap
+pub struct BufferBudget {
+    cap: Option<usize>,
+    buffers: Mutex<HashMap<u64, Registered>>,
+    next_id: AtomicU64,
+    shrinking: AtomicBool,
+}
+
+/// One buffer's share of the budget; it trims itself to `limit()` and reports its size with `set_held`
+pub struct BufferHandle {
+    id: u64,
+    budget: Arc<BufferBudget>,
+    limit: Arc<AtomicUsize>,
+    held: Arc<AtomicUsize>,
+}
+
+impl BufferBudget {
+    pub fn new(cap: Option<usize>) -> Arc<Self> {
+        if let Some(cap) = cap {
+            info!("Audio buffers capped at {} MiB in total", cap / (1024 * 1024));
+        }
+        Arc::new(BufferBudget {
+            cap,
+            buffers: Mutex::new(HashMap::new()),
+            next_id: AtomicU64::new(0),
+            shrinking: AtomicBool::new(false),
+        })
+    }
+
+    pub fn register(self: &Arc<Self>, requested: usize) -> BufferHandle {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let limit = Arc::new(AtomicUsize::new(requested));
+        let held = Arc::new(AtomicUsize::new(0));
+        let mut buffers = self.buffers.lock().expect("buffer budget lock poisoned");
+        buffers.insert(id, Registered { requested, limit: limit.clone(), held: held.clone() });
+        self.rebalance(&buffers);
+        BufferHandle { id, budget: self.clone(), limit, held }
+    }
+
+    fn rebalance(&self, buffers: &HashMap<u64, Registered>) {
+        let requested: usize = buffers.values().map(|b| b.requested).sum();
+        let scale = match self.cap {
+            Some(cap) if requested > cap => cap as f64 / requested as f64,
+            _ => 1.0,
+        };
+        for buffer in buffers.values() {
+            buffer.limit.store((buffer.requested as f64 * scale) as usize, Ordering::Relaxed);
+        }
+        let was_shrinking = self.shrinking.swap(scale < 1.0, Ordering::Relaxed);
+        if scale < 1.0 && !was_shrinking {
+            warn!("Audio buffers want {} MiB, over the {} MiB cap, shrinking each to {:.0}% of its configured size",
+                requested / (1024 * 1024), self.cap.unwrap_or_default() / (1024 * 1024), scale * 100.0);
+        } else if scale >= 1.0 && was_shrinking {
+            info!("Audio buffers fit under the cap again, back to their configured sizes");
+        } else {
+            debug!("Audio buffers rebalanced to {:.0}% of {} MiB requested", scale * 100.0, requested / (1024 * 1024));
+        }
+    }
+
+    /// Bytes currently held across all buffers
+    pub fn held_bytes(&self) -> usize {
+        self.buffers.lock().expect("buffer budget lock poisoned").values().map(|b| b.held.load(Ordering::Relaxed)).sum()
+    }
+
+    /// Bytes all buffers would hold at their configured durations
+    pub fn requested_bytes(&self) -> usize {
+        self.buffers.lock().expect("buffer budget lock poisoned").values().map(|b| b.requested).sum()
+    }
+
+    pub fn cap_bytes(&self) -> Option<usize> {
+        self.cap
+    }
+}
+
+impl BufferHandle {
+    pub fn limit(&self) -> usize {
+        self.limit.load(Ordering::Relaxed)
+    }
+
+    pub fn set_held(&self, bytes: usize) {
+        self.held.store(bytes, Ordering::Relaxed);
+    }
+}
+
+impl Drop for BufferHandle {
+    fn drop(&mut self) {
+        let mut buffers = self.budget.buffers.lock().expect("buffer budget lock poisoned");
+        buffers.remove(&self.id);
+        self.budget.rebalance(&buffers);
+    }
+}
@@ -1,51 +1,284 @@
-use std::{process::Stdio, sync::Arc, time::Duration};
+use std::{collections::VecDeque, process::Stdio, sync::Arc, time::Duration};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
 use chrono::{DateTime, Utc};
 use tokio::io::AsyncWriteExt;
-use tokio::sync::broadcast::{self, Receiver, Sender};
+use tokio::sync::broadcast::{self, error::RecvError, Receiver, Sender};
 use tokio::sync::Mutex;
-use tokio::process::Command;
-use tracing::{error, trace, warn, info};
-use tokio::io::AsyncReadExt;
-
-#[derive(Debug, Clone, PartialEq)]
-pub enum StreamHealth {
-    Running,
-    Stalled,
-    Dead
+use tokio::process::{Child, Command};
+use tracing::{debug, error, trace, warn, info};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, BufReader};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+pub use watchdog::api::{StderrClass, StderrSummary};
+use super::stderrlog::{message_key, StderrLimiter, StderrLog};
+use super::pcm::PcmNormalizer;
+
+// How many distinct stderr lines to keep per stream
+const RECENT_STDERR_LINES: usize = 10;
+// The same stderr message is only logged again after this long, with a count of the copies held back
+const STDERR_REPEAT_REPORT: Duration = Duration::from_secs(60);
+// While on a backup source, the primary is tried this often
+const PRIMARY_RECHECK: Duration = Duration::from_secs(60);
+// A primary trial counts as recovered when it produces output within this long
+const PRIMARY_TRIAL_TIMEOUT: Duration = Duration::from_secs(15);
+
+fn classify_stderr(line: &str) -> StderrClass {
+    let lower = line.to_ascii_lowercase();
+    if lower.contains("error") || lower.contains("failed") || lower.contains("invalid") {
+        StderrClass::Error
+    } else if lower.contains("warning") {
+        StderrClass::Warning
+    } else {
+        StderrClass::Info
+    }
+}
+
+pub use watchdog::api::StreamHealth;
+
+/// Source quirks, like playlist rollovers, where ffmpeg exits and a plain restart is all it takes
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct TransientEofConfig {
+    /// stderr substrings marking an EOF as transient; empty means any EOF is
+    #[serde(default)]
+    pub patterns: Vec<String>,
+    /// how close to the EOF a matching line has to be
+    #[serde(default = "default_transient_window")]
+    pub window_seconds: i64,
+    /// beyond this the source is really failing, back to normal death handling
+    #[serde(default = "default_transient_max_per_hour")]
+    pub max_per_hour: usize,
+}
+
+fn default_transient_window() -> i64 { 10 }
+fn default_transient_max_per_hour() -> usize { 6 }
+
+#[derive(Debug, Default)]
+struct TransientState {
+    config: Option<TransientEofConfig>,
+    last_match: Option<DateTime<Utc>>, // last stderr line matching a pattern
+    eof_at: Option<DateTime<Utc>>, // stdout EOF of the current process
+    recent: VecDeque<DateTime<Utc>>, // soft restarts in the last hour
+    total: u64,
+}
+
+impl TransientState {
+    /// Whether the current death is a transient EOF that should be restarted right away
+    fn is_soft(&self, now: DateTime<Utc>) -> bool {
+        let (Some(config), Some(eof_at)) = (&self.config, self.eof_at) else { return false };
+        let window = chrono::Duration::seconds(config.window_seconds);
+        // The matching line may be read just before or just after stdout closes
+        let matched = config.patterns.is_empty()
+            || self.last_match.is_some_and(|t| t >= eof_at - window && t <= eof_at + window);
+        let recent = self.recent.iter().filter(|t| now - **t < chrono::Duration::hours(1)).count();
+        matched && recent < config.max_per_hour
+    }
+}
+
+/// Fire-drill faults injected through `/api/debug/inject`
+#[derive(Debug, Clone, Copy, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Fault {
+    Silence, // output replaced with digital silence
+    Kill, // process killed, respawned like after any other death
+    Lag, // output held back, as a stalling upstream buffer would
+}
+
+// Without output this long a process counts as stalled, unless the stream or supervisor config says otherwise
+const DEFAULT_STALL_TIMEOUT: Duration = Duration::from_secs(30);
+// A respawned process running this long has recovered, and the next death starts the backoff over
+const RECOVERED_AFTER: chrono::Duration = chrono::Duration::seconds(60);
+
+/// When a process without output counts as stalled, and when a stall is given up on as a death
+#[derive(Debug, Clone, Copy)]
+struct StallLimits {
+    timeout: Option<Duration>, // the stream's own stall timeout
+    default_timeout: Duration, // the supervisor's, used when the stream sets none
+    dead_after: Option<Duration>, // stalled this much longer and the supervisor respawns it, instead of its own default
+}
+
+impl StallLimits {
+    fn timeout(&self) -> Duration {
+        self.timeout.unwrap_or(self.default_timeout)
+    }
+}
+
+/// How a stream is respawned after dying, each unset field falling back to the supervisor's
+#[derive(Debug, Clone, Default)]
+pub struct RestartPolicy {
+    pub max_restarts: Option<u32>, // consecutive respawns before giving up until someone restarts it
+    pub backoff_seconds: Option<Vec<u64>>, // delay before each consecutive respawn, the last entry repeating
+}
+
+/// Injected faults still in effect on a stream's output
+#[derive(Debug, Default)]
+struct InjectedFaults {
+    silence_until: Option<DateTime<Utc>>,
+    lag: Option<(Duration, DateTime<Utc>)>, // (delay, until)
+}
+
+impl InjectedFaults {
+    fn silenced(&self, now: DateTime<Utc>) -> bool {
+        self.silence_until.is_some_and(|until| now < until)
+    }
+
+    fn delay(&self, now: DateTime<Utc>) -> Duration {
+        self.lag.filter(|(_, until)| now < *until).map_or(Duration::ZERO, |(delay, _)| delay)
+    }
+}
+
+/// Standby source used when the primary stays dead past its grace period
+#[derive(Debug)]
+struct BackupSource {
+    args: Vec<String>,
+    grace: chrono::Duration,
+    active: Arc<AtomicBool>, // running the backup args right now
+    primary_recovered: Arc<AtomicBool>, // a trial run of the primary produced output, switch back
+    primary_down_since: Arc<std::sync::Mutex<Option<DateTime<Utc>>>>,
+}
+
+/// Briefly runs the primary command and reports whether it produces any output
+async fn primary_produces_output(command: &str, args: &[String]) -> bool {
+    let Ok(mut child) = Command::new(command)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .kill_on_drop(true)
+        .spawn() else { return false };
+    let Some(mut stdout) = child.stdout.take() else { return false };
+    let mut buffer = [0u8; 4096];
+    let produced = matches!(tokio::time::timeout(PRIMARY_TRIAL_TIMEOUT, stdout.read(&mut buffer)).await, Ok(Ok(n)) if n > 0);
+    let _ = child.kill().await;
+    produced
 }
 
 #[derive(Debug)]
 pub struct CommandHolder {
+    name: String,
     last_message: Arc<Mutex<DateTime<Utc>>>,
     health: Arc<Mutex<StreamHealth>>,
     command: String,
     args: Vec<String>,
     output: Sender<Vec<u8>>,
     input: Option<Receiver<Vec<u8>>>,
-    restart_count: Arc<Mutex<u32>>,
-    stall_timeout: Duration,
+    relay_source: Option<Receiver<Vec<u8>>>, // in-process audio relayed without a process, resubscribed on every respawn
+    restart_count: Arc<Mutex<u32>>, // respawns since the process last ran long enough to count as recovered
+    respawned_at: Arc<Mutex<DateTime<Utc>>>,
+    stall: Arc<std::sync::Mutex<StallLimits>>,
+    restart_policy: RestartPolicy,
     start_time: DateTime<Utc>,
+    recent_stderr: Arc<Mutex<VecDeque<StderrSummary>>>,
+    stderr_log: StderrLog,
+    child: Arc<std::sync::Mutex<Option<Child>>>, // current process, kept so it can be killed on removal
+    stopped: Arc<Mutex<bool>>,
+    transient: Arc<std::sync::Mutex<TransientState>>,
+    backup: Option<BackupSource>,
+    respawns: AtomicU64, // since startup, for rate-based alerting on flapping processes
+    generation: Arc<AtomicU64>, // bumped per spawn, so a process replaced on purpose can't report its own exit as a death
+    faults: Arc<std::sync::Mutex<InjectedFaults>>,
 }
 
 impl CommandHolder {
-    pub fn new(command: &str, args: Vec<&str>, input: Option<Receiver<Vec<u8>>>) -> Self {
+    pub fn new(name: &str, command: &str, args: Vec<&str>, input: Option<Receiver<Vec<u8>>>) -> Self {
+        let mut cmd = Self::build(name, command, args, input);
+        cmd.spawn();
+        cmd.start_watchdog();
+        cmd
+    }
+
+    /// Carries audio from an in-process source, such as an nrsc5 decoder's WAV output, converted to
+    /// s16le 44.1 kHz stereo natively instead of through an ffmpeg of its own
+    pub fn relay(name: &str, input: Receiver<Vec<u8>>) -> Self {
+        let mut cmd = Self::build(name, "relay", vec![], None);
+        cmd.relay_source = Some(input);
+        cmd.spawn();
+        cmd.start_watchdog();
+        cmd
+    }
+
+    fn build(name: &str, command: &str, args: Vec<&str>, input: Option<Receiver<Vec<u8>>>) -> Self {
         let broadcast = broadcast::channel(1024);
-        let mut cmd = CommandHolder {
+        CommandHolder {
+            name: name.to_string(),
             last_message: Arc::new(Mutex::new(Utc::now())),
             health: Arc::new(Mutex::new(StreamHealth::Running)),
             command: command.to_string(),
             args: args.iter().map(|s| s.to_string()).collect(),
             output: broadcast.0,
             input,
+            relay_source: None,
             restart_count: Arc::new(Mutex::new(0)),
-            stall_timeout: Duration::from_secs(30),
+            respawned_at: Arc::new(Mutex::new(Utc::now())),
+            stall: Arc::new(std::sync::Mutex::new(StallLimits { timeout: None, default_timeout: DEFAULT_STALL_TIMEOUT, dead_after: None })),
+            restart_policy: RestartPolicy::default(),
             start_time: Utc::now(),
-        };
+            recent_stderr: Arc::new(Mutex::new(VecDeque::new())),
+            stderr_log: StderrLog::new(),
+            child: Arc::new(std::sync::Mutex::new(None)),
+            stopped: Arc::new(Mutex::new(false)),
+            transient: Arc::new(std::sync::Mutex::new(TransientState::default())),
+            backup: None,
+            respawns: AtomicU64::new(0),
+            generation: Arc::new(AtomicU64::new(0)),
+            faults: Arc::new(std::sync::Mutex::new(InjectedFaults::default())),
+        }
+    }
 
-        cmd.spawn();
-        cmd.start_watchdog();
+    /// Treats matching EOFs as soft events: restarted immediately, without backoff, and reported as stalled rather than dead
+    pub fn with_transient_eof(self, config: TransientEofConfig) -> Self {
+        self.transient.lock().expect("transient lock poisoned").config = Some(config);
+        self
+    }
 
-        cmd
+    /// Overrides the supervisor's time without output that counts as a stall, and its limit on how much longer
+    /// a stall may last before the process is respawned
+    pub fn with_stall_limits(self, timeout: Option<Duration>, dead_after: Option<Duration>) -> Self {
+        {
+            let mut stall = self.stall.lock().expect("stall lock poisoned");
+            stall.timeout = timeout;
+            stall.dead_after = dead_after;
+        }
+        self
+    }
+
+    pub fn with_restart_policy(mut self, restart_policy: RestartPolicy) -> Self {
+        self.restart_policy = restart_policy;
+        self
+    }
+
+    /// The stall timeout of streams that don't set their own
+    pub fn set_default_stall_timeout(&self, timeout: Duration) {
+        self.stall.lock().expect("stall lock poisoned").default_timeout = timeout;
+    }
+
+    pub fn get_restart_policy(&self) -> &RestartPolicy {
+        &self.restart_policy
+    }
+
+    /// Switches to these args once the primary has been dead for `grace`, and back when the primary recovers
+    pub fn with_backup(mut self, args: Vec<&str>, grace: chrono::Duration) -> Self {
+        self.backup = Some(BackupSource {
+            args: args.iter().map(|s| s.to_string()).collect(),
+            grace,
+            active: Arc::new(AtomicBool::new(false)),
+            primary_recovered: Arc::new(AtomicBool::new(false)),
+            primary_down_since: Arc::new(std::sync::Mutex::new(None)),
+        });
+        self
+    }
+
+    pub fn has_backup(&self) -> bool {
+        self.backup.is_some()
+    }
+
+    pub fn is_on_backup(&self) -> bool {
+        self.backup.as_ref().is_some_and(|b| b.active.load(Ordering::Relaxed))
+    }
+
+    /// On backup and the primary works again, so the next respawn should return to it
+    pub fn primary_recovered(&self) -> bool {
+        self.backup.as_ref().is_some_and(|b| b.active.load(Ordering::Relaxed) && b.primary_recovered.load(Ordering::Relaxed))
     }
 
     pub fn get_reader(&self) -> broadcast::Receiver<Vec<u8>> {
@@ -53,20 +286,107 @@ impl CommandHolder {
     }
 
     pub async fn get_health(&self) -> StreamHealth {
-        self.health.lock().await.clone()
+        let health = self.health.lock().await.clone();
+        if health == StreamHealth::Dead && self.is_soft_eof() {
+            return StreamHealth::Stalled;
+        }
+        health
+    }
+
+    /// Whether the process is down from a transient EOF, waiting for its immediate restart
+    pub fn is_soft_eof(&self) -> bool {
+        self.transient.lock().expect("transient lock poisoned").is_soft(Utc::now())
+    }
+
+    /// Transient EOFs restarted as soft events since startup
+    pub fn get_soft_eof_count(&self) -> u64 {
+        self.transient.lock().expect("transient lock poisoned").total
+    }
+
+    pub fn get_respawn_count(&self) -> u64 {
+        self.respawns.load(Ordering::Relaxed)
     }
 
     pub async fn get_restart_count(&self) -> u32 {
         *self.restart_count.lock().await
     }
 
+    /// Starts counting consecutive restarts over, e.g. when someone restarts a stream the supervisor gave up on
+    pub async fn reset_restart_count(&self) {
+        *self.restart_count.lock().await = 0;
+    }
+
+    /// Most recent distinct stderr lines, oldest first
+    pub async fn get_recent_stderr(&self) -> Vec<StderrSummary> {
+        self.recent_stderr.lock().await.iter().cloned().collect()
+    }
+
+    pub fn get_stderr_log(&self) -> StderrLog {
+        self.stderr_log.clone()
+    }
+
     pub fn get_uptime(&self) -> chrono::Duration {
         Utc::now().signed_duration_since(self.start_time)
     }
 
+    /// How long the process has gone without output, once a stall outlasts the stream's own limit or else `default_limit`
+    pub async fn stalled_too_long(&self, default_limit: Option<Duration>) -> Option<Duration> {
+        let stall = *self.stall.lock().expect("stall lock poisoned");
+        let (timeout, limit) = (stall.timeout(), stall.dead_after.or(default_limit)?);
+        let silent = Utc::now().signed_duration_since(*self.last_message.lock().await).to_std().ok()?;
+        (silent > timeout + limit).then_some(silent)
+    }
+
+    /// Kills the current process without stopping supervision, so it can be respawned
+    pub fn kill(&self) {
+        if let Some(mut child) = self.child.lock().expect("child lock poisoned").take() {
+            let _ = child.start_kill();
+        }
+    }
+
+    /// Kills the process and stops supervision, used when a stream is removed at runtime
+    pub async fn stop(&self) {
+        *self.stopped.lock().await = true;
+        let child = self.child.lock().expect("child lock poisoned").take();
+        if let Some(mut child) = child {
+            if let Err(e) = child.kill().await {
+                warn!("Could not kill {}: {:?}", self.name, e);
+            }
+        }
+        info!("Stopped command for {}", self.name);
+    }
+
+    /// Kills the process, or mutes or delays its output by `delay` for `duration`, to fire-drill alerting
+    pub fn inject_fault(&self, fault: Fault, duration: Duration, delay: Duration) {
+        let until = Utc::now() + chrono::Duration::from_std(duration).unwrap_or_default();
+        match fault {
+            Fault::Kill => {
+                warn!("Fault injection: killing the process of {}", self.name);
+                self.kill();
+            }
+            Fault::Silence => {
+                warn!("Fault injection: muting {} for {}s", self.name, duration.as_secs());
+                self.faults.lock().expect("faults lock poisoned").silence_until = Some(until);
+            }
+            Fault::Lag => {
+                warn!("Fault injection: delaying {} by {:.1}s for {}s", self.name, delay.as_secs_f32(), duration.as_secs());
+                self.faults.lock().expect("faults lock poisoned").lag = Some((delay, until));
+            }
+        }
+    }
+
     fn spawn(&mut self) { 
+        if let Some(ref source) = self.relay_source {
+            self.spawn_relay(source.resubscribe());
+            return;
+        }
+        let args = match self.backup {
+            Some(ref backup) if backup.active.load(Ordering::Relaxed) => &backup.args,
+            _ => &self.args,
+        };
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed) + 1;
         let mut body = Command::new(self.command.clone())
-            .args(self.args.as_slice())
+            .args(args.as_slice())
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::piped()).spawn().expect("Could not spawn command");
@@ -95,19 +415,50 @@ impl CommandHolder {
                 let tx = self.output.clone();
                 let last_msg = self.last_message.clone();
                 let health = self.health.clone();
+                let transient = self.transient.clone();
+                let current_generation = self.generation.clone();
+                let faults = self.faults.clone();
+                // The first output from a primary process ends its outage
+                let mut primary_down_since = self.backup.as_ref()
+                    .filter(|b| !b.active.load(Ordering::Relaxed))
+                    .map(|b| b.primary_down_since.clone());
                 tokio::spawn(async move {
                     let mut buffer = [0u8; 176400]; // Match old implementation buffer size
+                    let mut held: VecDeque<(tokio::time::Instant, Vec<u8>)> = VecDeque::new(); // output delayed by a lag fault
                     loop {
-                        match stdout.read(&mut buffer).await {
+                        let result = stdout.read(&mut buffer).await;
+                        if current_generation.load(Ordering::Relaxed) != generation {
+                            break; // replaced by a newer process
+                        }
+                        match result {
                             Ok(n) if n == 0 => {
                                 warn!("Process stdout closed (EOF)");
+                                transient.lock().expect("transient lock poisoned").eof_at = Some(Utc::now());
                                 *health.lock().await = StreamHealth::Dead;
                                 break;
                             },
                             Ok(n) => {
+                                if let Some(down_since) = primary_down_since.take() {
+                                    *down_since.lock().expect("backup lock poisoned") = None;
+                                }
                                 *last_msg.lock().await = Utc::now();
                                 *health.lock().await = StreamHealth::Running;
-                                let _ = tx.send(buffer[..n].to_vec());
+                                let mut data = buffer[..n].to_vec();
+                                let (silenced, delay) = {
+                                    let faults = faults.lock().expect("faults lock poisoned");
+                                    let now = Utc::now();
+                                    (faults.silenced(now), faults.delay(now))
+                                };
+                                if silenced {
+                                    data.fill(0);
+                                }
+                                let now = tokio::time::Instant::now();
+                                held.push_back((now + delay, data));
+                                while held.front().is_some_and(|(release, _)| *release <= now) {
+                                    if let Some((_, data)) = held.pop_front() {
+                                        let _ = tx.send(data);
+                                    }
+                                }
                             }
                             Err(e) => {
                                 error!("Error reading stdout: {:?}", e);
@@ -119,46 +470,163 @@ impl CommandHolder {
                 });
             }
 
-            // Capture stderr for debugging
-            if let Some(mut stderr) = body.stderr.take() {
-                let cmd_name = self.command.clone();
+            // Capture stderr for debugging, rate-limiting each message so a looping error can't flood the log;
+            // the raw ring buffer keeps every line
+            if let Some(stderr) = body.stderr.take() {
+                let name = self.name.clone();
+                let recent = self.recent_stderr.clone();
+                let stderr_log = self.stderr_log.clone();
+                let transient = self.transient.clone();
                 tokio::spawn(async move {
-                    let mut buffer = [0u8; 1024];
-                    loop {
-                        match stderr.read(&mut buffer).await {
-                            Ok(n) if n == 0 => break,
-                            Ok(n) => {
-                                let stderr_str = String::from_utf8_lossy(&buffer[..n]);
-                                trace!("[{} stderr] {}", cmd_name, stderr_str.trim());
+                    let mut lines = BufReader::new(stderr).lines();
+                    let mut limiter = StderrLimiter::new(STDERR_REPEAT_REPORT);
+
+                    while let Ok(Some(line)) = lines.next_line().await {
+                        let line = line.trim().to_string();
+                        if line.is_empty() {
+                            continue;
+                        }
+                        stderr_log.push(&line).await;
+                        {
+                            let mut transient = transient.lock().expect("transient lock poisoned");
+                            let matched = transient.config.as_ref()
+                                .is_some_and(|c| c.patterns.iter().any(|p| line.contains(p.as_str())));
+                            if matched {
+                                transient.last_match = Some(Utc::now());
+                            }
+                        }
+
+                        if let Some(suppressed) = limiter.admit(&line) {
+                            let logged = match suppressed {
+                                0 => line.clone(),
+                                n => format!("{} (repeated {} times since last logged)", line, n),
+                            };
+                            match classify_stderr(&line) {
+                                StderrClass::Error => warn!("[{} stderr] {}", name, logged),
+                                StderrClass::Warning => debug!("[{} stderr] {}", name, logged),
+                                StderrClass::Info => trace!("[{} stderr] {}", name, logged),
                             }
-                            Err(_) => break,
+                        }
+
+                        // Keep the most recent distinct messages, moving repeats to the back with their latest wording
+                        let key = message_key(&line);
+                        let mut recent = recent.lock().await;
+                        let existing = recent.iter().position(|s| message_key(&s.line) == key).and_then(|i| recent.remove(i));
+                        let summary = match existing {
+                            Some(mut summary) => {
+                                summary.line = line;
+                                summary.count += 1;
+                                summary.last_seen = Utc::now();
+                                summary
+                            }
+                            None => StderrSummary {
+                                class: classify_stderr(&line),
+                                line,
+                                count: 1,
+                                last_seen: Utc::now(),
+                            },
+                        };
+                        recent.push_back(summary);
+                        while recent.len() > RECENT_STDERR_LINES {
+                            recent.pop_front();
                         }
                     }
                 });
             }
+
+            // Hold on to the handle; replacing a previous one just drops it, as respawn always did
+            *self.child.lock().expect("child lock poisoned") = Some(body);
+    }
+
+    fn spawn_relay(&self, mut input: Receiver<Vec<u8>>) {
+        let generation = self.generation.fetch_add(1, Ordering::Relaxed) + 1;
+        let name = self.name.clone();
+        let tx = self.output.clone();
+        let last_msg = self.last_message.clone();
+        let health = self.health.clone();
+        let current_generation = self.generation.clone();
+        let stopped = self.stopped.clone();
+        let faults = self.faults.clone();
+        let stderr_log = self.stderr_log.clone();
+        tokio::spawn(async move {
+            let mut normalizer = PcmNormalizer::new(&name);
+            let mut held: VecDeque<(tokio::time::Instant, Vec<u8>)> = VecDeque::new(); // output delayed by a lag fault
+            loop {
+                let chunk = match input.recv().await {
+                    Ok(chunk) => chunk,
+                    Err(RecvError::Lagged(skipped)) => {
+                        warn!("{} fell behind its source and lost {} chunks", name, skipped);
+                        continue;
+                    }
+                    Err(RecvError::Closed) => {
+                        warn!("Source of {} closed", name);
+                        *health.lock().await = StreamHealth::Dead;
+                        break;
+                    }
+                };
+                if current_generation.load(Ordering::Relaxed) != generation || *stopped.lock().await {
+                    break; // replaced or removed
+                }
+                let mut data = match normalizer.push(&chunk) {
+                    Ok(data) if data.is_empty() => continue,
+                    Ok(data) => data,
+                    Err(e) => {
+                        error!("Cannot relay {}: {}", name, e);
+                        stderr_log.push(&e).await;
+                        *health.lock().await = StreamHealth::Dead;
+                        break;
+                    }
+                };
+                *last_msg.lock().await = Utc::now();
+                *health.lock().await = StreamHealth::Running;
+                let (silenced, delay) = {
+                    let faults = faults.lock().expect("faults lock poisoned");
+                    let now = Utc::now();
+                    (faults.silenced(now), faults.delay(now))
+                };
+                if silenced {
+                    data.fill(0);
+                }
+                let now = tokio::time::Instant::now();
+                held.push_back((now + delay, data));
+                while held.front().is_some_and(|(release, _)| *release <= now) {
+                    if let Some((_, data)) = held.pop_front() {
+                        let _ = tx.send(data);
+                    }
+                }
+            }
+        });
     }
 
     fn start_watchdog(&self) {
         let last_msg = self.last_message.clone();
         let health = self.health.clone();
-        let timeout = self.stall_timeout;
+        let stall = self.stall.clone();
         let restart_count = self.restart_count.clone();
+        let respawned_at = self.respawned_at.clone();
         let command = self.command.clone();
+        let stopped = self.stopped.clone();
+        let transient = self.transient.clone();
 
         tokio::spawn(async move {
             loop {
                 tokio::time::sleep(Duration::from_secs(5)).await;
 
+                if *stopped.lock().await {
+                    break;
+                }
+
                 let current_health = health.lock().await.clone();
                 let last = *last_msg.lock().await;
                 let elapsed = Utc::now().signed_duration_since(last);
+                let timeout = stall.lock().expect("stall lock poisoned").timeout();
 
                 match current_health {
                     StreamHealth::Running => {
                         if elapsed.num_seconds() > timeout.as_secs() as i64 {
                             warn!("Stream {} stalled (no data for {}s)", command, elapsed.num_seconds());
                             *health.lock().await = StreamHealth::Stalled;
-                        } else if *restart_count.lock().await != 0 {
+                        } else if *restart_count.lock().await != 0 && Utc::now() - *respawned_at.lock().await > RECOVERED_AFTER {
                             info!("Stream {} recovered, resetting restart count", command);
                             *restart_count.lock().await = 0;
                         }
@@ -170,9 +638,11 @@ impl CommandHolder {
                             *health.lock().await = StreamHealth::Running;
                         }
                     },
+                    StreamHealth::Dead if transient.lock().expect("transient lock poisoned").is_soft(Utc::now()) => {
+                        debug!("Stream {} hit a transient EOF, waiting for its restart", command);
+                    }
                     StreamHealth::Dead => {
                         let count = *restart_count.lock().await;
-                        *restart_count.lock().await += 1;
                         warn!("Stream {} died, attempting restart {}", command, count + 1);
                         // Note: actual respawn needs to be handled by supervisor
                         // since we can't call spawn() from here (no &mut self)
@@ -182,10 +652,83 @@ impl CommandHolder {
         });
     }
 
-    pub async fn respawn(&mut self) -> bool {
-        let count = self.get_restart_count().await;
-        tokio::time::sleep(Duration::from_secs((30 * count).into())).await;
+    /// Moves to the backup once the primary has been down past its grace period, or back once the primary
+    /// works again; returns whether it switched
+    fn update_backup(&self) -> bool {
+        let Some(ref backup) = self.backup else { return false };
+        let now = Utc::now();
+
+        if backup.active.load(Ordering::Relaxed) {
+            if !backup.primary_recovered.swap(false, Ordering::Relaxed) {
+                return false;
+            }
+            info!("Primary source of {} recovered, switching back from backup", self.name);
+            backup.active.store(false, Ordering::Relaxed);
+            *backup.primary_down_since.lock().expect("backup lock poisoned") = None;
+            // The backup may be healthy, it still has to go
+            if let Some(mut child) = self.child.lock().expect("child lock poisoned").take() {
+                let _ = child.start_kill();
+            }
+            return true;
+        }
+
+        let since = *backup.primary_down_since.lock().expect("backup lock poisoned").get_or_insert(now);
+        if now - since < backup.grace {
+            return false;
+        }
+        warn!("Primary source of {} down for {}s, switching to backup", self.name, (now - since).num_seconds());
+        backup.active.store(true, Ordering::Relaxed);
+        backup.primary_recovered.store(false, Ordering::Relaxed);
+
+        let command = self.command.clone();
+        let args = self.args.clone();
+        let name = self.name.clone();
+        let active = backup.active.clone();
+        let recovered = backup.primary_recovered.clone();
+        let stopped = self.stopped.clone();
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(PRIMARY_RECHECK).await;
+                if *stopped.lock().await || !active.load(Ordering::Relaxed) {
+                    break;
+                }
+                if primary_produces_output(&command, &args).await {
+                    info!("Primary source of {} is producing audio again", name);
+                    recovered.store(true, Ordering::Relaxed);
+                    break;
+                }
+                debug!("Primary source of {} still down", name);
+            }
+        });
+        true
+    }
+
+    /// Restarts the process after `backoff`, or right away for a transient EOF or a switch between sources
+    pub async fn respawn(&mut self, backoff: Duration) -> bool {
+        let soft = {
+            let mut transient = self.transient.lock().expect("transient lock poisoned");
+            let now = Utc::now();
+            let soft = transient.is_soft(now);
+            if soft {
+                transient.recent.retain(|t| now - *t < chrono::Duration::hours(1));
+                transient.recent.push_back(now);
+                transient.total += 1;
+            }
+            transient.eof_at = None;
+            soft
+        };
+        let switched = self.update_backup();
+        if soft {
+            info!("Restarting {} after a transient EOF", self.name);
+        } else if !switched {
+            tokio::time::sleep(backoff).await;
+        }
         info!("Respawning command: {} {}", self.command, self.args.join(" "));
+        self.respawns.fetch_add(1, Ordering::Relaxed);
+        if !soft {
+            *self.restart_count.lock().await += 1;
+        }
+        *self.respawned_at.lock().await = Utc::now();
         *self.last_message.lock().await = Utc::now();
         *self.health.lock().await = StreamHealth::Running;
         self.spawn();
@@ -1,29 +1,220 @@
-use std::{collections::HashMap, sync::Arc, time::Duration};
+use std::{collections::{HashMap, VecDeque}, sync::{atomic::{AtomicU64, Ordering}, Arc}, time::Duration};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Deserialize;
 use tokio::sync::RwLock;
 use rusty_chromaprint::{match_fingerprints, Configuration};
-use tracing::{info, error, debug};
+use tracing::{info, error, debug, warn};
 use super::audiorouter::AudioRouter;
 use super::alertmanager::AlertManager;
+use super::timefmt;
+use super::tuning::ThresholdKind;
+use super::fastmatch::{self, FastMatchConfig};
+
+pub use watchdog::api::ComparisonResult;
+use watchdog::api::{AlertReason, ComparisonSample};
+
+// Comparison passes are every few seconds; history keeps one pass per minute for a bit over a week
+const HISTORY_SAMPLE_SECONDS: i64 = 60;
+const HISTORY_DAYS: i64 = 8;
+
+/// Declares two channels are expected to carry the same audio for a while (joint coverage,
+/// emergency simulcast), so collisions between them are annotated instead of alerted
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct CollisionWhitelist {
+    pub channels: [String; 2],
+    pub start: DateTime<Utc>,
+    pub end: DateTime<Utc>,
+    pub reason: Option<String>,
+}
+
+impl CollisionWhitelist {
+    fn covers(&self, channel1: &str, channel2: &str, now: DateTime<Utc>) -> bool {
+        let [a, b] = &self.channels;
+        let pair_matches = (a == channel1 && b == channel2) || (a == channel2 && b == channel1);
+        pair_matches && self.start <= now && now < self.end
+    }
 
-#[derive(Clone, Debug)]
-pub struct ComparisonResult {
-    pub stream1: String,
-    pub stream2: String,
-    pub similarity_percent: f32,
-    pub is_within_channel: bool,
-    pub is_error: bool,
-    pub offset_seconds: Option<f32>, // Time offset between streams (only for within-channel)
+    fn annotation(&self) -> String {
+        match self.reason {
+            Some(ref reason) => format!("{} (until {})", reason, timefmt::display(self.end, "%Y-%m-%d %H:%M")),
+            None => format!("whitelisted until {}", timefmt::display(self.end, "%Y-%m-%d %H:%M")),
+        }
+    }
 }
 
-pub struct StreamComparator {
-    router: Arc<AudioRouter>,
+/// Comparisons run since startup, by type and outcome
+#[derive(Default)]
+pub struct ComparisonCounters {
+    within_ok: AtomicU64,
+    within_error: AtomicU64,
+    cross_ok: AtomicU64,
+    cross_error: AtomicU64,
+    silence_ok: AtomicU64,
+    silence_error: AtomicU64,
+    matches_computed: AtomicU64, // fingerprint matches run
+    matches_cached: AtomicU64, // pairs whose fingerprints hadn't changed, answered from the last pass
+    fast_matches: AtomicU64, // matches run on the fast path instead of rusty_chromaprint
+    fast_match_disagreements: AtomicU64, // fast matches checked against rusty_chromaprint that came out too different
+}
+
+impl ComparisonCounters {
+    fn record(&self, result: &ComparisonResult) {
+        let counter = match (result.is_within_channel, result.is_error) {
+            _ if result.is_silence_check => if result.is_error { &self.silence_error } else { &self.silence_ok },
+            (true, false) => &self.within_ok,
+            (true, true) => &self.within_error,
+            (false, false) => &self.cross_ok,
+            (false, true) => &self.cross_error,
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// (computed, cached) fingerprint matches
+    pub fn get_match_counts(&self) -> (u64, u64) {
+        (self.matches_computed.load(Ordering::Relaxed), self.matches_cached.load(Ordering::Relaxed))
+    }
+
+    /// (fast matches, disagreements with rusty_chromaprint among those verified)
+    pub fn get_fast_match_counts(&self) -> (u64, u64) {
+        (self.fast_matches.load(Ordering::Relaxed), self.fast_match_disagreements.load(Ordering::Relaxed))
+    }
+
+    /// (comparison_type, result, count) in the labels the metrics use
+    pub fn snapshot(&self) -> [(&'static str, &'static str, u64); 6] {
+        [
+            ("within_channel", "ok", self.within_ok.load(Ordering::Relaxed)),
+            ("within_channel", "error", self.within_error.load(Ordering::Relaxed)),
+            ("cross_channel", "ok", self.cross_ok.load(Ordering::Relaxed)),
+            ("cross_channel", "error", self.cross_error.load(Ordering::Relaxed)),
+            ("silence_check", "ok", self.silence_ok.load(Ordering::Relaxed)),
+            ("silence_check", "error", self.silence_error.load(Ordering::Relaxed)),
+        ]
+    }
+}
+
+/// Last match of a stream pair and the fingerprint versions it was run on
+struct CachedMatch {
+    versions: (u64, u64),
+    window_size: usize,
+    similarity: Option<(f32, f32)>, // what get_similarity_time returned
+}
+
+/// Matches from earlier passes, by stream pair in the order they were matched. Stalled or slow sources
+/// keep the same fingerprint for many passes, and matching them again would only give the same answer
+struct MatchCache {
+    matches: HashMap<(String, String), CachedMatch>,
+    fast_match: Option<FastMatchConfig>,
+}
+
+impl MatchCache {
+    fn new(fast_match: Option<FastMatchConfig>) -> Self {
+        MatchCache { matches: HashMap::new(), fast_match }
+    }
+
+    /// Large buffers take the fast path when it's enabled, every so often checked against rusty_chromaprint
+    fn run_match(&self, counters: &ComparisonCounters, fp1: &[u32], fp2: &[u32], window_size: usize) -> Option<(f32, f32)> {
+        let Some(ref config) = self.fast_match else {
+            return StreamComparator::get_similarity_time(fp1, fp2, window_size);
+        };
+        if fp1.len().min(fp2.len()) < config.min_items() {
+            return StreamComparator::get_similarity_time(fp1, fp2, window_size);
+        }
+        let similarity = fastmatch::similarity(fp1, fp2, window_size);
+        let fast_matches = counters.fast_matches.fetch_add(1, Ordering::Relaxed) + 1;
+        if config.verify_every > 0 && fast_matches % config.verify_every == 0 {
+            let reference = Self::similar_seconds(StreamComparator::get_similarity_time(fp1, fp2, window_size));
+            let buffer = fp1.len() as f32 * Configuration::preset_test1().item_duration_in_seconds();
+            let difference = (Self::similar_seconds(similarity) - reference).abs() / buffer * 100.0;
+            if difference > config.verify_tolerance_percent {
+                counters.fast_match_disagreements.fetch_add(1, Ordering::Relaxed);
+                warn!("Fast fingerprint match is {:.1}% off rusty_chromaprint ({:.1}s vs {:.1}s similar)",
+                    difference, Self::similar_seconds(similarity), reference);
+            }
+        }
+        similarity
+    }
+
+    fn similar_seconds(similarity: Option<(f32, f32)>) -> f32 {
+        similarity.map_or(0.0, |(similar_time, _)| similar_time)
+    }
+
+    /// Similarity of `fp1` and `fp2`, matched again only if either fingerprint or the window changed
+    fn similarity(
+        &mut self,
+        counters: &ComparisonCounters,
+        (stream1, version1, fp1): (&str, u64, &[u32]),
+        (stream2, version2, fp2): (&str, u64, &[u32]),
+        window_size: usize
+    ) -> Option<(f32, f32)> {
+        let key = (stream1.to_string(), stream2.to_string());
+        if let Some(cached) = self.matches.get(&key) {
+            if cached.versions == (version1, version2) && cached.window_size == window_size {
+                counters.matches_cached.fetch_add(1, Ordering::Relaxed);
+                return cached.similarity;
+            }
+        }
+        counters.matches_computed.fetch_add(1, Ordering::Relaxed);
+        let similarity = self.run_match(counters, fp1, fp2, window_size);
+        self.matches.insert(key, CachedMatch { versions: (version1, version2), window_size, similarity });
+        similarity
+    }
+
+    /// Forgets pairs with a stream that was removed
+    fn prune(&mut self, router: &AudioRouter) {
+        let streams: Vec<String> = router.get_all_channels().iter()
+            .filter_map(|channel| router.get_channel_streams(channel))
+            .flatten()
+            .collect();
+        self.matches.retain(|(stream1, stream2), _| streams.contains(stream1) && streams.contains(stream2));
+    }
+}
+
+/// Streams of one channel that match each other as a majority, and those that don't
+struct Consensus {
+    peers: Vec<String>,
+    outliers: Vec<String>,
+}
+
+/// Settings a channel sets for itself instead of the global ones
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ComparisonOverrides {
+    pub match_threshold: Option<f32>,
+    pub divergence_threshold: Option<f32>,
+    pub comparison_duration: Option<f32>,
+}
+
+/// What one channel is compared with, its overrides applied over the global settings
+#[derive(Debug, Clone, Copy)]
+struct ChannelSettings {
     window_size: usize,
+    min_match_duration: f32,
+    match_threshold: f32,
+    divergence_threshold: f32,
+}
+
+/// Pass/fail limits of the comparisons, replaced in place when the config is reloaded
+#[derive(Debug, Clone, Copy)]
+struct Thresholds {
     min_match_duration: f32, // minimum similarity duration in seconds
-    min_buffer_size: usize, // minimum fingerprint buffer before comparisons start
     match_threshold: f32, // percentage threshold for within-channel matching
     divergence_threshold: f32, // percentage threshold for cross-channel divergence
+}
+
+pub struct StreamComparator {
+    router: Arc<AudioRouter>,
+    window_size: usize,
+    comparison_duration: f32,
+    min_buffer_size: usize, // minimum fingerprint buffer before comparisons start
+    thresholds: Arc<std::sync::RwLock<Thresholds>>,
     pub comparison_results: Arc<RwLock<Vec<ComparisonResult>>>,
+    comparison_history: Arc<RwLock<VecDeque<ComparisonSample>>>, // oldest first
+    counters: Arc<ComparisonCounters>,
     alert_manager: Option<Arc<AlertManager>>,
+    interval: Duration, // time between comparison passes
+    max_cross_pairs: Option<usize>, // cap on cross-channel pairs compared per pass
+    collision_whitelist: Vec<CollisionWhitelist>,
+    fast_match: Option<FastMatchConfig>, // XOR/popcount matching for large buffers instead of rusty_chromaprint
 }
 
 impl StreamComparator {
@@ -40,12 +231,21 @@ impl StreamComparator {
         StreamComparator {
             router,
             window_size,
-            min_match_duration: comparison_duration * (match_threshold / 100.0),
+            comparison_duration,
             min_buffer_size,
-            match_threshold,
-            divergence_threshold,
+            thresholds: Arc::new(std::sync::RwLock::new(Thresholds {
+                min_match_duration: comparison_duration * (match_threshold / 100.0),
+                match_threshold,
+                divergence_threshold,
+            })),
             comparison_results: Arc::new(RwLock::new(Vec::new())),
+            comparison_history: Arc::new(RwLock::new(VecDeque::new())),
+            counters: Arc::new(ComparisonCounters::default()),
             alert_manager: None,
+            interval: Duration::from_secs(5),
+            max_cross_pairs: None,
+            collision_whitelist: Vec::new(),
+            fast_match: None,
         }
     }
 
@@ -54,80 +254,359 @@ impl StreamComparator {
         self
     }
 
+    pub fn with_interval(mut self, interval_seconds: u64) -> Self {
+        self.interval = Duration::from_secs(interval_seconds.max(1));
+        self
+    }
+
+    /// Limits how many cross-channel channel pairs are compared each pass, rotating through the rest
+    pub fn with_max_cross_pairs(mut self, max_cross_pairs: usize) -> Self {
+        self.max_cross_pairs = Some(max_cross_pairs.max(1));
+        self
+    }
+
+    pub fn with_collision_whitelist(mut self, collision_whitelist: Vec<CollisionWhitelist>) -> Self {
+        self.collision_whitelist = collision_whitelist;
+        self
+    }
+
+    /// Matches buffers of at least `min_buffer_duration` on the fast path in the comparison passes; on-demand
+    /// comparisons keep using rusty_chromaprint
+    pub fn with_fast_match(mut self, fast_match: FastMatchConfig) -> Self {
+        self.fast_match = Some(fast_match);
+        self
+    }
+
+    /// Applies new thresholds from the next comparison pass on
+    pub fn set_thresholds(&self, match_threshold: f32, divergence_threshold: f32) {
+        *self.thresholds.write().expect("thresholds lock poisoned") = Thresholds {
+            min_match_duration: self.comparison_duration * (match_threshold / 100.0),
+            match_threshold,
+            divergence_threshold,
+        };
+    }
+
+    fn thresholds(&self) -> Thresholds {
+        *self.thresholds.read().expect("thresholds lock poisoned")
+    }
+
+    fn channel_settings(router: &AudioRouter, channel_name: &str, comparison_duration: f32, global: &Thresholds) -> ChannelSettings {
+        let overrides = router.get_comparison_overrides(channel_name);
+        let tuned = router.get_tuned_thresholds();
+        let scheduled = router.get_active_schedules();
+        // Runtime tuning, then the time of day schedule, beat the config at each level: the channel's, then the global one
+        let threshold = |kind: ThresholdKind, configured_channel: Option<f32>, configured_global: f32| {
+            tuned.channel(kind, channel_name)
+                .or(scheduled.channel(kind, channel_name))
+                .or(configured_channel)
+                .or(tuned.global(kind))
+                .or(scheduled.global(kind))
+                .unwrap_or(configured_global)
+        };
+        let comparison_duration = overrides.comparison_duration.unwrap_or(comparison_duration);
+        let match_threshold = threshold(ThresholdKind::Match, overrides.match_threshold, global.match_threshold);
+        ChannelSettings {
+            window_size: (comparison_duration / Configuration::preset_test1().item_duration_in_seconds()) as usize,
+            min_match_duration: comparison_duration * (match_threshold / 100.0),
+            match_threshold,
+            divergence_threshold: threshold(ThresholdKind::Divergence, overrides.divergence_threshold, global.divergence_threshold),
+        }
+    }
+
+    /// Settings for a pair of channels; the looser of the two channels' values applies
+    fn pair_settings(settings1: ChannelSettings, settings2: ChannelSettings) -> (usize, f32) {
+        (settings1.window_size.max(settings2.window_size), settings1.divergence_threshold.max(settings2.divergence_threshold))
+    }
+
+    pub fn get_min_buffer_size(&self) -> usize {
+        self.min_buffer_size
+    }
+
     pub fn get_results(&self) -> Arc<RwLock<Vec<ComparisonResult>>> {
         self.comparison_results.clone()
     }
 
+    pub fn get_history(&self) -> Arc<RwLock<VecDeque<ComparisonSample>>> {
+        self.comparison_history.clone()
+    }
+
+    pub fn get_counters(&self) -> Arc<ComparisonCounters> {
+        self.counters.clone()
+    }
+
+    /// Compares two streams right away over their whole buffers, outside the regular passes; collision
+    /// whitelists are not consulted
+    pub async fn compare_now(&self, stream1: &str, stream2: &str) -> Result<ComparisonResult, String> {
+        let channel_of = |stream: &str| self.router.get_all_channels().into_iter()
+            .find(|channel| self.router.get_channel_streams(channel).is_some_and(|streams| streams.iter().any(|s| s == stream)));
+        let Some(channel1) = channel_of(stream1) else { return Err(format!("Stream `{}` not found", stream1)) };
+        let Some(channel2) = channel_of(stream2) else { return Err(format!("Stream `{}` not found", stream2)) };
+
+        let fp1 = self.router.get_stream_fingerprint(stream1).await.unwrap_or_default();
+        let fp2 = self.router.get_stream_fingerprint(stream2).await.unwrap_or_default();
+        for (stream, fp) in [(stream1, &fp1), (stream2, &fp2)] {
+            let min_buffer_size = self.router.get_stream_min_buffer(stream, self.min_buffer_size).await;
+            if fp.len() < min_buffer_size {
+                return Err(format!("Stream `{}` is still buffering ({}/{} items)", stream, fp.len(), min_buffer_size));
+            }
+        }
+        let is_within_channel = channel1 == channel2;
+        let global = self.thresholds();
+        let settings1 = Self::channel_settings(&self.router, &channel1, self.comparison_duration, &global);
+        let settings2 = Self::channel_settings(&self.router, &channel2, self.comparison_duration, &global);
+        let (window_size, divergence_threshold) = if is_within_channel {
+            (settings1.window_size, settings1.divergence_threshold)
+        } else {
+            Self::pair_settings(settings1, settings2)
+        };
+
+        let Some((similar_time, offset)) = Self::get_similarity_time(&fp1, &fp2, window_size) else {
+            return Err(format!("Could not compare `{}` and `{}`", stream1, stream2));
+        };
+        let total_duration = fp1.len() as f32 * Configuration::preset_test1().item_duration_in_seconds();
+        let similarity_percent = (similar_time / total_duration) * 100.0;
+
+        let is_error = if is_within_channel {
+            similarity_percent < settings1.match_threshold
+        } else {
+            similarity_percent > divergence_threshold
+        };
+        let (first, second, offset) = if stream1 < stream2 { (stream1, stream2, offset) } else { (stream2, stream1, -offset) };
+        info!("On-demand comparison of '{}' and '{}': {:.1}% similar, offset: {:.2}s", first, second, similarity_percent, offset);
+
+        Ok(ComparisonResult {
+            stream1: first.to_string(),
+            stream2: second.to_string(),
+            similarity_percent,
+            is_within_channel,
+            is_error,
+            offset_seconds: is_within_channel.then_some(offset),
+            whitelisted: None,
+            is_silence_check: channel1 == "silence" || channel2 == "silence",
+        })
+    }
+
     pub async fn start_comparison_loop(&self) {
         info!("Starting fingerprint comparison loop (window: {} items, min match: {}s, min buffer: {} items)",
-              self.window_size, self.min_match_duration, self.min_buffer_size);
+              self.window_size, self.thresholds().min_match_duration, self.min_buffer_size);
         let router = self.router.clone();
-        let window_size = self.window_size;
-        let min_match = self.min_match_duration;
+        let comparison_duration = self.comparison_duration;
         let min_buffer = self.min_buffer_size;
-        let match_threshold = self.match_threshold;
-        let divergence_threshold = self.divergence_threshold;
+        let thresholds = self.thresholds.clone();
         let results = self.comparison_results.clone();
+        let history = self.comparison_history.clone();
+        let counters = self.counters.clone();
         let alert_manager = self.alert_manager.clone();
+        let interval = self.interval;
+        let max_cross_pairs = self.max_cross_pairs;
+        let collision_whitelist = self.collision_whitelist.clone();
+        let fast_match = self.fast_match.clone();
 
         tokio::spawn(async move {
+            let mut cross_offset = 0usize;
+            let mut last_history_sample: Option<DateTime<Utc>> = None;
+            let mut cache = MatchCache::new(fast_match);
             loop {
-                tokio::time::sleep(Duration::from_secs(5)).await;
+                tokio::time::sleep(interval).await;
+                cache.prune(&router);
 
+                let global = *thresholds.read().expect("thresholds lock poisoned");
                 let mut new_results = Vec::new();
+                let mut within_results = Vec::new();
 
                 // Compare streams within each channel (should be identical)
                 for channel_name in router.get_all_channels() {
                     if let Some(stream_names) = router.get_channel_streams(&channel_name) {
-                        let channel_results = Self::compare_channel_streams(&router, &channel_name, &stream_names, window_size, min_match, min_buffer, match_threshold).await;
-                        new_results.extend(channel_results);
+                        let settings = Self::channel_settings(&router, &channel_name, comparison_duration, &global);
+                        let channel_results = Self::compare_channel_streams(&router, &mut cache, &counters, &channel_name, &stream_names,
+                            settings.window_size, settings.min_match_duration, min_buffer, settings.match_threshold).await;
+                        new_results.extend(channel_results.iter().cloned());
+                        within_results.push((channel_name, channel_results, settings.match_threshold));
                     }
                 }
 
                 // Compare across channels (should be different)
-                // This includes comparing real channels against the silence channel
+                // The silence reference channel is only compared against channels that opt in
                 let mut channels = router.get_all_channels();
                 channels.sort();
+                let mut pairs = Vec::new();
                 for i in 0..channels.len() {
                     for j in (i + 1)..channels.len() {
-                        let cross_results = Self::compare_across_channels(&router, &channels[i], &channels[j], window_size, min_buffer, divergence_threshold).await;
-                        new_results.extend(cross_results);
+                        let silence_pair = match (channels[i].as_str(), channels[j].as_str()) {
+                            ("silence", other) | (other, "silence") => Some(other),
+                            _ => None,
+                        };
+                        if silence_pair.map_or(true, |other| router.has_silence_check(other)) {
+                            pairs.push((&channels[i], &channels[j]));
+                        }
+                    }
+                }
+                // When capped, walk a rotating window so every pair still gets compared eventually
+                if let Some(max) = max_cross_pairs {
+                    if pairs.len() > max {
+                        let start = cross_offset % pairs.len();
+                        pairs.rotate_left(start);
+                        pairs.truncate(max);
+                        cross_offset = start + max;
                     }
                 }
+                let now = Utc::now();
+                let scheduled = router.get_active_schedules();
+                let mut cross_thresholds = Vec::new(); // divergence threshold each cross-channel result was judged by
+                for (channel1, channel2) in pairs {
+                    // Matching the silence reference is a collision like any other, and scheduled dead air whitelists it
+                    let expected = match (channel1.as_str(), channel2.as_str()) {
+                        ("silence", other) | (other, "silence") => scheduled.silence_expected(other)
+                            .map(|schedule| format!("dead air expected during {}", schedule)),
+                        _ => collision_whitelist.iter().find(|w| w.covers(channel1, channel2, now)).map(|w| w.annotation()),
+                    };
+                    let (window_size, divergence_threshold) = Self::pair_settings(
+                        Self::channel_settings(&router, channel1, comparison_duration, &global),
+                        Self::channel_settings(&router, channel2, comparison_duration, &global));
+                    let cross_results = Self::compare_across_channels(&router, &mut cache, &counters, channel1, channel2, window_size, min_buffer, divergence_threshold, expected).await;
+                    cross_thresholds.extend(cross_results.iter().map(|result| (result.clone(), divergence_threshold)));
+                    new_results.extend(cross_results);
+                }
+
+                for result in &new_results {
+                    counters.record(result);
+                }
 
                 // Update alert manager if configured
                 if let Some(ref am) = alert_manager {
-                    for result in &new_results {
-                        let alert_id = format!("{}_{}", result.stream1, result.stream2);
-                        let message = if result.is_within_channel {
-                            if result.is_error {
-                                format!("Streams `{}` and `{}` are diverging ({:.1}% similar, need ≥{:.1}%)",
-                                    result.stream1, result.stream2, result.similarity_percent, match_threshold)
-                            } else {
-                                format!("Streams `{}` and `{}` are matching ({:.1}% similar)",
-                                    result.stream1, result.stream2, result.similarity_percent)
+                    for (channel_name, channel_results, match_threshold) in &within_results {
+                        match Self::find_consensus(channel_results) {
+                            Some(consensus) => Self::update_consensus_alerts(am, channel_name, channel_results, &consensus, *match_threshold).await,
+                            None => {
+                                for result in channel_results {
+                                    Self::update_pair_alert(am, result, *match_threshold, global.divergence_threshold).await;
+                                }
                             }
-                        } else {
-                            if result.is_error {
-                                format!("Streams `{}` and `{}` are colliding ({:.1}% similar, need <{:.1}%)",
-                                    result.stream1, result.stream2, result.similarity_percent, divergence_threshold)
-                            } else {
-                                format!("Streams `{}` and `{}` are different ({:.1}% similar)",
-                                    result.stream1, result.stream2, result.similarity_percent)
-                            }
-                        };
-                        am.update_alert(alert_id, result.is_error, message).await;
+                        }
+                    }
+                    for (result, divergence_threshold) in &cross_thresholds {
+                        Self::update_pair_alert(am, result, global.match_threshold, *divergence_threshold).await;
                     }
                 }
 
+                if last_history_sample.map_or(true, |t| (now - t).num_seconds() >= HISTORY_SAMPLE_SECONDS) {
+                    let mut history = history.write().await;
+                    history.extend(new_results.iter().map(|result| ComparisonSample { timestamp: now, result: result.clone() }));
+                    let cutoff = now - chrono::Duration::days(HISTORY_DAYS);
+                    while history.front().is_some_and(|s| s.timestamp < cutoff) {
+                        history.pop_front();
+                    }
+                    last_history_sample = Some(now);
+                }
+
                 // Update results
                 *results.write().await = new_results;
             }
         });
     }
 
+    async fn update_pair_alert(am: &AlertManager, result: &ComparisonResult, match_threshold: f32, divergence_threshold: f32) {
+        let alert_id = format!("{}_{}", result.stream1, result.stream2);
+        if result.is_silence_check {
+            let stream = if result.stream1 == "silence" { &result.stream2 } else { &result.stream1 };
+            let message = if result.is_error {
+                format!("Stream `{}` is silent ({:.1}% similar to the silence reference)", stream, result.similarity_percent)
+            } else if let Some(ref note) = result.whitelisted {
+                format!("Stream `{}` is silent, {}", stream, note)
+            } else {
+                format!("Stream `{}` is playing audio again", stream)
+            };
+            am.update_alert(alert_id, AlertReason::Silence, &[stream.as_str()], result.is_error, message).await;
+            return;
+        }
+        let message = if result.is_within_channel {
+            if result.is_error {
+                format!("Streams `{}` and `{}` are diverging ({:.1}% similar, need ≥{:.1}%)",
+                    result.stream1, result.stream2, result.similarity_percent, match_threshold)
+            } else {
+                format!("Streams `{}` and `{}` are matching ({:.1}% similar)",
+                    result.stream1, result.stream2, result.similarity_percent)
+            }
+        } else {
+            if result.is_error {
+                format!("Streams `{}` and `{}` are colliding ({:.1}% similar, need <{:.1}%)",
+                    result.stream1, result.stream2, result.similarity_percent, divergence_threshold)
+            } else if let Some(ref note) = result.whitelisted {
+                format!("Streams `{}` and `{}` are carrying the same audio as expected ({:.1}% similar): {}",
+                    result.stream1, result.stream2, result.similarity_percent, note)
+            } else {
+                format!("Streams `{}` and `{}` are different ({:.1}% similar)",
+                    result.stream1, result.stream2, result.similarity_percent)
+            }
+        };
+        let reason = if result.is_within_channel { AlertReason::Divergence } else { AlertReason::Collision };
+        am.update_alert(alert_id, reason, &[result.stream1.as_str(), result.stream2.as_str()], result.is_error, message).await;
+    }
+
+    /// With 3+ compared streams in a channel, finds the largest group matching one stream and, when it is a
+    /// majority, everything outside it
+    fn find_consensus(results: &[ComparisonResult]) -> Option<Consensus> {
+        let mut streams: Vec<&String> = results.iter().flat_map(|r| [&r.stream1, &r.stream2]).collect();
+        streams.sort();
+        streams.dedup();
+        if streams.len() < 3 {
+            return None;
+        }
+
+        let matches_of = |stream: &String| -> Vec<String> {
+            results.iter()
+                .filter(|r| !r.is_error)
+                .filter_map(|r| if &r.stream1 == stream { Some(r.stream2.clone()) } else if &r.stream2 == stream { Some(r.stream1.clone()) } else { None })
+                .collect()
+        };
+        // First stream with the most matches anchors the group, so ties pick the same group every pass
+        let anchor = streams.iter().copied().rev().max_by_key(|stream| matches_of(stream).len())?;
+        let mut peers = matches_of(anchor);
+        peers.push(anchor.clone());
+        if peers.len() * 2 <= streams.len() {
+            return None; // no majority, the pairwise alerts say more
+        }
+        peers.sort();
+        let outliers = streams.into_iter().filter(|s| !peers.contains(s)).cloned().collect();
+        Some(Consensus { peers, outliers })
+    }
+
+    /// Alerts on each outlier of a channel's consensus instead of on every diverging pair
+    async fn update_consensus_alerts(
+        am: &AlertManager,
+        channel_name: &str,
+        results: &[ComparisonResult],
+        consensus: &Consensus,
+        match_threshold: f32
+    ) {
+        for result in results {
+            let covered = format!("Streams `{}` and `{}` are covered by the consensus of channel `{}`",
+                result.stream1, result.stream2, channel_name);
+            am.update_alert(format!("{}_{}", result.stream1, result.stream2), AlertReason::Divergence,
+                &[result.stream1.as_str(), result.stream2.as_str()], false, covered).await;
+        }
+
+        for outlier in &consensus.outliers {
+            let best = results.iter()
+                .filter(|r| (&r.stream1 == outlier && consensus.peers.contains(&r.stream2)) || (&r.stream2 == outlier && consensus.peers.contains(&r.stream1)))
+                .map(|r| r.similarity_percent)
+                .fold(0.0f32, f32::max);
+            error!("CONSENSUS in channel '{}': '{}' disagrees with {} matching peers (best {:.1}% similar)",
+                channel_name, outlier, consensus.peers.len(), best);
+            let message = format!("Stream `{}` disagrees with {} matching peers in channel `{}` (best {:.1}% similar, need ≥{:.1}%)",
+                outlier, consensus.peers.len(), channel_name, best, match_threshold);
+            am.update_alert(format!("{}_consensus", outlier), AlertReason::Divergence, &[outlier.as_str()], true, message).await;
+        }
+        for peer in &consensus.peers {
+            let message = format!("Stream `{}` agrees with the consensus of channel `{}` again", peer, channel_name);
+            am.update_alert(format!("{}_consensus", peer), AlertReason::Divergence, &[peer.as_str()], false, message).await;
+        }
+    }
+
     async fn compare_channel_streams(
         router: &AudioRouter,
+        cache: &mut MatchCache,
+        counters: &ComparisonCounters,
         channel_name: &str,
         stream_names: &[String],
         window_size: usize,
@@ -140,13 +619,18 @@ impl StreamComparator {
             return results; // Nothing to compare
         }
 
-        let mut fingerprints: HashMap<String, Vec<u32>> = HashMap::new();
+        let mut fingerprints: HashMap<String, (u64, Vec<u32>)> = HashMap::new();
 
         // Collect fingerprints from all streams
         for stream_name in stream_names {
-            if let Some(fp) = router.get_stream_fingerprint(stream_name).await {
+            if !router.is_stream_sampled(stream_name) {
+                debug!("Stream {} not sampled right now, skipping", stream_name);
+                continue;
+            }
+            if let Some((version, fp)) = router.get_stream_versioned_fingerprint(stream_name).await {
+                let min_buffer_size = router.get_stream_min_buffer(stream_name, min_buffer_size).await;
                 if fp.len() >= min_buffer_size {
-                    fingerprints.insert(stream_name.clone(), fp);
+                    fingerprints.insert(stream_name.clone(), (version, fp));
                 } else {
                     debug!("Stream {} fingerprint buffering ({}/{} items)", stream_name, fp.len(), min_buffer_size);
                 }
@@ -162,10 +646,11 @@ impl StreamComparator {
         streams.sort();
         for i in 0..streams.len() {
             for j in (i + 1)..streams.len() {
-                let fp1 = &fingerprints[&streams[i]];
-                let fp2 = &fingerprints[&streams[j]];
+                let (version1, fp1) = &fingerprints[&streams[i]];
+                let (version2, fp2) = &fingerprints[&streams[j]];
 
-                if let Some((similar_time, offset)) = Self::get_similarity_time(fp1, fp2, window_size) {
+                let similarity = cache.similarity(counters, (&streams[i], *version1, fp1), (&streams[j], *version2, fp2), window_size);
+                if let Some((similar_time, offset)) = similarity {
                     let total_duration = fp1.len() as f32 * Configuration::preset_test1().item_duration_in_seconds();
                     let similarity_percent = (similar_time / total_duration) * 100.0;
 
@@ -197,6 +682,8 @@ impl StreamComparator {
                         is_within_channel: true,
                         is_error,
                         offset_seconds: Some(final_offset),
+                        whitelisted: None,
+                        is_silence_check: false,
                     });
                 } else {
                     debug!("Channel '{}': Could not compare '{}' and '{}'", channel_name, streams[i], streams[j]);
@@ -209,11 +696,14 @@ impl StreamComparator {
 
     async fn compare_across_channels(
         router: &AudioRouter,
+        cache: &mut MatchCache,
+        counters: &ComparisonCounters,
         channel1: &str,
         channel2: &str,
         window_size: usize,
         min_buffer_size: usize,
-        divergence_threshold: f32
+        divergence_threshold: f32,
+        expected: Option<String> // why a collision between the channels is expected right now
     ) -> Vec<ComparisonResult> {
         let mut results = Vec::new();
         let streams1 = router.get_channel_streams(channel1);
@@ -229,17 +719,25 @@ impl StreamComparator {
         // Compare each stream from channel1 against each stream from channel2
         for stream1_name in &streams1 {
             for stream2_name in &streams2 {
-                let fp1 = router.get_stream_fingerprint(stream1_name).await;
-                let fp2 = router.get_stream_fingerprint(stream2_name).await;
-
-                if let (Some(fp1), Some(fp2)) = (fp1, fp2) {
-                    if fp1.len() >= min_buffer_size && fp2.len() >= min_buffer_size {
-                        if let Some((similar_time, _offset)) = Self::get_similarity_time(&fp1, &fp2, window_size) {
+                if !router.is_stream_sampled(stream1_name) || !router.is_stream_sampled(stream2_name) {
+                    continue;
+                }
+                let fp1 = router.get_stream_versioned_fingerprint(stream1_name).await;
+                let fp2 = router.get_stream_versioned_fingerprint(stream2_name).await;
+
+                if let (Some((version1, fp1)), Some((version2, fp2))) = (fp1, fp2) {
+                    let min_buffer1 = router.get_stream_min_buffer(stream1_name, min_buffer_size).await;
+                    let min_buffer2 = router.get_stream_min_buffer(stream2_name, min_buffer_size).await;
+                    if fp1.len() >= min_buffer1 && fp2.len() >= min_buffer2 {
+                        let similarity = cache.similarity(counters, (stream1_name, version1, &fp1), (stream2_name, version2, &fp2), window_size);
+                        if let Some((similar_time, _offset)) = similarity {
                             let total_duration = fp1.len() as f32 * Configuration::preset_test1().item_duration_in_seconds();
                             let similarity_percent = (similar_time / total_duration) * 100.0;
 
                             // For different channels, we want LOW similarity (under divergence threshold)
-                            let is_error = similarity_percent > divergence_threshold;
+                            let is_collision = similarity_percent > divergence_threshold;
+                            let whitelisted = expected.clone().filter(|_| is_collision);
+                            let is_error = is_collision && whitelisted.is_none();
 
                             // Order streams alphabetically for consistent display
                             let (stream1, stream2) = if stream1_name < stream2_name {
@@ -253,6 +751,11 @@ impl StreamComparator {
                                     "COLLISION: '{}' and '{}' are too similar ({:.1}% match, should be <{:.1}%)",
                                     stream1, stream2, similarity_percent, divergence_threshold
                                 );
+                            } else if let Some(ref note) = whitelisted {
+                                info!(
+                                    "Whitelisted collision: '{}' and '{}' ({:.1}% match): {}",
+                                    stream1, stream2, similarity_percent, note
+                                );
                             } else {
                                 debug!(
                                     "Cross-channel: '{}' and '{}' are different ({:.1}% match) ✓",
@@ -267,6 +770,8 @@ impl StreamComparator {
                                 is_within_channel: false,
                                 is_error,
                                 offset_seconds: None, // Offset not relevant for cross-channel
+                                whitelisted,
+                                is_silence_check: channel1 == "silence" || channel2 == "silence",
                             });
                         }
                     }
@@ -277,7 +782,7 @@ impl StreamComparator {
         results
     }
 
-    fn get_similarity_time(fp1: &[u32], fp2: &[u32], window_size: usize) -> Option<(f32, f32)> {
+    pub fn get_similarity_time(fp1: &[u32], fp2: &[u32], window_size: usize) -> Option<(f32, f32)> {
         if fp1.len() < window_size || fp2.len() < window_size {
             return None;
         }
@@ -0,0 +1,93 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+#[derive(Debug)]
+pub enum ConfigDirEvent {
+    Added { channel: String, path: PathBuf },
+    Removed { channel: String },
+}
+
+/// Polls a directory of per-channel YAML files, named `<channel>.yaml`, for added, removed and edited files
+pub struct ConfigDirWatcher {
+    directory: PathBuf,
+    interval_seconds: u64,
+}
+
+/// Lists the channel files in a directory, keyed by channel name (the file stem)
+pub fn scan_config_dir(directory: &Path) -> Result<HashMap<String, (PathBuf, SystemTime)>, String> {
+    let entries = std::fs::read_dir(directory)
+        .map_err(|e| format!("Could not read config directory {}: {}", directory.display(), e))?;
+
+    let mut files = HashMap::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let is_yaml = matches!(path.extension().and_then(|e| e.to_str()), Some("yaml") | Some("yml"));
+        if !is_yaml || !path.is_file() {
+            continue;
+        }
+        let Some(channel) = path.file_stem().and_then(|s| s.to_str()).map(|s| s.to_string()) else { continue };
+        let modified = entry.metadata().and_then(|m| m.modified()).unwrap_or(SystemTime::UNIX_EPOCH);
+        files.insert(channel, (path, modified));
+    }
+    Ok(files)
+}
+
+impl ConfigDirWatcher {
+    pub fn new(directory: impl Into<PathBuf>, interval_seconds: u64) -> Self {
+        ConfigDirWatcher {
+            directory: directory.into(),
+            interval_seconds,
+        }
+    }
+
+    /// Watches for changes relative to `known` (the files already loaded at startup)
+    /// An edited file is reported as a removal followed by an addition
+    pub fn start_watch_loop(self, mut known: HashMap<String, (PathBuf, SystemTime)>) -> mpsc::Receiver<ConfigDirEvent> {
+        info!("Watching config directory {} (interval: {}s)", self.directory.display(), self.interval_seconds);
+        let (tx, rx) = mpsc::channel(64);
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(self.interval_seconds)).await;
+
+                let current = match scan_config_dir(&self.directory) {
+                    Ok(files) => files,
+                    Err(e) => {
+                        warn!("{}", e);
+                        continue;
+                    }
+                };
+
+                let mut events = Vec::new();
+                for channel in known.keys() {
+                    if !current.contains_key(channel) {
+                        events.push(ConfigDirEvent::Removed { channel: channel.clone() });
+                    }
+                }
+                for (channel, (path, modified)) in &current {
+                    match known.get(channel) {
+                        None => events.push(ConfigDirEvent::Added { channel: channel.clone(), path: path.clone() }),
+                        Some((_, previous)) if previous != modified => {
+                            events.push(ConfigDirEvent::Removed { channel: channel.clone() });
+                            events.push(ConfigDirEvent::Added { channel: channel.clone(), path: path.clone() });
+                        }
+                        Some(_) => {}
+                    }
+                }
+
+                for event in events {
+                    debug!("Config directory event: {:?}", event);
+                    if tx.send(event).await.is_err() {
+                        return; // nobody is listening any more
+                    }
+                }
+                known = current;
+            }
+        });
+
+        rx
+    }
+}
@@ -0,0 +1,192 @@
+use serde_json::{Map, Value};
+
+const INDENT: usize = 2;
+const EXAMPLE_KEY: &str = "example"; // stands in for the names of channels, SDRs and other user-named entries
+const MAX_DEPTH: usize = 12;
+
+const HEADER: &str = "\
+# Example watchdog configuration, written by `watchdog init`
+#
+# Every supported field is listed with its default. Optional fields and sections are commented out,
+# fields marked required need a real value. `watchdog validate` checks the result and `watchdog schema`
+# prints the JSON Schema this file was generated from.
+";
+
+/// A YAML config listing every field of `schema` (the JSON Schema of the config) with its description, type and default,
+/// so the example follows the same serde defaults the watchdog reads the config with
+pub fn render(schema: &Value) -> String {
+    let example = Example {
+        definitions: schema.get("definitions").and_then(Value::as_object),
+    };
+    let mut lines = vec![HEADER.to_string()];
+    example.properties(schema, 0, false, 0, &mut lines);
+    lines.join("\n") + "\n"
+}
+
+struct Example<'a> {
+    definitions: Option<&'a Map<String, Value>>,
+}
+
+/// What a field holds, which decides how it's laid out
+enum Shape<'a> {
+    Section(&'a Value), // a struct, its fields nested below
+    Map(&'a Value), // user-named entries, the schema of each
+    List(&'a Value), // the schema of each item
+    Scalar,
+}
+
+impl<'a> Example<'a> {
+    /// `schema` with references and single-schema wrappers followed, and whether `null` is allowed
+    fn resolve(&self, schema: &'a Value) -> (&'a Value, bool) {
+        if let Some(reference) = schema.get("$ref").and_then(Value::as_str) {
+            let name = reference.trim_start_matches("#/definitions/");
+            if let Some(definition) = self.definitions.and_then(|definitions| definitions.get(name)) {
+                return self.resolve(definition);
+            }
+        }
+        for key in ["allOf", "anyOf"] {
+            let Some(options) = schema.get(key).and_then(Value::as_array) else { continue };
+            let non_null: Vec<&Value> = options.iter().filter(|option| types(option) != ["null"]).collect();
+            if let [only] = non_null.as_slice() {
+                let (resolved, nullable) = self.resolve(only);
+                return (resolved, nullable || non_null.len() < options.len());
+            }
+        }
+        (schema, types(schema).contains(&"null"))
+    }
+
+    fn shape(&self, schema: &'a Value) -> Shape<'a> {
+        if schema.get("properties").is_some() {
+            return Shape::Section(schema);
+        }
+        if let Some(values) = schema.get("additionalProperties").filter(|values| values.is_object()) {
+            return Shape::Map(values);
+        }
+        match schema.get("items") {
+            Some(items) if types(schema).contains(&"array") => Shape::List(items),
+            _ => Shape::Scalar,
+        }
+    }
+
+    fn properties(&self, schema: &'a Value, indent: usize, commented: bool, depth: usize, lines: &mut Vec<String>) {
+        let Some(properties) = schema.get("properties").and_then(Value::as_object) else { return };
+        let required: Vec<&str> = schema.get("required").and_then(Value::as_array)
+            .map(|required| required.iter().filter_map(Value::as_str).collect())
+            .unwrap_or_default();
+        for (name, property) in properties {
+            self.property(name, property, required.contains(&name.as_str()), indent, commented, depth, lines);
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn property(&self, name: &str, schema: &'a Value, required: bool, indent: usize, commented: bool, depth: usize, lines: &mut Vec<String>) {
+        let (resolved, nullable) = self.resolve(schema);
+        let default = schema.get("default").or(resolved.get("default"));
+        let description = schema.get("description").or(resolved.get("description")).and_then(Value::as_str);
+        let optional = !required && default.is_none();
+        let commented = commented || optional;
+
+        if let Some(description) = description {
+            for text in description.lines() {
+                lines.push(format!("{}# {}", " ".repeat(indent), text).trim_end().to_string());
+            }
+        }
+        let note = describe(resolved, required, nullable);
+        if depth >= MAX_DEPTH {
+            lines.push(line(indent, &format!("{}: {{}}", name), true, &note));
+            return;
+        }
+        match self.shape(resolved) {
+            Shape::Section(section) => {
+                lines.push(line(indent, &format!("{}:", name), commented, &note));
+                self.properties(section, indent + INDENT, commented, depth + 1, lines);
+            }
+            Shape::Map(values) => {
+                // Entries are named by the user, so the one shown is an example left commented out
+                match default {
+                    Some(default) => lines.push(line(indent, &format!("{}: {}", name, default), commented, &note)),
+                    None => lines.push(line(indent, &format!("{}:", name), true, &note)),
+                }
+                self.property(EXAMPLE_KEY, values, false, indent + INDENT, true, depth + 1, lines);
+            }
+            Shape::List(items) => {
+                match default {
+                    Some(default) => lines.push(line(indent, &format!("{}: {}", name, default), commented, &note)),
+                    None => lines.push(line(indent, &format!("{}:", name), true, &note)),
+                }
+                let (item, _) = self.resolve(items);
+                match self.shape(item) {
+                    Shape::Section(section) => {
+                        lines.push(line(indent + INDENT, "-", true, ""));
+                        self.properties(section, indent + 2 * INDENT, true, depth + 1, lines);
+                    }
+                    _ => lines.push(line(indent + INDENT, &format!("- {}", placeholder(item)), true, &describe(item, false, false))),
+                }
+            }
+            Shape::Scalar => {
+                let value = default.map(Value::to_string).unwrap_or_else(|| placeholder(resolved));
+                lines.push(line(indent, &format!("{}: {}", name, value), commented, &note));
+            }
+        }
+    }
+}
+
+fn types(schema: &Value) -> Vec<&str> {
+    match schema.get("type") {
+        Some(Value::String(single)) => vec![single.as_str()],
+        Some(Value::Array(several)) => several.iter().filter_map(Value::as_str).collect(),
+        _ => Vec::new(),
+    }
+}
+
+/// The values an enum allows, plain or with each variant described on its own
+fn variants(schema: &Value) -> Vec<String> {
+    if let Some(values) = schema.get("enum").and_then(Value::as_array) {
+        return values.iter().filter(|value| !value.is_null()).map(|value| value.as_str().map_or(value.to_string(), str::to_string)).collect();
+    }
+    schema.get("oneOf").and_then(Value::as_array)
+        .map(|options| options.iter().flat_map(variants).collect())
+        .unwrap_or_default()
+}
+
+/// A value of the right type for fields without a default, JSON being valid YAML
+fn placeholder(schema: &Value) -> String {
+    if let Some(first) = variants(schema).first() {
+        return first.clone();
+    }
+    match types(schema).into_iter().find(|t| *t != "null") {
+        Some("string") => "\"\"".to_string(),
+        Some("integer") => "0".to_string(),
+        Some("number") => "0.0".to_string(),
+        Some("boolean") => "false".to_string(),
+        Some("array") => "[]".to_string(),
+        Some("object") => "{}".to_string(),
+        _ => "~".to_string(),
+    }
+}
+
+/// The trailing comment of a field: its type, allowed values and whether it has to be set
+fn describe(schema: &Value, required: bool, nullable: bool) -> String {
+    let variants = variants(schema);
+    let mut note = if !variants.is_empty() {
+        format!("one of {}", variants.join(", "))
+    } else {
+        let kind = types(schema).into_iter().find(|t| *t != "null").unwrap_or("value");
+        match schema.get("format").and_then(Value::as_str) {
+            Some(format) => format!("{} ({})", kind, format),
+            None => kind.to_string(),
+        }
+    };
+    if required {
+        note.push_str(", required");
+    } else if nullable {
+        note.push_str(", optional");
+    }
+    note
+}
+
+fn line(indent: usize, text: &str, commented: bool, note: &str) -> String {
+    let prefix = if commented { "# " } else { "" };
+    let note = if note.is_empty() { String::new() } else { format!(" # {}", note) };
+    format!("{}{}{}{}", " ".repeat(indent), prefix, text, note)
+}
@@ -0,0 +1,38 @@
+use std::path::Path;
+use serde::de::DeserializeOwned;
+
+use super::envinterp;
+
+/// Syntax of a config file; all of them go through the same environment interpolation and schema
+#[derive(Debug, Clone, Copy, PartialEq, clap::ValueEnum)]
+pub enum ConfigFormat {
+    Yaml,
+    Toml,
+    Json,
+}
+
+impl ConfigFormat {
+    /// The format a file's extension names, YAML for anything else
+    pub fn from_path(path: &Path) -> ConfigFormat {
+        match path.extension().and_then(|e| e.to_str()).map(|e| e.to_ascii_lowercase()).as_deref() {
+            Some("toml") => ConfigFormat::Toml,
+            Some("json") => ConfigFormat::Json,
+            _ => ConfigFormat::Yaml,
+        }
+    }
+
+    pub fn parse<T: DeserializeOwned>(&self, text: &str) -> Result<T, String> {
+        let value: serde_yaml::Value = match self {
+            ConfigFormat::Yaml => serde_yaml::from_str(text).map_err(|e| e.to_string())?,
+            ConfigFormat::Toml => {
+                let value: toml::Value = toml::from_str(text).map_err(|e| e.to_string())?;
+                serde_yaml::to_value(value).map_err(|e| e.to_string())?
+            }
+            ConfigFormat::Json => {
+                let value: serde_json::Value = serde_json::from_str(text).map_err(|e| e.to_string())?;
+                serde_yaml::to_value(value).map_err(|e| e.to_string())?
+            }
+        };
+        envinterp::resolve(value)
+    }
+}
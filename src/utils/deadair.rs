@@ -0,0 +1,122 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+use chrono::{Local, NaiveDate};
+use tokio::sync::Mutex;
+use tracing::info;
+use watchdog::api::AlertReason;
+
+use super::alertmanager::AlertManager;
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DeadAirStats {
+    pub today_seconds: f64,
+    pub total_seconds: f64, // since process start
+}
+
+struct DeadAirState {
+    day: NaiveDate,
+    last_pass: Instant, // a pass covers the time since the previous one: the sleep plus the analyses
+    stats: HashMap<String, DeadAirStats>,
+}
+
+/// Accumulates silent seconds per stream per (local) day for compliance reporting
+pub struct DeadAirTracker {
+    state: Mutex<DeadAirState>,
+    silence_threshold: f32, // max volume (dB) below which a stream counts as dead air, unless tuned, scheduled or overridden
+    daily_budget_seconds: Option<u64>,
+    alert_manager: Arc<AlertManager>,
+}
+
+impl DeadAirTracker {
+    pub fn new(silence_threshold: f32, daily_budget_seconds: Option<u64>, alert_manager: Arc<AlertManager>) -> Self {
+        DeadAirTracker {
+            state: Mutex::new(DeadAirState {
+                day: Local::now().date_naive(),
+                last_pass: Instant::now(),
+                stats: HashMap::new(),
+            }),
+            silence_threshold,
+            daily_budget_seconds,
+            alert_manager,
+        }
+    }
+
+    pub fn silence_threshold(&self) -> f32 {
+        self.silence_threshold
+    }
+
+    /// Called once per volume detection pass with whether each analyzed stream was silent, judged against its
+    /// own threshold and outside expected dead air; silent streams are charged the time since the previous pass
+    pub async fn record(&self, silent: &HashMap<String, bool>) {
+        let today = Local::now().date_naive();
+        let mut state = self.state.lock().await;
+        let elapsed = std::mem::replace(&mut state.last_pass, Instant::now()).elapsed().as_secs_f64();
+
+        if state.day != today {
+            let digest = Self::format_digest(state.day, &state.stats);
+            info!("Dead air day rollover, sending digest for {}", state.day);
+            for stats in state.stats.values_mut() {
+                stats.today_seconds = 0.0;
+            }
+            state.day = today;
+            self.alert_manager.send_digest(digest).await;
+        }
+
+        for (stream_name, is_silent) in silent {
+            // The silence reference channel is silent by design
+            if stream_name == "silence" {
+                continue;
+            }
+
+            let stats = state.stats.entry(stream_name.clone()).or_default();
+            if *is_silent {
+                stats.today_seconds += elapsed;
+                stats.total_seconds += elapsed;
+            }
+
+            if let Some(budget) = self.daily_budget_seconds {
+                let is_error = stats.today_seconds > budget as f64;
+                let message = if is_error {
+                    format!("Stream `{}` has {} of dead air today (budget {})",
+                        stream_name, format_seconds(stats.today_seconds as u64), format_seconds(budget))
+                } else {
+                    format!("Stream `{}` dead air is back within the daily budget", stream_name)
+                };
+                self.alert_manager.update_alert(format!("{}_dead_air_budget", stream_name), AlertReason::DeadAirBudget, &[stream_name.as_str()], is_error, message).await;
+            }
+        }
+    }
+
+    pub async fn get_stats(&self) -> HashMap<String, DeadAirStats> {
+        self.state.lock().await.stats.clone()
+    }
+
+    fn format_digest(day: NaiveDate, stats: &HashMap<String, DeadAirStats>) -> String {
+        let mut names: Vec<&String> = stats.keys().collect();
+        names.sort();
+
+        let lines: Vec<String> = names.iter()
+            .map(|name| format!("• `{}`: {}", name, format_seconds(stats[*name].today_seconds as u64)))
+            .collect();
+
+        if lines.is_empty() {
+            format!("*Daily dead air report for {}:* _no streams tracked_", day)
+        } else {
+            format!("*Daily dead air report for {}:*\n{}", day, lines.join("\n"))
+        }
+    }
+}
+
+pub fn format_seconds(seconds: u64) -> String {
+    let hours = seconds / 3600;
+    let minutes = (seconds % 3600) / 60;
+    let secs = seconds % 60;
+    if hours > 0 {
+        format!("{}h {}m {}s", hours, minutes, secs)
+    } else if minutes > 0 {
+        format!("{}m {}s", minutes, secs)
+    } else {
+        format!("{}s", secs)
+    }
+}
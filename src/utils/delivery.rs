@@ -0,0 +1,105 @@
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use tracing::info;
+
+use super::alertmanager::AlertManager;
+use watchdog::api::AlertReason;
+
+const DELIVERY_CHECK_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Delivery outcomes of one notification sink since startup
+#[derive(Default)]
+pub struct SinkCounters {
+    delivered: AtomicU64,
+    failed: AtomicU64,
+    retries: AtomicU64,
+    consecutive_failures: AtomicU64,
+    last_error: Mutex<Option<(DateTime<Utc>, String)>>,
+}
+
+impl SinkCounters {
+    pub fn delivered(&self) {
+        self.delivered.fetch_add(1, Ordering::Relaxed);
+        self.consecutive_failures.store(0, Ordering::Relaxed);
+    }
+
+    /// A delivery given up on, retries included
+    pub fn failed(&self, error: &str) {
+        self.failed.fetch_add(1, Ordering::Relaxed);
+        self.consecutive_failures.fetch_add(1, Ordering::Relaxed);
+        *self.last_error.lock().expect("last error lock poisoned") = Some((Utc::now(), error.to_string()));
+    }
+
+    pub fn retried(&self) {
+        self.retries.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn get_delivered(&self) -> u64 {
+        self.delivered.load(Ordering::Relaxed)
+    }
+
+    pub fn get_failed(&self) -> u64 {
+        self.failed.load(Ordering::Relaxed)
+    }
+
+    pub fn get_retries(&self) -> u64 {
+        self.retries.load(Ordering::Relaxed)
+    }
+
+    /// Failures since the last successful delivery
+    pub fn get_consecutive_failures(&self) -> u64 {
+        self.consecutive_failures.load(Ordering::Relaxed)
+    }
+
+    pub fn get_last_error(&self) -> Option<(DateTime<Utc>, String)> {
+        self.last_error.lock().expect("last error lock poisoned").clone()
+    }
+}
+
+/// Counters of every notification sink (Slack, health webhook, email), by sink name
+#[derive(Default)]
+pub struct DeliveryStats {
+    sinks: RwLock<BTreeMap<String, Arc<SinkCounters>>>,
+}
+
+impl DeliveryStats {
+    pub fn new() -> Self {
+        DeliveryStats::default()
+    }
+
+    /// The counters of `name`, registered on first use
+    pub fn sink(&self, name: &str) -> Arc<SinkCounters> {
+        if let Some(counters) = self.sinks.read().expect("sinks lock poisoned").get(name) {
+            return counters.clone();
+        }
+        self.sinks.write().expect("sinks lock poisoned").entry(name.to_string()).or_default().clone()
+    }
+
+    pub fn get_sinks(&self) -> Vec<(String, Arc<SinkCounters>)> {
+        self.sinks.read().expect("sinks lock poisoned").iter().map(|(name, counters)| (name.clone(), counters.clone())).collect()
+    }
+
+    /// Raise an alert once a sink fails `after_failures` deliveries in a row. It reaches the dashboard, the API
+    /// and whichever sinks still work, so a bad Slack token no longer only shows up in the logs
+    pub async fn start_alert_loop(self: Arc<Self>, alert_manager: Arc<AlertManager>, after_failures: u64) {
+        info!("Alerting when a notification sink fails {} deliveries in a row", after_failures);
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(DELIVERY_CHECK_INTERVAL).await;
+                for (sink, counters) in self.get_sinks() {
+                    let failures = counters.get_consecutive_failures();
+                    let is_error = failures >= after_failures;
+                    let message = match counters.get_last_error() {
+                        Some((_, error)) if is_error => format!("Notifications to {} have failed {} times in a row, last error: {}", sink, failures, error),
+                        _ => format!("Notifications to {} are being delivered again", sink),
+                    };
+                    alert_manager.update_alert(format!("{}_delivery", sink), AlertReason::DeliveryFailing, &[], is_error, message).await;
+                }
+            }
+        });
+    }
+}
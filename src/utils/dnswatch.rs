@@ -0,0 +1,136 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+use super::alertmanager::AlertManager;
+
+pub use watchdog::api::DnsRecord;
+use watchdog::api::AlertReason;
+
+// Without an expected IP list, a change stays alerted on until the addresses have been stable this long
+const CHANGE_ALERT_MINUTES: i64 = 60;
+
+pub struct DnsTarget {
+    pub stream_name: String,
+    pub host: String,
+    pub expected_ips: Option<Vec<String>>,
+}
+
+/// Periodically resolves stream origin hostnames and alerts on changes in the serving IPs
+pub struct DnsWatcher {
+    targets: Vec<DnsTarget>,
+    interval_seconds: u64,
+    records: Arc<RwLock<HashMap<String, DnsRecord>>>, // stream name -> record
+    alert_manager: Option<Arc<AlertManager>>,
+}
+
+/// Pulls the hostname out of a stream URL like `https://host:8000/path`
+pub fn host_from_url(url: &str) -> Option<String> {
+    let without_scheme = url.split("://").nth(1).unwrap_or(url);
+    let authority = without_scheme.split('/').next()?;
+    let authority = authority.rsplit('@').next()?;
+    let host = if authority.starts_with('[') {
+        authority.trim_start_matches('[').split(']').next()?
+    } else {
+        authority.split(':').next()?
+    };
+    if host.is_empty() { None } else { Some(host.to_string()) }
+}
+
+impl DnsWatcher {
+    pub fn new(targets: Vec<DnsTarget>, interval_seconds: u64) -> Self {
+        DnsWatcher {
+            targets,
+            interval_seconds,
+            records: Arc::new(RwLock::new(HashMap::new())),
+            alert_manager: None,
+        }
+    }
+
+    pub fn with_alert_manager(mut self, alert_manager: Arc<AlertManager>) -> Self {
+        self.alert_manager = Some(alert_manager);
+        self
+    }
+
+    pub async fn get_records(&self) -> HashMap<String, DnsRecord> {
+        self.records.read().await.clone()
+    }
+
+    pub async fn start_watch_loop(self: Arc<Self>) {
+        info!("Starting DNS watch for {} streams (interval: {}s)", self.targets.len(), self.interval_seconds);
+
+        tokio::spawn(async move {
+            loop {
+                for target in &self.targets {
+                    self.check(target).await;
+                }
+                tokio::time::sleep(Duration::from_secs(self.interval_seconds)).await;
+            }
+        });
+    }
+
+    async fn check(&self, target: &DnsTarget) {
+        let mut addresses: Vec<String> = match tokio::net::lookup_host((target.host.as_str(), 0)).await {
+            Ok(addrs) => addrs.map(|a| a.ip().to_string()).collect(),
+            Err(e) => {
+                warn!("Failed to resolve {} for stream {}: {}", target.host, target.stream_name, e);
+                return;
+            }
+        };
+        addresses.sort();
+        addresses.dedup();
+
+        let mut records = self.records.write().await;
+        let record = records.entry(target.stream_name.clone()).or_insert_with(|| DnsRecord {
+            host: target.host.clone(),
+            addresses: addresses.clone(),
+            previous_addresses: Vec::new(),
+            changes: 0,
+            last_changed: None,
+            checked_at: Utc::now(),
+        });
+
+        let changed = record.addresses != addresses;
+        if changed {
+            warn!("DNS for {} (stream {}) changed: {:?} -> {:?}", target.host, target.stream_name, record.addresses, addresses);
+            record.previous_addresses = std::mem::replace(&mut record.addresses, addresses.clone());
+            record.changes += 1;
+            record.last_changed = Some(Utc::now());
+        } else {
+            debug!("DNS for {} (stream {}) unchanged: {:?}", target.host, target.stream_name, addresses);
+        }
+        record.checked_at = Utc::now();
+        let last_changed = record.last_changed;
+        let previous = record.previous_addresses.clone();
+        drop(records);
+
+        let Some(ref am) = self.alert_manager else { return };
+        let alert_id = format!("{}_dns", target.stream_name);
+        let (is_error, message) = match target.expected_ips {
+            // Only addresses outside the expected list are unexpected
+            Some(ref expected) => {
+                let unexpected: Vec<&str> = addresses.iter().filter(|ip| !expected.contains(ip)).map(|ip| ip.as_str()).collect();
+                if unexpected.is_empty() {
+                    (false, format!("Stream `{}` origin `{}` resolves to expected addresses again", target.stream_name, target.host))
+                } else {
+                    (true, format!("Stream `{}` origin `{}` resolves to unexpected addresses: {}",
+                        target.stream_name, target.host, unexpected.join(", ")))
+                }
+            }
+            // Without one every change is, until the addresses have settled again
+            None => {
+                let settled = last_changed.map_or(true, |at| Utc::now() - at >= chrono::Duration::minutes(CHANGE_ALERT_MINUTES));
+                if settled {
+                    (false, format!("Stream `{}` origin `{}` addresses unchanged for {} minutes", target.stream_name, target.host, CHANGE_ALERT_MINUTES))
+                } else {
+                    (true, format!("Stream `{}` origin `{}` changed addresses: {} -> {}",
+                        target.stream_name, target.host, previous.join(", "), addresses.join(", ")))
+                }
+            }
+        };
+        am.update_alert(alert_id, AlertReason::DnsUnexpected, &[target.stream_name.as_str()], is_error, message).await;
+    }
+}
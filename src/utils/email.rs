@@ -0,0 +1,96 @@
+use std::sync::Arc;
+use lettre::message::{header::ContentType, Mailbox};
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{AsyncSmtpTransport, AsyncTransport, Message, Tokio1Executor};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tracing::{debug, info, warn};
+
+use super::delivery::{DeliveryStats, SinkCounters};
+
+/// SMTP server and recipients for emailed reports
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct EmailConfig {
+    pub smtp_host: String,
+    #[serde(default = "default_smtp_port")]
+    pub smtp_port: u16,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    /// Turn off only for a local relay that doesn't speak TLS
+    #[serde(default = "default_starttls")]
+    pub starttls: bool,
+    /// e.g. "Radio Watchdog <watchdog@example.org>"
+    pub from: String,
+    pub to: Vec<String>,
+}
+
+fn default_smtp_port() -> u16 { 587 }
+fn default_starttls() -> bool { true }
+
+pub struct EmailSender {
+    config: EmailConfig,
+    dry_run: bool,
+    counters: Arc<SinkCounters>,
+}
+
+impl EmailSender {
+    pub fn new(config: EmailConfig, dry_run: bool) -> Self {
+        EmailSender { config, dry_run, counters: Arc::new(SinkCounters::default()) }
+    }
+
+    /// Counts emails as the "email" sink of `stats`
+    pub fn with_delivery_stats(mut self, stats: &DeliveryStats) -> Self {
+        self.counters = stats.sink("email");
+        self
+    }
+
+    pub async fn send_html(&self, subject: &str, html: String) -> bool {
+        if self.dry_run {
+            info!("DRY RUN: Sending email \"{}\" to {}:\n{}", subject, self.config.to.join(", "), html);
+            self.counters.delivered();
+            return true;
+        }
+
+        match self.try_send(subject, html).await {
+            Ok(()) => {
+                debug!("Email \"{}\" sent successfully!", subject);
+                self.counters.delivered();
+                true
+            }
+            Err(e) => {
+                warn!("Failed to send email \"{}\": {}", subject, e);
+                self.counters.failed(&e);
+                false
+            }
+        }
+    }
+
+    async fn try_send(&self, subject: &str, html: String) -> Result<(), String> {
+        let from: Mailbox = self.config.from.parse().map_err(|e| format!("Bad from address {}: {}", self.config.from, e))?;
+        let mut builder = Message::builder().from(from).subject(subject);
+        for to in &self.config.to {
+            let mailbox: Mailbox = to.parse().map_err(|e| format!("Bad recipient {}: {}", to, e))?;
+            builder = builder.to(mailbox);
+        }
+        let message = builder
+            .header(ContentType::TEXT_HTML)
+            .body(html)
+            .map_err(|e| format!("Could not build message: {}", e))?;
+
+        let mut transport = if self.config.starttls {
+            AsyncSmtpTransport::<Tokio1Executor>::starttls_relay(&self.config.smtp_host)
+                .map_err(|e| format!("Could not set up SMTP to {}: {}", self.config.smtp_host, e))?
+        } else {
+            AsyncSmtpTransport::<Tokio1Executor>::builder_dangerous(&self.config.smtp_host)
+        }.port(self.config.smtp_port);
+        if let (Some(username), Some(password)) = (&self.config.username, &self.config.password) {
+            transport = transport.credentials(Credentials::new(username.clone(), password.clone()));
+        }
+
+        transport.build()
+            .send(message)
+            .await
+            .map(|_| ())
+            .map_err(|e| format!("SMTP delivery via {} failed: {}", self.config.smtp_host, e))
+    }
+}
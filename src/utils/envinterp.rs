@@ -0,0 +1,56 @@
+use serde::de::DeserializeOwned;
+use serde_yaml::Value;
+
+/// Deserializes a parsed config after replacing `${NAME}` in its string values with environment variables,
+/// so tokens don't have to live in the file. `$${NAME}` stays as a literal `${NAME}`. Comments are never expanded.
+pub fn resolve<T: DeserializeOwned>(mut value: Value) -> Result<T, String> {
+    interpolate_value(&mut value)?;
+    serde_yaml::from_value(value).map_err(|e| e.to_string())
+}
+
+fn interpolate_value(value: &mut Value) -> Result<(), String> {
+    match value {
+        Value::String(text) => *text = interpolate(text)?,
+        Value::Sequence(items) => {
+            for item in items {
+                interpolate_value(item)?;
+            }
+        }
+        Value::Mapping(mapping) => {
+            for (_, item) in mapping.iter_mut() {
+                interpolate_value(item)?;
+            }
+        }
+        Value::Tagged(tagged) => interpolate_value(&mut tagged.value)?,
+        Value::Null | Value::Bool(_) | Value::Number(_) => {}
+    }
+    Ok(())
+}
+
+fn interpolate(text: &str) -> Result<String, String> {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('$') {
+        result.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        if let Some(escaped) = after.strip_prefix("${") {
+            result.push_str("${");
+            rest = escaped;
+        } else if let Some(reference) = after.strip_prefix('{') {
+            let end = reference.find('}').ok_or_else(|| format!("Unterminated ${{ in \"{}\"", text))?;
+            let name = &reference[..end];
+            if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+                return Err(format!("Invalid environment variable name \"{}\" in the config", name));
+            }
+            let resolved = std::env::var(name)
+                .map_err(|_| format!("The config references environment variable {}, which is not set", name))?;
+            result.push_str(&resolved);
+            rest = &reference[end + 1..];
+        } else {
+            result.push('$');
+            rest = after;
+        }
+    }
+    result.push_str(rest);
+    Ok(result)
+}
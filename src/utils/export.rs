@@ -0,0 +1,113 @@
+use std::collections::HashMap;
+use chrono::{DateTime, Utc};
+use watchdog::api::{AlertEvent, AlertTransition, ComparisonSample, VolumeSample};
+
+const CSV_HEADER: &str = "timestamp,kind,stream,other_stream,similarity_percent,is_error,offset_seconds,mean_volume_db,max_volume_db,alert,reason,event,message";
+
+/// One spreadsheet row; comparisons, volume samples and alert transitions share the columns they have in common
+struct ExportRow {
+    timestamp: DateTime<Utc>,
+    fields: [String; 12], // every column after the timestamp
+}
+
+/// Quotes a field when it holds a delimiter, quote or line break
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+/// Flattens comparison, volume and alert history into one CSV, ordered by time
+pub fn history_csv(
+    comparisons: &[ComparisonSample],
+    volumes: &HashMap<String, Vec<VolumeSample>>,
+    alerts: &[AlertEvent],
+) -> String {
+    let mut rows = Vec::new();
+
+    for sample in comparisons {
+        let r = &sample.result;
+        rows.push(ExportRow {
+            timestamp: sample.timestamp,
+            fields: [
+                match r.comparison_type() {
+                    "within_channel" => "comparison_within",
+                    "silence_check" => "comparison_silence",
+                    _ => "comparison_cross",
+                }.to_string(),
+                r.stream1.clone(),
+                r.stream2.clone(),
+                format!("{:.1}", r.similarity_percent),
+                r.is_error.to_string(),
+                r.offset_seconds.map(|o| format!("{:.2}", o)).unwrap_or_default(),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+                String::new(),
+                r.whitelisted.clone().unwrap_or_default(),
+            ],
+        });
+    }
+
+    for (stream, samples) in volumes {
+        for sample in samples {
+            rows.push(ExportRow {
+                timestamp: sample.timestamp,
+                fields: [
+                    "volume".to_string(),
+                    stream.clone(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    format!("{:.1}", sample.mean_volume),
+                    format!("{:.1}", sample.max_volume),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                    String::new(),
+                ],
+            });
+        }
+    }
+
+    for event in alerts {
+        rows.push(ExportRow {
+            timestamp: event.timestamp,
+            fields: [
+                "alert".to_string(),
+                event.streams.first().cloned().unwrap_or_default(),
+                event.streams.get(1).cloned().unwrap_or_default(),
+                String::new(),
+                (event.transition == AlertTransition::Failing).to_string(),
+                String::new(),
+                String::new(),
+                String::new(),
+                event.alert.clone(),
+                event.reason.as_str().to_string(),
+                match event.transition {
+                    AlertTransition::Failing => "failing",
+                    AlertTransition::Cleared => "cleared",
+                }.to_string(),
+                event.message.clone(),
+            ],
+        });
+    }
+
+    rows.sort_by_key(|row| row.timestamp);
+
+    let mut csv = String::from(CSV_HEADER);
+    csv.push('\n');
+    for row in rows {
+        csv.push_str(&row.timestamp.to_rfc3339());
+        for field in &row.fields {
+            csv.push(',');
+            csv.push_str(&csv_field(field));
+        }
+        csv.push('\n');
+    }
+    csv
+}
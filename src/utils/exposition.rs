@@ -0,0 +1,95 @@
+use std::collections::{HashMap, HashSet};
+use tracing::debug;
+
+/// Content type of the Prometheus text exposition format
+pub const CONTENT_TYPE: &str = "text/plain; version=0.0.4; charset=utf-8";
+
+struct Family {
+    name: &'static str,
+    help: &'static str,
+    kind: &'static str,
+    samples: Vec<String>,
+}
+
+/// Builds Prometheus text exposition: samples grouped under one HELP/TYPE per metric, label values escaped and series unique
+#[derive(Default)]
+pub struct MetricsWriter {
+    families: Vec<Family>,
+    index: HashMap<&'static str, usize>,
+    series: HashSet<String>,
+}
+
+impl MetricsWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn gauge(&mut self, name: &'static str, help: &'static str) {
+        self.family(name, help, "gauge");
+    }
+
+    pub fn counter(&mut self, name: &'static str, help: &'static str) {
+        self.family(name, help, "counter");
+    }
+
+    fn family(&mut self, name: &'static str, help: &'static str, kind: &'static str) -> usize {
+        if let Some(&i) = self.index.get(name) {
+            return i;
+        }
+        self.families.push(Family { name, help, kind, samples: Vec::new() });
+        self.index.insert(name, self.families.len() - 1);
+        self.families.len() - 1
+    }
+
+    /// Adds one sample; a repeat of a series already written is dropped, since duplicates make the whole scrape fail
+    pub fn sample(&mut self, name: &'static str, labels: &[(&str, &str)], value: f64) {
+        let mut series = name.to_string();
+        if !labels.is_empty() {
+            let pairs: Vec<String> = labels.iter()
+                .map(|(label, value)| format!("{}=\"{}\"", label, escape_label_value(value)))
+                .collect();
+            series.push('{');
+            series.push_str(&pairs.join(","));
+            series.push('}');
+        }
+        if !self.series.insert(series.clone()) {
+            debug!("Dropping duplicate metric series {}", series);
+            return;
+        }
+        let i = self.family(name, "", "untyped");
+        self.families[i].samples.push(format!("{} {}", series, format_value(value)));
+    }
+
+    pub fn finish(self) -> String {
+        let mut out = String::new();
+        for family in self.families {
+            if !family.help.is_empty() {
+                out.push_str(&format!("# HELP {} {}\n", family.name, escape_help(family.help)));
+            }
+            out.push_str(&format!("# TYPE {} {}\n", family.name, family.kind));
+            for sample in family.samples {
+                out.push_str(&sample);
+                out.push('\n');
+            }
+        }
+        out
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+fn escape_help(help: &str) -> String {
+    help.replace('\\', "\\\\").replace('\n', "\\n")
+}
+
+fn format_value(value: f64) -> String {
+    if value.is_nan() {
+        "NaN".to_string()
+    } else if value.is_infinite() {
+        if value > 0.0 { "+Inf".to_string() } else { "-Inf".to_string() }
+    } else {
+        value.to_string()
+    }
+}
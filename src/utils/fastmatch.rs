@@ -0,0 +1,157 @@
+use rusty_chromaprint::Configuration;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+// Fingerprint items of unrelated audio differ in about half their 32 bits, aligned audio in a few
+const COARSE_STRIDE: usize = 4; // every this many items are sampled when ranking alignments
+const COARSE_MAX_BIT_ERRORS: u32 = 12; // mean differing bits per sampled item for an alignment to be a candidate
+const MAX_CANDIDATES: usize = 8; // best alignments checked item by item
+const SUPPRESS_ITEMS: usize = 4; // alignments this close to a better one are the same match smeared by overlapping items
+const SMOOTHING_ITEMS: usize = 8; // differing bits are averaged over this many items before thresholding
+const MATCH_BIT_ERRORS: u32 = 10; // averaged differing bits below which items match
+const MIN_SEGMENT_ITEMS: usize = 16; // ~2 s, shorter runs of matching items are chance
+
+/// Matches large buffers with plain XOR and popcount over aligned items instead of rusty_chromaprint, which
+/// dominates CPU on installs with many streams
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct FastMatchConfig {
+    /// seconds of fingerprint both streams need for the fast path, shorter buffers use rusty_chromaprint
+    #[serde(default = "default_fast_match_min_duration")]
+    pub min_buffer_duration: f32,
+    /// also match every Nth fast match with rusty_chromaprint and count disagreements; 0 never
+    #[serde(default = "default_verify_every")]
+    pub verify_every: u64,
+    /// similarity difference, in % of the buffer, that counts as a disagreement
+    #[serde(default = "default_verify_tolerance")]
+    pub verify_tolerance_percent: f32,
+}
+
+fn default_fast_match_min_duration() -> f32 { 60.0 }
+fn default_verify_every() -> u64 { 100 }
+fn default_verify_tolerance() -> f32 { 10.0 }
+
+impl FastMatchConfig {
+    pub fn min_items(&self) -> usize {
+        (self.min_buffer_duration / Configuration::preset_test1().item_duration_in_seconds()) as usize
+    }
+}
+
+/// fp1 and fp2 aligned so that fp1[i + shift1] lines up with fp2[i + shift2]
+#[derive(Debug, Clone, Copy)]
+struct Alignment {
+    shift1: usize,
+    shift2: usize,
+    len: usize,
+}
+
+impl Alignment {
+    fn items<'a>(&self, fp1: &'a [u32], fp2: &'a [u32]) -> (&'a [u32], &'a [u32]) {
+        (&fp1[self.shift1..self.shift1 + self.len], &fp2[self.shift2..self.shift2 + self.len])
+    }
+
+    /// Where the audio sits in fp2 minus where it sits in fp1, in items
+    fn offset(&self) -> isize {
+        self.shift2 as isize - self.shift1 as isize
+    }
+}
+
+/// Every alignment overlapping by at least `min_overlap` items
+fn alignments(len1: usize, len2: usize, min_overlap: usize) -> impl Iterator<Item = Alignment> {
+    let ahead1 = (1..=len1.saturating_sub(min_overlap)).map(move |shift1| Alignment { shift1, shift2: 0, len: (len1 - shift1).min(len2) });
+    let ahead2 = (0..=len2.saturating_sub(min_overlap)).map(move |shift2| Alignment { shift1: 0, shift2, len: len1.min(len2 - shift2) });
+    ahead1.chain(ahead2)
+}
+
+/// Total differing bits of sampled items, `None` once it's clear the mean can't get under the cutoff
+/// even if every item left matched perfectly. Contiguous blocks keep the XOR/popcount loop vectorizable
+fn sampled_bit_errors(items1: &[u32], items2: &[u32]) -> Option<u32> {
+    let sampled = items1.len().div_ceil(COARSE_STRIDE).max(1) as u32;
+    let budget = sampled * COARSE_MAX_BIT_ERRORS;
+    let mut total = 0;
+    for (block1, block2) in items1.chunks(COARSE_STRIDE * 16).zip(items2.chunks(COARSE_STRIDE * 16)) {
+        total += block1.iter().zip(block2).step_by(COARSE_STRIDE).map(|(a, b)| (a ^ b).count_ones()).sum::<u32>();
+        if total > budget {
+            return None;
+        }
+    }
+    Some(total)
+}
+
+/// Runs of matching items at one alignment, as (start, length) within the overlap
+fn matching_segments(items1: &[u32], items2: &[u32]) -> Vec<(usize, usize)> {
+    let errors: Vec<u32> = items1.iter().zip(items2).map(|(a, b)| (a ^ b).count_ones()).collect();
+    let mut segments = Vec::new();
+    let mut run_start = None;
+    let mut window: u32 = 0;
+    for (i, error) in errors.iter().enumerate() {
+        window += error;
+        if i >= SMOOTHING_ITEMS {
+            window -= errors[i - SMOOTHING_ITEMS];
+        }
+        let span = (i + 1).min(SMOOTHING_ITEMS) as u32;
+        let matching = window <= MATCH_BIT_ERRORS * span;
+        match (matching, run_start) {
+            (true, None) => run_start = Some(i),
+            (false, Some(start)) => {
+                if i - start >= MIN_SEGMENT_ITEMS {
+                    segments.push((start, i - start));
+                }
+                run_start = None;
+            }
+            _ => {}
+        }
+    }
+    if let Some(start) = run_start.filter(|start| errors.len() - start >= MIN_SEGMENT_ITEMS) {
+        segments.push((start, errors.len() - start));
+    }
+    segments
+}
+
+/// Seconds of `fp1` found in `fp2` and the mean offset of the matches in seconds, positive when fp2 is
+/// ahead; the same meaning as `StreamComparator::get_similarity_time`. `None` if either is shorter than the window
+pub fn similarity(fp1: &[u32], fp2: &[u32], window_size: usize) -> Option<(f32, f32)> {
+    if fp1.len() < window_size || fp2.len() < window_size {
+        return None;
+    }
+    let min_overlap = window_size.max(MIN_SEGMENT_ITEMS);
+
+    // Rank alignments on a sample of their items, then keep the best few that aren't neighbours of a better one
+    let mut ranked: Vec<(u32, Alignment)> = alignments(fp1.len(), fp2.len(), min_overlap)
+        .filter_map(|alignment| {
+            let (items1, items2) = alignment.items(fp1, fp2);
+            let total = sampled_bit_errors(items1, items2)?;
+            let mean = total / items1.len().div_ceil(COARSE_STRIDE).max(1) as u32;
+            Some((mean, alignment))
+        })
+        .collect();
+    ranked.sort_by_key(|(mean, alignment)| (*mean, alignment.offset().unsigned_abs()));
+    let mut candidates: Vec<Alignment> = Vec::new();
+    for (_, alignment) in ranked {
+        if candidates.len() >= MAX_CANDIDATES {
+            break;
+        }
+        if candidates.iter().all(|c| c.offset().abs_diff(alignment.offset()) > SUPPRESS_ITEMS) {
+            candidates.push(alignment);
+        }
+    }
+
+    // Time is counted once per fp1 item, even if it matches at more than one alignment
+    let mut covered = vec![false; fp1.len()];
+    let mut offsets = Vec::new();
+    for alignment in candidates {
+        let (items1, items2) = alignment.items(fp1, fp2);
+        for (start, len) in matching_segments(items1, items2) {
+            let from = alignment.shift1 + start;
+            covered[from..from + len].iter_mut().for_each(|item| *item = true);
+            offsets.push(alignment.offset());
+        }
+    }
+
+    let item_duration = Configuration::preset_test1().item_duration_in_seconds();
+    let similar_time = covered.iter().filter(|item| **item).count() as f32 * item_duration;
+    let offset = match offsets.len() {
+        0 => 0.0,
+        n => offsets.iter().sum::<isize>() as f32 / n as f32 * item_duration,
+    };
+    Some((similar_time, offset))
+}
@@ -0,0 +1,91 @@
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use tracing::{debug, info, warn};
+
+use super::audiorouter::AudioRouter;
+use super::audiostream::AudioStreamHealth;
+
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+// Older buffers describe audio too far back to line up with what the streams play now
+const MAX_AGE: Duration = Duration::from_secs(300);
+
+/// Each stream's fingerprint buffer saved to disk, so comparisons resume right after a watchdog restart
+/// instead of waiting for every buffer to fill again
+pub struct FingerprintCache {
+    directory: PathBuf,
+}
+
+impl FingerprintCache {
+    pub fn open(directory: &str) -> Result<Self, String> {
+        std::fs::create_dir_all(directory).map_err(|e| format!("Could not create fingerprint cache {}: {}", directory, e))?;
+        Ok(FingerprintCache { directory: PathBuf::from(directory) })
+    }
+
+    fn path(&self, stream_name: &str) -> PathBuf {
+        let file: String = stream_name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '_' { c } else { '_' })
+            .collect();
+        self.directory.join(format!("{}.fp", file))
+    }
+
+    /// The saved buffer of a stream, unless it is missing or too old to use
+    pub fn load(&self, stream_name: &str) -> Option<Vec<u32>> {
+        let path = self.path(stream_name);
+        let age = std::fs::metadata(&path).ok()?
+            .modified().ok()
+            .and_then(|modified| SystemTime::now().duration_since(modified).ok())?;
+        if age > MAX_AGE {
+            debug!("Saved fingerprint of {} is {}s old, starting empty", stream_name, age.as_secs());
+            return None;
+        }
+
+        let bytes = match std::fs::read(&path) {
+            Ok(bytes) => bytes,
+            Err(e) => {
+                warn!("Could not read saved fingerprint {}: {}", path.display(), e);
+                return None;
+            }
+        };
+        let fingerprint: Vec<u32> = bytes.chunks_exact(4)
+            .map(|item| u32::from_le_bytes([item[0], item[1], item[2], item[3]]))
+            .collect();
+        (!fingerprint.is_empty()).then_some(fingerprint)
+    }
+
+    async fn save(&self, stream_name: &str, fingerprint: &[u32]) -> Result<(), std::io::Error> {
+        let bytes: Vec<u8> = fingerprint.iter().flat_map(|item| item.to_le_bytes()).collect();
+        let path = self.path(stream_name);
+        // Written aside and renamed, a crash mid-write must not leave a truncated buffer behind
+        let partial = path.with_extension("fp.partial");
+        tokio::fs::write(&partial, bytes).await?;
+        tokio::fs::rename(&partial, &path).await
+    }
+
+    pub async fn start_save_loop(self: Arc<Self>, router: Arc<AudioRouter>) {
+        info!("Saving fingerprint buffers to {} every {}s", self.directory.display(), SAVE_INTERVAL.as_secs());
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(SAVE_INTERVAL).await;
+
+                let mut saved = 0;
+                for (stream_name, _, audio_health) in router.get_all_streams().await {
+                    // Only live audio is worth resuming from; a dead stream's buffer just ages out
+                    if audio_health != AudioStreamHealth::Running {
+                        continue;
+                    }
+                    let Some(fingerprint) = router.get_stream_fingerprint(&stream_name).await else { continue };
+                    if fingerprint.is_empty() {
+                        continue;
+                    }
+                    match self.save(&stream_name, &fingerprint).await {
+                        Ok(()) => saved += 1,
+                        Err(e) => warn!("Could not save fingerprint of {}: {}", stream_name, e),
+                    }
+                }
+                debug!("Saved {} fingerprint buffers", saved);
+            }
+        });
+    }
+}
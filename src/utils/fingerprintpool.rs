@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::sync::Arc;
+use rusty_chromaprint::{Configuration, Fingerprinter};
+use tokio::sync::oneshot;
+use tracing::{info, warn};
+
+enum Job {
+    Open { id: u64, channels: u32 },
+    Consume { id: u64, samples: Vec<i16>, keep: usize, reply: oneshot::Sender<Vec<u32>> },
+    Close { id: u64 },
+}
+
+/// A few OS threads doing every stream's fingerprinting, so streams themselves are cheap async tasks.
+/// Each fingerprinter lives on the worker it was opened on for its whole life.
+pub struct FingerprintPool {
+    workers: Vec<mpsc::Sender<Job>>,
+    next_worker: AtomicUsize,
+    next_id: AtomicU64,
+}
+
+/// One stream's fingerprinter on a pool worker, closed on drop
+pub struct PooledFingerprinter {
+    id: u64,
+    worker: mpsc::Sender<Job>,
+}
+
+impl FingerprintPool {
+    pub fn new(threads: usize) -> Arc<Self> {
+        let threads = threads.max(1);
+        let workers = (0..threads).map(|index| {
+            let (sender, jobs) = mpsc::channel();
+            std::thread::Builder::new()
+                .name(format!("fingerprint-{}", index))
+                .spawn(move || run_worker(jobs))
+                .expect("Could not start fingerprint worker thread");
+            sender
+        }).collect();
+        info!("Fingerprinting on {} worker threads", threads);
+
+        Arc::new(FingerprintPool {
+            workers,
+            next_worker: AtomicUsize::new(0),
+            next_id: AtomicU64::new(0),
+        })
+    }
+
+    /// Starts a fingerprinter for `channels` interleaved channels at 44.1 kHz, spread round robin over the workers
+    pub fn open(&self, channels: u32) -> PooledFingerprinter {
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let worker = self.workers[self.next_worker.fetch_add(1, Ordering::Relaxed) % self.workers.len()].clone();
+        let _ = worker.send(Job::Open { id, channels });
+        PooledFingerprinter { id, worker }
+    }
+}
+
+impl PooledFingerprinter {
+    /// Feeds samples and returns the newest `keep` items of the fingerprint so far, None once the worker is gone
+    pub async fn consume(&self, samples: Vec<i16>, keep: usize) -> Option<Vec<u32>> {
+        let (reply, fingerprint) = oneshot::channel();
+        self.worker.send(Job::Consume { id: self.id, samples, keep, reply }).ok()?;
+        fingerprint.await.ok()
+    }
+}
+
+impl Drop for PooledFingerprinter {
+    fn drop(&mut self) {
+        let _ = self.worker.send(Job::Close { id: self.id });
+    }
+}
+
+fn run_worker(jobs: mpsc::Receiver<Job>) {
+    let mut fingerprinters: HashMap<u64, Fingerprinter> = HashMap::new();
+    while let Ok(job) = jobs.recv() {
+        match job {
+            Job::Open { id, channels } => {
+                let mut fingerprinter = Fingerprinter::new(&Configuration::preset_test1());
+                match fingerprinter.start(44100, channels) {
+                    Ok(()) => { fingerprinters.insert(id, fingerprinter); }
+                    Err(e) => warn!("Could not start fingerprinter: {:?}", e),
+                }
+            }
+            Job::Consume { id, samples, keep, reply } => {
+                // Dropping the reply tells the stream its fingerprinter is gone
+                let Some(fingerprinter) = fingerprinters.get_mut(&id) else { continue };
+                fingerprinter.consume(&samples);
+                let fingerprint = fingerprinter.fingerprint();
+                let start = fingerprint.len().saturating_sub(keep);
+                let _ = reply.send(fingerprint[start..].to_vec());
+            }
+            Job::Close { id } => { fingerprinters.remove(&id); }
+        }
+    }
+}
@@ -0,0 +1,150 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tokio::process::Command;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+use super::alertmanager::AlertManager;
+
+pub use watchdog::api::StreamFormat;
+use watchdog::api::AlertReason;
+
+/// What the upstream encoder is supposed to be sending; unset fields aren't checked
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ExpectedFormat {
+    /// ffprobe codec name, e.g. mp3, aac, opus
+    pub codec: Option<String>,
+    pub sample_rate: Option<u32>,
+    pub bitrate_kbps: Option<u32>,
+}
+
+pub struct FormatTarget {
+    pub stream_name: String,
+    pub url: String,
+    pub expected: ExpectedFormat,
+}
+
+/// Periodically probes web streams with ffprobe and compares codec parameters against config
+pub struct FormatChecker {
+    targets: Vec<FormatTarget>,
+    interval_seconds: u64,
+    results: Arc<RwLock<HashMap<String, StreamFormat>>>,
+    alert_manager: Option<Arc<AlertManager>>,
+}
+
+impl FormatChecker {
+    pub fn new(targets: Vec<FormatTarget>, interval_seconds: u64) -> Self {
+        FormatChecker {
+            targets,
+            interval_seconds,
+            results: Arc::new(RwLock::new(HashMap::new())),
+            alert_manager: None,
+        }
+    }
+
+    pub fn with_alert_manager(mut self, alert_manager: Arc<AlertManager>) -> Self {
+        self.alert_manager = Some(alert_manager);
+        self
+    }
+
+    pub async fn get_results(&self) -> HashMap<String, StreamFormat> {
+        self.results.read().await.clone()
+    }
+
+    pub async fn start_check_loop(self: Arc<Self>) {
+        info!("Starting format verification for {} streams (interval: {}s)", self.targets.len(), self.interval_seconds);
+
+        tokio::spawn(async move {
+            loop {
+                for target in &self.targets {
+                    match probe_format(&target.url).await {
+                        Ok(mut format) => {
+                            format.mismatches = mismatches(&target.expected, &format);
+                            self.report(target, &format).await;
+                            self.results.write().await.insert(target.stream_name.clone(), format);
+                        }
+                        Err(e) => warn!("Format probe of {} failed: {}", target.stream_name, e),
+                    }
+                }
+                tokio::time::sleep(Duration::from_secs(self.interval_seconds)).await;
+            }
+        });
+    }
+
+    async fn report(&self, target: &FormatTarget, format: &StreamFormat) {
+        let Some(ref am) = self.alert_manager else { return };
+        let is_error = !format.mismatches.is_empty();
+        let message = if is_error {
+            format!("Stream `{}` format differs from config: {}", target.stream_name, format.mismatches.join(", "))
+        } else {
+            format!("Stream `{}` format matches config again", target.stream_name)
+        };
+        am.update_alert(format!("{}_format", target.stream_name), AlertReason::FormatMismatch, &[target.stream_name.as_str()], is_error, message).await;
+    }
+}
+
+fn mismatches(expected: &ExpectedFormat, actual: &StreamFormat) -> Vec<String> {
+    let mut out = Vec::new();
+    if let Some(ref codec) = expected.codec {
+        if actual.codec.as_deref() != Some(codec.as_str()) {
+            out.push(format!("codec {} (expected {})", actual.codec.as_deref().unwrap_or("unknown"), codec));
+        }
+    }
+    if let Some(rate) = expected.sample_rate {
+        if actual.sample_rate != Some(rate) {
+            out.push(format!("sample rate {} (expected {})", actual.sample_rate.map(|r| r.to_string()).unwrap_or("unknown".to_string()), rate));
+        }
+    }
+    if let Some(kbps) = expected.bitrate_kbps {
+        // Declared bitrates are sometimes rounded (e.g. 127999), so allow a little slack
+        if !actual.bitrate_kbps.is_some_and(|actual| actual.abs_diff(kbps) <= 1) {
+            out.push(format!("bitrate {} kbps (expected {})", actual.bitrate_kbps.map(|b| b.to_string()).unwrap_or("unknown".to_string()), kbps));
+        }
+    }
+    out
+}
+
+/// Ask ffprobe for the first audio stream's codec, sample rate and declared bitrate
+async fn probe_format(url: &str) -> Result<StreamFormat, String> {
+    let output = tokio::time::timeout(Duration::from_secs(30), Command::new("ffprobe")
+        .args([
+            "-v", "error",
+            "-select_streams", "a:0",
+            "-show_entries", "stream=codec_name,sample_rate,bit_rate:format=bit_rate",
+            "-of", "json",
+            url,
+        ])
+        .kill_on_drop(true)
+        .output())
+        .await
+        .map_err(|_| "ffprobe timed out".to_string())?
+        .map_err(|e| format!("Failed to run ffprobe: {}", e))?;
+
+    if !output.status.success() {
+        return Err(format!("ffprobe exited with {}: {}", output.status, String::from_utf8_lossy(&output.stderr).trim()));
+    }
+
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout)
+        .map_err(|e| format!("Failed to parse ffprobe output: {}", e))?;
+    let stream = json.get("streams").and_then(|s| s.get(0));
+    let field = |value: Option<&serde_json::Value>, name: &str| {
+        value.and_then(|v| v.get(name)).and_then(|v| v.as_str()).map(|v| v.to_string())
+    };
+
+    // Streams often only declare a bitrate at the container level
+    let bit_rate = field(stream, "bit_rate").or_else(|| field(json.get("format"), "bit_rate"));
+
+    let format = StreamFormat {
+        codec: field(stream, "codec_name"),
+        sample_rate: field(stream, "sample_rate").and_then(|r| r.parse().ok()),
+        bitrate_kbps: bit_rate.and_then(|b| b.parse::<u32>().ok()).map(|b| (b + 500) / 1000),
+        mismatches: Vec::new(),
+        checked_at: Utc::now(),
+    };
+    debug!("Probed format of {}: {:?}", url, format);
+    Ok(format)
+}
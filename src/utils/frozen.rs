@@ -0,0 +1,116 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use rusty_chromaprint::Configuration;
+use tracing::{debug, info};
+
+use super::alertmanager::AlertManager;
+use super::audiorouter::AudioRouter;
+use super::audiostream::AudioStreamHealth;
+use super::commandprocessor::StreamHealth;
+use watchdog::api::AlertReason;
+
+const CHECK_INTERVAL: Duration = Duration::from_secs(10);
+const WINDOW_SECONDS: f32 = 5.0; // newest audio looked for elsewhere in the buffer, long enough that real audio never repeats it exactly
+
+/// Where the newest fingerprint window was seen before, if anywhere
+#[derive(Debug, PartialEq)]
+enum Repeat {
+    Stuck, // same newest window as the previous check, the buffer stopped advancing
+    Looping, // newest window already appears earlier in the buffer, the same audio is playing again
+}
+
+#[derive(Default)]
+struct StreamState {
+    last_window: Vec<u32>,
+    frozen_since: Option<Instant>,
+}
+
+/// Catches streams whose process and audio look healthy while the audio itself is frozen: an origin that
+/// keeps the connection open but replays its last buffer. Silence is left to the silence checks
+pub struct FrozenDetector {
+    router: Arc<AudioRouter>,
+    frozen_after: Duration,
+    window_items: usize,
+    alert_manager: Option<Arc<AlertManager>>,
+}
+
+impl FrozenDetector {
+    pub fn new(router: Arc<AudioRouter>, frozen_after: Duration) -> Self {
+        let window_items = (WINDOW_SECONDS / Configuration::preset_test1().item_duration_in_seconds()) as usize;
+        FrozenDetector { router, frozen_after, window_items, alert_manager: None }
+    }
+
+    pub fn with_alert_manager(mut self, alert_manager: Arc<AlertManager>) -> Self {
+        self.alert_manager = Some(alert_manager);
+        self
+    }
+
+    fn find_repeat(&self, fingerprint: &[u32], last_window: &[u32]) -> Option<Repeat> {
+        let window = &fingerprint[fingerprint.len() - self.window_items..];
+        // A constant fingerprint is digital silence, not a frozen source
+        if window.iter().all(|item| *item == window[0]) {
+            return None;
+        }
+        if window == last_window {
+            return Some(Repeat::Stuck);
+        }
+        let earlier = &fingerprint[..fingerprint.len() - 1];
+        earlier.windows(self.window_items).any(|candidate| candidate == window).then_some(Repeat::Looping)
+    }
+
+    pub async fn start_check_loop(self: Arc<Self>) {
+        info!("Starting frozen audio check (window: {}s, alert after {}s)", WINDOW_SECONDS, self.frozen_after.as_secs());
+
+        tokio::spawn(async move {
+            let mut states: HashMap<String, StreamState> = HashMap::new();
+            loop {
+                tokio::time::sleep(CHECK_INTERVAL).await;
+
+                let streams = self.router.get_all_streams().await;
+                states.retain(|name, _| streams.iter().any(|(stream, _, _)| stream == name));
+                for (stream_name, command_health, audio_health) in streams {
+                    let state = states.entry(stream_name.clone()).or_default();
+                    let running = command_health == StreamHealth::Running && audio_health == AudioStreamHealth::Running;
+                    let fingerprint = if running {
+                        self.router.get_stream_fingerprint(&stream_name).await.unwrap_or_default()
+                    } else {
+                        Vec::new()
+                    };
+                    // Dead and stalled streams are alerted on as such, a short buffer has nothing to repeat yet
+                    if fingerprint.len() < self.window_items * 2 {
+                        state.last_window.clear();
+                        state.frozen_since = None;
+                        self.update_alert(&stream_name, None).await;
+                        continue;
+                    }
+
+                    let repeat = self.find_repeat(&fingerprint, &state.last_window);
+                    state.last_window = fingerprint[fingerprint.len() - self.window_items..].to_vec();
+                    let frozen_for = match repeat {
+                        Some(ref repeat) => {
+                            let since = *state.frozen_since.get_or_insert_with(Instant::now);
+                            debug!("Stream {} repeats its audio ({:?}) for {}s", stream_name, repeat, since.elapsed().as_secs());
+                            Some(since.elapsed())
+                        }
+                        None => {
+                            state.frozen_since = None;
+                            None
+                        }
+                    };
+                    self.update_alert(&stream_name, frozen_for.filter(|elapsed| *elapsed >= self.frozen_after)).await;
+                }
+            }
+        });
+    }
+
+    async fn update_alert(&self, stream_name: &str, frozen_for: Option<Duration>) {
+        let Some(ref am) = self.alert_manager else { return };
+        let message = match frozen_for {
+            Some(elapsed) => format!("Stream `{}` is frozen: the same audio has been repeating for {}s while the process keeps running, the source is likely replaying its last buffer",
+                stream_name, elapsed.as_secs()),
+            None => format!("Stream `{}` audio is advancing again", stream_name),
+        };
+        am.update_alert(format!("{}_frozen", stream_name), AlertReason::AudioFrozen, &[stream_name], frozen_for.is_some(), message).await;
+    }
+}
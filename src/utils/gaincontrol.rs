@@ -0,0 +1,97 @@
+use std::sync::Arc;
+use std::time::Duration;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tracing::{debug, info, warn};
+
+use super::nrsc::NrscManager;
+
+// Share of IQ samples at the 8-bit rails above which the front end is overloaded
+const CLIP_RATIO_LIMIT: f32 = 0.005;
+// Below this the signal is using too little of the ADC range to decode reliably
+const MIN_RMS_DBFS: f32 = -30.0;
+
+/// Automatic rtl_tcp gain adjustment from measured IQ level and nrsc5 bit error rate
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct GainControlConfig {
+    /// dB, never go below this
+    pub min_gain: f32,
+    /// dB, never go above this
+    pub max_gain: f32,
+    /// dB moved per adjustment
+    #[serde(default = "default_gain_step")]
+    pub step: f32,
+    /// How often the level is measured and the gain adjusted
+    #[serde(default = "default_gain_interval")]
+    pub interval_seconds: u64,
+    /// Raise the gain while nrsc5 reports a bit error rate above this
+    #[serde(default = "default_max_ber")]
+    pub max_ber: f32,
+}
+
+fn default_gain_step() -> f32 { 1.0 }
+fn default_gain_interval() -> u64 { 60 }
+fn default_max_ber() -> f32 { 0.001 }
+
+/// Nudges an SDR's gain one step at a time: down while the ADC clips, up while the
+/// signal is weak or nrsc5 is struggling, always within the configured bounds
+pub struct GainController {
+    sdr_name: String,
+    nrsc: Arc<NrscManager>,
+    config: GainControlConfig,
+    gain: f32,
+}
+
+impl GainController {
+    pub fn new(sdr_name: String, nrsc: Arc<NrscManager>, config: GainControlConfig, initial_gain: Option<f32>) -> Self {
+        let gain = initial_gain
+            .unwrap_or((config.min_gain + config.max_gain) / 2.0)
+            .clamp(config.min_gain, config.max_gain);
+        GainController { sdr_name, nrsc, config, gain }
+    }
+
+    pub async fn start_control_loop(mut self) {
+        info!("Starting gain control for SDR {} ({:.1}-{:.1} dB, starting at {:.1} dB)",
+            self.sdr_name, self.config.min_gain, self.config.max_gain, self.gain);
+        self.nrsc.enable_level_measurement();
+        if let Err(e) = self.nrsc.set_gain(self.gain).await {
+            warn!("Could not set initial gain on SDR {}: {}", self.sdr_name, e);
+        }
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(self.config.interval_seconds)).await;
+                self.adjust().await;
+            }
+        });
+    }
+
+    async fn adjust(&mut self) {
+        let Some(level) = self.nrsc.take_signal_level() else {
+            debug!("No IQ samples from SDR {} this interval, leaving gain alone", self.sdr_name);
+            return;
+        };
+        let ber = self.nrsc.get_ber();
+        debug!("SDR {} level: {:.1} dBFS, {:.2}% clipped, BER {:.6}",
+            self.sdr_name, level.rms_dbfs, level.clip_ratio * 100.0, ber);
+
+        let target = if level.clip_ratio > CLIP_RATIO_LIMIT {
+            self.gain - self.config.step
+        } else if ber > self.config.max_ber || level.rms_dbfs < MIN_RMS_DBFS {
+            self.gain + self.config.step
+        } else {
+            return;
+        };
+        let target = target.clamp(self.config.min_gain, self.config.max_gain);
+        if target == self.gain {
+            return;
+        }
+
+        info!("Adjusting SDR {} gain {:.1} -> {:.1} dB ({:.1} dBFS, {:.2}% clipped, BER {:.6})",
+            self.sdr_name, self.gain, target, level.rms_dbfs, level.clip_ratio * 100.0, ber);
+        match self.nrsc.set_gain(target).await {
+            Ok(()) => self.gain = target,
+            Err(e) => warn!("Could not set gain on SDR {}: {}", self.sdr_name, e),
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::sync::Arc;
+use async_graphql::{Context, EmptyMutation, EmptySubscription, Object, Schema, SimpleObject};
+use tokio::sync::RwLock;
+
+use super::audiorouter::AudioRouter;
+use super::comparator::ComparisonResult;
+
+pub type WatchdogSchema = Schema<QueryRoot, EmptyMutation, EmptySubscription>;
+
+// Newtype so the comparison results can be fetched from the schema context by type
+struct Comparisons(Arc<RwLock<Vec<ComparisonResult>>>);
+
+pub fn build_schema(router: Arc<AudioRouter>, comparison_results: Arc<RwLock<Vec<ComparisonResult>>>) -> WatchdogSchema {
+    Schema::build(QueryRoot, EmptyMutation, EmptySubscription)
+        .data(router)
+        .data(Comparisons(comparison_results))
+        .finish()
+}
+
+pub struct QueryRoot;
+
+#[Object]
+impl QueryRoot {
+    /// Every monitored stream, optionally limited to one channel
+    async fn streams(&self, ctx: &Context<'_>, channel: Option<String>) -> Vec<StreamNode> {
+        let router = ctx.data_unchecked::<Arc<AudioRouter>>();
+        let mut nodes = Vec::new();
+        for channel_name in router.get_all_channels() {
+            if channel.as_ref().is_some_and(|c| c != &channel_name) {
+                continue;
+            }
+            for name in router.get_channel_streams(&channel_name).unwrap_or_default() {
+                nodes.push(StreamNode { name, channel: channel_name.clone() });
+            }
+        }
+        nodes.sort_by(|a, b| a.name.cmp(&b.name));
+        nodes
+    }
+
+    async fn stream(&self, ctx: &Context<'_>, name: String) -> Option<StreamNode> {
+        let router = ctx.data_unchecked::<Arc<AudioRouter>>();
+        router.get_all_channels().into_iter().find_map(|channel| {
+            let streams = router.get_channel_streams(&channel)?;
+            streams.contains(&name).then(|| StreamNode { name: name.clone(), channel })
+        })
+    }
+
+    async fn channels(&self, ctx: &Context<'_>) -> Vec<ChannelNode> {
+        let router = ctx.data_unchecked::<Arc<AudioRouter>>();
+        let mut channels: Vec<ChannelNode> = router.get_all_channels().into_iter().map(|name| ChannelNode { name }).collect();
+        channels.sort_by(|a, b| a.name.cmp(&b.name));
+        channels
+    }
+
+    /// Latest comparison results; `errors_only` drops passing pairs
+    async fn comparisons(&self, ctx: &Context<'_>, #[graphql(default)] errors_only: bool) -> Vec<ComparisonNode> {
+        let results = ctx.data_unchecked::<Comparisons>().0.read().await;
+        results.iter()
+            .filter(|r| !errors_only || r.is_error)
+            .map(ComparisonNode::from)
+            .collect()
+    }
+}
+
+pub struct ChannelNode {
+    name: String,
+}
+
+#[Object]
+impl ChannelNode {
+    async fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn streams(&self, ctx: &Context<'_>) -> Vec<StreamNode> {
+        let router = ctx.data_unchecked::<Arc<AudioRouter>>();
+        router.get_channel_streams(&self.name)
+            .unwrap_or_default()
+            .into_iter()
+            .map(|name| StreamNode { name, channel: self.name.clone() })
+            .collect()
+    }
+}
+
+/// Fields are resolved lazily, so only what the query selects is looked up
+pub struct StreamNode {
+    name: String,
+    channel: String,
+}
+
+#[Object]
+impl StreamNode {
+    async fn name(&self) -> &str {
+        &self.name
+    }
+
+    async fn channel(&self) -> &str {
+        &self.channel
+    }
+
+    async fn command_health(&self, ctx: &Context<'_>) -> Option<String> {
+        let router = ctx.data_unchecked::<Arc<AudioRouter>>();
+        router.get_stream_health(&self.name).await.map(|(cmd, _)| format!("{:?}", cmd))
+    }
+
+    async fn audio_health(&self, ctx: &Context<'_>) -> Option<String> {
+        let router = ctx.data_unchecked::<Arc<AudioRouter>>();
+        router.get_stream_health(&self.name).await.map(|(_, audio)| format!("{:?}", audio))
+    }
+
+    async fn uptime_seconds(&self, ctx: &Context<'_>) -> Option<i64> {
+        let router = ctx.data_unchecked::<Arc<AudioRouter>>();
+        router.get_stream_uptime(&self.name).await.map(|u| u.num_seconds())
+    }
+
+    async fn volume(&self, ctx: &Context<'_>) -> Option<VolumeNode> {
+        let router = ctx.data_unchecked::<Arc<AudioRouter>>();
+        router.get_stream_volume(&self.name).await.map(|v| VolumeNode {
+            timestamp: None,
+            mean_db: v.mean_volume,
+            max_db: v.max_volume,
+        })
+    }
+
+    /// Most recent volume samples, newest last
+    async fn volume_history(&self, ctx: &Context<'_>, limit: Option<usize>) -> Vec<VolumeNode> {
+        let router = ctx.data_unchecked::<Arc<AudioRouter>>();
+        let history = router.get_stream_volume_history(&self.name).await;
+        let skip = limit.map(|l| history.len().saturating_sub(l)).unwrap_or(0);
+        history.into_iter().skip(skip).map(|s| VolumeNode {
+            timestamp: Some(s.timestamp.to_rfc3339()),
+            mean_db: s.mean_volume,
+            max_db: s.max_volume,
+        }).collect()
+    }
+
+    async fn comparisons(&self, ctx: &Context<'_>) -> Vec<ComparisonNode> {
+        let results = ctx.data_unchecked::<Comparisons>().0.read().await;
+        results.iter()
+            .filter(|r| r.stream1 == self.name || r.stream2 == self.name)
+            .map(ComparisonNode::from)
+            .collect()
+    }
+}
+
+#[derive(SimpleObject)]
+pub struct VolumeNode {
+    timestamp: Option<String>, // RFC 3339, unset for the live reading
+    mean_db: f32,
+    max_db: f32,
+}
+
+#[derive(SimpleObject)]
+pub struct ComparisonNode {
+    stream1: String,
+    stream2: String,
+    similarity_percent: f32,
+    is_within_channel: bool,
+    is_error: bool,
+    offset_seconds: Option<f32>,
+    whitelisted: Option<String>,
+    is_silence_check: bool,
+}
+
+impl From<&ComparisonResult> for ComparisonNode {
+    fn from(r: &ComparisonResult) -> Self {
+        ComparisonNode {
+            stream1: r.stream1.clone(),
+            stream2: r.stream2.clone(),
+            similarity_percent: r.similarity_percent,
+            is_within_channel: r.is_within_channel,
+            is_error: r.is_error,
+            offset_seconds: r.offset_seconds,
+            whitelisted: r.whitelisted.clone(),
+            is_silence_check: r.is_silence_check,
+        }
+    }
+}
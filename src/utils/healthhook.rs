@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tokio::sync::mpsc;
+use tracing::{debug, info, warn};
+
+use super::audiorouter::AudioRouter;
+use super::audiostream::AudioStreamHealth;
+use super::commandprocessor::StreamHealth;
+use super::delivery::{DeliveryStats, SinkCounters};
+use watchdog::api::{HealthState, HealthTransition};
+
+const DELIVERY_ATTEMPTS: u32 = 3;
+const RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// Endpoint told about every stream health change, separate from alerts so an audio switcher
+/// can move to backup program audio without waiting out grace periods
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct HealthWebhookConfig {
+    pub url: String,
+    /// How often health is checked; a change reaches the webhook within about this long
+    #[serde(default = "default_health_webhook_interval_ms")]
+    pub interval_ms: u64,
+    /// Sent as a bearer token
+    pub token: Option<String>,
+    /// Only report these streams, every stream when empty
+    #[serde(default)]
+    pub streams: Vec<String>,
+}
+
+fn default_health_webhook_interval_ms() -> u64 { 1000 }
+
+pub struct HealthWebhook {
+    config: HealthWebhookConfig,
+    router: Arc<AudioRouter>,
+    client: reqwest::Client,
+    counters: Arc<SinkCounters>,
+}
+
+impl HealthWebhook {
+    pub fn new(config: HealthWebhookConfig, router: Arc<AudioRouter>) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(5))
+            .build()
+            .expect("Could not build HTTP client for the health webhook");
+
+        HealthWebhook { config, router, client, counters: Arc::new(SinkCounters::default()) }
+    }
+
+    /// Counts deliveries as the "health_webhook" sink of `stats`
+    pub fn with_delivery_stats(mut self, stats: &DeliveryStats) -> Self {
+        self.counters = stats.sink("health_webhook");
+        self
+    }
+
+    pub async fn start_watch_loop(self: Arc<Self>) {
+        info!("Posting stream health changes to {} (checked every {}ms)", self.config.url, self.config.interval_ms);
+
+        // Deliveries run one at a time off the watch loop, so a slow endpoint never delays noticing
+        // the next change and transitions arrive in the order they happened
+        let (sender, mut transitions) = mpsc::unbounded_channel::<HealthTransition>();
+        let delivery = self.clone();
+        tokio::spawn(async move {
+            while let Some(transition) = transitions.recv().await {
+                delivery.deliver(&transition).await;
+            }
+        });
+
+        tokio::spawn(async move {
+            let mut known: HashMap<String, HealthState> = HashMap::new();
+            let mut interval = tokio::time::interval(Duration::from_millis(self.config.interval_ms.max(100)));
+            loop {
+                interval.tick().await;
+
+                let channels: HashMap<String, String> = self.router.get_all_channels().into_iter()
+                    .flat_map(|channel| self.router.get_channel_streams(&channel).unwrap_or_default().into_iter()
+                        .map(move |stream| (stream, channel.clone())))
+                    .collect();
+                let streams = self.router.get_all_streams().await;
+                known.retain(|name, _| streams.iter().any(|(stream, _, _)| stream == name));
+
+                for (stream, command_health, audio_health) in streams {
+                    if !self.config.streams.is_empty() && !self.config.streams.contains(&stream) {
+                        continue;
+                    }
+                    let usable = command_health == StreamHealth::Running && audio_health == AudioStreamHealth::Running;
+                    let after = HealthState { command_health, audio_health, usable };
+                    // The first sighting of a stream is its baseline, only changes from it are posted
+                    let Some(before) = known.insert(stream.clone(), after.clone()) else { continue };
+                    if before == after {
+                        continue;
+                    }
+                    let _ = sender.send(HealthTransition {
+                        timestamp: Utc::now(),
+                        channel: channels.get(&stream).cloned(),
+                        stream,
+                        before,
+                        after,
+                    });
+                }
+            }
+        });
+    }
+
+    async fn deliver(&self, transition: &HealthTransition) {
+        let mut error = String::new();
+        for attempt in 1..=DELIVERY_ATTEMPTS {
+            if attempt > 1 {
+                self.counters.retried();
+            }
+            let mut request = self.client.post(&self.config.url).json(transition);
+            if let Some(ref token) = self.config.token {
+                request = request.bearer_auth(token);
+            }
+            match request.send().await {
+                Ok(res) if res.status().is_success() => {
+                    debug!("Posted health change of {} to {}", transition.stream, self.config.url);
+                    self.counters.delivered();
+                    return;
+                }
+                Ok(res) => {
+                    error = format!("HTTP {}", res.status());
+                    warn!("Health webhook {} rejected the change of {} (attempt {}/{}): {}",
+                        self.config.url, transition.stream, attempt, DELIVERY_ATTEMPTS, error);
+                }
+                Err(e) => {
+                    error = e.to_string();
+                    warn!("Could not post the health change of {} to {} (attempt {}/{}): {}",
+                        transition.stream, self.config.url, attempt, DELIVERY_ATTEMPTS, error);
+                }
+            }
+            if attempt < DELIVERY_ATTEMPTS {
+                tokio::time::sleep(RETRY_DELAY).await;
+            }
+        }
+        self.counters.failed(&error);
+    }
+}
@@ -8,4 +8,47 @@ pub mod webserver;
 pub mod alertmanager;
 pub mod nrsc;
 pub mod sdr;
-pub mod volumedetect;
\ No newline at end of file
+pub mod volumedetect;
+pub mod recorder;
+pub mod deadair;
+pub mod stderrlog;
+pub mod prober;
+pub mod dnswatch;
+pub mod formatcheck;
+pub mod graphql;
+pub mod configdir;
+pub mod beacon;
+pub mod scanner;
+pub mod gaincontrol;
+pub mod export;
+pub mod email;
+pub mod report;
+pub mod timesync;
+pub mod pushgateway;
+pub mod statsd;
+pub mod pin;
+pub mod spectrum;
+pub mod exposition;
+pub mod slackhome;
+pub mod timefmt;
+pub mod uptimestore;
+pub mod bufferbudget;
+pub mod artifacts;
+pub mod fingerprintcache;
+pub mod fingerprintpool;
+pub mod healthhook;
+pub mod webauth;
+pub mod frozen;
+pub mod proxy;
+pub mod outbound;
+pub mod envinterp;
+pub mod branding;
+pub mod pcm;
+pub mod configformat;
+pub mod delivery;
+pub mod tuning;
+pub mod overrides;
+pub mod thresholdschedule;
+pub mod weekly;
+pub mod fastmatch;
+pub mod configexample;
\ No newline at end of file
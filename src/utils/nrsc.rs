@@ -1,19 +1,70 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::process::Stdio;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, Ordering};
 use tokio::io::{AsyncReadExt, AsyncWriteExt};
 use tokio::net::TcpStream;
+use tokio::net::tcp::OwnedWriteHalf;
 use tokio::process::{Child, Command};
-use tokio::sync::broadcast::{self, Receiver, Sender};
+use tokio::sync::broadcast::{self, error::RecvError, Receiver, Sender};
 use tokio::sync::Mutex;
 use tokio::time::{sleep, Duration};
 use tracing::{debug, error, info, trace, warn};
 
+use watchdog::api::AlertReason;
+
+use super::alertmanager::AlertManager;
+use super::sdr::NRSC5_SAMPLE_RATE;
+use super::stderrlog::StderrLog;
+
+// rtl_tcp runtime commands: one command byte followed by a big-endian u32 parameter
+const RTL_TCP_SET_FREQUENCY: u8 = 0x01;
+const RTL_TCP_SET_GAIN_MODE: u8 = 0x03;
+const RTL_TCP_SET_GAIN: u8 = 0x04;
+
+// IQ delivery is judged over windows this long
+const IQ_WINDOW_SECONDS: u64 = 30;
+// Alert when more than this share of the expected samples went missing in a window
+const IQ_DROP_ALERT_RATIO: f32 = 0.02;
+// Or when nrsc5 lost sync more often than this in a window, the other face of buffer overruns
+const IQ_RESYNC_ALERT_COUNT: u64 = 3;
+// nrsc5 audio breaks up above roughly this bit error rate
+const BER_ALERT_THRESHOLD: f32 = 0.01;
+
+/// Sample-delivery counters of an SDR feed, shared between the reader, the decoders and the monitor
+#[derive(Default)]
+pub struct IqStats {
+    bytes_received: AtomicU64,
+    lagged_chunks: AtomicU64, // IQ chunks a decoder fell too far behind to receive
+    resyncs: AtomicU64, // nrsc5 "Lost synchronization" events across all programs
+    drop_ratio_permille: AtomicU32, // share of expected samples missing in the last window
+    measure_level: AtomicBool, // only pay for per-sample level math when gain control wants it
+    level_samples: AtomicU64,
+    level_clipped: AtomicU64, // samples at the 8-bit rails
+    level_power: AtomicU64, // sum of squared distance from the 127.5 midpoint, doubled to stay integral
+    ber_ppm: AtomicU32, // latest nrsc5 bit error rate, in parts per million
+}
+
+/// IQ signal level over a gain control interval
+#[derive(Debug, Clone, Copy)]
+pub struct SignalLevel {
+    pub clip_ratio: f32,
+    pub rms_dbfs: f32,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct IqSnapshot {
+    pub drop_ratio: f32,
+    pub lagged_chunks: u64,
+    pub resyncs: u64,
+}
+
 /// Represents an RTL-SDR device connection via rtl_tcp
 pub struct RtlTcpConnection {
     host: String,
     port: u16,
     stream: Option<TcpStream>,
+    writer: Option<OwnedWriteHalf>, // command channel back to rtl_tcp once reading has started
 }
 
 impl RtlTcpConnection {
@@ -22,6 +73,7 @@ impl RtlTcpConnection {
             host,
             port,
             stream: None,
+            writer: None,
         }
     }
 
@@ -54,7 +106,7 @@ impl RtlTcpConnection {
     }
 
     /// Read data from rtl_tcp and broadcast to multiple nrsc5 processes
-    pub async fn start_reading(&mut self, broadcaster: Sender<Vec<u8>>) -> Result<(), std::io::Error> {
+    pub async fn start_reading(&mut self, broadcaster: Sender<Vec<u8>>, stats: Arc<IqStats>) -> Result<(), std::io::Error> {
         if self.stream.is_none() {
             return Err(std::io::Error::new(
                 std::io::ErrorKind::NotConnected,
@@ -62,7 +114,8 @@ impl RtlTcpConnection {
             ));
         }
 
-        let mut stream = self.stream.take().unwrap();
+        let (mut stream, writer) = self.stream.take().unwrap().into_split();
+        self.writer = Some(writer);
         info!("Starting to read from rtl_tcp and broadcast to nrsc5 processes");
 
         tokio::spawn(async move {
@@ -75,6 +128,21 @@ impl RtlTcpConnection {
                     }
                     Ok(n) => {
                         trace!("Read {} bytes from rtl_tcp", n);
+                        stats.bytes_received.fetch_add(n as u64, Ordering::Relaxed);
+                        if stats.measure_level.load(Ordering::Relaxed) {
+                            let mut clipped = 0u64;
+                            let mut power = 0u64;
+                            for &sample in &buffer[..n] {
+                                if sample == 0 || sample == 255 {
+                                    clipped += 1;
+                                }
+                                let distance = (2 * sample as i64 - 255).unsigned_abs();
+                                power += distance * distance;
+                            }
+                            stats.level_samples.fetch_add(n as u64, Ordering::Relaxed);
+                            stats.level_clipped.fetch_add(clipped, Ordering::Relaxed);
+                            stats.level_power.fetch_add(power, Ordering::Relaxed);
+                        }
                         // Broadcast to all subscribers (nrsc5 processes)
                         if broadcaster.send(buffer[..n].to_vec()).is_err() {
                             warn!("No active nrsc5 receivers");
@@ -90,28 +158,45 @@ impl RtlTcpConnection {
 
         Ok(())
     }
+
+    /// Sends a runtime command to rtl_tcp, e.g. to retune
+    pub async fn send_command(&mut self, command: u8, parameter: u32) -> Result<(), std::io::Error> {
+        let Some(ref mut writer) = self.writer else {
+            return Err(std::io::Error::new(std::io::ErrorKind::NotConnected, "Not connected to rtl_tcp"));
+        };
+        let mut message = [0u8; 5];
+        message[0] = command;
+        message[1..].copy_from_slice(&parameter.to_be_bytes());
+        writer.write_all(&message).await
+    }
 }
 
 /// Represents an nrsc5 process that decodes HD Radio
 pub struct Nrsc5Process {
     program_number: String,
+    frequency: Option<u32>, // only fed IQ while the SDR is tuned here, when the SDR scans
     child: Option<Child>,
     output_sender: Sender<Vec<u8>>,
+    stderr_log: StderrLog,
+    streams: HashSet<String>, // streams relaying this decoder's audio
 }
 
 impl Nrsc5Process {
     /// Create a new nrsc5 process
-    pub fn new(program_number: &str) -> Self {
+    pub fn new(program_number: &str, frequency: Option<u32>) -> Self {
         let (tx, _) = broadcast::channel(1024);
         Nrsc5Process {
             program_number: program_number.to_string(),
+            frequency,
             child: None,
             output_sender: tx,
+            stderr_log: StderrLog::new(),
+            streams: HashSet::new(),
         }
     }
 
     /// Spawn the nrsc5 process with input from rtl_tcp
-    pub async fn spawn(&mut self, mut input: Receiver<Vec<u8>>) -> Result<(), std::io::Error> {
+    pub async fn spawn(&mut self, mut input: Receiver<Vec<u8>>, tuned_frequency: Arc<AtomicU32>, stats: Arc<IqStats>) -> Result<(), std::io::Error> {
         info!("Spawning nrsc5 process for program {}", self.program_number);
 
         let mut child = Command::new("nrsc5")
@@ -128,16 +213,27 @@ impl Nrsc5Process {
         // Handle stdin - write data from rtl_tcp
         if let Some(mut stdin) = child.stdin.take() {
             let program = self.program_number.clone();
+            let frequency = self.frequency;
+            let stats = stats.clone();
             tokio::spawn(async move {
                 trace!("Starting stdin writer for nrsc5 program {}", program);
                 loop {
                     match input.recv().await {
                         Ok(data) => {
+                            // While a scanning SDR is tuned elsewhere the decoder is paused rather than fed another station
+                            if frequency.is_some_and(|f| f != tuned_frequency.load(Ordering::Relaxed)) {
+                                continue;
+                            }
                             if let Err(e) = stdin.write_all(&data).await {
                                 error!("Failed to write to nrsc5 program {} stdin: {}", program, e);
                                 break;
                             }
                         }
+                        Err(RecvError::Lagged(skipped)) => {
+                            // The decoder couldn't keep up; the samples are gone but the feed goes on
+                            warn!("nrsc5 program {} fell behind and lost {} IQ chunks", program, skipped);
+                            stats.lagged_chunks.fetch_add(skipped, Ordering::Relaxed);
+                        }
                         Err(e) => {
                             error!("Failed to receive data for nrsc5 program {}: {}", program, e);
                             break;
@@ -175,6 +271,7 @@ impl Nrsc5Process {
         // Handle stderr - log messages
         if let Some(mut stderr) = child.stderr.take() {
             let program = self.program_number.clone();
+            let stderr_log = self.stderr_log.clone();
             tokio::spawn(async move {
                 let mut buffer = [0u8; 1024];
                 loop {
@@ -183,14 +280,19 @@ impl Nrsc5Process {
                         Ok(n) => {
                             let stderr_str = String::from_utf8_lossy(&buffer[..n]);
                             for line in stderr_str.lines() {
+                                stderr_log.push(line).await;
                                 // Check for important status messages
                                 if line.contains("Lost synchronization") {
                                     warn!("nrsc5 program {} lost synchronization", program);
+                                    stats.resyncs.fetch_add(1, Ordering::Relaxed);
                                 } else if line.contains("Synchronized") {
                                     info!("nrsc5 program {} synchronized", program);
                                 } else if line.contains("BER:") {
                                     // Log bit error rate
                                     trace!("nrsc5 program {}: {}", program, line);
+                                    if let Some(ber) = parse_ber(line) {
+                                        stats.ber_ppm.store((ber * 1_000_000.0) as u32, Ordering::Relaxed);
+                                    }
                                 } else {
                                     trace!("nrsc5 program {} stderr: {}", program, line);
                                 }
@@ -210,53 +312,236 @@ impl Nrsc5Process {
     pub fn get_output_receiver(&self) -> Receiver<Vec<u8>> {
         self.output_sender.subscribe()
     }
+
+    /// Get the buffered stderr lines of this decoder
+    pub fn get_stderr_log(&self) -> StderrLog {
+        self.stderr_log.clone()
+    }
+
+    /// Kill the nrsc5 process, its reader tasks end once the pipes close
+    pub async fn stop(&mut self) {
+        if let Some(mut child) = self.child.take() {
+            info!("Stopping nrsc5 process for program {}", self.program_number);
+            if let Err(e) = child.kill().await {
+                warn!("Failed to kill nrsc5 process for program {}: {}", self.program_number, e);
+            }
+        }
+    }
 }
 
 /// Manages an SDR with multiple NRSC5 decoders
 pub struct NrscManager {
+    name: String,
     rtl_tcp: Arc<Mutex<RtlTcpConnection>>,
     nrsc5_processes: Arc<Mutex<HashMap<String, Nrsc5Process>>>,
     rtl_broadcaster: Sender<Vec<u8>>,
+    tuned_frequency: Arc<AtomicU32>, // Hz, 0 until retuned at runtime
+    iq_stats: Arc<IqStats>,
+    alert_manager: Option<Arc<AlertManager>>,
 }
 
 impl NrscManager {
     /// Create a new NRSC manager
-    pub fn new(host: String, port: u16) -> Self {
+    pub fn new(name: &str, host: String, port: u16) -> Self {
         let (tx, _) = broadcast::channel(1024);
         NrscManager {
+            name: name.to_string(),
             rtl_tcp: Arc::new(Mutex::new(RtlTcpConnection::new(host, port))),
             nrsc5_processes: Arc::new(Mutex::new(HashMap::new())),
             rtl_broadcaster: tx,
+            tuned_frequency: Arc::new(AtomicU32::new(0)),
+            iq_stats: Arc::new(IqStats::default()),
+            alert_manager: None,
         }
     }
 
+    pub fn with_alert_manager(mut self, alert_manager: Arc<AlertManager>) -> Self {
+        self.alert_manager = Some(alert_manager);
+        self
+    }
+
     /// Initialize the connection and start reading from rtl_tcp
     pub async fn start(&self) -> Result<(), std::io::Error> {
         let mut rtl = self.rtl_tcp.lock().await;
         rtl.connect().await?;
-        rtl.start_reading(self.rtl_broadcaster.clone()).await?;
+        rtl.start_reading(self.rtl_broadcaster.clone(), self.iq_stats.clone()).await?;
+        self.start_iq_monitor();
+        Ok(())
+    }
+
+    /// Switches rtl_tcp to manual gain and sets it, in dB
+    pub async fn set_gain(&self, gain_db: f32) -> Result<(), std::io::Error> {
+        let mut rtl = self.rtl_tcp.lock().await;
+        rtl.send_command(RTL_TCP_SET_GAIN_MODE, 1).await?;
+        rtl.send_command(RTL_TCP_SET_GAIN, (gain_db * 10.0).round() as u32).await?;
+        info!("Set SDR {} gain to {:.1} dB", self.name, gain_db);
+        Ok(())
+    }
+
+    pub fn enable_level_measurement(&self) {
+        self.iq_stats.measure_level.store(true, Ordering::Relaxed);
+    }
+
+    /// Signal level since the previous call, None if no samples arrived
+    pub fn take_signal_level(&self) -> Option<SignalLevel> {
+        let samples = self.iq_stats.level_samples.swap(0, Ordering::Relaxed);
+        let clipped = self.iq_stats.level_clipped.swap(0, Ordering::Relaxed);
+        let power = self.iq_stats.level_power.swap(0, Ordering::Relaxed);
+        if samples == 0 {
+            return None;
+        }
+        // power is in doubled units, so full scale is 255^2
+        let rms = (power as f64 / samples as f64).sqrt() / 255.0;
+        Some(SignalLevel {
+            clip_ratio: clipped as f32 / samples as f32,
+            rms_dbfs: if rms > 0.0 { (20.0 * rms.log10()) as f32 } else { -100.0 },
+        })
+    }
+
+    /// Latest bit error rate any decoder on this SDR reported
+    pub fn get_ber(&self) -> f32 {
+        self.iq_stats.ber_ppm.load(Ordering::Relaxed) as f32 / 1_000_000.0
+    }
+
+    pub fn get_iq_stats(&self) -> IqSnapshot {
+        IqSnapshot {
+            drop_ratio: self.iq_stats.drop_ratio_permille.load(Ordering::Relaxed) as f32 / 1000.0,
+            lagged_chunks: self.iq_stats.lagged_chunks.load(Ordering::Relaxed),
+            resyncs: self.iq_stats.resyncs.load(Ordering::Relaxed),
+        }
+    }
+
+    /// Compares the IQ actually delivered against what the sample rate promises, window by window
+    fn start_iq_monitor(&self) {
+        let name = self.name.clone();
+        let stats = self.iq_stats.clone();
+        let alert_manager = self.alert_manager.clone();
+        // rtl_tcp sends 8-bit I and Q per sample
+        let expected_bytes = NRSC5_SAMPLE_RATE as f64 * 2.0 * IQ_WINDOW_SECONDS as f64;
+
+        tokio::spawn(async move {
+            let mut last_bytes = 0u64;
+            let mut last_resyncs = 0u64;
+            loop {
+                sleep(Duration::from_secs(IQ_WINDOW_SECONDS)).await;
+
+                let bytes = stats.bytes_received.load(Ordering::Relaxed);
+                let resyncs = stats.resyncs.load(Ordering::Relaxed);
+                let received = (bytes - last_bytes) as f64;
+                let window_resyncs = resyncs - last_resyncs;
+                last_bytes = bytes;
+                last_resyncs = resyncs;
+
+                let drop_ratio = (1.0 - received / expected_bytes).clamp(0.0, 1.0) as f32;
+                stats.drop_ratio_permille.store((drop_ratio * 1000.0) as u32, Ordering::Relaxed);
+                debug!("SDR {} IQ window: {:.1}% dropped, {} resyncs", name, drop_ratio * 100.0, window_resyncs);
+
+                // Nothing at all is an outage, not a host that can't keep up
+                let is_down = received == 0.0;
+                let is_error = !is_down && (drop_ratio > IQ_DROP_ALERT_RATIO || window_resyncs > IQ_RESYNC_ALERT_COUNT);
+                let ber = stats.ber_ppm.load(Ordering::Relaxed) as f32 / 1_000_000.0;
+                let is_ber_high = !is_down && ber > BER_ALERT_THRESHOLD;
+                if is_down {
+                    warn!("SDR {} delivered no IQ samples in {}s", name, IQ_WINDOW_SECONDS);
+                }
+                if is_error {
+                    warn!("SDR {} is dropping IQ samples: {:.1}% missing, {} nrsc5 resyncs in {}s",
+                        name, drop_ratio * 100.0, window_resyncs, IQ_WINDOW_SECONDS);
+                }
+                if let Some(ref am) = alert_manager {
+                    let message = if is_error {
+                        format!("SDR `{}` host can't keep up with the sample rate: {:.1}% of IQ samples missing, {} nrsc5 resyncs in {}s",
+                            name, drop_ratio * 100.0, window_resyncs, IQ_WINDOW_SECONDS)
+                    } else {
+                        format!("SDR `{}` is delivering IQ samples at full rate again", name)
+                    };
+                    am.update_alert(format!("{}_iq_drops", name), AlertReason::IqDrops, &[], is_error, message).await;
+
+                    let message = if is_down {
+                        format!("SDR `{}` delivered no IQ samples in {}s, rtl_tcp or the dongle may be down", name, IQ_WINDOW_SECONDS)
+                    } else {
+                        format!("SDR `{}` is delivering IQ samples again", name)
+                    };
+                    am.update_alert(format!("{}_down", name), AlertReason::SdrDown, &[], is_down, message).await;
+
+                    let message = if is_ber_high {
+                        format!("SDR `{}` nrsc5 bit error rate is {:.4} (limit {:.4}), check antenna and gain", name, ber, BER_ALERT_THRESHOLD)
+                    } else {
+                        format!("SDR `{}` nrsc5 bit error rate is back to {:.4}", name, ber)
+                    };
+                    am.update_alert(format!("{}_ber", name), AlertReason::BerHigh, &[], is_ber_high, message).await;
+                }
+            }
+        });
+    }
+
+    /// Retunes the SDR through rtl_tcp's command channel
+    pub async fn set_frequency(&self, frequency: u32) -> Result<(), std::io::Error> {
+        self.rtl_tcp.lock().await.send_command(RTL_TCP_SET_FREQUENCY, frequency).await?;
+        self.tuned_frequency.store(frequency, Ordering::Relaxed);
+        info!("Retuned SDR to {} Hz", frequency);
         Ok(())
     }
 
-    /// Add an nrsc5 decoder for a specific program number
-    pub async fn add_program(&self, program_number: &str) -> Result<Receiver<Vec<u8>>, std::io::Error> {
+    /// Add an nrsc5 decoder for a specific program number, fed to `stream_name`
+    /// `frequency` is set when the SDR scans, so the same program number on different stations gets its own decoder
+    pub async fn add_program(&self, program_number: &str, frequency: Option<u32>, stream_name: &str) -> Result<Receiver<Vec<u8>>, std::io::Error> {
         let mut processes = self.nrsc5_processes.lock().await;
+        let key = program_key(program_number, frequency);
 
         // Check if program already exists
-        if let Some(existing) = processes.get(program_number) {
-            debug!("Program {} already exists, returning new receiver", program_number);
+        if let Some(existing) = processes.get_mut(&key) {
+            debug!("Program {} already exists, returning new receiver", key);
+            existing.streams.insert(stream_name.to_string());
             return Ok(existing.get_output_receiver());
         }
 
         // Create new nrsc5 process
-        let mut nrsc5 = Nrsc5Process::new(program_number);
+        let mut nrsc5 = Nrsc5Process::new(program_number, frequency);
         let input_receiver = self.rtl_broadcaster.subscribe();
-        nrsc5.spawn(input_receiver).await?;
+        nrsc5.spawn(input_receiver, self.tuned_frequency.clone(), self.iq_stats.clone()).await?;
 
         let output_receiver = nrsc5.get_output_receiver();
-        processes.insert(program_number.to_string(), nrsc5);
+        nrsc5.streams.insert(stream_name.to_string());
+        processes.insert(key.clone(), nrsc5);
 
-        info!("Added nrsc5 decoder for program {}", program_number);
+        info!("Added nrsc5 decoder for program {}", key);
         Ok(output_receiver)
     }
+
+    /// Detach a stream from the decoder it relays, stopping the decoder once no stream uses it
+    pub async fn remove_program(&self, stream_name: &str) {
+        let mut processes = self.nrsc5_processes.lock().await;
+        let Some(key) = processes.iter().find(|(_, p)| p.streams.contains(stream_name)).map(|(key, _)| key.clone()) else {
+            return;
+        };
+        let Some(process) = processes.get_mut(&key) else {
+            return;
+        };
+        process.streams.remove(stream_name);
+        if process.streams.is_empty() {
+            if let Some(mut process) = processes.remove(&key) {
+                process.stop().await;
+            }
+            info!("Removed nrsc5 decoder for program {}", key);
+        }
+    }
+
+    /// Get the stderr log of the decoder for a program, if it exists
+    pub async fn get_program_stderr(&self, program_number: &str, frequency: Option<u32>) -> Option<StderrLog> {
+        let processes = self.nrsc5_processes.lock().await;
+        processes.get(&program_key(program_number, frequency)).map(|p| p.get_stderr_log())
+    }
+}
+
+fn program_key(program_number: &str, frequency: Option<u32>) -> String {
+    match frequency {
+        Some(frequency) => format!("{}@{}", program_number, frequency),
+        None => program_number.to_string(),
+    }
+}
+
+/// Pulls the instantaneous value out of an nrsc5 line like `BER: 0.000123, avg: 0.000234, ...`
+fn parse_ber(line: &str) -> Option<f32> {
+    line.split("BER:").nth(1)?.split(',').next()?.trim().parse().ok()
 }
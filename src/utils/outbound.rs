@@ -0,0 +1,64 @@
+use std::collections::HashMap;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use super::proxy::ProxyConfig;
+
+/// User-Agent and extra headers sent on one kind of outbound request, e.g. for a CDN that blocks unknown clients
+#[derive(Debug, Clone, Default, Deserialize, JsonSchema)]
+pub struct HttpIdentity {
+    /// Replaces the default User-Agent
+    pub user_agent: Option<String>,
+    /// Extra request headers, e.g. X-Token: abc
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+impl HttpIdentity {
+    fn header_map(&self) -> Result<HeaderMap, String> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let header = HeaderName::from_bytes(name.as_bytes()).map_err(|e| format!("Invalid header name {}: {}", name, e))?;
+            let value = HeaderValue::from_str(value).map_err(|e| format!("Invalid value for header {}: {}", name, e))?;
+            headers.insert(header, value);
+        }
+        Ok(headers)
+    }
+
+    /// A reqwest client sending these headers, `default_user_agent` unless one is configured, through `proxy` when set
+    pub fn client(&self, default_user_agent: &str, proxy: Option<&ProxyConfig>) -> Result<reqwest::Client, String> {
+        let mut builder = reqwest::Client::builder()
+            .user_agent(self.user_agent.as_deref().unwrap_or(default_user_agent))
+            .default_headers(self.header_map()?);
+        if let Some(proxy) = proxy {
+            builder = builder.proxy(proxy.reqwest_proxy()?);
+        }
+        builder.build().map_err(|e| format!("Could not build HTTP client: {}", e))
+    }
+}
+
+/// How ffmpeg fetches web streams: the stream identity and the outbound proxy
+#[derive(Debug, Clone, Default)]
+pub struct WebFetch {
+    pub identity: HttpIdentity,
+    pub proxy: Option<ProxyConfig>,
+}
+
+impl WebFetch {
+    /// ffmpeg input options for `url`; only HTTP(S) inputs take them, anything else gets none
+    pub fn ffmpeg_args(&self, url: &str) -> Vec<String> {
+        if !url.starts_with("http://") && !url.starts_with("https://") {
+            return vec![];
+        }
+        let mut args = self.proxy.as_ref().map(|proxy| proxy.ffmpeg_args(url)).unwrap_or_default();
+        if let Some(ref user_agent) = self.identity.user_agent {
+            args.extend(["-user_agent".to_string(), user_agent.clone()]);
+        }
+        if !self.identity.headers.is_empty() {
+            let headers: String = self.identity.headers.iter().map(|(name, value)| format!("{}: {}\r\n", name, value)).collect();
+            args.extend(["-headers".to_string(), headers]);
+        }
+        args
+    }
+}
@@ -0,0 +1,114 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use serde::{Deserialize, Serialize};
+use tokio::sync::Mutex;
+use tracing::{debug, info, warn};
+
+use super::alertmanager::AlertManager;
+use super::audiorouter::AudioRouter;
+use super::tuning::TunedThresholds;
+
+const SAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+/// Operational changes made while the watchdog runs, kept apart from the declarative config
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuntimeOverrides {
+    #[serde(default)]
+    pub thresholds: TunedThresholds, // set with `set threshold` or POST /api/thresholds
+    #[serde(default)]
+    pub acknowledged: BTreeMap<String, String>, // failing alert -> who acknowledged it, kept if it is still failing after a restart
+}
+
+impl RuntimeOverrides {
+    /// The overrides in `path`, empty when the file doesn't exist yet
+    pub fn read(path: &str) -> Result<Self, String> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(RuntimeOverrides::default()),
+            Err(e) => return Err(format!("Could not read overrides from {}: {}", path, e)),
+        };
+        serde_json::from_str(&text).map_err(|e| format!("Invalid overrides in {}: {}", path, e))
+    }
+
+    pub fn write(&self, path: &str) -> Result<(), String> {
+        let json = serde_json::to_string_pretty(self).map_err(|e| format!("Could not serialize overrides: {}", e))?;
+        let partial = format!("{}.partial", path);
+        std::fs::write(&partial, json).map_err(|e| format!("Could not write overrides to {}: {}", partial, e))?;
+        std::fs::rename(&partial, path).map_err(|e| format!("Could not save overrides to {}: {}", path, e))
+    }
+}
+
+/// Keeps the runtime overrides in a JSON file: restored at startup, saved whenever they change
+pub struct OverridesStore {
+    path: String,
+    router: Arc<AudioRouter>,
+    alert_manager: Arc<AlertManager>,
+    saved: Mutex<RuntimeOverrides>, // what the file holds, one write at a time
+}
+
+impl OverridesStore {
+    pub fn new(path: &str, router: Arc<AudioRouter>, alert_manager: Arc<AlertManager>) -> Self {
+        OverridesStore {
+            path: path.to_string(),
+            router,
+            alert_manager,
+            saved: Mutex::new(RuntimeOverrides::default()),
+        }
+    }
+
+    /// Applies the overrides saved by an earlier run, if any
+    pub async fn restore(&self) {
+        let overrides = match RuntimeOverrides::read(&self.path) {
+            Ok(overrides) => overrides,
+            Err(e) => {
+                warn!("{}, starting without runtime overrides", e);
+                return;
+            }
+        };
+        if overrides != RuntimeOverrides::default() {
+            info!("Restoring runtime overrides from {}: {} channel thresholds, {} acknowledged alerts",
+                self.path, overrides.thresholds.channels.len(), overrides.acknowledged.len());
+        }
+        self.router.set_tuned_thresholds(overrides.thresholds.clone());
+        self.alert_manager.restore_acknowledgements(overrides.acknowledged.clone()).await;
+        *self.saved.lock().await = overrides;
+    }
+
+    async fn current(&self) -> RuntimeOverrides {
+        RuntimeOverrides {
+            thresholds: self.router.get_tuned_thresholds(),
+            acknowledged: self.alert_manager.get_acknowledgements().await,
+        }
+    }
+
+    /// Writes the overrides if they changed since the last write
+    pub async fn save(&self) -> Result<(), String> {
+        let mut saved = self.saved.lock().await;
+        let current = self.current().await;
+        if *saved == current {
+            return Ok(());
+        }
+        let path = self.path.clone();
+        let written = current.clone();
+        tokio::task::spawn_blocking(move || written.write(&path)).await
+            .map_err(|e| format!("Overrides writer failed: {}", e))??;
+        debug!("Saved runtime overrides to {}", self.path);
+        *saved = current;
+        Ok(())
+    }
+
+    /// Acknowledgements change with alerts coming and going, so they are saved on an interval
+    pub async fn start_save_loop(self: Arc<Self>) {
+        info!("Keeping runtime overrides in {}", self.path);
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(SAVE_INTERVAL).await;
+                if let Err(e) = self.save().await {
+                    warn!("{}", e);
+                }
+            }
+        });
+    }
+}
@@ -0,0 +1,161 @@
+use tracing::info;
+
+// What every stream hands the fingerprinter and volume detector: s16le stereo at 44.1 kHz
+pub const OUTPUT_SAMPLE_RATE: u32 = 44100;
+const OUTPUT_CHANNELS: u16 = 2;
+// A WAV header longer than this is not a header we understand
+const MAX_HEADER_BYTES: usize = 4096;
+
+/// Layout of incoming 16-bit PCM
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PcmFormat {
+    pub sample_rate: u32,
+    pub channels: u16,
+}
+
+impl PcmFormat {
+    pub const OUTPUT: PcmFormat = PcmFormat { sample_rate: OUTPUT_SAMPLE_RATE, channels: OUTPUT_CHANNELS };
+}
+
+/// Turns a decoder's WAV output (or headerless s16le stereo) into 44.1 kHz s16le stereo in-process,
+/// so an HD program doesn't need its own ffmpeg just to re-assert the format
+pub struct PcmNormalizer {
+    name: String,
+    header: Option<Vec<u8>>, // bytes gathered while the WAV header is incomplete, None once past it
+    format: PcmFormat,
+    pending: Vec<u8>, // partial frame carried over to the next chunk
+    previous: Option<(i16, i16)>, // last input frame, interpolated from across chunk boundaries
+    position: f64, // resampler read position in input frames, 0 being `previous`
+}
+
+impl PcmNormalizer {
+    pub fn new(name: &str) -> Self {
+        PcmNormalizer {
+            name: name.to_string(),
+            header: Some(Vec::new()),
+            format: PcmFormat::OUTPUT,
+            pending: Vec::new(),
+            previous: None,
+            position: 0.0,
+        }
+    }
+
+    /// Converts the next chunk, Err when the stream is in a format that can't be converted
+    pub fn push(&mut self, bytes: &[u8]) -> Result<Vec<u8>, String> {
+        let data = match self.header.take() {
+            None => bytes.to_vec(),
+            Some(mut header) => {
+                header.extend_from_slice(bytes);
+                match parse_wav_header(&header)? {
+                    WavHeader::NotWav => header,
+                    WavHeader::Incomplete if header.len() > MAX_HEADER_BYTES => {
+                        return Err(format!("{}: WAV header longer than {} bytes", self.name, MAX_HEADER_BYTES));
+                    }
+                    WavHeader::Incomplete => {
+                        self.header = Some(header);
+                        return Ok(Vec::new());
+                    }
+                    WavHeader::Complete { format, data_offset } => {
+                        if format != PcmFormat::OUTPUT {
+                            info!("{} delivers {} Hz with {} channels, converting to {} Hz stereo",
+                                self.name, format.sample_rate, format.channels, OUTPUT_SAMPLE_RATE);
+                        }
+                        self.format = format;
+                        header[data_offset..].to_vec()
+                    }
+                }
+            }
+        };
+
+        if self.format == PcmFormat::OUTPUT {
+            return Ok(data);
+        }
+        Ok(self.convert(&data))
+    }
+
+    fn convert(&mut self, data: &[u8]) -> Vec<u8> {
+        let frame_bytes = 2 * self.format.channels as usize;
+        self.pending.extend_from_slice(data);
+        let whole = self.pending.len() / frame_bytes * frame_bytes;
+        let frames: Vec<(i16, i16)> = self.pending[..whole].chunks_exact(frame_bytes)
+            .map(|frame| {
+                let left = i16::from_le_bytes([frame[0], frame[1]]);
+                let right = if self.format.channels == 1 { left } else { i16::from_le_bytes([frame[2], frame[3]]) };
+                (left, right)
+            })
+            .collect();
+        self.pending.drain(..whole);
+
+        if self.format.sample_rate == OUTPUT_SAMPLE_RATE {
+            return frames.iter().flat_map(|(left, right)| [left.to_le_bytes(), right.to_le_bytes()]).flatten().collect();
+        }
+
+        // Linear interpolation is plenty for fingerprinting and level metering
+        let source: Vec<(i16, i16)> = self.previous.into_iter().chain(frames).collect();
+        let Some(&last) = source.last() else { return Vec::new() };
+        let step = self.format.sample_rate as f64 / OUTPUT_SAMPLE_RATE as f64;
+        let mut output = Vec::new();
+        while self.position + 1.0 < source.len() as f64 {
+            let index = self.position as usize;
+            let fraction = self.position - index as f64;
+            let (a, b) = (source[index], source[index + 1]);
+            let left = a.0 as f64 + (b.0 as f64 - a.0 as f64) * fraction;
+            let right = a.1 as f64 + (b.1 as f64 - a.1 as f64) * fraction;
+            output.extend_from_slice(&(left.round() as i16).to_le_bytes());
+            output.extend_from_slice(&(right.round() as i16).to_le_bytes());
+            self.position += step;
+        }
+        self.position -= (source.len() - 1) as f64;
+        self.previous = Some(last);
+        output
+    }
+}
+
+enum WavHeader {
+    NotWav,
+    Incomplete,
+    Complete { format: PcmFormat, data_offset: usize },
+}
+
+fn parse_wav_header(bytes: &[u8]) -> Result<WavHeader, String> {
+    if bytes.len() < 12 {
+        return Ok(if b"RIFF".starts_with(&bytes[..bytes.len().min(4)]) { WavHeader::Incomplete } else { WavHeader::NotWav });
+    }
+    if &bytes[..4] != b"RIFF" || &bytes[8..12] != b"WAVE" {
+        return Ok(WavHeader::NotWav);
+    }
+
+    let mut format = None;
+    let mut offset = 12;
+    while offset + 8 <= bytes.len() {
+        let id = &bytes[offset..offset + 4];
+        let size = u32::from_le_bytes([bytes[offset + 4], bytes[offset + 5], bytes[offset + 6], bytes[offset + 7]]) as usize;
+        let body = offset + 8;
+        if id == b"data" {
+            // Streamed WAVs carry a placeholder length here, the data runs until the stream ends
+            let format = format.ok_or("WAV data arrived before its format chunk")?;
+            return Ok(WavHeader::Complete { format, data_offset: body });
+        }
+        if body + size > bytes.len() {
+            return Ok(WavHeader::Incomplete);
+        }
+        if id == b"fmt " {
+            if size < 16 {
+                return Err("WAV format chunk is too short".to_string());
+            }
+            let field = |at: usize| u16::from_le_bytes([bytes[body + at], bytes[body + at + 1]]);
+            let (encoding, channels, bits) = (field(0), field(2), field(14));
+            let sample_rate = u32::from_le_bytes([bytes[body + 4], bytes[body + 5], bytes[body + 6], bytes[body + 7]]);
+            // 0xFFFE is WAVE_FORMAT_EXTENSIBLE, still plain integer PCM for 16-bit audio
+            if !matches!(encoding, 1 | 0xFFFE) || bits != 16 {
+                return Err(format!("WAV audio is encoding {} with {} bits, only 16-bit PCM is supported", encoding, bits));
+            }
+            if !matches!(channels, 1 | 2) || sample_rate == 0 {
+                return Err(format!("WAV audio has {} channels at {} Hz, only mono or stereo is supported", channels, sample_rate));
+            }
+            format = Some(PcmFormat { sample_rate, channels });
+        }
+        offset = body + size + size % 2; // chunks are padded to an even length
+    }
+    Ok(WavHeader::Incomplete)
+}
@@ -0,0 +1,185 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use rusty_chromaprint::Configuration;
+use serde::Deserialize;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+use super::audiorouter::AudioRouter;
+use super::comparator::StreamComparator;
+use super::slack::SlackMessageSender;
+
+pub use watchdog::api::PinnedComparison;
+
+const REPORT_SECONDS: i64 = 30; // Slack rate limits rule out a message per comparison
+const MAX_PIN_MINUTES: u64 = 120;
+
+/// Request to compare one pair more often and over a chosen window for a while
+#[derive(Debug, Clone, Deserialize)]
+pub struct PinRequest {
+    pub stream1: String,
+    pub stream2: String,
+    #[serde(default = "default_pin_interval")]
+    pub interval_seconds: u64,
+    #[serde(default = "default_pin_window")]
+    pub window_seconds: u64,
+    #[serde(default = "default_pin_minutes")]
+    pub minutes: u64,
+}
+
+pub fn default_pin_interval() -> u64 { 1 }
+pub fn default_pin_window() -> u64 { 30 }
+pub fn default_pin_minutes() -> u64 { 10 }
+
+/// Runs pinned comparisons next to the regular comparison loop, reporting each into its own Slack thread
+pub struct PairPinner {
+    router: Arc<AudioRouter>,
+    slack: Arc<SlackMessageSender>,
+    match_threshold: f32,
+    pins: Arc<RwLock<HashMap<(String, String), (u64, PinnedComparison)>>>, // pair -> (pin id, state)
+    next_id: std::sync::atomic::AtomicU64,
+}
+
+impl PairPinner {
+    pub fn new(router: Arc<AudioRouter>, slack: Arc<SlackMessageSender>, match_threshold: f32) -> Self {
+        PairPinner {
+            router,
+            slack,
+            match_threshold,
+            pins: Arc::new(RwLock::new(HashMap::new())),
+            next_id: std::sync::atomic::AtomicU64::new(0),
+        }
+    }
+
+    pub async fn get_pins(&self) -> Vec<PinnedComparison> {
+        self.pins.read().await.values().map(|(_, pin)| pin.clone()).collect()
+    }
+
+    /// Starts (or replaces) a pin; results go to `thread_ts`, or a new thread when unset
+    pub async fn pin(&self, request: PinRequest, thread_ts: Option<String>) -> Result<PinnedComparison, String> {
+        for stream in [&request.stream1, &request.stream2] {
+            if self.router.get_stream_health(stream).await.is_none() {
+                return Err(format!("Stream `{}` not found", stream));
+            }
+        }
+        if request.stream1 == request.stream2 {
+            return Err("Pin two different streams".to_string());
+        }
+        if request.interval_seconds == 0 || request.window_seconds < 5 {
+            return Err("Interval must be at least 1s and the window at least 5s".to_string());
+        }
+        let minutes = request.minutes.clamp(1, MAX_PIN_MINUTES);
+
+        let (stream1, stream2) = if request.stream1 < request.stream2 {
+            (request.stream1, request.stream2)
+        } else {
+            (request.stream2, request.stream1)
+        };
+        let pin = PinnedComparison {
+            stream1: stream1.clone(),
+            stream2: stream2.clone(),
+            interval_seconds: request.interval_seconds,
+            window_seconds: request.window_seconds,
+            until: Utc::now() + chrono::Duration::minutes(minutes as i64),
+            samples: 0,
+            similarity_percent: None,
+            offset_seconds: None,
+        };
+        let id = self.next_id.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
+        let key = (stream1, stream2);
+        self.pins.write().await.insert(key.clone(), (id, pin.clone()));
+
+        let summary = format!("Pinned `{}` vs `{}`: comparing every {}s over {}s windows for {} minutes",
+            key.0, key.1, pin.interval_seconds, pin.window_seconds, minutes);
+        info!("{}", summary);
+        let thread_ts = match thread_ts {
+            Some(ts) => Some(ts),
+            None => self.slack.start_thread(summary).await,
+        };
+
+        let router = self.router.clone();
+        let slack = self.slack.clone();
+        let pins = self.pins.clone();
+        let match_threshold = self.match_threshold;
+        let settings = pin.clone();
+        tokio::spawn(async move {
+            let item_duration = Configuration::preset_test1().item_duration_in_seconds();
+            let window_items = (settings.window_seconds as f32 / item_duration) as usize;
+            let mut batch: Vec<(f32, f32)> = Vec::new();
+            let mut all: Vec<f32> = Vec::new();
+            let mut last_report = Utc::now();
+
+            while Utc::now() < settings.until {
+                tokio::time::sleep(Duration::from_secs(settings.interval_seconds)).await;
+
+                // A newer pin of the same pair takes over its reporting
+                if pins.read().await.get(&key).map(|(pin_id, _)| *pin_id) != Some(id) {
+                    return;
+                }
+
+                let fp1 = router.get_stream_fingerprint(&key.0).await.unwrap_or_default();
+                let fp2 = router.get_stream_fingerprint(&key.1).await.unwrap_or_default();
+                if fp1.len() < window_items || fp2.len() < window_items {
+                    debug!("Pinned {} vs {}: not enough fingerprint data yet", key.0, key.1);
+                    continue;
+                }
+                let recent1 = &fp1[fp1.len() - window_items..];
+                let recent2 = &fp2[fp2.len() - window_items..];
+                let Some((similar_time, offset)) = StreamComparator::get_similarity_time(recent1, recent2, window_items) else { continue };
+                let similarity = (similar_time / settings.window_seconds as f32 * 100.0).min(100.0);
+                batch.push((similarity, offset));
+                all.push(similarity);
+
+                if let Some((_, pin)) = pins.write().await.get_mut(&key) {
+                    pin.samples += 1;
+                    pin.similarity_percent = Some(similarity);
+                    pin.offset_seconds = Some(offset);
+                }
+
+                if (Utc::now() - last_report).num_seconds() >= REPORT_SECONDS && !batch.is_empty() {
+                    let similarities: Vec<f32> = batch.iter().map(|(s, _)| *s).collect();
+                    let (min, avg, max) = spread(&similarities);
+                    let offset = batch.last().map(|(_, o)| *o).unwrap_or(0.0);
+                    let marker = if min < match_threshold { "⚠" } else { "✓" };
+                    let message = format!("{} {} samples: {:.1}% min / {:.1}% avg / {:.1}% max similar, offset {:.2}s",
+                        marker, batch.len(), min, avg, max, offset);
+                    if let Some(ref ts) = thread_ts {
+                        slack.send_in_thread(message, ts).await;
+                    }
+                    batch.clear();
+                    last_report = Utc::now();
+                }
+            }
+
+            {
+                let mut pins = pins.write().await;
+                if pins.get(&key).map(|(pin_id, _)| *pin_id) == Some(id) {
+                    pins.remove(&key);
+                }
+            }
+            let message = if all.is_empty() {
+                format!("Pin of `{}` vs `{}` ended without any comparisons", key.0, key.1)
+            } else {
+                let (min, avg, max) = spread(&all);
+                format!("Pin of `{}` vs `{}` ended after {} comparisons: {:.1}% min / {:.1}% avg / {:.1}% max similar",
+                    key.0, key.1, all.len(), min, avg, max)
+            };
+            info!("{}", message);
+            match thread_ts {
+                Some(ref ts) => { slack.send_in_thread(message, ts).await; },
+                None => warn!("No Slack thread for the pin of {} vs {}", key.0, key.1),
+            }
+        });
+
+        Ok(pin)
+    }
+}
+
+fn spread(values: &[f32]) -> (f32, f32, f32) {
+    let min = values.iter().copied().fold(f32::MAX, f32::min);
+    let max = values.iter().copied().fold(f32::MIN, f32::max);
+    let avg = values.iter().sum::<f32>() / values.len() as f32;
+    (min, avg, max)
+}
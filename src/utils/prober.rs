@@ -0,0 +1,123 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use chrono::Utc;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+use super::alertmanager::AlertManager;
+
+pub use watchdog::api::ProbeResult;
+use watchdog::api::AlertReason;
+
+/// Checks web stream origins directly over HTTP, independently of ffmpeg
+pub struct StreamProber {
+    targets: Vec<(String, String)>, // (stream name, url)
+    interval_seconds: u64,
+    client: reqwest::Client,
+    results: Arc<RwLock<HashMap<String, ProbeResult>>>,
+    alert_manager: Option<Arc<AlertManager>>,
+}
+
+impl StreamProber {
+    pub fn new(targets: Vec<(String, String)>, interval_seconds: u64) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("Could not build HTTP client for stream prober");
+
+        StreamProber {
+            targets,
+            interval_seconds,
+            client,
+            results: Arc::new(RwLock::new(HashMap::new())),
+            alert_manager: None,
+        }
+    }
+
+    pub fn with_alert_manager(mut self, alert_manager: Arc<AlertManager>) -> Self {
+        self.alert_manager = Some(alert_manager);
+        self
+    }
+
+    pub async fn get_results(&self) -> HashMap<String, ProbeResult> {
+        self.results.read().await.clone()
+    }
+
+    pub async fn start_probe_loop(self: Arc<Self>) {
+        info!("Starting HTTP origin prober for {} streams (interval: {}s)", self.targets.len(), self.interval_seconds);
+
+        tokio::spawn(async move {
+            loop {
+                for (stream_name, url) in &self.targets {
+                    let result = self.probe(url).await;
+
+                    if let Some(ref am) = self.alert_manager {
+                        let is_error = !result.is_up();
+                        let message = if is_error {
+                            let reason = match (result.status_code, &result.error) {
+                                (Some(code), _) => format!("HTTP {}", code),
+                                (None, Some(e)) => e.clone(),
+                                (None, None) => "unknown error".to_string(),
+                            };
+                            format!("Origin server for stream `{}` is down ({})", stream_name, reason)
+                        } else {
+                            format!("Origin server for stream `{}` is reachable again", stream_name)
+                        };
+                        am.update_alert(format!("{}_origin", stream_name), AlertReason::OriginDown, &[stream_name.as_str()], is_error, message).await;
+                    }
+
+                    self.results.write().await.insert(stream_name.clone(), result);
+                }
+
+                tokio::time::sleep(Duration::from_secs(self.interval_seconds)).await;
+            }
+        });
+    }
+
+    /// Requests the URL and inspects only the response head; the body is never read
+    async fn probe(&self, url: &str) -> ProbeResult {
+        let started = Instant::now();
+        let response = self.client.get(url)
+            .header("Icy-MetaData", "1") // ask Icecast/Shoutcast servers for their icy-* headers
+            .send()
+            .await;
+        let latency_ms = started.elapsed().as_millis() as u64;
+
+        match response {
+            Ok(res) => {
+                let header = |name: &str| res.headers().get(name).and_then(|v| v.to_str().ok()).map(|v| v.to_string());
+                let result = ProbeResult {
+                    status_code: Some(res.status().as_u16()),
+                    content_type: header("content-type"),
+                    icy_name: header("icy-name"),
+                    icy_bitrate: header("icy-br"),
+                    latency_ms: Some(latency_ms),
+                    error: None,
+                    checked_at: Utc::now(),
+                };
+                debug!("Probed {}: HTTP {:?} ({}ms)", url, result.status_code, latency_ms);
+                result
+            }
+            Err(e) => {
+                let error = if e.is_timeout() {
+                    "timed out".to_string()
+                } else if e.is_connect() {
+                    "connection failed".to_string()
+                } else {
+                    e.to_string()
+                };
+                warn!("Probe of {} failed: {}", url, error);
+                ProbeResult {
+                    status_code: None,
+                    content_type: None,
+                    icy_name: None,
+                    icy_bitrate: None,
+                    latency_ms: None,
+                    error: Some(error),
+                    checked_at: Utc::now(),
+                }
+            }
+        }
+    }
+}
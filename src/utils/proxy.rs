@@ -0,0 +1,86 @@
+use base64::Engine;
+use reqwest::Url;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::TcpStream;
+use tracing::debug;
+
+/// HTTP(S) proxy for outbound Slack requests and web stream ingestion, for sites only reaching out through a corporate proxy
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ProxyConfig {
+    /// http://[user:password@]host:port
+    pub url: String,
+    /// Hosts reached directly, e.g. encoders on the local network; ".example.org" also covers its subdomains
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+}
+
+impl ProxyConfig {
+    fn parsed(&self) -> Result<Url, String> {
+        let url = Url::parse(&self.url).map_err(|e| format!("Invalid proxy URL {}: {}", self.url, e))?;
+        if url.scheme() != "http" || url.host_str().is_none() {
+            return Err(format!("Proxy URL {} must be http://host:port", self.url));
+        }
+        Ok(url)
+    }
+
+    /// The proxy for a reqwest client, sending everything outside `no_proxy` through it
+    pub fn reqwest_proxy(&self) -> Result<reqwest::Proxy, String> {
+        Ok(reqwest::Proxy::all(self.parsed()?)
+            .map_err(|e| format!("Invalid proxy URL {}: {}", self.url, e))?
+            .no_proxy(reqwest::NoProxy::from_string(&self.no_proxy.join(","))))
+    }
+
+    pub fn bypasses(&self, host: &str) -> bool {
+        self.no_proxy.iter().any(|entry| {
+            let entry = entry.trim();
+            match entry.strip_prefix('.') {
+                Some(domain) => host == domain || host.ends_with(entry),
+                None => entry == "*" || host == entry,
+            }
+        })
+    }
+
+    /// ffmpeg input options routing `url` through the proxy, empty when it is not HTTP(S) or bypasses the proxy
+    pub fn ffmpeg_args(&self, url: &str) -> Vec<String> {
+        let Ok(parsed) = Url::parse(url) else { return vec![] };
+        if !matches!(parsed.scheme(), "http" | "https") || parsed.host_str().is_some_and(|host| self.bypasses(host)) {
+            return vec![];
+        }
+        vec!["-http_proxy".to_string(), self.url.clone()]
+    }
+
+    /// A TCP stream tunneled to `host:port` with HTTP CONNECT, for connections reqwest doesn't make (websockets)
+    pub async fn connect(&self, host: &str, port: u16) -> Result<TcpStream, String> {
+        let proxy = self.parsed()?;
+        let proxy_addr = format!("{}:{}", proxy.host_str().unwrap_or_default(), proxy.port_or_known_default().unwrap_or(80));
+        let mut stream = TcpStream::connect(&proxy_addr).await
+            .map_err(|e| format!("Could not reach proxy {}: {}", proxy_addr, e))?;
+
+        let mut request = format!("CONNECT {host}:{port} HTTP/1.1\r\nHost: {host}:{port}\r\n");
+        if !proxy.username().is_empty() {
+            let credentials = format!("{}:{}", proxy.username(), proxy.password().unwrap_or_default());
+            request.push_str(&format!("Proxy-Authorization: Basic {}\r\n", base64::engine::general_purpose::STANDARD.encode(credentials)));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes()).await.map_err(|e| format!("Proxy {} dropped the connection: {}", proxy_addr, e))?;
+
+        // The proxy sends nothing past its headers until our TLS handshake, so the reader buffers nothing of the tunnel
+        let mut reader = BufReader::new(&mut stream);
+        let mut status = String::new();
+        reader.read_line(&mut status).await.map_err(|e| format!("Proxy {} dropped the connection: {}", proxy_addr, e))?;
+        loop {
+            let mut line = String::new();
+            let read = reader.read_line(&mut line).await.map_err(|e| format!("Proxy {} dropped the connection: {}", proxy_addr, e))?;
+            if read == 0 || line == "\r\n" || line == "\n" {
+                break;
+            }
+        }
+        if status.split_whitespace().nth(1) != Some("200") {
+            return Err(format!("Proxy {} refused to tunnel to {}:{}: {}", proxy_addr, host, port, status.trim()));
+        }
+        debug!("Tunneled to {}:{} through proxy {}", host, port, proxy_addr);
+        Ok(stream)
+    }
+}
@@ -0,0 +1,67 @@
+use std::time::Duration;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tracing::{debug, warn};
+
+use super::exposition::CONTENT_TYPE;
+
+/// Prometheus Pushgateway to push metrics to, for sites behind NAT that can't be scraped
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct PushgatewayConfig {
+    /// e.g. "http://pushgateway.example.org:9091"
+    pub url: String,
+    #[serde(default = "default_pushgateway_job")]
+    pub job: String,
+    /// Grouping label telling sites apart, defaults to the hostname
+    pub instance: Option<String>,
+    #[serde(default = "default_pushgateway_interval")]
+    pub interval_seconds: u64,
+    /// Basic auth, for a Pushgateway behind an authenticating proxy
+    pub username: Option<String>,
+    pub password: Option<String>,
+}
+
+fn default_pushgateway_job() -> String { "watchdog".to_string() }
+fn default_pushgateway_interval() -> u64 { 60 }
+
+pub struct Pusher {
+    config: PushgatewayConfig,
+    url: String,
+    client: reqwest::Client,
+}
+
+impl Pusher {
+    pub fn new(config: PushgatewayConfig) -> Self {
+        let instance = config.instance.clone()
+            .or_else(|| std::env::var("HOSTNAME").ok())
+            .or_else(|| std::fs::read_to_string("/etc/hostname").ok().map(|h| h.trim().to_string()))
+            .unwrap_or_else(|| "unknown".to_string());
+        let url = format!("{}/metrics/job/{}/instance/{}", config.url.trim_end_matches('/'), config.job, instance);
+        let client = reqwest::Client::builder()
+            .timeout(Duration::from_secs(10))
+            .build()
+            .expect("Could not build HTTP client for Pushgateway");
+
+        Pusher { config, url, client }
+    }
+
+    pub fn target_url(&self) -> &str {
+        &self.url
+    }
+
+    /// Replaces this instance's metric group with `metrics`; failures are logged and retried next interval
+    pub async fn push(&self, metrics: String) {
+        let mut request = self.client.put(&self.url)
+            .header("Content-Type", CONTENT_TYPE)
+            .body(metrics);
+        if let Some(ref username) = self.config.username {
+            request = request.basic_auth(username, self.config.password.as_ref());
+        }
+
+        match request.send().await {
+            Ok(res) if res.status().is_success() => debug!("Pushed metrics to {}", self.url),
+            Ok(res) => warn!("Pushgateway {} rejected metrics: HTTP {} {:?}", self.url, res.status(), res.text().await),
+            Err(e) => warn!("Could not push metrics to {}: {}", self.url, e),
+        }
+    }
+}
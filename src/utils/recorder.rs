@@ -0,0 +1,202 @@
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use chrono::{DateTime, Local, Timelike, Utc};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tokio::sync::broadcast::error::RecvError;
+use tokio::time::{Duration, Instant};
+use tracing::{debug, error, info, warn};
+
+use super::audiorouter::AudioRouter;
+
+/// A recurring clip capture, e.g. the top-of-hour legal ID
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct RecordingSchedule {
+    pub streams: Vec<String>,
+    /// minute of the hour to start recording, local time
+    #[serde(default)]
+    pub minute: u32,
+    /// hours of the day (local), every hour if unset
+    pub hours: Option<Vec<u32>>,
+    #[serde(default = "default_clip_duration")]
+    pub duration_seconds: u64,
+}
+
+fn default_clip_duration() -> u64 { 30 }
+
+pub use watchdog::api::RecordingEntry;
+
+pub struct RecordingScheduler {
+    router: Arc<AudioRouter>,
+    directory: PathBuf,
+    retention_days: u64,
+    schedules: Vec<RecordingSchedule>,
+}
+
+impl RecordingScheduler {
+    pub fn new(router: Arc<AudioRouter>, directory: String, retention_days: u64, schedules: Vec<RecordingSchedule>) -> Self {
+        RecordingScheduler {
+            router,
+            directory: PathBuf::from(directory),
+            retention_days,
+            schedules,
+        }
+    }
+
+    pub async fn start_schedule_loop(self: Arc<Self>) {
+        info!("Starting recording scheduler ({} schedules, {} day retention, archive at {})",
+              self.schedules.len(), self.retention_days, self.directory.display());
+
+        tokio::spawn(async move {
+            loop {
+                // Wake up at the start of every minute
+                let now = Local::now();
+                let until_next_minute = 60 - now.second() as u64;
+                tokio::time::sleep(Duration::from_secs(until_next_minute)).await;
+
+                let now = Local::now();
+                for schedule in &self.schedules {
+                    if schedule.minute != now.minute() {
+                        continue;
+                    }
+                    if let Some(ref hours) = schedule.hours {
+                        if !hours.contains(&now.hour()) {
+                            continue;
+                        }
+                    }
+
+                    for stream_name in &schedule.streams {
+                        let scheduler = self.clone();
+                        let stream_name = stream_name.clone();
+                        let duration = schedule.duration_seconds;
+                        tokio::spawn(async move {
+                            if let Err(e) = scheduler.record_clip(&stream_name, duration).await {
+                                error!("Scheduled recording of {} failed: {}", stream_name, e);
+                            }
+                        });
+                    }
+                }
+
+                self.prune_archive().await;
+            }
+        });
+    }
+
+    /// Capture `duration_seconds` of a stream into a WAV file in the archive
+    pub async fn record_clip(&self, stream_name: &str, duration_seconds: u64) -> Result<PathBuf, String> {
+        let mut reader = self.router.subscribe_stream(stream_name).await
+            .ok_or_else(|| format!("Stream '{}' not found", stream_name))?;
+
+        info!("Recording {}s clip from {}", duration_seconds, stream_name);
+        let started = Utc::now();
+        let deadline = Instant::now() + Duration::from_secs(duration_seconds);
+        let mut pcm: Vec<u8> = Vec::new();
+
+        loop {
+            match tokio::time::timeout_at(deadline, reader.recv()).await {
+                Ok(Ok(data)) => pcm.extend_from_slice(&data),
+                Ok(Err(RecvError::Lagged(n))) => warn!("Recording of {} lagged, skipped {} chunks", stream_name, n),
+                Ok(Err(RecvError::Closed)) => {
+                    warn!("Stream {} closed during recording", stream_name);
+                    break;
+                }
+                Err(_) => break, // deadline reached
+            }
+        }
+
+        if pcm.is_empty() {
+            return Err("no audio received".to_string());
+        }
+
+        let stream_dir = self.directory.join(stream_name);
+        tokio::fs::create_dir_all(&stream_dir).await
+            .map_err(|e| format!("Failed to create {}: {}", stream_dir.display(), e))?;
+
+        let path = stream_dir.join(format!("{}.wav", started.format("%Y%m%dT%H%M%SZ")));
+        tokio::fs::write(&path, wav_file(&pcm)).await
+            .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+
+        info!("Saved recording {} ({} bytes)", path.display(), pcm.len());
+        Ok(path)
+    }
+
+    /// Delete recordings older than the retention window
+    async fn prune_archive(&self) {
+        let cutoff = std::time::SystemTime::now() - Duration::from_secs(self.retention_days * 86400);
+        for entry in self.list_files().await {
+            let modified = tokio::fs::metadata(&entry).await.and_then(|m| m.modified());
+            if let Ok(modified) = modified {
+                if modified < cutoff {
+                    debug!("Pruning expired recording {}", entry.display());
+                    if let Err(e) = tokio::fs::remove_file(&entry).await {
+                        warn!("Failed to prune {}: {}", entry.display(), e);
+                    }
+                }
+            }
+        }
+    }
+
+    async fn list_files(&self) -> Vec<PathBuf> {
+        let mut files = Vec::new();
+        let Ok(mut streams) = tokio::fs::read_dir(&self.directory).await else { return files };
+        while let Ok(Some(stream_dir)) = streams.next_entry().await {
+            let Ok(mut clips) = tokio::fs::read_dir(stream_dir.path()).await else { continue };
+            while let Ok(Some(clip)) = clips.next_entry().await {
+                if clip.path().extension().is_some_and(|ext| ext == "wav") {
+                    files.push(clip.path());
+                }
+            }
+        }
+        files
+    }
+
+    /// Index of archived clips, newest first
+    pub async fn list_recordings(&self) -> Vec<RecordingEntry> {
+        let mut entries = Vec::new();
+        for path in self.list_files().await {
+            let Ok(metadata) = tokio::fs::metadata(&path).await else { continue };
+            let stream = path.parent().and_then(Path::file_name).map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let file = path.file_name().map(|n| n.to_string_lossy().to_string()).unwrap_or_default();
+            let recorded_at = metadata.modified().map(DateTime::<Utc>::from).unwrap_or_else(|_| Utc::now());
+            entries.push(RecordingEntry { stream, file, size_bytes: metadata.len(), recorded_at });
+        }
+        entries.sort_by(|a, b| b.recorded_at.cmp(&a.recorded_at));
+        entries
+    }
+
+    /// Read a clip from the archive, refusing anything that could escape the directory
+    pub async fn read_recording(&self, stream: &str, file: &str) -> Option<Vec<u8>> {
+        let is_safe = |part: &str| !part.is_empty() && !part.contains(['/', '\\']) && part != "." && part != "..";
+        if !is_safe(stream) || !is_safe(file) {
+            return None;
+        }
+        tokio::fs::read(self.directory.join(stream).join(file)).await.ok()
+    }
+}
+
+/// Wrap raw s16le 44.1kHz stereo PCM in a WAV header
+fn wav_file(pcm: &[u8]) -> Vec<u8> {
+    let sample_rate: u32 = 44100;
+    let channels: u16 = 2;
+    let bits_per_sample: u16 = 16;
+    let byte_rate = sample_rate * channels as u32 * bits_per_sample as u32 / 8;
+    let block_align = channels * bits_per_sample / 8;
+    let data_len = pcm.len() as u32;
+
+    let mut out = Vec::with_capacity(44 + pcm.len());
+    out.extend_from_slice(b"RIFF");
+    out.extend_from_slice(&(36 + data_len).to_le_bytes());
+    out.extend_from_slice(b"WAVE");
+    out.extend_from_slice(b"fmt ");
+    out.extend_from_slice(&16u32.to_le_bytes());
+    out.extend_from_slice(&1u16.to_le_bytes()); // PCM
+    out.extend_from_slice(&channels.to_le_bytes());
+    out.extend_from_slice(&sample_rate.to_le_bytes());
+    out.extend_from_slice(&byte_rate.to_le_bytes());
+    out.extend_from_slice(&block_align.to_le_bytes());
+    out.extend_from_slice(&bits_per_sample.to_le_bytes());
+    out.extend_from_slice(b"data");
+    out.extend_from_slice(&data_len.to_le_bytes());
+    out.extend_from_slice(pcm);
+    out
+}
@@ -0,0 +1,239 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, Datelike, Local, NaiveDate, Timelike, Utc, Weekday};
+use maud::{html, Markup};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tracing::info;
+use watchdog::api::{AlertEvent, AlertTransition};
+
+use super::alertmanager::AlertManager;
+use super::audiorouter::AudioRouter;
+use super::deadair::format_seconds;
+use super::email::EmailSender;
+
+const REPORT_PERIOD_DAYS: i64 = 7;
+
+/// When the weekly availability email goes out, in local time
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct WeeklyReportConfig {
+    #[serde(default = "default_report_weekday")]
+    pub weekday: Weekday,
+    /// 0-23
+    #[serde(default = "default_report_hour")]
+    pub hour: u32,
+    /// How many of the noisiest alerts to list
+    #[serde(default = "default_report_top_alerts")]
+    pub top_alerts: usize,
+}
+
+fn default_report_weekday() -> Weekday { Weekday::Mon }
+fn default_report_hour() -> u32 { 8 }
+fn default_report_top_alerts() -> usize { 5 }
+
+/// An alert's failing stretch, clamped to the report window
+struct Incident {
+    streams: Vec<String>,
+    message: String,
+    start: DateTime<Utc>,
+    end: DateTime<Utc>,
+    ongoing: bool,
+}
+
+/// Pairs failing/cleared transitions into incidents overlapping `from..to`
+fn incidents(events: &[AlertEvent], from: DateTime<Utc>, to: DateTime<Utc>) -> Vec<Incident> {
+    let mut open: HashMap<&str, &AlertEvent> = HashMap::new();
+    let mut incidents = Vec::new();
+    let mut close = |failing: &AlertEvent, end: DateTime<Utc>, ongoing: bool| {
+        if end > from {
+            incidents.push(Incident {
+                streams: failing.streams.clone(),
+                message: failing.message.clone(),
+                start: failing.timestamp.max(from),
+                end: end.min(to),
+                ongoing,
+            });
+        }
+    };
+
+    for event in events.iter().filter(|e| e.timestamp <= to) {
+        match event.transition {
+            AlertTransition::Failing => { open.insert(event.alert.as_str(), event); },
+            AlertTransition::Cleared => {
+                if let Some(failing) = open.remove(event.alert.as_str()) {
+                    close(failing, event.timestamp, false);
+                }
+            }
+        }
+    }
+    for failing in open.into_values() {
+        close(failing, to, true);
+    }
+
+    incidents.sort_by_key(|i| i.start);
+    incidents
+}
+
+/// Seconds covered by the union of the stream's incidents, so overlapping alerts aren't counted twice
+fn downtime_seconds(stream: &str, incidents: &[Incident]) -> u64 {
+    let mut spans: Vec<(DateTime<Utc>, DateTime<Utc>)> = incidents.iter()
+        .filter(|i| i.streams.iter().any(|s| s == stream))
+        .map(|i| (i.start, i.end))
+        .collect();
+    spans.sort();
+
+    let mut total = 0;
+    let mut current: Option<(DateTime<Utc>, DateTime<Utc>)> = None;
+    for (start, end) in spans {
+        match current {
+            Some((cur_start, cur_end)) if start <= cur_end => current = Some((cur_start, cur_end.max(end))),
+            _ => {
+                if let Some((cur_start, cur_end)) = current {
+                    total += (cur_end - cur_start).num_seconds().max(0) as u64;
+                }
+                current = Some((start, end));
+            }
+        }
+    }
+    if let Some((cur_start, cur_end)) = current {
+        total += (cur_end - cur_start).num_seconds().max(0) as u64;
+    }
+    total
+}
+
+/// Emails a weekly HTML summary of stream availability built from alert history,
+/// separate from the daily dead air digest on Slack
+pub struct WeeklyReporter {
+    router: Arc<AudioRouter>,
+    alert_manager: Arc<AlertManager>,
+    email: Arc<EmailSender>,
+    config: WeeklyReportConfig,
+    started: DateTime<Utc>, // history before this doesn't exist, so availability is measured from here
+}
+
+impl WeeklyReporter {
+    pub fn new(router: Arc<AudioRouter>, alert_manager: Arc<AlertManager>, email: Arc<EmailSender>, config: WeeklyReportConfig) -> Self {
+        WeeklyReporter { router, alert_manager, email, config, started: Utc::now() }
+    }
+
+    pub async fn start_report_loop(self) {
+        info!("Weekly availability report scheduled for {:?} at {:02}:00", self.config.weekday, self.config.hour);
+
+        tokio::spawn(async move {
+            let mut last_sent: Option<NaiveDate> = None;
+            loop {
+                tokio::time::sleep(Duration::from_secs(60)).await;
+                let now = Local::now();
+                let due = now.weekday() == self.config.weekday && now.hour() == self.config.hour;
+                if due && last_sent != Some(now.date_naive()) {
+                    last_sent = Some(now.date_naive());
+                    self.send_report().await;
+                }
+            }
+        });
+    }
+
+    async fn send_report(&self) {
+        let to = Utc::now();
+        let from = to - chrono::Duration::days(REPORT_PERIOD_DAYS);
+        let events = self.alert_manager.get_history(from - chrono::Duration::days(1), to).await;
+        let incidents = incidents(&events, from, to);
+
+        let measured_from = from.max(self.started);
+        let measured_seconds = (to - measured_from).num_seconds().max(1) as u64;
+        let mut availability = Vec::new();
+        for channel in self.router.get_all_channels() {
+            for stream in self.router.get_channel_streams(&channel).unwrap_or_default() {
+                let down = downtime_seconds(&stream, &incidents).min(measured_seconds);
+                let uptime_percent = 100.0 * (1.0 - down as f64 / measured_seconds as f64);
+                availability.push((stream, uptime_percent, down));
+            }
+        }
+        availability.sort_by(|a, b| a.1.total_cmp(&b.1).then_with(|| a.0.cmp(&b.0)));
+
+        let mut noisy: HashMap<&str, (usize, &str)> = HashMap::new();
+        for event in events.iter().filter(|e| e.transition == AlertTransition::Failing && e.timestamp >= from) {
+            let entry = noisy.entry(event.alert.as_str()).or_insert((0, ""));
+            entry.0 += 1;
+            entry.1 = event.message.as_str();
+        }
+        let mut noisy: Vec<_> = noisy.into_iter().collect();
+        noisy.sort_by(|a, b| b.1.0.cmp(&a.1.0).then_with(|| a.0.cmp(b.0)));
+        noisy.truncate(self.config.top_alerts);
+
+        let subject = format!("Radio watchdog weekly availability report, week ending {}", to.with_timezone(&Local).format("%Y-%m-%d"));
+        let html = render_report(&subject, measured_from, to, &availability, &incidents, &noisy);
+        info!("Sending weekly availability report ({} streams, {} incidents)", availability.len(), incidents.len());
+        self.email.send_html(&subject, html.into_string()).await;
+    }
+}
+
+fn local_time(time: DateTime<Utc>) -> String {
+    time.with_timezone(&Local).format("%a %Y-%m-%d %H:%M").to_string()
+}
+
+fn render_report(
+    title: &str,
+    from: DateTime<Utc>,
+    to: DateTime<Utc>,
+    availability: &[(String, f64, u64)],
+    incidents: &[Incident],
+    noisy: &[(&str, (usize, &str))],
+) -> Markup {
+    html! {
+        html {
+            body style="font-family: sans-serif;" {
+                h2 { (title) }
+                p { "Covering " (local_time(from)) " to " (local_time(to)) "." }
+
+                h3 { "Uptime per stream" }
+                table border="1" cellpadding="4" style="border-collapse: collapse;" {
+                    tr { th { "Stream" } th { "Uptime" } th { "Time alerting" } }
+                    @for (stream, uptime_percent, down) in availability {
+                        tr {
+                            td { (stream) }
+                            td { (format!("{:.3}%", uptime_percent)) }
+                            td { (format_seconds(*down)) }
+                        }
+                    }
+                }
+
+                h3 { "Incidents (" (incidents.len()) ")" }
+                @if incidents.is_empty() {
+                    p { "No incidents this week." }
+                } @else {
+                    table border="1" cellpadding="4" style="border-collapse: collapse;" {
+                        tr { th { "Started" } th { "Duration" } th { "Alert" } }
+                        @for incident in incidents {
+                            tr {
+                                td { (local_time(incident.start)) }
+                                td {
+                                    (format_seconds((incident.end - incident.start).num_seconds().max(0) as u64))
+                                    @if incident.ongoing { " (ongoing)" }
+                                }
+                                td { (incident.message) }
+                            }
+                        }
+                    }
+                }
+
+                h3 { "Noisiest alerts" }
+                @if noisy.is_empty() {
+                    p { "No alerts fired this week." }
+                } @else {
+                    table border="1" cellpadding="4" style="border-collapse: collapse;" {
+                        tr { th { "Alert" } th { "Times failing" } th { "Last message" } }
+                        @for (alert, (count, message)) in noisy {
+                            tr {
+                                td { (alert) }
+                                td { (count) }
+                                td { (message) }
+                            }
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use std::sync::Arc;
+use std::time::Duration;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tracing::{info, warn};
+
+use super::audiorouter::AudioRouter;
+use super::nrsc::NrscManager;
+
+/// Time-multiplexes one SDR across several stations, for sites with more stations than dongles
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ScanConfig {
+    /// Hz, visited in this order
+    pub frequencies: Vec<u32>,
+    /// How long to stay on each frequency
+    #[serde(default = "default_dwell")]
+    pub dwell_seconds: u64,
+}
+
+fn default_dwell() -> u64 { 300 }
+
+/// Cycles an SDR through its scan frequencies, marking the streams of the other frequencies as not sampled
+pub struct SdrScanner {
+    sdr_name: String,
+    nrsc: Arc<NrscManager>,
+    router: Arc<AudioRouter>,
+    config: ScanConfig,
+    members: Vec<(u32, String)>, // (frequency, stream name)
+}
+
+impl SdrScanner {
+    pub fn new(sdr_name: String, nrsc: Arc<NrscManager>, router: Arc<AudioRouter>, config: ScanConfig, members: Vec<(u32, String)>) -> Self {
+        SdrScanner { sdr_name, nrsc, router, config, members }
+    }
+
+    pub async fn start_scan_loop(self) {
+        info!("Starting scan of SDR {} across {} frequencies ({}s each)",
+            self.sdr_name, self.config.frequencies.len(), self.config.dwell_seconds);
+
+        tokio::spawn(async move {
+            loop {
+                for frequency in &self.config.frequencies {
+                    if let Err(e) = self.nrsc.set_frequency(*frequency).await {
+                        warn!("Could not retune SDR {} to {} Hz: {}", self.sdr_name, frequency, e);
+                    }
+                    for (stream_frequency, stream_name) in &self.members {
+                        self.router.set_stream_sampled(stream_name, stream_frequency == frequency);
+                    }
+                    tokio::time::sleep(Duration::from_secs(self.config.dwell_seconds)).await;
+                }
+            }
+        });
+    }
+}
@@ -5,14 +5,49 @@ use tokio::net::TcpStream;
 use tokio::io::{AsyncBufReadExt, BufReader};
 use tokio::process::Command as TokioCommand;
 use tracing::{info, error, debug};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use super::stderrlog::StderrLog;
+
+/// IQ sample rate nrsc5 decodes at; rtl_tcp feeding it must run at exactly this rate or nrsc5 never syncs
+pub const NRSC5_SAMPLE_RATE: u32 = 1488375;
+
+/// How to reach an rtl_tcp server that isn't exposed directly; the SDR's host/port become the local end
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum TunnelConfig {
+    /// Forward the SDR port over SSH
+    Ssh(SshTunnel),
+    /// Any command (VPN, knock script, autossh...) that makes rtl_tcp reachable at host/port
+    Command(Vec<String>),
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct SshTunnel {
+    /// user@remote-site
+    pub destination: String,
+    /// Where rtl_tcp listens, as seen from the SSH server
+    #[serde(default = "default_tunnel_remote_host")]
+    pub remote_host: String,
+    pub remote_port: u16,
+    pub ssh_port: Option<u16>,
+    pub identity_file: Option<String>,
+}
+
+fn default_tunnel_remote_host() -> String { "127.0.0.1".to_string() }
+
+enum Launch {
+    RtlTcp { frequency: u32, size: u32, gain: f32 },
+    Tunnel(TunnelConfig),
+}
 
 pub struct SdrManager {
     host: String,
     port: u16,
-    frequency: u32,
-    size: u32,
-    gain: f32,
+    launch: Launch,
     process: Arc<Mutex<Option<Child>>>,
+    stderr_log: StderrLog,
 }
 
 impl SdrManager {
@@ -20,25 +55,99 @@ impl SdrManager {
         Self {
             host,
             port,
-            frequency,
-            size,
-            gain,
+            launch: Launch::RtlTcp { frequency, size, gain },
             process: Arc::new(Mutex::new(None)),
+            stderr_log: StderrLog::new(),
+        }
+    }
+
+    /// Manages a tunnel to a remote rtl_tcp instead of a local rtl_tcp process
+    pub fn tunneled(host: String, port: u16, tunnel: TunnelConfig) -> Self {
+        Self {
+            host,
+            port,
+            launch: Launch::Tunnel(tunnel),
+            process: Arc::new(Mutex::new(None)),
+            stderr_log: StderrLog::new(),
+        }
+    }
+
+    /// Name of the managed process, for logs
+    pub fn process_name(&self) -> &'static str {
+        match self.launch {
+            Launch::RtlTcp { .. } => "rtl_tcp",
+            Launch::Tunnel(TunnelConfig::Ssh(_)) => "ssh tunnel",
+            Launch::Tunnel(TunnelConfig::Command(_)) => "pre-connect command",
+        }
+    }
+
+    fn build_command(&self) -> Result<TokioCommand, String> {
+        match self.launch {
+            Launch::RtlTcp { frequency, size, gain } => {
+                info!(
+                    "Spawning rtl_tcp on {}:{} with frequency={}, size={}, gain={}",
+                    self.host, self.port, frequency, size, gain
+                );
+
+                // Build the rtl_tcp command
+                // rtl_tcp -a 0.0.0.0 -p <port> -f <frequency> -s <size> -g <gain>
+                let mut cmd = TokioCommand::new("rtl_tcp");
+                cmd.arg("-a").arg(&self.host)
+                    .arg("-p").arg(self.port.to_string())
+                    .arg("-f").arg(frequency.to_string())
+                    .arg("-s").arg(size.to_string())
+                    .arg("-g").arg(gain.to_string());
+
+                debug!("Executing command: rtl_tcp -a {} -p {} -f {} -s {} -g {}",
+                    self.host, self.port, frequency, size, gain);
+                Ok(cmd)
+            }
+            Launch::Tunnel(TunnelConfig::Ssh(ref ssh)) => {
+                info!("Opening SSH tunnel {}:{} -> {}:{} via {}",
+                    self.host, self.port, ssh.remote_host, ssh.remote_port, ssh.destination);
+
+                // ssh -N -L <host>:<port>:<remote_host>:<remote_port> <destination>
+                // BatchMode so a missing key fails instead of waiting on a password prompt
+                let mut cmd = TokioCommand::new("ssh");
+                cmd.arg("-N")
+                    .arg("-o").arg("BatchMode=yes")
+                    .arg("-o").arg("ExitOnForwardFailure=yes")
+                    .arg("-o").arg("ServerAliveInterval=15")
+                    .arg("-L").arg(format!("{}:{}:{}:{}", self.host, self.port, ssh.remote_host, ssh.remote_port));
+                if let Some(ssh_port) = ssh.ssh_port {
+                    cmd.arg("-p").arg(ssh_port.to_string());
+                }
+                if let Some(ref identity_file) = ssh.identity_file {
+                    cmd.arg("-i").arg(identity_file);
+                }
+                cmd.arg(&ssh.destination);
+                Ok(cmd)
+            }
+            Launch::Tunnel(TunnelConfig::Command(ref args)) => {
+                let Some((program, rest)) = args.split_first() else {
+                    return Err("Pre-connect command is empty".to_string());
+                };
+                info!("Running pre-connect command for {}:{}: {}", self.host, self.port, args.join(" "));
+                let mut cmd = TokioCommand::new(program);
+                cmd.args(rest);
+                Ok(cmd)
+            }
         }
     }
 
     pub async fn spawn(&self) -> Result<(), String> {
+        let name = self.process_name();
         let mut process_lock = self.process.lock().await;
 
         if process_lock.is_some() {
-            return Err("rtl_tcp process is already running".to_string());
+            return Err(format!("{} process is already running", name));
         }
 
         // Check if the port is already in use
         let addr = format!("{}:{}", self.host, self.port);
         match TcpStream::connect(&addr).await {
             Ok(_) => {
-                debug!("Port {}:{} is already in use, skipping rtl_tcp spawn", self.host, self.port);
+                debug!("Port {}:{} is already in use, skipping {} spawn", self.host, self.port, name);
                 return Err(format!("Port {}:{} is already in use", self.host, self.port));
             }
             Err(_) => {
@@ -46,29 +155,14 @@ impl SdrManager {
             }
         }
 
-        info!(
-            "Spawning rtl_tcp on {}:{} with frequency={}, size={}, gain={}",
-            self.host, self.port, self.frequency, self.size, self.gain
-        );
-
-        // Build the rtl_tcp command
-        // rtl_tcp -a 0.0.0.0 -p <port> -f <frequency> -s <size> -g <gain>
-        let mut cmd = TokioCommand::new("rtl_tcp");
-        cmd.arg("-a").arg(&self.host)
-            .arg("-p").arg(self.port.to_string())
-            .arg("-f").arg(self.frequency.to_string())
-            .arg("-s").arg(self.size.to_string())
-            .arg("-g").arg(self.gain.to_string())
-            .stdout(std::process::Stdio::piped())
+        let mut cmd = self.build_command()?;
+        cmd.stdout(std::process::Stdio::piped())
             .stderr(std::process::Stdio::piped());
 
-        debug!("Executing command: rtl_tcp -a {} -p {} -f {} -s {} -g {}",
-            self.host, self.port, self.frequency, self.size, self.gain);
-
         match cmd.spawn() {
             Ok(mut child) => {
                 let pid = child.id();
-                info!("Spawned rtl_tcp process (PID: {:?}), verifying it's accepting connections...", pid);
+                info!("Spawned {} process (PID: {:?}), verifying {}:{} is accepting connections...", name, pid, self.host, self.port);
 
                 // Capture stdout and stderr for logging
                 let stdout = child.stdout.take();
@@ -80,17 +174,19 @@ impl SdrManager {
                         let reader = BufReader::new(stdout);
                         let mut lines = reader.lines();
                         while let Ok(Some(line)) = lines.next_line().await {
-                            debug!("[rtl_tcp stdout] {}", line);
+                            debug!("[{} stdout] {}", name, line);
                         }
                     });
                 }
 
                 if let Some(stderr) = stderr {
+                    let stderr_log = self.stderr_log.clone();
                     tokio::spawn(async move {
                         let reader = BufReader::new(stderr);
                         let mut lines = reader.lines();
                         while let Ok(Some(line)) = lines.next_line().await {
-                            debug!("[rtl_tcp stderr] {}", line);
+                            debug!("[{} stderr] {}", name, line);
+                            stderr_log.push(&line).await;
                         }
                     });
                 }
@@ -102,7 +198,11 @@ impl SdrManager {
 
                 // Wait a bit and verify the port is accepting connections
                 let addr = format!("{}:{}", self.host, self.port);
-                let max_retries = 10;
+                // Tunnels need time for the SSH handshake or VPN to come up
+                let max_retries = match self.launch {
+                    Launch::RtlTcp { .. } => 10,
+                    Launch::Tunnel(_) => 60,
+                };
                 let mut connected = false;
 
                 for attempt in 1..=max_retries {
@@ -126,34 +226,37 @@ impl SdrManager {
                     if let Some(ref mut child) = *process_lock {
                         match child.try_wait() {
                             Ok(Some(status)) => {
-                                error!("rtl_tcp process (PID: {:?}) exited with status: {}. Is an SDR connected?", pid, status);
+                                match self.launch {
+                                    Launch::RtlTcp { .. } => error!("rtl_tcp process (PID: {:?}) exited with status: {}. Is an SDR connected?", pid, status),
+                                    Launch::Tunnel(_) => error!("{} (PID: {:?}) exited with status: {} before {} became reachable", name, pid, status, addr),
+                                }
                                 *process_lock = None;
-                                return Err(format!("rtl_tcp process exited (status: {})", status));
+                                return Err(format!("{} process exited (status: {})", name, status));
                             }
                             Ok(None) => {
                                 // Process is still running but not accepting connections
-                                error!("rtl_tcp process (PID: {:?}) is running but not accepting connections on {}", pid, addr);
+                                error!("{} process (PID: {:?}) is running but nothing is accepting connections on {}", name, pid, addr);
                                 child.kill().await.ok();
                                 child.wait().await.ok();
                                 *process_lock = None;
                                 return Err(format!("rtl_tcp not accepting connections on {}", addr));
                             }
                             Err(e) => {
-                                error!("Failed to check rtl_tcp process status: {}", e);
+                                error!("Failed to check {} process status: {}", name, e);
                                 *process_lock = None;
-                                return Err(format!("Failed to verify rtl_tcp status: {}", e));
+                                return Err(format!("Failed to verify {} status: {}", name, e));
                             }
                         }
                     } else {
-                        return Err("rtl_tcp process disappeared".to_string());
+                        return Err(format!("{} process disappeared", name));
                     }
                 }
 
                 Ok(())
             }
             Err(e) => {
-                error!("Failed to spawn rtl_tcp: {}", e);
-                Err(format!("Failed to spawn rtl_tcp: {}", e))
+                error!("Failed to spawn {}: {}", name, e);
+                Err(format!("Failed to spawn {}: {}", name, e))
             }
         }
     }
@@ -162,26 +265,30 @@ impl SdrManager {
         let mut process_lock = self.process.lock().await;
 
         if let Some(mut child) = process_lock.take() {
-            info!("Stopping rtl_tcp process (PID: {:?})", child.id());
+            info!("Stopping {} process (PID: {:?})", self.process_name(), child.id());
             match child.kill().await {
                 Ok(_) => {
                     let _ = child.wait().await;
-                    info!("Successfully stopped rtl_tcp process");
+                    info!("Successfully stopped {} process", self.process_name());
                     Ok(())
                 }
                 Err(e) => {
-                    error!("Failed to kill rtl_tcp process: {}", e);
-                    Err(format!("Failed to kill rtl_tcp process: {}", e))
+                    error!("Failed to kill {} process: {}", self.process_name(), e);
+                    Err(format!("Failed to kill {} process: {}", self.process_name(), e))
                 }
             }
         } else {
-            Err("No rtl_tcp process is running".to_string())
+            Err(format!("No {} process is running", self.process_name()))
         }
     }
 
     pub async fn is_running(&self) -> bool {
         self.process.lock().await.is_some()
     }
+
+    pub fn get_stderr_log(&self) -> StderrLog {
+        self.stderr_log.clone()
+    }
 }
 
 impl Drop for SdrManager {
@@ -1,10 +1,16 @@
+use std::sync::Arc;
 use tracing::{debug, info, trace, warn};
 
+use super::delivery::{DeliveryStats, SinkCounters};
+
+pub const DEFAULT_USER_AGENT: &str = "wrek-watchdog/1.0";
 
 pub struct SlackMessageSender {
     authorization: String,
     channel_id: String,
     dry_run: bool,
+    client: reqwest::Client,
+    counters: Arc<SinkCounters>,
 }
 
 impl SlackMessageSender {
@@ -15,27 +21,114 @@ impl SlackMessageSender {
         SlackMessageSender {
             authorization: auth,
             channel_id: channel,
-            dry_run
+            dry_run,
+            client: reqwest::Client::builder().user_agent(DEFAULT_USER_AGENT).build().unwrap_or_default(),
+            counters: Arc::new(SinkCounters::default()),
         }
     }
 
+    /// Sends through `client` instead of a default one, e.g. with configured headers or going through the outbound proxy
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Counts messages as the "slack" sink of `stats`
+    pub fn with_delivery_stats(mut self, stats: &DeliveryStats) -> Self {
+        self.counters = stats.sink("slack");
+        self
+    }
+
+    /// Messages sent and failed since startup; dry runs count as sent
+    pub fn get_counts(&self) -> (u64, u64) {
+        (self.counters.get_delivered(), self.counters.get_failed())
+    }
+
     pub async fn send(&self, message: String) -> bool {
+        self.post(message, None).await.is_some()
+    }
+
+    /// Posts a message and returns its timestamp, which replies use to join its thread
+    pub async fn start_thread(&self, message: String) -> Option<String> {
+        self.post(message, None).await
+    }
+
+    pub async fn send_in_thread(&self, message: String, thread_ts: &str) -> bool {
+        self.post(message, Some(thread_ts)).await.is_some()
+    }
+
+    /// Replaces a user's App Home tab with `view`
+    pub async fn publish_home(&self, user_id: &str, view: serde_json::Value) -> bool {
         if self.dry_run {
-            info!("DRY RUN: Sending Slack Message: {}", message);
+            debug!("DRY RUN: Publishing home tab for {}", user_id);
             return true;
         }
 
-        let json_payload = serde_json::json!({
+        let payload = serde_json::json!({
+            "user_id": user_id,
+            "view": view
+        });
+        let response = self.client
+            .post("https://slack.com/api/views.publish")
+            .header("Authorization", format!("Bearer {}", self.authorization))
+            .json(&payload)
+            .send()
+            .await;
+        match response {
+            Ok(res) => match res.json::<serde_json::Value>().await {
+                Ok(body) if body["ok"].as_bool() == Some(true) => true,
+                Ok(body) => {
+                    warn!("Slack rejected home tab for {}: {}", user_id, body["error"]);
+                    false
+                }
+                Err(e) => {
+                    warn!("Could not read Slack response: {:?}", e);
+                    false
+                }
+            },
+            Err(e) => {
+                warn!("Failed to publish home tab: {:?}", e);
+                false
+            }
+        }
+    }
+
+    async fn post(&self, message: String, thread_ts: Option<&str>) -> Option<String> {
+        match self.post_message(message, thread_ts).await {
+            Ok(ts) => {
+                self.counters.delivered();
+                Some(ts)
+            }
+            Err(e) => {
+                warn!("{}", e);
+                self.counters.failed(&e);
+                None
+            }
+        }
+    }
+
+    async fn post_message(&self, message: String, thread_ts: Option<&str>) -> Result<String, String> {
+        if self.dry_run {
+            match thread_ts {
+                Some(ts) => info!("DRY RUN: Sending Slack Message in thread {}: {}", ts, message),
+                None => info!("DRY RUN: Sending Slack Message: {}", message),
+            }
+            return Ok("dry-run".to_string());
+        }
+
+        let mut json_payload = serde_json::json!({
             "channel": self.channel_id,
             "text": message
         });
+        if let Some(ts) = thread_ts {
+            json_payload["thread_ts"] = serde_json::json!(ts);
+        }
         
         let json_str = serde_json::to_string(&json_payload).unwrap();
 
 
-        let client = reqwest::Client::new()
+        let client = self.client
             .post("https://slack.com/api/chat.postMessage")
-            .header("User-Agent", "wrek-watchdog/1.0")
             .header("Authorization", format!("Bearer {}", self.authorization))
             .header("Content-Type", "application/json")
             .body(json_str)
@@ -44,17 +137,21 @@ impl SlackMessageSender {
         match client {
             Ok(res) => {
                 if res.status().is_success() {
-                    debug!("Slack message sent successfully!");
-                    return true;
+                    // Slack answers 200 even for rejected messages, the body says whether it was posted
+                    match res.json::<serde_json::Value>().await {
+                        Ok(body) if body["ok"].as_bool() == Some(true) => {
+                            debug!("Slack message sent successfully!");
+                            Ok(body["ts"].as_str().unwrap_or_default().to_string())
+                        }
+                        Ok(body) => Err(format!("Slack rejected message: {}", body["error"])),
+                        Err(e) => Err(format!("Could not read Slack response: {:?}", e)),
+                    }
                 } else {
-                    warn!("Failed to send Slack message: {:?}", res.text().await);
-                    return false;
+                    let status = res.status();
+                    Err(format!("Failed to send Slack message: HTTP {}: {:?}", status, res.text().await))
                 }
             },
-            Err(e) => {
-                warn!("Failed to send slack message: {:?}", e);
-                return false;
-            }
+            Err(e) => Err(format!("Failed to send slack message: {:?}", e)),
         }
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,162 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::Utc;
+use serde_json::{json, Value};
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+use watchdog::api::{AlertSeverity, AudioStreamHealth, StreamHealth};
+
+use super::alertmanager::AlertManager;
+use super::audiorouter::AudioRouter;
+use super::slack::SlackMessageSender;
+use super::timefmt;
+
+const HOME_REFRESH_SECONDS: u64 = 60;
+const HOME_MAX_ALERTS: usize = 20; // a view is capped at 100 blocks
+
+/// The bot's App Home tab: a status dashboard with buttons for common actions, kept fresh for everyone who opened it
+pub struct HomeTab {
+    slack: Arc<SlackMessageSender>,
+    router: Arc<AudioRouter>,
+    alert_manager: Arc<AlertManager>,
+    viewers: RwLock<HashSet<String>>, // users who opened the tab, republished on every refresh
+}
+
+impl HomeTab {
+    pub fn new(slack: Arc<SlackMessageSender>, router: Arc<AudioRouter>, alert_manager: Arc<AlertManager>) -> Self {
+        HomeTab {
+            slack,
+            router,
+            alert_manager,
+            viewers: RwLock::new(HashSet::new()),
+        }
+    }
+
+    /// Publishes the dashboard to a user who just opened the tab
+    pub async fn open(&self, user: &str) {
+        self.viewers.write().await.insert(user.to_string());
+        let view = self.render().await;
+        self.slack.publish_home(user, view).await;
+    }
+
+    /// Runs a button press from the tab, then refreshes it for the user who pressed it
+    pub async fn handle_action(&self, user: &str, action_id: &str, value: &str) {
+        let result = match action_id {
+            "ack" => self.alert_manager.acknowledge(value, &format!("<@{}>", user)).await,
+            "alert_now" => self.alert_manager.alert_now(value).await,
+            "restart" => self.router.restart_stream(value).await,
+            "refresh" => Ok(()),
+            _ => {
+                debug!("Ignoring unknown home tab action {}", action_id);
+                return;
+            }
+        };
+        match result {
+            Ok(_) => info!("Home tab action {} on {} by {}", action_id, value, user),
+            Err(e) => warn!("Home tab action {} on {} by {} failed: {}", action_id, value, user, e),
+        }
+        self.open(user).await;
+    }
+
+    pub async fn start_refresh_loop(self: Arc<Self>) {
+        info!("Starting Slack home tab refresh (interval: {}s)", HOME_REFRESH_SECONDS);
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(Duration::from_secs(HOME_REFRESH_SECONDS)).await;
+
+                let viewers: Vec<String> = self.viewers.read().await.iter().cloned().collect();
+                if viewers.is_empty() {
+                    continue;
+                }
+                let view = self.render().await;
+                for user in viewers {
+                    self.slack.publish_home(&user, view.clone()).await;
+                }
+            }
+        });
+    }
+
+    async fn render(&self) -> Value {
+        let streams = self.router.get_all_streams().await;
+        let running = streams.iter().filter(|(_, cmd, audio)| *cmd == StreamHealth::Running && *audio == AudioStreamHealth::Running).count();
+        let dead = streams.iter().filter(|(_, cmd, audio)| *cmd == StreamHealth::Dead || *audio == AudioStreamHealth::Dead).count();
+        let alerts = self.alert_manager.get_active_alerts().await;
+
+        let mut blocks = vec![
+            json!({"type": "header", "text": {"type": "plain_text", "text": "🐕 Watchdog"}}),
+            json!({"type": "section", "text": {"type": "mrkdwn", "text": format!(
+                "*{}* streams healthy, *{}* degraded, *{}* dead · *{}* active alerts",
+                running, streams.len() - running - dead, dead, alerts.len()
+            )}}),
+            json!({"type": "context", "elements": [{"type": "mrkdwn", "text": format!("Updated {}", timefmt::slack(Utc::now(), "{date_short_pretty} {time_secs}", "%Y-%m-%d %H:%M:%S"))}]}),
+            json!({"type": "actions", "elements": [button("Refresh", "refresh", "refresh", None)]}),
+            json!({"type": "divider"}),
+            json!({"type": "header", "text": {"type": "plain_text", "text": "Active alerts"}}),
+        ];
+
+        if alerts.is_empty() {
+            blocks.push(json!({"type": "section", "text": {"type": "mrkdwn", "text": "No active alerts, all good!"}}));
+        }
+        for alert in alerts.iter().take(HOME_MAX_ALERTS) {
+            let icon = if alert.severity == AlertSeverity::Critical { "🔴" } else { "🟠" };
+            let state = match (&alert.acknowledged_by, alert.announce_at) {
+                (Some(by), _) => format!("ack'd by {}", by),
+                (None, Some(announce_at)) => format!("in grace period until {}", timefmt::slack(announce_at, "{time_secs}", "%H:%M:%S")),
+                (None, None) => format!("failing since {}", timefmt::slack(alert.failing_since, "{time_secs}", "%H:%M:%S")),
+            };
+            blocks.push(json!({"type": "section", "text": {"type": "mrkdwn", "text": format!("{} {}\n_{}_", icon, alert.message, state)}}));
+
+            let mut buttons = Vec::new();
+            if alert.acknowledged_by.is_none() {
+                buttons.push(button("Acknowledge", "ack", &alert.alert, None));
+            }
+            if alert.announce_at.is_some() {
+                buttons.push(button("Alert now", "alert_now", &alert.alert, Some("danger")));
+            }
+            if let [stream] = alert.streams.as_slice() {
+                buttons.push(button(&format!("Restart {}", stream), "restart", stream, None));
+            }
+            if !buttons.is_empty() {
+                blocks.push(json!({"type": "actions", "elements": buttons}));
+            }
+        }
+        if alerts.len() > HOME_MAX_ALERTS {
+            blocks.push(json!({"type": "context", "elements": [{"type": "mrkdwn", "text": format!("…and {} more", alerts.len() - HOME_MAX_ALERTS)}]}));
+        }
+
+        blocks.push(json!({"type": "divider"}));
+        blocks.push(json!({"type": "header", "text": {"type": "plain_text", "text": "Streams"}}));
+        let mut channels = self.router.get_all_channels();
+        channels.sort();
+        for channel_name in channels.into_iter().filter(|c| c != "silence") {
+            let Some(stream_names) = self.router.get_channel_streams(&channel_name) else { continue };
+            let lines: Vec<String> = stream_names.iter().filter_map(|name| {
+                let (_, cmd, audio) = streams.iter().find(|(n, _, _)| n == name)?;
+                let icon = match (cmd, audio) {
+                    (StreamHealth::Running, AudioStreamHealth::Running) => "🟢",
+                    (StreamHealth::Dead, _) | (_, AudioStreamHealth::Dead) => "🔴",
+                    _ => "🟠",
+                };
+                Some(format!("{} `{}`", icon, name))
+            }).collect();
+            blocks.push(json!({"type": "section", "text": {"type": "mrkdwn", "text": format!("*{}*\n{}", channel_name, lines.join("\n"))}}));
+        }
+
+        json!({"type": "home", "blocks": blocks})
+    }
+}
+
+fn button(text: &str, action_id: &str, value: &str, style: Option<&str>) -> Value {
+    let mut button = json!({
+        "type": "button",
+        "text": {"type": "plain_text", "text": text},
+        "action_id": action_id,
+        "value": value,
+    });
+    if let Some(style) = style {
+        button["style"] = json!(style);
+    }
+    button
+}
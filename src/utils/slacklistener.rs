@@ -1,18 +1,81 @@
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use chrono::{DateTime, Utc};
+use tokio::sync::RwLock;
 use tracing::{info, warn, error, debug, trace};
 use serde::{Deserialize, Serialize};
 use futures_util::{SinkExt, StreamExt};
-use tokio_tungstenite::{connect_async, tungstenite::protocol::Message};
+use watchdog::api::AlertReason;
+use tokio_tungstenite::{client_async_tls, connect_async, tungstenite::protocol::Message};
 
 use super::slack::SlackMessageSender;
+use super::proxy::ProxyConfig;
 use super::audiorouter::AudioRouter;
+use super::alertmanager::{AlertManager, DEFAULT_MAINTENANCE_MINUTES};
+use super::comparator::StreamComparator;
+use super::email::EmailSender;
+use super::slackhome::HomeTab;
+use super::timefmt;
+use super::tuning::{ThresholdKind, ThresholdTuner};
+use super::pin::{default_pin_interval, default_pin_minutes, default_pin_window, PairPinner, PinRequest};
 
 #[derive(Debug, Deserialize)]
 struct SocketModeEnvelope {
-    envelope_id: String,
+    envelope_id: Option<String>, // hello and disconnect envelopes don't carry one
     #[serde(rename = "type")]
     event_type: String,
     payload: Option<serde_json::Value>,
+    reason: Option<String>, // set on disconnect envelopes
+}
+
+// Slack API errors meaning the app token itself is bad, not that the network is
+const TOKEN_ERRORS: [&str; 5] = ["invalid_auth", "not_authed", "token_revoked", "token_expired", "account_inactive"];
+
+// How long the Socket Mode link may be down before alerting through chat.postMessage and email
+const LINK_DOWN_ALERT_SECONDS: i64 = 300;
+
+/// Connection state of the Socket Mode link, shared with the web server
+pub struct ListenerState {
+    connected: AtomicBool,
+    reconnects: AtomicU64,
+    disconnected_since: RwLock<Option<DateTime<Utc>>>,
+}
+
+impl ListenerState {
+    fn new() -> Self {
+        ListenerState {
+            connected: AtomicBool::new(false),
+            reconnects: AtomicU64::new(0),
+            disconnected_since: RwLock::new(Some(Utc::now())),
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected.load(Ordering::Relaxed)
+    }
+
+    pub fn reconnect_count(&self) -> u64 {
+        self.reconnects.load(Ordering::Relaxed)
+    }
+
+    async fn set_connected(&self) {
+        self.connected.store(true, Ordering::Relaxed);
+        *self.disconnected_since.write().await = None;
+    }
+
+    async fn set_disconnected(&self) {
+        if self.connected.swap(false, Ordering::Relaxed) {
+            self.reconnects.fetch_add(1, Ordering::Relaxed);
+            *self.disconnected_since.write().await = Some(Utc::now());
+        }
+    }
+}
+
+/// What to do after a Socket Mode session ends
+enum SessionEnd {
+    Refresh,   // Slack asked us to reconnect, do it right away
+    Dropped,   // socket closed or errored
+    Disabled,  // Socket Mode turned off for the app
 }
 
 #[derive(Debug, Deserialize)]
@@ -29,6 +92,7 @@ struct SlackEvent {
     user: Option<String>,
     ts: Option<String>,
     bot_id: Option<String>,
+    tab: Option<String>, // app_home_opened: "home" or "messages"
 }
 
 #[derive(Debug, Serialize)]
@@ -41,7 +105,16 @@ pub struct SlackListener {
     bot_user_id: String,
     slack_sender: Arc<SlackMessageSender>,
     audio_router: Arc<AudioRouter>,
+    alert_manager: Arc<AlertManager>,
     dry_run: bool,
+    state: Arc<ListenerState>,
+    pinner: Option<Arc<PairPinner>>,
+    comparator: Option<Arc<StreamComparator>>,
+    tuner: Option<Arc<ThresholdTuner>>,
+    home: Option<Arc<HomeTab>>,
+    email: Option<Arc<EmailSender>>, // reaches someone about the link going down without going through Slack
+    proxy: Option<ProxyConfig>,
+    client: reqwest::Client,
 }
 
 impl SlackListener {
@@ -50,6 +123,7 @@ impl SlackListener {
         bot_user_id: String,
         slack_sender: Arc<SlackMessageSender>,
         audio_router: Arc<AudioRouter>,
+        alert_manager: Arc<AlertManager>,
         dry_run: bool,
     ) -> Self {
         SlackListener {
@@ -57,13 +131,103 @@ impl SlackListener {
             bot_user_id,
             slack_sender,
             audio_router,
+            alert_manager,
             dry_run,
+            state: Arc::new(ListenerState::new()),
+            pinner: None,
+            comparator: None,
+            tuner: None,
+            home: None,
+            email: None,
+            proxy: None,
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// Opens the Socket Mode websocket through the outbound proxy
+    pub fn with_proxy(mut self, proxy: ProxyConfig) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Makes API calls with `client` instead of a default one, e.g. with configured headers or going through the outbound proxy
+    pub fn with_client(mut self, client: reqwest::Client) -> Self {
+        self.client = client;
+        self
+    }
+
+    pub fn with_home_tab(mut self, home: Arc<HomeTab>) -> Self {
+        self.home = Some(home);
+        self
+    }
+
+    pub fn with_comparator(mut self, comparator: Arc<StreamComparator>) -> Self {
+        self.comparator = Some(comparator);
+        self
+    }
+
+    pub fn with_pinner(mut self, pinner: Arc<PairPinner>) -> Self {
+        self.pinner = Some(pinner);
+        self
+    }
+
+    pub fn with_tuner(mut self, tuner: Arc<ThresholdTuner>) -> Self {
+        self.tuner = Some(tuner);
+        self
+    }
+
+    pub fn with_email(mut self, email: Arc<EmailSender>) -> Self {
+        self.email = Some(email);
+        self
+    }
+
+    pub fn get_state(&self) -> Arc<ListenerState> {
+        self.state.clone()
+    }
+
+    /// Raise an alert (delivered with the bot token, not the socket) if the link stays down, and email it
+    /// when email is configured, since whatever took the link down may keep the Slack alert from arriving too
+    fn start_link_monitor(&self) {
+        let state = self.state.clone();
+        let alert_manager = self.alert_manager.clone();
+        let email = self.email.clone();
+        tokio::spawn(async move {
+            let mut emailed = false;
+            loop {
+                tokio::time::sleep(tokio::time::Duration::from_secs(30)).await;
+                let down_for = state.disconnected_since.read().await.map(|since| Utc::now() - since);
+                let is_error = down_for.is_some_and(|d| d.num_seconds() >= LINK_DOWN_ALERT_SECONDS);
+                let message = match down_for {
+                    Some(d) if is_error => format!("Slack Socket Mode link has been down for {}m, bot commands are unavailable", d.num_minutes()),
+                    _ => "Slack Socket Mode link is connected again".to_string(),
+                };
+                if let Some(ref email) = email {
+                    if is_error != emailed {
+                        let subject = if is_error { "Slack link down" } else { "Slack link restored" };
+                        email.send_html(subject, format!("<p>{}</p>", message)).await;
+                        emailed = is_error;
+                    }
+                }
+                alert_manager.update_alert("slack_listener".to_string(), AlertReason::SlackDisconnected, &[], is_error, message).await;
+            }
+        });
+    }
+
+    async fn connect_websocket(&self, ws_url: &str) -> Result<tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>, String> {
+        let Some(ref proxy) = self.proxy else {
+            return connect_async(ws_url).await.map(|(ws_stream, _)| ws_stream).map_err(|e| format!("{:?}", e));
+        };
+        let url = reqwest::Url::parse(ws_url).map_err(|e| format!("Invalid WebSocket URL: {}", e))?;
+        let host = url.host_str().ok_or("WebSocket URL has no host")?;
+        if proxy.bypasses(host) {
+            return connect_async(ws_url).await.map(|(ws_stream, _)| ws_stream).map_err(|e| format!("{:?}", e));
         }
+        let tunnel = proxy.connect(host, url.port_or_known_default().unwrap_or(443)).await?;
+        client_async_tls(ws_url, tunnel).await.map(|(ws_stream, _)| ws_stream).map_err(|e| format!("{:?}", e))
     }
 
     async fn get_websocket_url(&self) -> Result<String, String> {
-        let client = reqwest::Client::new();
-        let response = client
+        let response = self.client
             .post("https://slack.com/api/apps.connections.open")
             .header("Authorization", format!("Bearer {}", self.app_token))
             .header("Content-Type", "application/json")
@@ -100,11 +264,17 @@ impl SlackListener {
         }
 
         info!("Starting Slack Socket Mode listener");
+        self.start_link_monitor();
 
         loop {
             // Get WebSocket URL from Slack API
             let ws_url = match self.get_websocket_url().await {
                 Ok(url) => url,
+                Err(e) if TOKEN_ERRORS.iter().any(|t| e.contains(t)) => {
+                    error!("Slack rejected the app token ({}), check slack_app_token. Retrying in 5 minutes...", e);
+                    tokio::time::sleep(tokio::time::Duration::from_secs(300)).await;
+                    continue;
+                }
                 Err(e) => {
                     error!("Failed to get WebSocket URL: {}", e);
                     warn!("Retrying in 10 seconds...");
@@ -114,64 +284,111 @@ impl SlackListener {
             };
 
             info!("Connecting to WebSocket URL: {}", ws_url);
-            match connect_async(&ws_url).await {
-                Ok((ws_stream, _)) => {
+            match self.connect_websocket(&ws_url).await {
+                Ok(ws_stream) => {
                     info!("Connected to Slack Socket Mode");
-                    let (mut write, mut read) = ws_stream.split();
-
-                    while let Some(msg) = read.next().await {
-                        //trace!("msg: {:?}", msg);
-                        match msg {
-                            Ok(Message::Text(text)) => {
-                                debug!("Received message: {}", text);
-
-                                if let Ok(envelope) = serde_json::from_str::<SocketModeEnvelope>(&text) {
-                                    // Send acknowledgment
-                                    let ack = AckMessage {
-                                        envelope_id: envelope.envelope_id.clone(),
-                                    };
-                                    if let Ok(ack_json) = serde_json::to_string(&ack) {
-                                        if let Err(e) = write.send(Message::Text(ack_json)).await {
-                                            error!("Failed to send ack: {:?}", e);
+                    self.state.set_connected().await;
+                    let end = self.run_session(ws_stream).await;
+                    self.state.set_disconnected().await;
+
+                    match end {
+                        SessionEnd::Refresh => {
+                            info!("Slack requested a connection refresh, reconnecting now");
+                        }
+                        SessionEnd::Dropped => {
+                            warn!("WebSocket connection ended, reconnecting in 5 seconds...");
+                            tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                        }
+                        SessionEnd::Disabled => {
+                            error!("Socket Mode is disabled for this Slack app, retrying in 60 seconds...");
+                            tokio::time::sleep(tokio::time::Duration::from_secs(60)).await;
+                        }
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to connect to Slack: {}", e);
+                    warn!("Retrying in 10 seconds...");
+                    tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
+                }
+            }
+        }
+    }
+
+    async fn run_session<S>(&mut self, ws_stream: tokio_tungstenite::WebSocketStream<S>) -> SessionEnd
+    where
+        S: tokio::io::AsyncRead + tokio::io::AsyncWrite + Unpin,
+    {
+        let (mut write, mut read) = ws_stream.split();
+
+        while let Some(msg) = read.next().await {
+            //trace!("msg: {:?}", msg);
+            match msg {
+                Ok(Message::Text(text)) => {
+                    debug!("Received message: {}", text);
+
+                    if let Ok(envelope) = serde_json::from_str::<SocketModeEnvelope>(&text) {
+                        // Send acknowledgment
+                        if let Some(envelope_id) = envelope.envelope_id.clone() {
+                            let ack = AckMessage { envelope_id };
+                            if let Ok(ack_json) = serde_json::to_string(&ack) {
+                                if let Err(e) = write.send(Message::Text(ack_json)).await {
+                                    error!("Failed to send ack: {:?}", e);
+                                }
+                            }
+                        }
+
+                        // Handle the event
+                        match envelope.event_type.as_str() {
+                            "events_api" => {
+                                if let Some(payload) = envelope.payload {
+                                    if let Ok(event_payload) = serde_json::from_value::<EventPayload>(payload) {
+                                        if let Some(event) = event_payload.event {
+                                            self.handle_event(event).await;
                                         }
                                     }
-
-                                    // Handle the event
-                                    if envelope.event_type == "events_api" {
-                                        if let Some(payload) = envelope.payload {
-                                            if let Ok(event_payload) = serde_json::from_value::<EventPayload>(payload) {
-                                                if let Some(event) = event_payload.event {
-                                                    self.handle_event(event).await;
-                                                }
-                                            }
+                                }
+                            }
+                            // Button presses, only the home tab has any
+                            "interactive" => {
+                                if let (Some(payload), Some(home)) = (envelope.payload, self.home.clone()) {
+                                    if payload["type"] == "block_actions" {
+                                        let user = payload["user"]["id"].as_str().unwrap_or_default().to_string();
+                                        for action in payload["actions"].as_array().into_iter().flatten() {
+                                            let action_id = action["action_id"].as_str().unwrap_or_default();
+                                            let value = action["value"].as_str().unwrap_or_default();
+                                            home.handle_action(&user, action_id, value).await;
                                         }
-                                    } else if envelope.event_type == "hello" {
-                                        info!("Received hello from Slack");
                                     }
                                 }
                             }
-                            Ok(Message::Close(_)) => {
-                                warn!("WebSocket connection closed by Slack");
-                                break;
+                            "hello" => {
+                                info!("Received hello from Slack");
                             }
-                            Err(e) => {
-                                error!("WebSocket error: {:?}", e);
-                                break;
+                            "disconnect" => {
+                                let reason = envelope.reason.unwrap_or_default();
+                                info!("Slack sent disconnect (reason: {})", reason);
+                                return match reason.as_str() {
+                                    "link_disabled" => SessionEnd::Disabled,
+                                    _ => SessionEnd::Refresh, // refresh_requested, warning
+                                };
                             }
                             _ => {}
                         }
                     }
-
-                    warn!("WebSocket connection ended, reconnecting in 5 seconds...");
-                    tokio::time::sleep(tokio::time::Duration::from_secs(5)).await;
+                }
+                Ok(Message::Close(_)) => {
+                    warn!("WebSocket connection closed by Slack");
+                    return SessionEnd::Dropped;
                 }
                 Err(e) => {
-                    error!("Failed to connect to Slack: {:?}", e);
-                    warn!("Retrying in 10 seconds...");
-                    tokio::time::sleep(tokio::time::Duration::from_secs(10)).await;
+                    error!("WebSocket error: {:?}", e);
+                    return SessionEnd::Dropped;
                 }
+                _ => {}
             }
         }
+
+        SessionEnd::Dropped
     }
 
     async fn handle_event(&mut self, event: SlackEvent) {
@@ -181,6 +398,13 @@ impl SlackListener {
             return;
         }
 
+        if event.event_type == "app_home_opened" {
+            if let (Some(home), Some(user), Some("home")) = (&self.home, &event.user, event.tab.as_deref()) {
+                home.open(user).await;
+            }
+            return;
+        }
+
         let text = match event.text {
             Some(t) => t,
             None => return,
@@ -206,7 +430,7 @@ impl SlackListener {
         info!("Processing message: {}", text);
 
         // Parse command
-        let response = self.parse_and_execute_command(&text).await;
+        let response = self.parse_and_execute_command(&text, event.ts.as_deref(), event.user.as_deref()).await;
 
         // Send response back to Slack
         if event.channel.is_some() {
@@ -222,7 +446,9 @@ impl SlackListener {
         }
     }
 
-    async fn parse_and_execute_command(&self, text: &str) -> String {
+    /// `ts` is the command message's timestamp, commands that report over time reply in its thread;
+    /// `user` is who sent it, for acknowledgements
+    async fn parse_and_execute_command(&self, text: &str, ts: Option<&str>, user: Option<&str>) -> String {
         // Remove bot mention if present
         let cleaned_text = text
             .split_whitespace()
@@ -233,7 +459,7 @@ impl SlackListener {
         let parts: Vec<&str> = cleaned_text.trim().split_whitespace().collect();
 
         if parts.is_empty() {
-            return "Available commands: `status`, `list`, `restart <stream>`, `help`, `yeller`".to_string();
+            return "Available commands: `status`, `list`, `alerts`, `ack <alert>`, `alert-now <alert>`, `logs <stream>`, `restart <stream>`, `compare <stream1> <stream2>`, `pin <stream1> <stream2>`, `set threshold <match|divergence|silence> <value> [channel]`, `maintenance [on [minutes]|off]`, `help`, `yeller`".to_string();
         }
 
         match parts[0].to_lowercase().as_str() {
@@ -241,7 +467,15 @@ impl SlackListener {
                 "Here are the commands I learned!\n\
                 • `status` - Show health of all streams\n\
                 • `list` - List all stream names\n\
-                • `restart <stream_name>` - Restart a specific stream\n\
+                • `alerts` - Show currently failing alerts\n\
+                • `ack <alert>` - Mark a failing alert as handled, stopping its reminders until it clears\n\
+                • `alert-now <alert>` - Send an alert held by its grace period right away\n\
+                • `logs <stream_name>` - Show recent stderr output for a stream\n\
+                • `restart <stream_name>` - Restart a specific stream, also confirms respawns of manual streams\n\
+                • `compare <stream1> <stream2>` - Compare two streams right now\n\
+                • `pin <stream1> <stream2> [interval_s] [window_s] [minutes]` - Compare a pair more often for a while, with results in a thread (defaults 1s, 30s, 10 minutes)\n\
+                • `set threshold <match|divergence|silence> <value> [channel]` - Change a threshold live, for one channel or all of them; kept across restarts with an overrides_file\n\
+                • `maintenance [on [minutes]|off]` - Show, start (default 60 minutes) or end maintenance, which holds new alerts and reminders\n\
                 • `help` - Show this help message\n\
                 • `yeller` - Bark bark!".to_string()
             }
@@ -251,6 +485,34 @@ impl SlackListener {
             "list" => {
                 self.list_streams().await
             }
+            "alerts" => {
+                self.list_alerts().await
+            }
+            "ack" => {
+                if parts.len() < 2 {
+                    return "Usage: `ack <alert>`".to_string();
+                }
+                let by = user.map(|u| format!("<@{}>", u)).unwrap_or_else(|| "slack".to_string());
+                match self.alert_manager.acknowledge(parts[1], &by).await {
+                    Ok(_) => format!("Alert `{}` acknowledged by {}, reminders paused until it clears", parts[1], by),
+                    Err(e) => e,
+                }
+            }
+            "alert-now" => {
+                if parts.len() < 2 {
+                    return "Usage: `alert-now <alert>`".to_string();
+                }
+                match self.alert_manager.alert_now(parts[1]).await {
+                    Ok(_) => format!("Sent alert `{}` without waiting out its grace period", parts[1]),
+                    Err(e) => e,
+                }
+            }
+            "logs" => {
+                if parts.len() < 2 {
+                    return "Usage: `logs <stream_name>`".to_string();
+                }
+                self.stream_logs(parts[1]).await
+            }
             "restart" => {
                 if parts.len() < 2 {
                     return "Usage: `restart <stream_name>`".to_string();
@@ -258,6 +520,28 @@ impl SlackListener {
                 let stream_name = parts[1];
                 self.restart_stream(stream_name).await
             }
+            "compare" => {
+                if parts.len() < 3 {
+                    return "Usage: `compare <stream1> <stream2>`".to_string();
+                }
+                self.compare_pair(parts[1], parts[2]).await
+            }
+            "pin" => {
+                if parts.len() < 3 {
+                    return "Usage: `pin <stream1> <stream2> [interval_s] [window_s] [minutes]`".to_string();
+                }
+                self.pin_pair(&parts[1..], ts).await
+            }
+            "set" => {
+                if parts.len() < 4 || !parts[1].eq_ignore_ascii_case("threshold") {
+                    return "Usage: `set threshold <match|divergence|silence> <value> [channel]`".to_string();
+                }
+                self.set_threshold(parts[2], parts[3], parts.get(4).copied()).await
+            }
+            "maintenance" => {
+                let by = user.map(|u| format!("<@{}>", u)).unwrap_or_else(|| "slack".to_string());
+                self.maintenance(&parts[1..], &by).await
+            }
             "yeller" => {
                 "Bark bark!".to_string()
             }
@@ -297,6 +581,136 @@ impl SlackListener {
         format!("*Configured Streams:*\n{}", stream_names.join("\n"))
     }
 
+    async fn list_alerts(&self) -> String {
+        let alerts = self.alert_manager.get_active_alerts().await;
+
+        if alerts.is_empty() {
+            return "No active alerts, all good!".to_string();
+        }
+
+        let alert_lines: Vec<String> = alerts.iter().map(|alert| {
+            let state = match (&alert.acknowledged_by, alert.announce_at) {
+                (Some(by), _) => format!(" _(ack'd by {})_", by),
+                (None, Some(announce_at)) => format!(" _(in grace period until {}, `alert-now {}` to send it)_",
+                    timefmt::slack(announce_at, "{time_secs}", "%H:%M:%S"), alert.alert),
+                (None, None) => String::new(),
+            };
+            format!("• `{}`: {}{}", alert.alert, alert.message, state)
+        }).collect();
+        format!("*Active Alerts:*\n{}", alert_lines.join("\n"))
+    }
+
+    async fn stream_logs(&self, stream_name: &str) -> String {
+        // Slack truncates long messages, so only the tail of each log is sent
+        const SLACK_LOG_LINES: usize = 15;
+
+        let Some(logs) = self.audio_router.get_stream_logs(stream_name).await else {
+            return format!("Stream `{}` not found", stream_name);
+        };
+
+        let mut sections = vec![format!("*Recent stderr for `{}`:*", stream_name)];
+        for log in logs {
+            if log.lines.is_empty() {
+                sections.push(format!("_{}_: no output", log.process));
+            } else {
+                let tail = &log.lines[log.lines.len().saturating_sub(SLACK_LOG_LINES)..];
+                sections.push(format!("_{}_:\n```{}```", log.process, tail.join("\n")));
+            }
+        }
+        sections.join("\n")
+    }
+
+    async fn compare_pair(&self, stream1: &str, stream2: &str) -> String {
+        let Some(ref comparator) = self.comparator else {
+            return "Comparisons are not available".to_string();
+        };
+        match comparator.compare_now(stream1, stream2).await {
+            Ok(result) => {
+                let verdict = match (result.is_within_channel, result.is_error) {
+                    _ if result.is_silence_check => if result.is_error { "silent" } else { "not silent" },
+                    (true, false) => "matching",
+                    (true, true) => "diverging",
+                    (false, false) => "different",
+                    (false, true) => "colliding",
+                };
+                let offset = result.offset_seconds.map(|o| format!(", offset {:.2}s", o)).unwrap_or_default();
+                format!("`{}` vs `{}`: {:.1}% similar ({}){}", result.stream1, result.stream2, result.similarity_percent, verdict, offset)
+            }
+            Err(e) => format!("Could not compare: {}", e),
+        }
+    }
+
+    async fn pin_pair(&self, args: &[&str], ts: Option<&str>) -> String {
+        let Some(ref pinner) = self.pinner else {
+            return "Pinning comparisons is not available".to_string();
+        };
+        let number = |index: usize, default: u64| -> Result<u64, String> {
+            match args.get(index) {
+                Some(value) => value.parse().map_err(|_| format!("`{}` is not a whole number", value)),
+                None => Ok(default),
+            }
+        };
+        let request = match (number(2, default_pin_interval()), number(3, default_pin_window()), number(4, default_pin_minutes())) {
+            (Ok(interval_seconds), Ok(window_seconds), Ok(minutes)) => PinRequest {
+                stream1: args[0].to_string(),
+                stream2: args[1].to_string(),
+                interval_seconds,
+                window_seconds,
+                minutes,
+            },
+            (Err(e), _, _) | (_, Err(e), _) | (_, _, Err(e)) => return e,
+        };
+        match pinner.pin(request, ts.map(|t| t.to_string())).await {
+            Ok(pin) => format!("Pinned `{}` vs `{}` until {}, results follow in the thread of your message",
+                pin.stream1, pin.stream2, timefmt::slack(pin.until, "{time}", "%H:%M")),
+            Err(e) => format!("Could not pin: {}", e),
+        }
+    }
+
+    async fn set_threshold(&self, kind: &str, value: &str, channel_name: Option<&str>) -> String {
+        let Some(ref tuner) = self.tuner else {
+            return "Changing thresholds is not available".to_string();
+        };
+        let kind = match kind.parse::<ThresholdKind>() {
+            Ok(kind) => kind,
+            Err(e) => return e,
+        };
+        let Ok(value) = value.parse::<f32>() else {
+            return format!("`{}` is not a number", value);
+        };
+        match tuner.set(kind, value, channel_name).await {
+            Ok(message) => message,
+            Err(e) => format!("Could not set the threshold: {}", e),
+        }
+    }
+
+    async fn maintenance(&self, args: &[&str], by: &str) -> String {
+        match args.first().map(|arg| arg.to_lowercase()).as_deref() {
+            None => match self.alert_manager.get_maintenance().await {
+                Some(status) => format!("Maintenance ({}) until {}, new alerts and reminders are held",
+                    status.reason, timefmt::slack(status.until, "{time}", "%H:%M")),
+                None => "No maintenance in progress".to_string(),
+            },
+            Some("on") => {
+                let minutes = match args.get(1).map(|minutes| minutes.parse::<i64>()) {
+                    None => DEFAULT_MAINTENANCE_MINUTES,
+                    Some(Ok(minutes)) => minutes,
+                    Some(Err(_)) => return format!("`{}` is not a number of minutes", args[1]),
+                };
+                match self.alert_manager.start_maintenance(minutes, by).await {
+                    Ok(status) => format!("Maintenance started until {}, new alerts and reminders are held",
+                        timefmt::slack(status.until, "{time}", "%H:%M")),
+                    Err(e) => e,
+                }
+            }
+            Some("off") => match self.alert_manager.end_maintenance(by).await {
+                Ok(()) => "Maintenance ended, alerts are sent again".to_string(),
+                Err(e) => e,
+            },
+            Some(_) => "Usage: `maintenance [on [minutes]|off]`".to_string(),
+        }
+    }
+
     async fn restart_stream(&self, stream_name: &str) -> String {
         match self.audio_router.restart_stream(stream_name).await {
             Ok(_) => format!("Successfully restarted stream `{}`", stream_name),
@@ -0,0 +1,223 @@
+use std::f32::consts::PI;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::time::{timeout, Duration};
+use tracing::{debug, info};
+
+// rtl_tcp runtime commands: one command byte followed by a big-endian u32 parameter
+const RTL_TCP_SET_FREQUENCY: u8 = 0x01;
+const RTL_TCP_SET_SAMPLE_RATE: u8 = 0x02;
+const RTL_TCP_SET_GAIN_MODE: u8 = 0x03;
+const RTL_TCP_SET_GAIN: u8 = 0x04;
+
+const SCAN_SAMPLE_RATE: u32 = 2_048_000;
+const USABLE_BANDWIDTH: f32 = 0.8; // dongle filters roll off near the band edges, like rtl_power's crop
+const FFT_SIZE: usize = 256; // 8 kHz bins at the scan sample rate
+const FRAMES_PER_HOP: usize = 256;
+const SETTLE_BYTES: usize = 2 * 131_072; // stale samples from the previous hop still in rtl_tcp's buffers
+const READ_TIMEOUT: Duration = Duration::from_secs(5);
+const PEAK_ABOVE_FLOOR_DB: f32 = 10.0;
+const CLIP_WARNING_DBFS: f32 = -3.0;
+
+/// Average power of one frequency bin
+#[derive(Debug, Clone, Copy)]
+pub struct PowerBin {
+    pub frequency: u32, // Hz, bin center
+    pub power_db: f32, // relative to full scale
+}
+
+/// Sweeps an rtl_tcp server from `from` to `to` Hz, hopping the tuner and averaging FFT frames at each stop
+pub async fn sweep(host: &str, port: u16, from: u32, to: u32, gain: Option<f32>) -> Result<Vec<PowerBin>, String> {
+    if from >= to {
+        return Err(format!("Start frequency {} Hz must be below end frequency {} Hz", from, to));
+    }
+    let address = format!("{}:{}", host, port);
+    let mut stream = TcpStream::connect(&address).await
+        .map_err(|e| format!("Could not connect to rtl_tcp at {}: {}", address, e))?;
+
+    // rtl_tcp greets with "RTL0", the tuner type and its gain count
+    let mut header = [0u8; 12];
+    read_exact(&mut stream, &mut header).await?;
+    if header[..4] != *b"RTL0" {
+        return Err(format!("{} did not answer like an rtl_tcp server", address));
+    }
+
+    send_command(&mut stream, RTL_TCP_SET_SAMPLE_RATE, SCAN_SAMPLE_RATE).await?;
+    match gain {
+        Some(gain) => {
+            send_command(&mut stream, RTL_TCP_SET_GAIN_MODE, 1).await?;
+            send_command(&mut stream, RTL_TCP_SET_GAIN, (gain * 10.0).round() as i32 as u32).await?;
+        }
+        None => send_command(&mut stream, RTL_TCP_SET_GAIN_MODE, 0).await?,
+    }
+
+    let bin_width = SCAN_SAMPLE_RATE / FFT_SIZE as u32;
+    let kept_bins = (FFT_SIZE as f32 * USABLE_BANDWIDTH) as usize;
+    let first_kept = (FFT_SIZE - kept_bins) / 2;
+    let hop = kept_bins as u32 * bin_width;
+    let window: Vec<f32> = (0..FFT_SIZE)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / FFT_SIZE as f32).cos())
+        .collect();
+    let window_power: f32 = window.iter().map(|w| w * w).sum();
+
+    let hops = (to - from).div_ceil(hop);
+    info!("Sweeping {} from {:.3} to {:.3} MHz in {} hops", address, from as f64 / 1e6, to as f64 / 1e6, hops);
+
+    let mut bins = Vec::new();
+    let mut settle = vec![0u8; SETTLE_BYTES];
+    let mut raw = vec![0u8; FFT_SIZE * 2 * FRAMES_PER_HOP];
+    for hop_index in 0..hops {
+        let center = from + hop_index * hop + hop / 2;
+        send_command(&mut stream, RTL_TCP_SET_FREQUENCY, center).await?;
+        read_exact(&mut stream, &mut settle).await?;
+        read_exact(&mut stream, &mut raw).await?;
+
+        let mut power = [0f32; FFT_SIZE];
+        let mut re = [0f32; FFT_SIZE];
+        let mut im = [0f32; FFT_SIZE];
+        for frame in raw.chunks_exact(FFT_SIZE * 2) {
+            for (i, iq) in frame.chunks_exact(2).enumerate() {
+                re[i] = (iq[0] as f32 - 127.5) / 127.5 * window[i];
+                im[i] = (iq[1] as f32 - 127.5) / 127.5 * window[i];
+            }
+            fft(&mut re, &mut im);
+            for i in 0..FFT_SIZE {
+                power[i] += re[i] * re[i] + im[i] * im[i];
+            }
+        }
+
+        for kept in first_kept..first_kept + kept_bins {
+            // FFT output runs 0..fs/2 then -fs/2..0, shift so bins ascend in frequency
+            let bin = (kept + FFT_SIZE / 2) % FFT_SIZE;
+            let offset = (kept as i64 - FFT_SIZE as i64 / 2) * bin_width as i64;
+            let Some(frequency) = u32::try_from(center as i64 + offset).ok().filter(|f| (from..=to).contains(f)) else { continue };
+            let mean = power[bin] / FRAMES_PER_HOP as f32 / window_power;
+            bins.push(PowerBin { frequency, power_db: 10.0 * mean.max(1e-12).log10() });
+        }
+        debug!("Hop {}/{} at {} Hz done", hop_index + 1, hops, center);
+    }
+
+    Ok(bins)
+}
+
+/// Text report of the sweep averaged into `step` Hz rows, with the noise floor and strongest signals
+pub fn report(bins: &[PowerBin], step: u32) -> String {
+    if bins.is_empty() {
+        return "No samples in range".to_string();
+    }
+    let step = step.max(1);
+    let mut rows: Vec<(u32, f32)> = Vec::new(); // (row start, mean linear power)
+    let mut counts: Vec<usize> = Vec::new();
+    for bin in bins {
+        let row_start = bin.frequency / step * step;
+        let linear = 10f32.powf(bin.power_db / 10.0);
+        match rows.last_mut() {
+            Some((start, sum)) if *start == row_start => {
+                *sum += linear;
+                *counts.last_mut().expect("a count per row") += 1;
+            }
+            _ => {
+                rows.push((row_start, linear));
+                counts.push(1);
+            }
+        }
+    }
+    let rows: Vec<(u32, f32)> = rows.iter().zip(&counts)
+        .map(|((start, sum), count)| (*start, 10.0 * (sum / *count as f32).log10()))
+        .collect();
+
+    let mut sorted: Vec<f32> = rows.iter().map(|(_, db)| *db).collect();
+    sorted.sort_by(|a, b| a.total_cmp(b));
+    let floor = sorted[sorted.len() / 2];
+    let top = sorted[sorted.len() - 1];
+
+    let mut out = String::new();
+    out.push_str(&format!("{:>12}  {:>8}\n", "MHz", "dBFS"));
+    for (start, db) in &rows {
+        let width = if top > floor { ((db - floor) / (top - floor) * 50.0).clamp(0.0, 50.0) as usize } else { 0 };
+        out.push_str(&format!("{:>12.3}  {:>8.1}  {}\n", (*start as f64 + step as f64 / 2.0) / 1e6, db, "#".repeat(width)));
+    }
+
+    let mut peaks: Vec<&(u32, f32)> = rows.iter().filter(|(_, db)| *db > floor + PEAK_ABOVE_FLOOR_DB).collect();
+    peaks.sort_by(|a, b| b.1.total_cmp(&a.1));
+    out.push_str(&format!("\nNoise floor (median): {:.1} dBFS, strongest: {:.1} dBFS\n", floor, top));
+    if peaks.is_empty() {
+        out.push_str(&format!("No signals {:.0} dB above the floor, check the antenna or raise the gain\n", PEAK_ABOVE_FLOOR_DB));
+    } else {
+        out.push_str("Strongest signals:\n");
+        for (start, db) in peaks.iter().take(10) {
+            out.push_str(&format!("  {:.3} MHz  {:.1} dBFS ({:+.1} dB over floor)\n", (*start as f64 + step as f64 / 2.0) / 1e6, db, db - floor));
+        }
+    }
+    if top > CLIP_WARNING_DBFS {
+        out.push_str("Strongest signal is close to full scale, lower the gain to avoid overload\n");
+    }
+    out
+}
+
+/// Parses frequencies written like rtl_power's, e.g. "88M", "96.5M", "433.92M", "1.2G", "500k" or plain Hz
+pub fn parse_frequency(text: &str) -> Result<u32, String> {
+    let text = text.trim();
+    let (number, multiplier) = match text.chars().last() {
+        Some('k' | 'K') => (&text[..text.len() - 1], 1e3),
+        Some('m' | 'M') => (&text[..text.len() - 1], 1e6),
+        Some('g' | 'G') => (&text[..text.len() - 1], 1e9),
+        _ => (text, 1.0),
+    };
+    let value: f64 = number.parse().map_err(|_| format!("`{}` is not a frequency", text))?;
+    let hz = value * multiplier;
+    if !(0.0..=u32::MAX as f64).contains(&hz) {
+        return Err(format!("`{}` is out of range", text));
+    }
+    Ok(hz.round() as u32)
+}
+
+async fn send_command(stream: &mut TcpStream, command: u8, parameter: u32) -> Result<(), String> {
+    let mut message = [0u8; 5];
+    message[0] = command;
+    message[1..].copy_from_slice(&parameter.to_be_bytes());
+    stream.write_all(&message).await.map_err(|e| format!("Could not send command to rtl_tcp: {}", e))
+}
+
+async fn read_exact(stream: &mut TcpStream, buffer: &mut [u8]) -> Result<(), String> {
+    timeout(READ_TIMEOUT, stream.read_exact(buffer)).await
+        .map_err(|_| "Timed out waiting for samples from rtl_tcp, is another client such as a running watchdog connected?".to_string())?
+        .map_err(|e| format!("Could not read from rtl_tcp: {}", e))?;
+    Ok(())
+}
+
+/// In-place radix-2 FFT; the length must be a power of two
+fn fft(re: &mut [f32], im: &mut [f32]) {
+    let n = re.len();
+    let mut j = 0;
+    for i in 1..n {
+        let mut bit = n >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j {
+            re.swap(i, j);
+            im.swap(i, j);
+        }
+    }
+
+    let mut len = 2;
+    while len <= n {
+        let angle = -2.0 * PI / len as f32;
+        for start in (0..n).step_by(len) {
+            for k in 0..len / 2 {
+                let (sin, cos) = (angle * k as f32).sin_cos();
+                let (a, b) = (start + k, start + k + len / 2);
+                let t_re = re[b] * cos - im[b] * sin;
+                let t_im = re[b] * sin + im[b] * cos;
+                re[b] = re[a] - t_re;
+                im[b] = im[a] - t_im;
+                re[a] += t_re;
+                im[a] += t_im;
+            }
+        }
+        len <<= 1;
+    }
+}
@@ -0,0 +1,232 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tokio::net::UdpSocket;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+use super::alertmanager::AlertManager;
+use super::audiorouter::AudioRouter;
+use super::audiostream::AudioStreamHealth;
+use super::commandprocessor::StreamHealth;
+use super::comparator::{ComparisonCounters, ComparisonResult};
+use super::slack::SlackMessageSender;
+use super::delivery::DeliveryStats;
+
+const MAX_DATAGRAM_BYTES: usize = 1432; // stays under a typical MTU
+
+/// statsd/DogStatsD server to emit the core gauges and counters to
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct StatsdConfig {
+    /// host:port of the statsd or Datadog agent
+    #[serde(default = "default_statsd_address")]
+    pub address: String,
+    #[serde(default = "default_statsd_prefix")]
+    pub prefix: String,
+    /// DogStatsD tags added to every metric, e.g. "site:tower-a"
+    #[serde(default)]
+    pub tags: Vec<String>,
+    #[serde(default = "default_statsd_interval")]
+    pub interval_seconds: u64,
+}
+
+fn default_statsd_address() -> String { "127.0.0.1:8125".to_string() }
+fn default_statsd_prefix() -> String { "watchdog".to_string() }
+fn default_statsd_interval() -> u64 { 10 }
+
+pub struct StatsdEmitter {
+    config: StatsdConfig,
+    router: Arc<AudioRouter>,
+    comparison_results: Arc<RwLock<Vec<ComparisonResult>>>,
+    comparison_counters: Option<Arc<ComparisonCounters>>,
+    alert_manager: Option<Arc<AlertManager>>,
+    slack: Option<Arc<SlackMessageSender>>,
+    delivery_stats: Option<Arc<DeliveryStats>>,
+}
+
+impl StatsdEmitter {
+    pub fn new(config: StatsdConfig, router: Arc<AudioRouter>, comparison_results: Arc<RwLock<Vec<ComparisonResult>>>) -> Self {
+        StatsdEmitter {
+            config,
+            router,
+            comparison_results,
+            comparison_counters: None,
+            alert_manager: None,
+            slack: None,
+            delivery_stats: None,
+        }
+    }
+
+    pub fn with_comparison_counters(mut self, comparison_counters: Arc<ComparisonCounters>) -> Self {
+        self.comparison_counters = Some(comparison_counters);
+        self
+    }
+
+    pub fn with_alert_manager(mut self, alert_manager: Arc<AlertManager>) -> Self {
+        self.alert_manager = Some(alert_manager);
+        self
+    }
+
+    pub fn with_slack_sender(mut self, slack: Arc<SlackMessageSender>) -> Self {
+        self.slack = Some(slack);
+        self
+    }
+
+    pub fn with_delivery_stats(mut self, delivery_stats: Arc<DeliveryStats>) -> Self {
+        self.delivery_stats = Some(delivery_stats);
+        self
+    }
+
+    pub async fn start_emit_loop(self: Arc<Self>) {
+        info!("Starting statsd emitter to {} (prefix: {}, interval: {}s)", self.config.address, self.config.prefix, self.config.interval_seconds);
+
+        tokio::spawn(async move {
+            let socket = match UdpSocket::bind("0.0.0.0:0").await {
+                Ok(socket) => socket,
+                Err(e) => {
+                    warn!("Could not open a UDP socket for statsd: {}", e);
+                    return;
+                }
+            };
+            // statsd counters are increments, so remember the totals already sent
+            let mut last_totals: HashMap<String, u64> = HashMap::new();
+
+            loop {
+                tokio::time::sleep(Duration::from_secs(self.config.interval_seconds.max(1))).await;
+
+                let lines = self.collect(&mut last_totals).await;
+                for datagram in pack(&lines) {
+                    if let Err(e) = socket.send_to(datagram.as_bytes(), self.config.address.as_str()).await {
+                        warn!("Could not send metrics to statsd at {}: {}", self.config.address, e);
+                        break;
+                    }
+                }
+                debug!("Sent {} statsd metrics", lines.len());
+            }
+        });
+    }
+
+    async fn collect(&self, last_totals: &mut HashMap<String, u64>) -> Vec<String> {
+        let mut lines = Vec::new();
+        let volumes = self.router.get_all_stream_volumes().await;
+
+        for channel_name in self.router.get_all_channels() {
+            let Some(stream_names) = self.router.get_channel_streams(&channel_name) else { continue };
+            for stream_name in stream_names {
+                let Some((cmd_health, audio_health)) = self.router.get_stream_health(&stream_name).await else { continue };
+                let tags = [format!("stream:{}", stream_name), format!("channel:{}", channel_name)];
+
+                let health = match cmd_health {
+                    StreamHealth::Running => 2,
+                    StreamHealth::Stalled => 1,
+                    StreamHealth::Dead => 0,
+                };
+                lines.push(self.line("stream.health", health as f64, "g", &tags));
+                let audio = match audio_health {
+                    AudioStreamHealth::Running => 3,
+                    AudioStreamHealth::Degraded => 2,
+                    AudioStreamHealth::NoData => 1,
+                    AudioStreamHealth::Dead => 0,
+                };
+                lines.push(self.line("audio.health", audio as f64, "g", &tags));
+                if let Some(uptime) = self.router.get_stream_uptime(&stream_name).await {
+                    lines.push(self.line("stream.uptime_seconds", uptime.num_seconds() as f64, "g", &tags));
+                }
+                if let Some(volume) = volumes.get(&stream_name) {
+                    lines.push(self.line("volume.mean_db", volume.mean_volume as f64, "g", &tags));
+                    lines.push(self.line("volume.max_db", volume.max_volume as f64, "g", &tags));
+                }
+                if let Some(respawns) = self.router.get_stream_respawns(&stream_name).await {
+                    lines.push(self.counter("process.respawns", respawns, &tags, last_totals));
+                }
+            }
+        }
+
+        for result in self.comparison_results.read().await.iter() {
+            let comparison_type = result.comparison_type();
+            let tags = [
+                format!("stream1:{}", result.stream1),
+                format!("stream2:{}", result.stream2),
+                format!("comparison_type:{}", comparison_type),
+            ];
+            lines.push(self.line("comparison.similarity_percent", result.similarity_percent as f64, "g", &tags));
+            lines.push(self.line("comparison.is_error", if result.is_error { 1.0 } else { 0.0 }, "g", &tags));
+        }
+
+        if let Some(ref counters) = self.comparison_counters {
+            for (comparison_type, result, count) in counters.snapshot() {
+                let tags = [format!("comparison_type:{}", comparison_type), format!("result:{}", result)];
+                lines.push(self.counter("comparisons", count, &tags, last_totals));
+            }
+            let (computed, cached) = counters.get_match_counts();
+            lines.push(self.counter("fingerprint_matches", computed, &["result:computed".to_string()], last_totals));
+            lines.push(self.counter("fingerprint_matches", cached, &["result:cached".to_string()], last_totals));
+            let (fast, disagreements) = counters.get_fast_match_counts();
+            lines.push(self.counter("fast_matches", fast, &[], last_totals));
+            lines.push(self.counter("fast_match_disagreements", disagreements, &[], last_totals));
+        }
+
+        if let Some(ref am) = self.alert_manager {
+            lines.push(self.line("alerts.failing", am.get_active_alerts().await.len() as f64, "g", &[]));
+            for (reason, count) in am.get_fired_counts() {
+                lines.push(self.counter("alerts.fired", count, &[format!("reason:{}", reason.as_str())], last_totals));
+            }
+        }
+
+        if let Some(ref slack) = self.slack {
+            let (sent, failed) = slack.get_counts();
+            lines.push(self.counter("slack.messages_sent", sent, &["result:ok".to_string()], last_totals));
+            lines.push(self.counter("slack.messages_sent", failed, &["result:error".to_string()], last_totals));
+        }
+
+        if let Some(ref delivery_stats) = self.delivery_stats {
+            for (sink, counters) in delivery_stats.get_sinks() {
+                let sink_tag = format!("sink:{}", sink);
+                lines.push(self.counter("notifications.deliveries", counters.get_delivered(), &[sink_tag.clone(), "result:ok".to_string()], last_totals));
+                lines.push(self.counter("notifications.deliveries", counters.get_failed(), &[sink_tag.clone(), "result:error".to_string()], last_totals));
+                lines.push(self.counter("notifications.retries", counters.get_retries(), &[sink_tag.clone()], last_totals));
+                lines.push(self.line("notifications.consecutive_failures", counters.get_consecutive_failures() as f64, "g", &[sink_tag]));
+            }
+        }
+
+        lines
+    }
+
+    /// Counter line for the increase since the last emission of the same metric and tags
+    fn counter(&self, name: &str, total: u64, tags: &[String], last_totals: &mut HashMap<String, u64>) -> String {
+        let key = format!("{}|{}", name, tags.join(","));
+        let previous = last_totals.insert(key, total).unwrap_or(0);
+        self.line(name, total.saturating_sub(previous) as f64, "c", tags)
+    }
+
+    fn line(&self, name: &str, value: f64, kind: &str, tags: &[String]) -> String {
+        let mut line = format!("{}.{}:{}|{}", self.config.prefix, name, value, kind);
+        let all_tags: Vec<&str> = self.config.tags.iter().map(|t| t.as_str()).chain(tags.iter().map(|t| t.as_str())).collect();
+        if !all_tags.is_empty() {
+            line.push_str("|#");
+            line.push_str(&all_tags.join(","));
+        }
+        line
+    }
+}
+
+/// Joins metric lines into newline-separated datagrams small enough to arrive unfragmented
+fn pack(lines: &[String]) -> Vec<String> {
+    let mut datagrams = Vec::new();
+    let mut current = String::new();
+    for line in lines {
+        if !current.is_empty() && current.len() + 1 + line.len() > MAX_DATAGRAM_BYTES {
+            datagrams.push(std::mem::take(&mut current));
+        }
+        if !current.is_empty() {
+            current.push('\n');
+        }
+        current.push_str(line);
+    }
+    if !current.is_empty() {
+        datagrams.push(current);
+    }
+    datagrams
+}
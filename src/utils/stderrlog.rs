@@ -0,0 +1,80 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+
+// Enough context to debug a failing child remotely without keeping whole logs around
+const STDERR_LOG_LINES: usize = 50;
+// Past this many distinct messages, ones not logged within the window are forgotten
+const LIMITER_MAX_MESSAGES: usize = 256;
+
+/// Ring buffer of the most recent raw stderr lines from a child process
+#[derive(Debug, Clone)]
+pub struct StderrLog {
+    lines: Arc<Mutex<VecDeque<String>>>,
+}
+
+impl StderrLog {
+    pub fn new() -> Self {
+        StderrLog {
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(STDERR_LOG_LINES))),
+        }
+    }
+
+    pub async fn push(&self, line: &str) {
+        let mut lines = self.lines.lock().await;
+        if lines.len() >= STDERR_LOG_LINES {
+            lines.pop_front();
+        }
+        lines.push_back(line.to_string());
+    }
+
+    /// Buffered lines, oldest first
+    pub async fn get_lines(&self) -> Vec<String> {
+        self.lines.lock().await.iter().cloned().collect()
+    }
+}
+
+/// `line` with its numbers blanked, so lines differing only in timestamps, offsets or counters are one message
+pub fn message_key(line: &str) -> String {
+    line.chars().map(|c| if c.is_ascii_digit() { '#' } else { c }).collect()
+}
+
+struct LoggedMessage {
+    logged_at: Instant,
+    suppressed: u64,
+}
+
+/// Rate limit per message rather than per previous line, so spam alternating between a few messages
+/// is held back as well as a line repeated back to back
+pub struct StderrLimiter {
+    window: Duration,
+    messages: HashMap<String, LoggedMessage>,
+}
+
+impl StderrLimiter {
+    pub fn new(window: Duration) -> Self {
+        StderrLimiter { window, messages: HashMap::new() }
+    }
+
+    /// Whether `line` should be logged now, with how many copies of its message were held back since it
+    /// last was; `None` while its message was logged within the window
+    pub fn admit(&mut self, line: &str) -> Option<u64> {
+        let key = message_key(line);
+        if let Some(message) = self.messages.get_mut(&key) {
+            if message.logged_at.elapsed() < self.window {
+                message.suppressed += 1;
+                return None;
+            }
+            let suppressed = message.suppressed;
+            *message = LoggedMessage { logged_at: Instant::now(), suppressed: 0 };
+            return Some(suppressed);
+        }
+        if self.messages.len() >= LIMITER_MAX_MESSAGES {
+            let window = self.window;
+            self.messages.retain(|_, message| message.logged_at.elapsed() < window);
+        }
+        self.messages.insert(key, LoggedMessage { logged_at: Instant::now(), suppressed: 0 });
+        Some(0)
+    }
+}
@@ -0,0 +1,96 @@
+use chrono::NaiveDateTime;
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use super::tuning::{ThresholdKind, ThresholdSet};
+use super::weekly::WeeklyWindow;
+
+/// Thresholds and silence expectations for part of the week, e.g. quieter overnight automation or
+/// Sunday morning dead air
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ThresholdSchedule {
+    /// shown in alerts and logs, the time window when unset
+    pub name: Option<String>,
+    /// days, start and end
+    #[serde(flatten)]
+    pub window: WeeklyWindow,
+    /// channels it applies to, every channel if unset
+    pub channels: Option<Vec<String>>,
+    pub match_threshold: Option<f32>,
+    pub divergence_threshold: Option<f32>,
+    /// max volume (dB) below which a stream is silent
+    pub silence_threshold: Option<f32>,
+    /// dead air is expected, silent streams aren't flagged
+    #[serde(default)]
+    pub silence_expected: bool,
+}
+
+impl ThresholdSchedule {
+    pub fn label(&self) -> String {
+        match self.name {
+            Some(ref name) => name.clone(),
+            None => self.window.describe(),
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.window.validate().map_err(|e| format!("Threshold schedule {}: {}", self.label(), e))?;
+        let thresholds = [
+            (ThresholdKind::Match, self.match_threshold),
+            (ThresholdKind::Divergence, self.divergence_threshold),
+            (ThresholdKind::Silence, self.silence_threshold),
+        ];
+        for (kind, value) in thresholds {
+            if let Some(value) = value {
+                kind.check(value).map_err(|e| format!("Threshold schedule {}: {}", self.label(), e))?;
+            }
+        }
+        Ok(())
+    }
+
+    fn covers(&self, channel_name: &str) -> bool {
+        self.channels.as_ref().map_or(true, |channels| channels.iter().any(|c| c == channel_name))
+    }
+
+    fn thresholds(&self) -> ThresholdSet {
+        ThresholdSet {
+            match_threshold: self.match_threshold,
+            divergence_threshold: self.divergence_threshold,
+            silence_threshold: self.silence_threshold,
+        }
+    }
+}
+
+/// The schedules in effect at one moment. Like runtime tuning, a schedule naming its channels
+/// applies at the channel level and one without at the global level; the first listed wins
+#[derive(Debug, Clone, Default)]
+pub struct ActiveSchedules {
+    schedules: Vec<ThresholdSchedule>,
+}
+
+impl ActiveSchedules {
+    pub fn at(schedules: &[ThresholdSchedule], now: NaiveDateTime) -> Self {
+        ActiveSchedules {
+            schedules: schedules.iter().filter(|schedule| schedule.window.is_active(now)).cloned().collect(),
+        }
+    }
+
+    pub fn global(&self, kind: ThresholdKind) -> Option<f32> {
+        self.schedules.iter()
+            .filter(|schedule| schedule.channels.is_none())
+            .find_map(|schedule| schedule.thresholds().get(kind))
+    }
+
+    pub fn channel(&self, kind: ThresholdKind, channel_name: &str) -> Option<f32> {
+        self.schedules.iter()
+            .filter(|schedule| schedule.channels.is_some() && schedule.covers(channel_name))
+            .find_map(|schedule| schedule.thresholds().get(kind))
+    }
+
+    /// The label of the schedule expecting dead air on `channel_name` right now, if any
+    pub fn silence_expected(&self, channel_name: &str) -> Option<String> {
+        self.schedules.iter()
+            .find(|schedule| schedule.silence_expected && schedule.covers(channel_name))
+            .map(|schedule| schedule.label())
+    }
+}
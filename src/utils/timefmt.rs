@@ -0,0 +1,29 @@
+use std::sync::OnceLock;
+use chrono::{DateTime, Local, Utc};
+use chrono_tz::Tz;
+
+// Unset keeps the system timezone, which reports, recordings and the dead air day already follow
+static DISPLAY_TIMEZONE: OnceLock<Option<Tz>> = OnceLock::new();
+
+/// Sets the timezone timestamps are shown in, by IANA name like "America/New_York"; call once at startup
+pub fn set_display_timezone(name: Option<&str>) -> Result<(), String> {
+    let tz = match name {
+        Some(name) => Some(name.parse::<Tz>().map_err(|e| format!("Unknown display_timezone {}: {}", name, e))?),
+        None => None,
+    };
+    DISPLAY_TIMEZONE.set(tz).map_err(|_| "Display timezone is already set".to_string())
+}
+
+/// `time` formatted in the display timezone and followed by the zone, so nobody has to guess
+pub fn display(time: DateTime<Utc>, format: &str) -> String {
+    let format = format!("{} %Z", format);
+    match DISPLAY_TIMEZONE.get().copied().flatten() {
+        Some(tz) => time.with_timezone(&tz).format(&format).to_string(),
+        None => time.with_timezone(&Local).format(&format).to_string(),
+    }
+}
+
+/// Slack date token, shown in each reader's own timezone; `token` is Slack's, e.g. "{date_short_pretty} {time_secs}"
+pub fn slack(time: DateTime<Utc>, token: &str, fallback_format: &str) -> String {
+    format!("<!date^{}^{}|{}>", time.timestamp(), token, display(time, fallback_format))
+}
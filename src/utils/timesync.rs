@@ -0,0 +1,155 @@
+use std::sync::Arc;
+use std::time::Duration;
+use chrono::{DateTime, TimeZone, Utc};
+use schemars::JsonSchema;
+use serde::Deserialize;
+use tokio::net::UdpSocket;
+use tokio::sync::RwLock;
+use tracing::{debug, info, warn};
+
+use super::alertmanager::AlertManager;
+use watchdog::api::AlertReason;
+
+const NTP_UNIX_EPOCH_OFFSET: i64 = 2_208_988_800; // seconds from 1900-01-01 to 1970-01-01
+const NTP_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Periodic SNTP query comparing the system clock against a time server
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct TimeSyncConfig {
+    #[serde(default = "default_time_sync_enabled")]
+    pub enabled: bool,
+    /// host:port of the NTP server
+    #[serde(default = "default_ntp_server")]
+    pub server: String,
+    #[serde(default = "default_time_sync_interval")]
+    pub interval_seconds: u64,
+    /// Skew beyond this breaks grace periods, uptimes and offsets enough to warn
+    #[serde(default = "default_max_offset_ms")]
+    pub max_offset_ms: i64,
+}
+
+fn default_time_sync_enabled() -> bool { true }
+fn default_ntp_server() -> String { "pool.ntp.org:123".to_string() }
+fn default_time_sync_interval() -> u64 { 3600 }
+fn default_max_offset_ms() -> i64 { 1000 }
+
+impl Default for TimeSyncConfig {
+    fn default() -> Self {
+        TimeSyncConfig {
+            enabled: default_time_sync_enabled(),
+            server: default_ntp_server(),
+            interval_seconds: default_time_sync_interval(),
+            max_offset_ms: default_max_offset_ms(),
+        }
+    }
+}
+
+/// Last measured difference between the NTP server and the system clock
+#[derive(Debug, Clone, Copy)]
+pub struct ClockOffset {
+    pub offset_ms: i64, // positive when the system clock is behind
+    pub checked_at: DateTime<Utc>,
+}
+
+pub struct TimeSyncChecker {
+    config: TimeSyncConfig,
+    offset: Arc<RwLock<Option<ClockOffset>>>,
+    alert_manager: Option<Arc<AlertManager>>,
+}
+
+impl TimeSyncChecker {
+    pub fn new(config: TimeSyncConfig) -> Self {
+        TimeSyncChecker {
+            config,
+            offset: Arc::new(RwLock::new(None)),
+            alert_manager: None,
+        }
+    }
+
+    pub fn with_alert_manager(mut self, alert_manager: Arc<AlertManager>) -> Self {
+        self.alert_manager = Some(alert_manager);
+        self
+    }
+
+    /// Latest offset, only when it is beyond the configured threshold
+    pub async fn get_skew(&self) -> Option<ClockOffset> {
+        let offset = *self.offset.read().await;
+        offset.filter(|o| o.offset_ms.abs() > self.config.max_offset_ms)
+    }
+
+    pub async fn start_check_loop(self: Arc<Self>) {
+        info!("Starting clock check against {} (interval: {}s, max offset: {}ms)",
+            self.config.server, self.config.interval_seconds, self.config.max_offset_ms);
+
+        tokio::spawn(async move {
+            loop {
+                match query_offset(&self.config.server).await {
+                    Ok(offset) => {
+                        let offset_ms = offset.num_milliseconds();
+                        let is_error = offset_ms.abs() > self.config.max_offset_ms;
+                        if is_error {
+                            warn!("System clock is off by {}ms from {}", offset_ms, self.config.server);
+                        } else {
+                            debug!("System clock is within {}ms of {}", offset_ms, self.config.server);
+                        }
+                        *self.offset.write().await = Some(ClockOffset { offset_ms, checked_at: Utc::now() });
+
+                        if let Some(ref am) = self.alert_manager {
+                            let message = if is_error {
+                                format!("System clock is off by {:.1}s from {} (max {:.1}s), grace periods, uptimes and offsets may be wrong",
+                                    offset_ms as f64 / 1000.0, self.config.server, self.config.max_offset_ms as f64 / 1000.0)
+                            } else {
+                                format!("System clock is back within {:.1}s of {}", offset_ms as f64 / 1000.0, self.config.server)
+                            };
+                            am.update_alert("clock_skew".to_string(), AlertReason::ClockSkew, &[], is_error, message).await;
+                        }
+                    }
+                    // An unreachable time server says nothing about the clock, keep the last result
+                    Err(e) => warn!("Could not check the system clock against {}: {}", self.config.server, e),
+                }
+
+                tokio::time::sleep(Duration::from_secs(self.config.interval_seconds)).await;
+            }
+        });
+    }
+}
+
+/// Sends one SNTP request and returns how far the server's clock is ahead of ours
+async fn query_offset(server: &str) -> Result<chrono::Duration, String> {
+    let addr = tokio::net::lookup_host(server).await
+        .map_err(|e| format!("could not resolve: {}", e))?
+        .next()
+        .ok_or("no addresses")?;
+    let bind = if addr.is_ipv6() { "[::]:0" } else { "0.0.0.0:0" };
+    let socket = UdpSocket::bind(bind).await.map_err(|e| format!("could not bind: {}", e))?;
+    socket.connect(addr).await.map_err(|e| format!("could not connect: {}", e))?;
+
+    let mut request = [0u8; 48];
+    request[0] = 0x23; // leap indicator 0, version 4, client mode
+    let sent_at = Utc::now();
+    socket.send(&request).await.map_err(|e| format!("could not send: {}", e))?;
+
+    let mut response = [0u8; 48];
+    let received = tokio::time::timeout(NTP_TIMEOUT, socket.recv(&mut response)).await
+        .map_err(|_| "timed out".to_string())?
+        .map_err(|e| format!("could not receive: {}", e))?;
+    let received_at = Utc::now();
+
+    if received < response.len() || response[0] & 0x07 != 4 {
+        return Err("not an NTP server response".to_string());
+    }
+    if response[1] == 0 {
+        return Err("server sent a kiss-of-death packet".to_string());
+    }
+    let server_received = ntp_timestamp(&response[32..40]).ok_or("bad receive timestamp")?;
+    let server_sent = ntp_timestamp(&response[40..48]).ok_or("bad transmit timestamp")?;
+
+    Ok(((server_received - sent_at) + (server_sent - received_at)) / 2)
+}
+
+fn ntp_timestamp(bytes: &[u8]) -> Option<DateTime<Utc>> {
+    let seconds = u32::from_be_bytes(bytes[0..4].try_into().ok()?) as i64 - NTP_UNIX_EPOCH_OFFSET;
+    let fraction = u32::from_be_bytes(bytes[4..8].try_into().ok()?) as u64;
+    let nanos = (fraction * 1_000_000_000) >> 32;
+    Utc.timestamp_opt(seconds, nanos as u32).single()
+}
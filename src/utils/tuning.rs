@@ -0,0 +1,172 @@
+use std::collections::BTreeMap;
+use std::str::FromStr;
+use std::sync::Arc;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use super::audiorouter::AudioRouter;
+use super::overrides::OverridesStore;
+
+/// A threshold that can be changed while the watchdog runs
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThresholdKind {
+    Match, // % of the window streams of one channel must match
+    Divergence, // % of the window streams of different channels may match
+    Silence, // max volume (dB) below which a stream is silent
+}
+
+impl FromStr for ThresholdKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "match" => Ok(ThresholdKind::Match),
+            "divergence" => Ok(ThresholdKind::Divergence),
+            "silence" => Ok(ThresholdKind::Silence),
+            _ => Err(format!("Unknown threshold `{}`, use match, divergence or silence", s)),
+        }
+    }
+}
+
+impl ThresholdKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ThresholdKind::Match => "match",
+            ThresholdKind::Divergence => "divergence",
+            ThresholdKind::Silence => "silence",
+        }
+    }
+
+    pub fn check(&self, value: f32) -> Result<(), String> {
+        match self {
+            ThresholdKind::Match | ThresholdKind::Divergence if !(0.0..=100.0).contains(&value) => {
+                Err(format!("The {} threshold is a percentage between 0 and 100", self.as_str()))
+            }
+            ThresholdKind::Silence if !value.is_finite() || value > 0.0 => {
+                Err("The silence threshold is a level in dB, 0 or below".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn display(&self, value: f32) -> String {
+        match self {
+            ThresholdKind::Silence => format!("{:.1} dB", value),
+            _ => format!("{:.1}%", value),
+        }
+    }
+}
+
+/// Thresholds of one scope set at runtime, each replacing the configured one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ThresholdSet {
+    pub match_threshold: Option<f32>,
+    pub divergence_threshold: Option<f32>,
+    pub silence_threshold: Option<f32>,
+}
+
+impl ThresholdSet {
+    pub fn get(&self, kind: ThresholdKind) -> Option<f32> {
+        match kind {
+            ThresholdKind::Match => self.match_threshold,
+            ThresholdKind::Divergence => self.divergence_threshold,
+            ThresholdKind::Silence => self.silence_threshold,
+        }
+    }
+
+    fn set(&mut self, kind: ThresholdKind, value: f32) {
+        match kind {
+            ThresholdKind::Match => self.match_threshold = Some(value),
+            ThresholdKind::Divergence => self.divergence_threshold = Some(value),
+            ThresholdKind::Silence => self.silence_threshold = Some(value),
+        }
+    }
+}
+
+/// Thresholds set at runtime, globally or per channel. They win over the config's, so a config reload
+/// doesn't undo tuning done during an incident; a channel's wins over the global one
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct TunedThresholds {
+    #[serde(default)]
+    pub global: ThresholdSet,
+    #[serde(default)]
+    pub channels: BTreeMap<String, ThresholdSet>,
+}
+
+impl TunedThresholds {
+    pub fn global(&self, kind: ThresholdKind) -> Option<f32> {
+        self.global.get(kind)
+    }
+
+    pub fn channel(&self, kind: ThresholdKind, channel_name: &str) -> Option<f32> {
+        self.channels.get(channel_name).and_then(|set| set.get(kind))
+    }
+
+    pub fn set(&mut self, kind: ThresholdKind, value: f32, channel_name: Option<&str>) {
+        match channel_name {
+            Some(channel_name) => self.channels.entry(channel_name.to_string()).or_default().set(kind, value),
+            None => self.global.set(kind, value),
+        }
+    }
+}
+
+/// Body of `POST /api/thresholds`, the channel is left out to set the threshold of all channels
+#[derive(Debug, Clone, Deserialize)]
+pub struct ThresholdRequest {
+    pub kind: ThresholdKind,
+    pub value: f32,
+    pub channel: Option<String>,
+}
+
+/// Changes thresholds live from Slack or the API, saved with the other runtime overrides so they outlive restarts
+pub struct ThresholdTuner {
+    router: Arc<AudioRouter>,
+    store: Option<Arc<OverridesStore>>,
+}
+
+impl ThresholdTuner {
+    pub fn new(router: Arc<AudioRouter>) -> Self {
+        ThresholdTuner { router, store: None }
+    }
+
+    pub fn with_store(mut self, store: Arc<OverridesStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+
+    pub fn get(&self) -> TunedThresholds {
+        self.router.get_tuned_thresholds()
+    }
+
+    /// Applies `value` from the next comparison or volume pass on, for one channel or every one, and saves it
+    pub async fn set(&self, kind: ThresholdKind, value: f32, channel_name: Option<&str>) -> Result<String, String> {
+        kind.check(value)?;
+        if let Some(channel_name) = channel_name {
+            if self.router.get_channel_streams(channel_name).is_none() {
+                return Err(format!("Channel `{}` not found", channel_name));
+            }
+        }
+        if kind == ThresholdKind::Silence && !self.router.has_silence_threshold() {
+            return Err("Volume silence alerts are not enabled, silence is detected by comparing against the reference channel".to_string());
+        }
+
+        self.router.tune_threshold(kind, value, channel_name);
+        let scope = match channel_name {
+            Some(channel_name) => format!("channel `{}`", channel_name),
+            None => "all channels".to_string(),
+        };
+        let applied = format!("Set the {} threshold of {} to {}", kind.as_str(), scope, kind.display(value));
+        info!("{}", applied);
+        let Some(ref store) = self.store else {
+            return Ok(format!("{}, until the watchdog restarts", applied));
+        };
+        match store.save().await {
+            Ok(()) => Ok(applied),
+            Err(e) => {
+                warn!("{}", e);
+                Ok(format!("{}, but it could not be saved and is lost on restart: {}", applied, e))
+            }
+        }
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use rusqlite::{params, Connection};
+use tracing::{debug, info, warn};
+
+// A watchdog restart shorter than this keeps streams' uptime running; longer gaps are unknown and reset it
+const CONTINUITY_GAP_SECONDS: i64 = 300;
+const FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+/// A stream's statistics as kept across watchdog restarts
+#[derive(Debug, Clone, Copy)]
+pub struct PersistedStats {
+    pub up_since: Option<DateTime<Utc>>, // start of the current healthy run, None while down
+    pub cumulative_uptime_seconds: i64,
+    pub restarts: u64,
+}
+
+struct StreamRecord {
+    stats: PersistedStats,
+    last_state: String,
+    last_seen: DateTime<Utc>,
+    respawns_seen: u64, // respawn count of this process's command at the last record, it starts over at 0
+    dirty: bool,
+}
+
+/// Per-stream uptime, restart counts and last known state in SQLite, so uptime survives restarts of the watchdog itself
+pub struct UptimeStore {
+    path: String,
+    connection: Arc<Mutex<Connection>>,
+    records: Mutex<HashMap<String, StreamRecord>>,
+}
+
+impl UptimeStore {
+    pub fn open(path: &str) -> Result<Self, String> {
+        let connection = Connection::open(path).map_err(|e| format!("Could not open stats database {}: {}", path, e))?;
+        connection.execute_batch(
+            "CREATE TABLE IF NOT EXISTS stream_stats (
+                stream TEXT PRIMARY KEY,
+                up_since TEXT,
+                cumulative_uptime_seconds INTEGER NOT NULL,
+                restarts INTEGER NOT NULL,
+                last_state TEXT NOT NULL,
+                last_seen TEXT NOT NULL
+            )"
+        ).map_err(|e| format!("Could not create stats table in {}: {}", path, e))?;
+
+        let now = Utc::now();
+        let mut records = HashMap::new();
+        {
+            let mut statement = connection.prepare(
+                "SELECT stream, up_since, cumulative_uptime_seconds, restarts, last_state, last_seen FROM stream_stats"
+            ).map_err(|e| format!("Could not read stats from {}: {}", path, e))?;
+            let rows = statement.query_map([], |row| {
+                Ok((
+                    row.get::<_, String>(0)?,
+                    row.get::<_, Option<String>>(1)?,
+                    row.get::<_, i64>(2)?,
+                    row.get::<_, i64>(3)?,
+                    row.get::<_, String>(4)?,
+                    row.get::<_, String>(5)?,
+                ))
+            }).map_err(|e| format!("Could not read stats from {}: {}", path, e))?;
+
+            for row in rows.flatten() {
+                let (stream, up_since, cumulative_uptime_seconds, restarts, last_state, last_seen) = row;
+                let Some(last_seen) = parse_time(&last_seen) else { continue };
+                let continuous = (now - last_seen).num_seconds() <= CONTINUITY_GAP_SECONDS;
+                let up_since = up_since.as_deref().and_then(parse_time).filter(|_| continuous);
+                records.insert(stream, StreamRecord {
+                    stats: PersistedStats { up_since, cumulative_uptime_seconds, restarts: restarts as u64 },
+                    last_state,
+                    last_seen,
+                    respawns_seen: 0,
+                    dirty: false,
+                });
+            }
+        }
+        info!("Loaded stats of {} streams from {}", records.len(), path);
+
+        Ok(UptimeStore {
+            path: path.to_string(),
+            connection: Arc::new(Mutex::new(connection)),
+            records: Mutex::new(records),
+        })
+    }
+
+    /// Called on every supervisor pass; `respawns` is the command's respawn count since this process started
+    pub fn record(&self, stream: &str, healthy: bool, state: &str, respawns: u64) {
+        let now = Utc::now();
+        let mut records = self.records.lock().expect("uptime records lock poisoned");
+        let record = records.entry(stream.to_string()).or_insert_with(|| StreamRecord {
+            stats: PersistedStats { up_since: None, cumulative_uptime_seconds: 0, restarts: 0 },
+            last_state: String::new(),
+            last_seen: now,
+            respawns_seen: 0,
+            dirty: true,
+        });
+
+        let elapsed = (now - record.last_seen).num_seconds().clamp(0, CONTINUITY_GAP_SECONDS);
+        match (healthy, record.stats.up_since) {
+            (true, Some(_)) => record.stats.cumulative_uptime_seconds += elapsed,
+            (true, None) => record.stats.up_since = Some(now),
+            (false, _) => record.stats.up_since = None,
+        }
+        record.stats.restarts += respawns.saturating_sub(record.respawns_seen);
+        record.respawns_seen = respawns;
+        record.last_state = state.to_string();
+        record.last_seen = now;
+        record.dirty = true;
+    }
+
+    pub fn get(&self, stream: &str) -> Option<PersistedStats> {
+        self.records.lock().expect("uptime records lock poisoned").get(stream).map(|r| r.stats)
+    }
+
+    pub async fn start_flush_loop(self: Arc<Self>) {
+        info!("Persisting stream uptime and restarts to {} every {}s", self.path, FLUSH_INTERVAL.as_secs());
+
+        tokio::spawn(async move {
+            loop {
+                tokio::time::sleep(FLUSH_INTERVAL).await;
+
+                let rows: Vec<(String, Option<String>, i64, i64, String, String)> = {
+                    let mut records = self.records.lock().expect("uptime records lock poisoned");
+                    records.iter_mut()
+                        .filter(|(_, r)| r.dirty)
+                        .map(|(stream, r)| {
+                            r.dirty = false;
+                            (stream.clone(), r.stats.up_since.map(|t| t.to_rfc3339()), r.stats.cumulative_uptime_seconds,
+                                r.stats.restarts as i64, r.last_state.clone(), r.last_seen.to_rfc3339())
+                        })
+                        .collect()
+                };
+                if rows.is_empty() {
+                    continue;
+                }
+
+                let connection = self.connection.clone();
+                let written = tokio::task::spawn_blocking(move || -> Result<usize, rusqlite::Error> {
+                    let mut connection = connection.lock().expect("stats database lock poisoned");
+                    let transaction = connection.transaction()?;
+                    for row in &rows {
+                        transaction.execute(
+                            "INSERT INTO stream_stats (stream, up_since, cumulative_uptime_seconds, restarts, last_state, last_seen)
+                             VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+                             ON CONFLICT(stream) DO UPDATE SET up_since = ?2, cumulative_uptime_seconds = ?3,
+                                 restarts = ?4, last_state = ?5, last_seen = ?6",
+                            params![row.0, row.1, row.2, row.3, row.4, row.5],
+                        )?;
+                    }
+                    transaction.commit()?;
+                    Ok(rows.len())
+                }).await;
+
+                match written {
+                    Ok(Ok(count)) => debug!("Persisted stats of {} streams", count),
+                    Ok(Err(e)) => warn!("Could not persist stream stats to {}: {}", self.path, e),
+                    Err(e) => warn!("Stream stats writer failed: {}", e),
+                }
+            }
+        });
+    }
+}
+
+fn parse_time(text: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_rfc3339(text).ok().map(|t| t.with_timezone(&Utc))
+}
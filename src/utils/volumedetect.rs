@@ -6,11 +6,109 @@ use tokio::process::Command;
 use tokio::sync::{broadcast::Receiver, Mutex};
 use std::process::Stdio;
 use tracing::{warn, trace, error};
+use chrono::{DateTime, Utc};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+use super::bufferbudget::BufferBudget;
+
+const DROPOUT_HISTORY_HOURS: i64 = 24;
+
+/// Continuous watch for short near-silent runs, which fall between the periodic volume samples
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct DropoutConfig {
+    /// A near-silent run at least this long counts as one dropout
+    #[serde(default = "default_dropout_min_seconds")]
+    pub min_seconds: f32,
+    /// Samples quieter than this (dBFS) count as near-silent
+    #[serde(default = "default_dropout_level_db")]
+    pub level_db: f32,
+    /// Alert when a stream drops out this often within an hour, 0 disables
+    #[serde(default = "default_dropouts_per_hour")]
+    pub alert_per_hour: usize,
+}
+
+fn default_dropout_min_seconds() -> f32 { 2.0 }
+fn default_dropout_level_db() -> f32 { -60.0 }
+fn default_dropouts_per_hour() -> usize { 3 }
+
+impl Default for DropoutConfig {
+    fn default() -> Self {
+        DropoutConfig {
+            min_seconds: default_dropout_min_seconds(),
+            level_db: default_dropout_level_db(),
+            alert_per_hour: default_dropouts_per_hour(),
+        }
+    }
+}
+
+#[derive(Default)]
+struct DropoutLog {
+    recent: VecDeque<DateTime<Utc>>,
+    total: u64,
+}
+
+/// Counts near-silent runs sample by sample as PCM arrives
+struct DropoutWatch {
+    level: i32, // absolute sample value at or below which a sample is near-silent
+    min_run: u64, // samples (both channels) a run needs to count
+    run: u64,
+    carry: Option<u8>, // chunks aren't guaranteed to end on a sample boundary
+    log: Arc<std::sync::Mutex<DropoutLog>>,
+}
+
+impl DropoutWatch {
+    fn new(config: &DropoutConfig, log: Arc<std::sync::Mutex<DropoutLog>>) -> Self {
+        DropoutWatch {
+            level: (32768.0 * 10f32.powf(config.level_db / 20.0)) as i32,
+            min_run: (config.min_seconds.max(0.1) * 44100.0 * 2.0) as u64,
+            run: 0,
+            carry: None,
+            log,
+        }
+    }
+
+    fn consume(&mut self, mut data: &[u8]) {
+        if let Some(low) = self.carry.take() {
+            let Some((&high, rest)) = data.split_first() else {
+                self.carry = Some(low);
+                return;
+            };
+            self.sample(i16::from_le_bytes([low, high]));
+            data = rest;
+        }
+        let mut samples = data.chunks_exact(2);
+        for sample in &mut samples {
+            self.sample(i16::from_le_bytes([sample[0], sample[1]]));
+        }
+        self.carry = samples.remainder().first().copied();
+    }
+
+    fn sample(&mut self, value: i16) {
+        if (value as i32).abs() > self.level {
+            self.run = 0;
+            return;
+        }
+        self.run += 1;
+        // Counted once when the run gets long enough, however long it then lasts
+        if self.run == self.min_run {
+            let at = Utc::now();
+            let mut log = self.log.lock().expect("dropout lock poisoned");
+            log.recent.push_back(at);
+            log.total += 1;
+            while log.recent.front().is_some_and(|t| at - *t > chrono::Duration::hours(DROPOUT_HISTORY_HOURS)) {
+                log.recent.pop_front();
+            }
+        }
+    }
+}
 
 #[derive(Debug, Clone, Copy)]
 pub struct VolumeMetrics {
     pub mean_volume: f32,
     pub max_volume: f32,
+    pub stereo: StereoMetrics,
+    pub analyzed: bool, // false for the placeholder returned while the buffer fills or when analysis fails
 }
 
 impl Default for VolumeMetrics {
@@ -18,37 +116,202 @@ impl Default for VolumeMetrics {
         VolumeMetrics {
             mean_volume: -100.0, // Very quiet default
             max_volume: -100.0,
+            stereo: StereoMetrics::default(),
+            analyzed: false,
+        }
+    }
+}
+
+/// Per-channel levels of the interleaved stereo buffer, which the mono fingerprint can't see
+#[derive(Debug, Clone, Copy)]
+pub struct StereoMetrics {
+    pub left_rms: f32,   // dBFS
+    pub right_rms: f32,  // dBFS
+    pub correlation: f32, // -1.0 (inverted) to 1.0 (identical)
+}
+
+impl Default for StereoMetrics {
+    fn default() -> Self {
+        StereoMetrics {
+            left_rms: -100.0,
+            right_rms: -100.0,
+            correlation: 0.0,
+        }
+    }
+}
+
+impl StereoMetrics {
+    /// Computes levels and L/R correlation from s16le stereo bytes
+    pub fn from_pcm(data: &[u8]) -> Self {
+        let mut sum_l = 0.0f64;
+        let mut sum_r = 0.0f64;
+        let mut sum_lr = 0.0f64;
+        let mut frames = 0usize;
+
+        for frame in data.chunks_exact(4) {
+            let l = i16::from_le_bytes([frame[0], frame[1]]) as f64 / 32768.0;
+            let r = i16::from_le_bytes([frame[2], frame[3]]) as f64 / 32768.0;
+            sum_l += l * l;
+            sum_r += r * r;
+            sum_lr += l * r;
+            frames += 1;
+        }
+
+        if frames == 0 {
+            return StereoMetrics::default();
+        }
+
+        let to_db = |sum: f64| -> f32 {
+            let rms = (sum / frames as f64).sqrt();
+            if rms > 0.0 { (20.0 * rms.log10()).max(-100.0) as f32 } else { -100.0 }
+        };
+
+        let denominator = (sum_l * sum_r).sqrt();
+        let correlation = if denominator > 0.0 { (sum_lr / denominator) as f32 } else { 0.0 };
+
+        StereoMetrics {
+            left_rms: to_db(sum_l),
+            right_rms: to_db(sum_r),
+            correlation,
+        }
+    }
+
+    /// Left minus right level in dB, positive when the left channel is louder
+    pub fn balance(&self) -> f32 {
+        self.left_rms - self.right_rms
+    }
+}
+
+// Stereo profiles are levels per short block, enough to line two streams up and compare their stereo image
+const PROFILE_BLOCK_FRAMES: usize = 2205; // 50 ms at 44.1 kHz
+const PROFILE_MAX_LAG_BLOCKS: isize = 200; // 10 s, more than the delay between an HD and an analog feed
+const PROFILE_MIN_OVERLAP_BLOCKS: usize = 100;
+const PROFILE_MIN_ALIGNMENT: f32 = 0.7; // mid level correlation for two profiles to count as the same audio
+const PROFILE_MIN_BALANCE_SPREAD_DB: f32 = 0.5; // a balance that barely moves says nothing about left and right
+
+/// Mid level and L/R balance of each 50 ms block of the analysis window
+#[derive(Debug, Clone, Default)]
+pub struct StereoProfile {
+    mid: Vec<f32>, // dB of (L + R) / 2
+    balance: Vec<f32>, // dB of L minus R
+}
+
+impl StereoProfile {
+    pub fn from_pcm(data: &[u8]) -> Self {
+        let to_db = |sum: f64, frames: usize| -> f32 {
+            let rms = (sum / frames as f64).sqrt();
+            if rms > 0.0 { (20.0 * rms.log10()).max(-100.0) as f32 } else { -100.0 }
+        };
+        let mut profile = StereoProfile::default();
+        for block in data.chunks_exact(PROFILE_BLOCK_FRAMES * 4) {
+            let (mut sum_l, mut sum_r, mut sum_m) = (0.0f64, 0.0f64, 0.0f64);
+            for frame in block.chunks_exact(4) {
+                let l = i16::from_le_bytes([frame[0], frame[1]]) as f64 / 32768.0;
+                let r = i16::from_le_bytes([frame[2], frame[3]]) as f64 / 32768.0;
+                sum_l += l * l;
+                sum_r += r * r;
+                sum_m += (l + r) * (l + r) / 4.0;
+            }
+            profile.mid.push(to_db(sum_m, PROFILE_BLOCK_FRAMES));
+            profile.balance.push(to_db(sum_l, PROFILE_BLOCK_FRAMES) - to_db(sum_r, PROFILE_BLOCK_FRAMES));
         }
+        profile
     }
+
+    /// How alike the two stereo images are once the streams are lined up on their mid level: near 1 for the
+    /// same image, near -1 when one has left and right swapped. `None` when they can't be lined up or either
+    /// balance barely moves, as with near-mono audio
+    pub fn image_correlation(&self, other: &StereoProfile) -> Option<f32> {
+        let mut best: Option<(f32, isize)> = None;
+        for lag in -PROFILE_MAX_LAG_BLOCKS..=PROFILE_MAX_LAG_BLOCKS {
+            let (a, b) = lagged(&self.mid, &other.mid, lag);
+            if a.len() < PROFILE_MIN_OVERLAP_BLOCKS {
+                continue;
+            }
+            if let Some(correlation) = pearson(a, b) {
+                if !best.is_some_and(|(c, _)| c >= correlation) {
+                    best = Some((correlation, lag));
+                }
+            }
+        }
+        let (alignment, lag) = best.filter(|(c, _)| *c >= PROFILE_MIN_ALIGNMENT)?;
+        let (a, b) = lagged(&self.balance, &other.balance, lag);
+        if spread(a) < PROFILE_MIN_BALANCE_SPREAD_DB || spread(b) < PROFILE_MIN_BALANCE_SPREAD_DB {
+            return None;
+        }
+        trace!("Stereo profiles aligned at {} blocks (mid correlation {:.2})", lag, alignment);
+        pearson(a, b)
+    }
+}
+
+/// The overlapping parts of `a` and `b` with `b` shifted `lag` blocks later
+fn lagged<'a>(a: &'a [f32], b: &'a [f32], lag: isize) -> (&'a [f32], &'a [f32]) {
+    let (a, b) = if lag >= 0 {
+        (a, b.get(lag as usize..).unwrap_or_default())
+    } else {
+        (a.get(lag.unsigned_abs()..).unwrap_or_default(), b)
+    };
+    let len = a.len().min(b.len());
+    (&a[..len], &b[..len])
+}
+
+fn mean(values: &[f32]) -> f32 {
+    values.iter().sum::<f32>() / values.len().max(1) as f32
 }
 
+/// Standard deviation
+fn spread(values: &[f32]) -> f32 {
+    let m = mean(values);
+    (values.iter().map(|v| (v - m) * (v - m)).sum::<f32>() / values.len().max(1) as f32).sqrt()
+}
+
+fn pearson(a: &[f32], b: &[f32]) -> Option<f32> {
+    let (mean_a, mean_b) = (mean(a), mean(b));
+    let (mut covariance, mut variance_a, mut variance_b) = (0.0f32, 0.0f32, 0.0f32);
+    for (x, y) in a.iter().zip(b) {
+        covariance += (x - mean_a) * (y - mean_b);
+        variance_a += (x - mean_a) * (x - mean_a);
+        variance_b += (y - mean_b) * (y - mean_b);
+    }
+    let denominator = (variance_a * variance_b).sqrt();
+    (denominator > 0.0).then(|| covariance / denominator)
+}
+
+#[derive(Clone)]
 pub struct VolumeDetector {
-    buffer: Arc<Mutex<VecDeque<u8>>>,
-    buffer_duration: f32,
+    buffer: Arc<Mutex<VecDeque<u8>>>, // the most recent `analysis_window` seconds of PCM
+    dropouts: Arc<std::sync::Mutex<DropoutLog>>,
 }
 
 impl VolumeDetector {
-    pub fn new(mut input: Receiver<Vec<u8>>, buffer_duration: f32) -> Self {
+    /// Keeps the last `analysis_window` seconds of audio, which is what every volume check analyzes
+    pub fn new(mut input: Receiver<Vec<u8>>, analysis_window: f32, dropout: &DropoutConfig, budget: &Arc<BufferBudget>) -> Self {
         // Calculate max buffer size: 44100 Hz * 2 channels * 2 bytes/sample * duration
-        let max_buffer_size = (44100.0 * 2.0 * 2.0 * buffer_duration) as usize;
+        let max_buffer_size = (44100.0 * 2.0 * 2.0 * analysis_window) as usize;
+        let handle = budget.register(max_buffer_size);
 
-        let buffer = Arc::new(Mutex::new(VecDeque::with_capacity(max_buffer_size)));
+        let buffer = Arc::new(Mutex::new(VecDeque::with_capacity(handle.limit())));
         let thread_buffer = buffer.clone();
+        let dropouts = Arc::new(std::sync::Mutex::new(DropoutLog::default()));
+        let mut dropout_watch = DropoutWatch::new(dropout, dropouts.clone());
 
         // Spawn a task to continuously fill the circular buffer
         tokio::spawn(async move {
             loop {
                 match input.recv().await {
                     Ok(data) => {
+                        dropout_watch.consume(&data);
                         let mut buf = thread_buffer.lock().await;
+                        let limit = handle.limit();
 
-                        // Add new data to buffer
-                        buf.extend(data.iter());
-
-                        // Trim buffer if it exceeds max size
-                        while buf.len() > max_buffer_size {
-                            buf.pop_front();
+                        // Make room first so the buffer never grows past its share of the budget
+                        let overflow = (buf.len() + data.len()).saturating_sub(limit);
+                        buf.drain(..overflow.min(buf.len()));
+                        buf.extend(data[data.len().saturating_sub(limit)..].iter());
+                        if buf.capacity() > limit * 2 {
+                            buf.shrink_to(limit);
                         }
+                        handle.set_held(buf.len());
                     },
                     Err(e) => {
                         warn!("VolumeDetector input closed: {:?}", e);
@@ -60,13 +323,66 @@ impl VolumeDetector {
 
         VolumeDetector {
             buffer,
-            buffer_duration,
+            dropouts,
         }
     }
 
+    /// Dropouts within `window`, and in total since startup
+    pub fn get_dropouts(&self, window: chrono::Duration) -> (usize, u64) {
+        let log = self.dropouts.lock().expect("dropout lock poisoned");
+        let since = Utc::now() - window;
+        (log.recent.iter().filter(|t| **t >= since).count(), log.total)
+    }
+
+    /// Stereo profile of the buffered audio, for comparing stereo images across streams
+    pub async fn get_stereo_profile(&self) -> StereoProfile {
+        let buffer_snapshot = {
+            let buf = self.buffer.lock().await;
+            Vec::from_iter(buf.iter().copied())
+        };
+        StereoProfile::from_pcm(&buffer_snapshot)
+    }
+
+    /// Computes volume metrics directly from the buffered PCM without spawning ffmpeg
+    /// Mean is the RMS level over both channels, max is the sample peak
+    pub async fn get_native_metrics(&self) -> VolumeMetrics {
+        let buffer_snapshot = {
+            let buf = self.buffer.lock().await;
+            Vec::from_iter(buf.iter().copied())
+        };
+
+        if buffer_snapshot.len() < 1024 {
+            return VolumeMetrics::default();
+        }
+
+        let mut sum = 0.0f64;
+        let mut peak = 0.0f64;
+        let mut samples = 0usize;
+        for sample in buffer_snapshot.chunks_exact(2) {
+            let value = i16::from_le_bytes([sample[0], sample[1]]) as f64 / 32768.0;
+            sum += value * value;
+            peak = peak.max(value.abs());
+            samples += 1;
+        }
+
+        let to_db = |level: f64| -> f32 {
+            if level > 0.0 { (20.0 * level.log10()).max(-100.0) as f32 } else { -100.0 }
+        };
+
+        let metrics = VolumeMetrics {
+            mean_volume: to_db((sum / samples as f64).sqrt()),
+            max_volume: to_db(peak),
+            stereo: StereoMetrics::from_pcm(&buffer_snapshot),
+            analyzed: true,
+        };
+        trace!("Native volume metrics: mean={} dB, max={} dB", metrics.mean_volume, metrics.max_volume);
+        metrics
+    }
+
     /// Analyzes the current buffered audio and returns volume metrics
     /// This spawns ffmpeg on-demand to analyze the sliding window
     pub async fn get_metrics(&self) -> VolumeMetrics {
+        // Copy the buffer out so the fill task isn't blocked while ffmpeg runs
         let buffer_snapshot = {
             let buf = self.buffer.lock().await;
             Vec::from_iter(buf.iter().copied())
@@ -77,6 +393,8 @@ impl VolumeDetector {
             return VolumeMetrics::default();
         }
 
+        let stereo = StereoMetrics::from_pcm(&buffer_snapshot);
+
         // Spawn ffmpeg to analyze the buffered audio
         let mut child = match Command::new("ffmpeg")
             .args(&[
@@ -152,6 +470,8 @@ impl VolumeDetector {
                 VolumeMetrics {
                     mean_volume: mean,
                     max_volume: max,
+                    stereo,
+                    analyzed: true,
                 }
             },
             _ => {
@@ -0,0 +1,173 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use argon2::{Argon2, PasswordHash, PasswordVerifier};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::response::{IntoResponse, Response};
+use chrono::{DateTime, Utc};
+use rand::RngCore;
+use schemars::JsonSchema;
+use serde::Deserialize;
+use subtle::ConstantTimeEq;
+use tracing::{info, warn};
+
+pub const SESSION_COOKIE: &str = "watchdog_session";
+const SESSION_HOURS: i64 = 12;
+// Random argon2id hash with the default parameters that no password matches, verified against when the username is unknown
+const DUMMY_PASSWORD_HASH: &str = "$argon2id$v=19$m=19456,t=2,p=1$+yQxPlFlhV4Q/65xhpv3Zw$6pSPdgxUZ7U69/4aN8ae6xPst45+HL1fUBnh1ofRwqI";
+
+/// What a web token may do, each role can also do everything below it
+#[derive(Debug, Clone, Copy, Deserialize, PartialEq, Eq, PartialOrd, Ord, JsonSchema)]
+pub enum Role {
+    /// read-only, no control endpoints
+    Viewer,
+    /// restart streams, acknowledge and escalate alerts, start and end maintenance, pin and run comparisons
+    Operator,
+    /// also fault injection and changing thresholds
+    Admin,
+}
+
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct WebToken {
+    /// who holds the token, logged with every control action
+    pub name: String,
+    /// sent as "Authorization: Bearer <token>"
+    pub token: String,
+    pub role: Role,
+}
+
+/// Dashboard login for people rather than scripts
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct WebUser {
+    pub username: String,
+    /// argon2 PHC string, e.g. from `echo -n pass | argon2 somesalt -id -e`
+    pub password_hash: String,
+    pub role: Role,
+}
+
+struct Session {
+    username: String,
+    role: Role,
+    expires: DateTime<Utc>,
+}
+
+/// Role checks for the control endpoints and dashboard logins; with neither tokens nor users
+/// configured everything stays open as before
+#[derive(Default)]
+pub struct WebAuth {
+    tokens: Vec<WebToken>,
+    users: Vec<WebUser>,
+    sessions: Mutex<HashMap<String, Session>>,
+}
+
+impl WebAuth {
+    pub fn new(tokens: Vec<WebToken>, users: Vec<WebUser>) -> Self {
+        for user in &users {
+            if let Err(e) = PasswordHash::new(&user.password_hash) {
+                warn!("Password hash of web user {} is not a valid argon2 PHC string, they cannot log in: {}", user.username, e);
+            }
+        }
+        WebAuth { tokens, users, sessions: Mutex::new(HashMap::new()) }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        !self.tokens.is_empty() || !self.users.is_empty()
+    }
+
+    /// Dashboard pages need a login once any web user is configured
+    pub fn requires_login(&self) -> bool {
+        !self.users.is_empty()
+    }
+
+    /// The caller's name and role, from a bearer token or a session cookie
+    pub fn caller(&self, headers: &HeaderMap) -> Option<(String, Role)> {
+        self.token_holder(headers).or_else(|| self.session_holder(headers))
+    }
+
+    fn token_holder(&self, headers: &HeaderMap) -> Option<(String, Role)> {
+        let presented = headers.get(header::AUTHORIZATION)?.to_str().ok()?.strip_prefix("Bearer ")?.trim();
+        self.tokens.iter()
+            // Constant time, so response time doesn't reveal how much of a token was right
+            .find(|token| bool::from(token.token.as_bytes().ct_eq(presented.as_bytes())))
+            .map(|token| (token.name.clone(), token.role))
+    }
+
+    fn session_holder(&self, headers: &HeaderMap) -> Option<(String, Role)> {
+        let id = session_id(headers)?;
+        let mut sessions = self.sessions.lock().expect("sessions lock poisoned");
+        let now = Utc::now();
+        sessions.retain(|_, session| session.expires > now);
+        sessions.get(&id).map(|session| (session.username.clone(), session.role))
+    }
+
+    /// Checks a username and password, returning a new session id on success.
+    /// Runs argon2, so call it from a blocking task
+    pub fn login(&self, username: &str, password: &str) -> Option<String> {
+        let user = self.users.iter().find(|user| user.username == username);
+        // Unknown users still pay for a verification, so response time doesn't tell which usernames exist
+        let phc = user.map_or(DUMMY_PASSWORD_HASH, |user| user.password_hash.as_str());
+        let verified = PasswordHash::new(phc).or_else(|_| PasswordHash::new(DUMMY_PASSWORD_HASH))
+            .is_ok_and(|hash| Argon2::default().verify_password(password.as_bytes(), &hash).is_ok());
+        let Some(user) = user.filter(|_| verified) else {
+            warn!("Web login failed for {}", username);
+            return None;
+        };
+
+        let mut bytes = [0u8; 32];
+        rand::thread_rng().fill_bytes(&mut bytes);
+        let id: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+        self.sessions.lock().expect("sessions lock poisoned").insert(id.clone(), Session {
+            username: user.username.clone(),
+            role: user.role,
+            expires: Utc::now() + chrono::Duration::hours(SESSION_HOURS),
+        });
+        info!("Web login: {} ({:?})", user.username, user.role);
+        Some(id)
+    }
+
+    pub fn logout(&self, headers: &HeaderMap) {
+        if let Some(id) = session_id(headers) {
+            if let Some(session) = self.sessions.lock().expect("sessions lock poisoned").remove(&id) {
+                info!("Web logout: {}", session.username);
+            }
+        }
+    }
+
+    /// `Set-Cookie` value for a new session, scoped to the dashboard's base path
+    pub fn session_cookie(id: &str, path: &str) -> String {
+        format!("{}={}; Path={}; Max-Age={}; HttpOnly; SameSite=Strict", SESSION_COOKIE, id, path, SESSION_HOURS * 3600)
+    }
+
+    /// `Set-Cookie` value telling the browser to drop its session
+    pub fn expired_session_cookie(path: &str) -> String {
+        format!("{}=; Path={}; Max-Age=0; HttpOnly; SameSite=Strict", SESSION_COOKIE, path)
+    }
+
+    /// Lets the request through when its token or session has at least `required`, returning the caller's name.
+    /// Every decision is logged, denials at warn
+    pub fn authorize(&self, headers: &HeaderMap, required: Role, action: &str) -> Result<Option<String>, Response> {
+        if !self.is_enabled() {
+            return Ok(None);
+        }
+        match self.caller(headers) {
+            Some((name, role)) if role >= required => {
+                info!("Web control: {} ({:?}) {}", name, role, action);
+                Ok(Some(name))
+            }
+            Some((name, role)) => {
+                warn!("Web control denied: {} ({:?}) tried to {}, which needs {:?}", name, role, action, required);
+                Err((StatusCode::FORBIDDEN, format!("{} needs the {:?} role", action, required)).into_response())
+            }
+            None => {
+                warn!("Web control denied: request without a valid token or login tried to {}", action);
+                Err((StatusCode::UNAUTHORIZED, "A valid bearer token or login is required").into_response())
+            }
+        }
+    }
+}
+
+fn session_id(headers: &HeaderMap) -> Option<String> {
+    headers.get_all(header::COOKIE).iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .find_map(|cookie| cookie.trim().strip_prefix(SESSION_COOKIE)?.strip_prefix('=').map(str::to_string))
+}
@@ -1,21 +1,81 @@
-use std::sync::Arc;
+use std::{collections::{HashMap, VecDeque}, convert::Infallible, net::SocketAddr, sync::Arc, time::Duration};
 use axum::{
-    extract::State,
-    response::{Html, IntoResponse},
-    routing::get,
+    extract::{Path, Query, Request, State},
+    middleware::{self, Next},
+    response::{sse::{Event, KeepAlive, Sse}, Html, IntoResponse, Redirect, Response},
+    routing::{get, post},
+    Form,
+    Json,
     Router,
-    http::StatusCode,
+    http::{header, HeaderMap, HeaderValue, Method, StatusCode},
 };
-use chrono::Utc;
+use chrono::{DateTime, Utc};
 use maud::{html, Markup};
-use tracing::info;
+use serde::Deserialize;
+use tracing::{debug, info};
 
 use super::audiorouter::AudioRouter;
 use super::audiostream::AudioStreamHealth;
-use super::commandprocessor::StreamHealth;
-use super::comparator::ComparisonResult;
+use super::commandprocessor::{Fault, StderrSummary, StreamHealth};
+use super::comparator::{ComparisonCounters, ComparisonResult, StreamComparator};
+use super::slack::SlackMessageSender;
+use super::delivery::DeliveryStats;
+use super::pushgateway::{PushgatewayConfig, Pusher};
+use super::exposition::{self, MetricsWriter};
+use super::pin::{PairPinner, PinRequest};
+use super::tuning::{ThresholdRequest, ThresholdTuner};
 use super::volumedetect::VolumeMetrics;
+use super::recorder::RecordingScheduler;
+use super::slacklistener::ListenerState;
+use super::prober::StreamProber;
+use super::artifacts::ArtifactDetector;
+use super::timesync::{ClockOffset, TimeSyncChecker};
+use super::dnswatch::DnsWatcher;
+use super::formatcheck::FormatChecker;
+use super::nrsc::NrscManager;
+use super::alertmanager::{AlertManager, DEFAULT_MAINTENANCE_MINUTES};
+use super::export::history_csv;
+use super::graphql::{build_schema, WatchdogSchema};
+use super::timefmt;
+use super::uptimestore::PersistedStats;
+use super::webauth::{Role, WebAuth, WebToken, WebUser};
+use super::branding::{label, Branding};
+use async_graphql::http::GraphiQLSource;
+use async_graphql_axum::{GraphQLRequest, GraphQLResponse};
 use tokio::sync::RwLock;
+use futures_util::Stream;
+use watchdog::api::{ActiveAlert, AlertEvent, AlertSeverity, AlertTransition, BufferStatus, ComparisonSample, LiveEvent, MaintenanceStatus, StatusPayload, StreamStatus};
+
+// Cleared alerts listed under the active ones on the status page
+const RECENT_ALERT_HOURS: i64 = 24;
+const RECENT_ALERT_COUNT: usize = 10;
+// How often each /api/events subscriber looks for changes
+const LIVE_POLL_INTERVAL: Duration = Duration::from_secs(2);
+// Longest fault injections, a drill shouldn't outlast the shift running it
+const MAX_INJECT_SECONDS: u64 = 24 * 60 * 60;
+const MAX_INJECT_DELAY_SECONDS: f32 = 300.0;
+
+// Rewrites <time> elements into the viewer's own timezone once they opt in, remembered per browser
+const BROWSER_TIME_SCRIPT: &str = r#"
+(function () {
+    var key = 'watchdog-browser-time';
+    var on = localStorage.getItem(key) === '1';
+    var toggle = document.getElementById('tz-toggle');
+    if (toggle) {
+        toggle.textContent = on ? 'Show server times' : 'Show times in my timezone';
+        toggle.onclick = function (e) { e.preventDefault(); localStorage.setItem(key, on ? '0' : '1'); location.reload(); };
+    }
+    if (!on) return;
+    document.querySelectorAll('time[datetime]').forEach(function (el) {
+        el.textContent = new Date(el.getAttribute('datetime')).toLocaleString(undefined, { timeZoneName: 'short' });
+    });
+})();
+"#;
+
+/// Timestamp in the display timezone, which the browser script can swap for the viewer's
+fn local_time(time: DateTime<Utc>, format: &str) -> Markup {
+    html! { time datetime=(time.to_rfc3339()) { (timefmt::display(time, format)) } }
+}
 
 fn format_duration(duration: chrono::Duration) -> String {
     let secs = duration.num_seconds();
@@ -37,25 +97,269 @@ fn format_duration(duration: chrono::Duration) -> String {
 
 pub struct WebServer {
     router: Arc<AudioRouter>,
+    graphql_schema: WatchdogSchema,
     comparison_results: Arc<RwLock<Vec<ComparisonResult>>>,
+    recorder: Option<Arc<RecordingScheduler>>,
+    slack_listener: Option<Arc<ListenerState>>,
+    prober: Option<Arc<StreamProber>>,
+    dns_watcher: Option<Arc<DnsWatcher>>,
+    format_checker: Option<Arc<FormatChecker>>,
+    artifact_detector: Option<Arc<ArtifactDetector>>,
+    min_buffer_size: usize, // fingerprint items a stream needs before it is compared
+    nrsc_managers: HashMap<String, Arc<NrscManager>>, // SDR name -> manager, for IQ delivery metrics
+    comparison_history: Option<Arc<RwLock<VecDeque<ComparisonSample>>>>,
+    alert_manager: Option<Arc<AlertManager>>, // active alerts and their history for the API, metrics and exports
+    time_sync: Option<Arc<TimeSyncChecker>>,
+    comparison_counters: Option<Arc<ComparisonCounters>>,
+    slack: Option<Arc<SlackMessageSender>>, // for message counters
+    delivery_stats: Option<Arc<DeliveryStats>>,
+    tuner: Option<Arc<ThresholdTuner>>,
+    pushgateway: Option<PushgatewayConfig>,
+    pinner: Option<Arc<PairPinner>>,
+    comparator: Option<Arc<StreamComparator>>, // for on-demand comparisons
+    base_path: String, // "" or a prefix like "/watchdog" every route and link lives under
+    fault_injection: bool, // serves /api/debug/inject, for fire drills in staging
+    auth: WebAuth, // roles required by the control endpoints
+    cors_origins: Vec<String>, // browser origins allowed to call /api/*, "*" for any
+    api_requires_token: bool, // reads under /api/* need a token or login too
+    branding: Branding,
+}
+
+#[derive(Deserialize)]
+struct ExportQuery {
+    format: Option<String>,
+    from: Option<DateTime<Utc>>, // RFC 3339, defaults to everything retained
+    to: Option<DateTime<Utc>>, // RFC 3339, defaults to now
+}
+
+#[derive(Deserialize)]
+struct AckQuery {
+    by: Option<String>, // who is handling it, shown on the status page and in Slack; only used when auth is off, otherwise the token or login holder is
+}
+
+#[derive(Deserialize)]
+struct MaintenanceQuery {
+    minutes: Option<i64>, // defaults to an hour
+    by: Option<String>, // who started or ended it, shown in Slack; only used when auth is off, otherwise the token or login holder is
+}
+
+#[derive(Deserialize)]
+struct LoginForm {
+    username: String,
+    password: String,
+}
+
+#[derive(Deserialize)]
+struct InjectQuery {
+    stream: String,
+    fault: Fault,
+    seconds: Option<u64>, // how long silence or lag lasts, defaults to a minute, at most a day
+    delay: Option<f32>, // seconds of lag, defaults to 5, at most 300
+}
+
+#[derive(Deserialize)]
+struct CompareQuery {
+    stream1: String,
+    stream2: String,
+}
+
+/// Still warming up while nothing has been compared and some stream hasn't filled its buffer
+fn is_warming_up(buffers: &[BufferStatus], comparison_results: &[ComparisonResult]) -> bool {
+    comparison_results.is_empty() && buffers.iter().any(|b| !b.is_ready())
 }
 
 impl WebServer {
     pub fn new(router: Arc<AudioRouter>, comparison_results: Arc<RwLock<Vec<ComparisonResult>>>) -> Self {
-        WebServer { router, comparison_results }
+        let graphql_schema = build_schema(router.clone(), comparison_results.clone());
+        WebServer { router, graphql_schema, comparison_results, recorder: None, slack_listener: None, prober: None, dns_watcher: None, format_checker: None, artifact_detector: None, min_buffer_size: 0, nrsc_managers: HashMap::new(), comparison_history: None, alert_manager: None, time_sync: None, comparison_counters: None, slack: None, delivery_stats: None, tuner: None, pushgateway: None, pinner: None, comparator: None, base_path: String::new(), fault_injection: false, auth: WebAuth::default(), cors_origins: Vec::new(), api_requires_token: false, branding: Branding::default() }
+    }
+
+    pub fn with_comparison_history(mut self, comparison_history: Arc<RwLock<VecDeque<ComparisonSample>>>) -> Self {
+        self.comparison_history = Some(comparison_history);
+        self
+    }
+
+    pub fn with_alert_manager(mut self, alert_manager: Arc<AlertManager>) -> Self {
+        self.alert_manager = Some(alert_manager);
+        self
+    }
+
+    /// Serves every route under `base_path`, for a reverse proxy routing on path instead of hostname
+    pub fn with_base_path(mut self, base_path: &str) -> Self {
+        let trimmed = base_path.trim_matches('/');
+        self.base_path = if trimmed.is_empty() { String::new() } else { format!("/{}", trimmed) };
+        self
+    }
+
+    pub fn with_comparison_counters(mut self, comparison_counters: Arc<ComparisonCounters>) -> Self {
+        self.comparison_counters = Some(comparison_counters);
+        self
+    }
+
+    /// Also pushes the metrics to a Pushgateway, for sites Prometheus can't scrape
+    pub fn with_pushgateway(mut self, pushgateway: PushgatewayConfig) -> Self {
+        self.pushgateway = Some(pushgateway);
+        self
+    }
+
+    /// Serves `/api/debug/inject`, which kills, mutes or delays a stream on request; never enable this in production
+    pub fn with_fault_injection(mut self) -> Self {
+        self.fault_injection = true;
+        self
+    }
+
+    pub fn with_web_auth(mut self, tokens: Vec<WebToken>, users: Vec<WebUser>) -> Self {
+        self.auth = WebAuth::new(tokens, users);
+        self
+    }
+
+    pub fn with_api_access(mut self, cors_origins: Vec<String>, api_requires_token: bool) -> Self {
+        self.cors_origins = cors_origins;
+        self.api_requires_token = api_requires_token;
+        self
+    }
+
+    pub fn with_branding(mut self, branding: Branding) -> Self {
+        self.branding = branding;
+        self
+    }
+
+    pub fn with_comparator(mut self, comparator: Arc<StreamComparator>) -> Self {
+        self.comparator = Some(comparator);
+        self
     }
 
-    pub async fn start(self, port: u16) {
+    pub fn with_pinner(mut self, pinner: Arc<PairPinner>) -> Self {
+        self.pinner = Some(pinner);
+        self
+    }
+
+    pub fn with_tuner(mut self, tuner: Arc<ThresholdTuner>) -> Self {
+        self.tuner = Some(tuner);
+        self
+    }
+
+    pub fn with_slack_sender(mut self, slack: Arc<SlackMessageSender>) -> Self {
+        self.slack = Some(slack);
+        self
+    }
+
+    pub fn with_delivery_stats(mut self, delivery_stats: Arc<DeliveryStats>) -> Self {
+        self.delivery_stats = Some(delivery_stats);
+        self
+    }
+
+    pub fn with_time_sync(mut self, time_sync: Arc<TimeSyncChecker>) -> Self {
+        self.time_sync = Some(time_sync);
+        self
+    }
+
+    pub fn with_nrsc_managers(mut self, nrsc_managers: HashMap<String, Arc<NrscManager>>) -> Self {
+        self.nrsc_managers = nrsc_managers;
+        self
+    }
+
+    pub fn with_min_buffer_size(mut self, min_buffer_size: usize) -> Self {
+        self.min_buffer_size = min_buffer_size;
+        self
+    }
+
+    pub fn with_slack_listener_state(mut self, state: Arc<ListenerState>) -> Self {
+        self.slack_listener = Some(state);
+        self
+    }
+
+    pub fn with_prober(mut self, prober: Arc<StreamProber>) -> Self {
+        self.prober = Some(prober);
+        self
+    }
+
+    pub fn with_dns_watcher(mut self, dns_watcher: Arc<DnsWatcher>) -> Self {
+        self.dns_watcher = Some(dns_watcher);
+        self
+    }
+
+    pub fn with_artifact_detector(mut self, detector: Arc<ArtifactDetector>) -> Self {
+        self.artifact_detector = Some(detector);
+        self
+    }
+
+    pub fn with_format_checker(mut self, format_checker: Arc<FormatChecker>) -> Self {
+        self.format_checker = Some(format_checker);
+        self
+    }
+
+    pub fn with_recorder(mut self, recorder: Arc<RecordingScheduler>) -> Self {
+        self.recorder = Some(recorder);
+        self
+    }
+
+    pub async fn start(self, address: SocketAddr) {
         let server = Arc::new(self);
-        let app = Router::new()
+        let base_path = server.base_path.clone();
+
+        if let Some(ref config) = server.pushgateway {
+            let pusher = Pusher::new(config.clone());
+            let interval = std::time::Duration::from_secs(config.interval_seconds.max(1));
+            let server = server.clone();
+            info!("Pushing metrics to {} every {}s", pusher.target_url(), interval.as_secs());
+            tokio::spawn(async move {
+                loop {
+                    tokio::time::sleep(interval).await;
+                    pusher.push(render_metrics(&server).await).await;
+                }
+            });
+        }
+        // Pages meant for people sit behind the login once web users are configured;
+        // the JSON API has its own token rules so scripts, scrapers and the CLI keep working
+        let pages = Router::new()
             .route("/", get(status_page))
+            .route("/stream/:name", get(stream_page))
+            .route("/recordings", get(recordings_index))
+            .route("/recordings/:stream/:file", get(recording_file))
+            .route_layer(middleware::from_fn_with_state(server.clone(), require_login));
+        // The JSON API, GraphQL and metrics answer browsers on other origins and can require a token for reads
+        let api = Router::new()
+            .route("/api/status", get(status_api))
+            .route("/api/events", get(events_api))
+            .route("/api/streams/:name/stderr", get(stream_stderr_api))
+            .route("/api/streams/:name/restart", post(stream_restart_api))
+            .route("/api/probes", get(probes_api))
+            .route("/api/dns", get(dns_api))
+            .route("/api/formats", get(formats_api))
+            .route("/api/artifacts", get(artifacts_api))
+            .route("/api/export", get(export_api))
+            .route("/api/alerts", get(alerts_api))
+            .route("/api/alerts/:alert/ack", post(alert_ack_api))
+            .route("/api/alerts/:alert/alert-now", post(alert_now_api))
+            .route("/api/maintenance", get(maintenance_api).post(maintenance_start_api).delete(maintenance_end_api))
+            .route("/api/pins", get(pins_api).post(pin_api))
+            .route("/api/compare", post(compare_api))
+            .route("/api/thresholds", get(thresholds_api).post(threshold_api))
+            .route("/api/debug/inject", post(inject_api))
+            .route("/graphql", post(graphql_handler).get(graphiql_page))
             .route("/metrics", get(metrics_endpoint))
+            .layer(middleware::from_fn_with_state(server.clone(), api_access));
+        let app = Router::new()
+            .merge(pages)
+            .merge(api)
+            .route("/login", get(login_page).post(login_submit))
+            .route("/logout", get(logout))
             .with_state(server);
 
-        let addr = format!("0.0.0.0:{}", port);
-        info!("Starting web server on {}", addr);
+        let app = if base_path.is_empty() {
+            app
+        } else {
+            // Proxies commonly forward the prefix with a trailing slash
+            let home = base_path.clone();
+            Router::new()
+                .nest(&base_path, app)
+                .route(&format!("{}/", base_path), get(move || async move { Redirect::permanent(&home) }))
+        };
+
+        info!("Starting web server on {}{}", address, base_path);
 
-        let listener = tokio::net::TcpListener::bind(&addr)
+        let listener = tokio::net::TcpListener::bind(address)
             .await
             .expect("Failed to bind web server");
 
@@ -65,6 +369,75 @@ impl WebServer {
     }
 }
 
+/// CORS for allowed browser origins, and the token requirement of `api_requires_token`;
+/// control endpoints additionally check their own role
+async fn api_access(State(server): State<Arc<WebServer>>, request: Request, next: Next) -> Response {
+    let allowed_origin = request.headers().get(header::ORIGIN)
+        .filter(|origin| origin.to_str().is_ok_and(|origin| server.cors_origins.iter().any(|allowed| allowed == "*" || allowed == origin)))
+        .cloned();
+
+    let mut response = if request.method() == Method::OPTIONS && allowed_origin.is_some() {
+        // Preflight for a cross-origin request carrying a token or a JSON body
+        let mut preflight = StatusCode::NO_CONTENT.into_response();
+        let headers = preflight.headers_mut();
+        headers.insert(header::ACCESS_CONTROL_ALLOW_METHODS, HeaderValue::from_static("GET, POST, DELETE, OPTIONS"));
+        headers.insert(header::ACCESS_CONTROL_ALLOW_HEADERS, HeaderValue::from_static("Authorization, Content-Type"));
+        headers.insert(header::ACCESS_CONTROL_MAX_AGE, HeaderValue::from_static("600"));
+        preflight
+    } else if server.api_requires_token && server.auth.caller(request.headers()).is_none() {
+        debug!("Refused {} {} without a valid token or login", request.method(), request.uri().path());
+        (StatusCode::UNAUTHORIZED, "A valid bearer token or login is required").into_response()
+    } else {
+        next.run(request).await
+    };
+
+    if let Some(origin) = allowed_origin {
+        response.headers_mut().insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin);
+        response.headers_mut().append(header::VARY, HeaderValue::from_static("Origin"));
+    }
+    response
+}
+
+/// Sends visitors without a session or token to the login page once web users are configured
+async fn require_login(State(server): State<Arc<WebServer>>, request: Request, next: Next) -> Response {
+    if !server.auth.requires_login() || server.auth.caller(request.headers()).is_some() {
+        return next.run(request).await;
+    }
+    Redirect::to(&format!("{}/login", server.base_path)).into_response()
+}
+
+async fn login_page(State(server): State<Arc<WebServer>>) -> Response {
+    if !server.auth.requires_login() {
+        return Redirect::to(&format!("{}/", server.base_path)).into_response();
+    }
+    Html(render_login_page(None, &server.branding, &server.base_path).into_string()).into_response()
+}
+
+async fn login_submit(State(server): State<Arc<WebServer>>, Form(form): Form<LoginForm>) -> Response {
+    let auth_server = server.clone();
+    // argon2 is deliberately slow, keep it off the async workers
+    let session = tokio::task::spawn_blocking(move || auth_server.auth.login(&form.username, &form.password)).await.ok().flatten();
+    match session {
+        Some(id) => {
+            let path = if server.base_path.is_empty() { "/" } else { server.base_path.as_str() };
+            (
+                [(header::SET_COOKIE, WebAuth::session_cookie(&id, path))],
+                Redirect::to(&format!("{}/", server.base_path)),
+            ).into_response()
+        }
+        None => (StatusCode::UNAUTHORIZED, Html(render_login_page(Some("Wrong username or password"), &server.branding, &server.base_path).into_string())).into_response(),
+    }
+}
+
+async fn logout(State(server): State<Arc<WebServer>>, headers: HeaderMap) -> Response {
+    server.auth.logout(&headers);
+    let path = if server.base_path.is_empty() { "/" } else { server.base_path.as_str() };
+    (
+        [(header::SET_COOKIE, WebAuth::expired_session_cookie(path))],
+        Redirect::to(&format!("{}/login", server.base_path)),
+    ).into_response()
+}
+
 async fn status_page(State(server): State<Arc<WebServer>>) -> impl IntoResponse {
     let router = &server.router;
     let channels = router.get_all_channels();
@@ -81,7 +454,10 @@ async fn status_page(State(server): State<Arc<WebServer>>) -> impl IntoResponse
                 if let Some((cmd_health, audio_health)) = router.get_stream_health(&stream_name).await {
                     let uptime = router.get_stream_uptime(&stream_name).await;
                     let volume = volume_metrics.get(&stream_name).copied();
-                    streams.push((stream_name, cmd_health, audio_health, uptime, volume));
+                    let buffer = router.get_stream_buffer(&stream_name, server.min_buffer_size).await;
+                    let sampled = router.is_stream_sampled(&stream_name);
+                    let on_backup = router.is_stream_on_backup(&stream_name).await;
+                    streams.push((stream_name, cmd_health, audio_health, uptime, volume, buffer, sampled, on_backup));
                 }
             }
 
@@ -90,50 +466,483 @@ async fn status_page(State(server): State<Arc<WebServer>>) -> impl IntoResponse
     }
 
     let comparison_results = server.comparison_results.read().await.clone();
+    let clock_skew = match server.time_sync {
+        Some(ref time_sync) => time_sync.get_skew().await,
+        None => None,
+    };
+
+    // Cleared alerts of the last day, newest first
+    let (alerts, recent_alerts, maintenance) = match server.alert_manager {
+        Some(ref am) => {
+            let now = Utc::now();
+            let mut recent: Vec<AlertEvent> = am.get_history(now - chrono::Duration::hours(RECENT_ALERT_HOURS), now).await
+                .into_iter()
+                .filter(|e| e.transition == AlertTransition::Cleared)
+                .collect();
+            recent.reverse();
+            recent.truncate(RECENT_ALERT_COUNT);
+            (am.get_active_alerts().await, recent, am.get_maintenance().await)
+        }
+        None => (Vec::new(), Vec::new(), None),
+    };
 
-    let html = render_status_page(channel_data, comparison_results);
+    let html = render_status_page(channel_data, comparison_results, alerts, recent_alerts, server.min_buffer_size, clock_skew, maintenance, &server.branding, &server.base_path);
     Html(html.into_string())
 }
 
-async fn metrics_endpoint(State(server): State<Arc<WebServer>>) -> impl IntoResponse {
+async fn stream_page(
+    State(server): State<Arc<WebServer>>,
+    Path(name): Path<String>
+) -> Response {
+    let router = &server.router;
+    let Some((cmd_health, audio_health)) = router.get_stream_health(&name).await else {
+        return (StatusCode::NOT_FOUND, "Stream not found").into_response();
+    };
+    let uptime = router.get_stream_uptime(&name).await;
+    let persisted = router.get_persisted_stats(&name);
+    let volume = router.get_stream_volume(&name).await;
+    let stderr = router.get_stream_stderr(&name).await.unwrap_or_default();
+
+    Html(render_stream_page(&name, cmd_health, audio_health, uptime, persisted, volume, stderr, &server.branding, &server.base_path).into_string()).into_response()
+}
+
+async fn stream_stderr_api(
+    State(server): State<Arc<WebServer>>,
+    Path(name): Path<String>
+) -> Response {
+    match server.router.get_stream_logs(&name).await {
+        Some(logs) => Json(logs).into_response(),
+        None => (StatusCode::NOT_FOUND, "Stream not found").into_response(),
+    }
+}
+
+async fn stream_restart_api(
+    State(server): State<Arc<WebServer>>,
+    Path(name): Path<String>,
+    headers: HeaderMap
+) -> Response {
+    if let Err(denied) = server.auth.authorize(&headers, Role::Operator, &format!("restart stream {}", name)) {
+        return denied;
+    }
+    match server.router.restart_stream(&name).await {
+        Ok(()) => (StatusCode::OK, format!("Restarted stream {}", name)).into_response(),
+        Err(e) => (StatusCode::NOT_FOUND, e).into_response(),
+    }
+}
+
+/// Fire drill: `POST /api/debug/inject?stream=<name>&fault=silence|kill|lag[&seconds=60][&delay=5]`
+/// kills the stream's process or mutes or delays its audio, so alerting and escalation can be exercised end to end
+async fn inject_api(State(server): State<Arc<WebServer>>, Query(query): Query<InjectQuery>, headers: HeaderMap) -> Response {
+    if !server.fault_injection {
+        return (StatusCode::NOT_FOUND, "Fault injection is not enabled").into_response();
+    }
+    if let Err(denied) = server.auth.authorize(&headers, Role::Admin, &format!("inject {:?} into {}", query.fault, query.stream)) {
+        return denied;
+    }
+    let seconds = query.seconds.unwrap_or(60);
+    if seconds > MAX_INJECT_SECONDS {
+        return (StatusCode::BAD_REQUEST, format!("seconds must be at most {}", MAX_INJECT_SECONDS)).into_response();
+    }
+    let duration = std::time::Duration::from_secs(seconds);
+    let delay = match std::time::Duration::try_from_secs_f32(query.delay.unwrap_or(5.0)) {
+        Ok(delay) if delay.as_secs_f32() <= MAX_INJECT_DELAY_SECONDS => delay,
+        _ => return (StatusCode::BAD_REQUEST, format!("delay must be between 0 and {} seconds", MAX_INJECT_DELAY_SECONDS)).into_response(),
+    };
+    match server.router.inject_fault(&query.stream, query.fault, duration, delay).await {
+        Ok(()) => (StatusCode::OK, format!("Injected {:?} into {}", query.fault, query.stream)).into_response(),
+        Err(e) => (StatusCode::NOT_FOUND, e).into_response(),
+    }
+}
+
+/// Runs a one-off comparison right away instead of waiting for the next pass
+async fn compare_api(State(server): State<Arc<WebServer>>, Query(query): Query<CompareQuery>, headers: HeaderMap) -> Response {
+    if let Err(denied) = server.auth.authorize(&headers, Role::Operator, &format!("compare {} and {}", query.stream1, query.stream2)) {
+        return denied;
+    }
+    let Some(ref comparator) = server.comparator else {
+        return (StatusCode::NOT_FOUND, "Comparisons are not configured").into_response();
+    };
+    match comparator.compare_now(&query.stream1, &query.stream2).await {
+        Ok(result) => Json(result).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e).into_response(),
+    }
+}
+
+async fn pins_api(State(server): State<Arc<WebServer>>) -> Response {
+    match server.pinner {
+        Some(ref pinner) => Json(pinner.get_pins().await).into_response(),
+        None => (StatusCode::NOT_FOUND, "Pinning comparisons is not configured").into_response(),
+    }
+}
+
+/// Starts a pinned comparison, reported into a new Slack thread
+async fn pin_api(State(server): State<Arc<WebServer>>, headers: HeaderMap, Json(request): Json<PinRequest>) -> Response {
+    if let Err(denied) = server.auth.authorize(&headers, Role::Operator, "pin a comparison") {
+        return denied;
+    }
+    let Some(ref pinner) = server.pinner else {
+        return (StatusCode::NOT_FOUND, "Pinning comparisons is not configured").into_response();
+    };
+    match pinner.pin(request, None).await {
+        Ok(pin) => Json(pin).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e).into_response(),
+    }
+}
+
+async fn thresholds_api(State(server): State<Arc<WebServer>>) -> Response {
+    match server.tuner {
+        Some(ref tuner) => Json(tuner.get()).into_response(),
+        None => (StatusCode::NOT_FOUND, "Changing thresholds is not configured").into_response(),
+    }
+}
+
+/// Changes a threshold live, for one channel or all of them
+async fn threshold_api(State(server): State<Arc<WebServer>>, headers: HeaderMap, Json(request): Json<ThresholdRequest>) -> Response {
+    if let Err(denied) = server.auth.authorize(&headers, Role::Admin, &format!("set the {} threshold to {}", request.kind.as_str(), request.value)) {
+        return denied;
+    }
+    let Some(ref tuner) = server.tuner else {
+        return (StatusCode::NOT_FOUND, "Changing thresholds is not configured").into_response();
+    };
+    match tuner.set(request.kind, request.value, request.channel.as_deref()).await {
+        Ok(_) => Json(tuner.get()).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e).into_response(),
+    }
+}
+
+async fn probes_api(State(server): State<Arc<WebServer>>) -> Response {
+    match server.prober {
+        Some(ref prober) => Json(prober.get_results().await).into_response(),
+        None => (StatusCode::NOT_FOUND, "HTTP probing is not configured").into_response(),
+    }
+}
+
+async fn dns_api(State(server): State<Arc<WebServer>>) -> Response {
+    match server.dns_watcher {
+        Some(ref watcher) => Json(watcher.get_records().await).into_response(),
+        None => (StatusCode::NOT_FOUND, "DNS watching is not configured").into_response(),
+    }
+}
+
+async fn formats_api(State(server): State<Arc<WebServer>>) -> Response {
+    match server.format_checker {
+        Some(ref checker) => Json(checker.get_results().await).into_response(),
+        None => (StatusCode::NOT_FOUND, "Format verification is not configured").into_response(),
+    }
+}
+
+async fn artifacts_api(State(server): State<Arc<WebServer>>) -> Response {
+    match server.artifact_detector {
+        Some(ref detector) => Json(detector.get_stats().await).into_response(),
+        None => (StatusCode::NOT_FOUND, "Artifact detection is not configured").into_response(),
+    }
+}
+
+async fn alerts_api(State(server): State<Arc<WebServer>>) -> Response {
+    match server.alert_manager {
+        Some(ref am) => Json(am.get_active_alerts().await).into_response(),
+        None => (StatusCode::NOT_FOUND, "Alerting is not configured").into_response(),
+    }
+}
+
+async fn alert_ack_api(
+    State(server): State<Arc<WebServer>>,
+    Path(alert): Path<String>,
+    Query(query): Query<AckQuery>,
+    headers: HeaderMap
+) -> Response {
+    let holder = match server.auth.authorize(&headers, Role::Operator, &format!("acknowledge alert {}", alert)) {
+        Ok(holder) => holder,
+        Err(denied) => return denied,
+    };
+    let Some(ref am) = server.alert_manager else {
+        return (StatusCode::NOT_FOUND, "Alerting is not configured").into_response();
+    };
+    match am.acknowledge(&alert, holder.as_deref().or(query.by.as_deref()).unwrap_or("web")).await {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::NOT_FOUND, e).into_response(),
+    }
+}
+
+async fn alert_now_api(State(server): State<Arc<WebServer>>, Path(alert): Path<String>, headers: HeaderMap) -> Response {
+    if let Err(denied) = server.auth.authorize(&headers, Role::Operator, &format!("escalate alert {}", alert)) {
+        return denied;
+    }
+    let Some(ref am) = server.alert_manager else {
+        return (StatusCode::NOT_FOUND, "Alerting is not configured").into_response();
+    };
+    match am.alert_now(&alert).await {
+        Ok(_) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::CONFLICT, e).into_response(),
+    }
+}
+
+async fn maintenance_api(State(server): State<Arc<WebServer>>) -> Response {
+    match server.alert_manager {
+        Some(ref am) => Json(am.get_maintenance().await).into_response(),
+        None => (StatusCode::NOT_FOUND, "Alerting is not configured").into_response(),
+    }
+}
+
+/// Holds new alerts and reminders for `minutes`
+async fn maintenance_start_api(State(server): State<Arc<WebServer>>, Query(query): Query<MaintenanceQuery>, headers: HeaderMap) -> Response {
+    let minutes = query.minutes.unwrap_or(DEFAULT_MAINTENANCE_MINUTES);
+    let holder = match server.auth.authorize(&headers, Role::Operator, &format!("start {} minutes of maintenance", minutes)) {
+        Ok(holder) => holder,
+        Err(denied) => return denied,
+    };
+    let Some(ref am) = server.alert_manager else {
+        return (StatusCode::NOT_FOUND, "Alerting is not configured").into_response();
+    };
+    match am.start_maintenance(minutes, holder.as_deref().or(query.by.as_deref()).unwrap_or("web")).await {
+        Ok(status) => Json(status).into_response(),
+        Err(e) => (StatusCode::BAD_REQUEST, e).into_response(),
+    }
+}
+
+async fn maintenance_end_api(State(server): State<Arc<WebServer>>, Query(query): Query<MaintenanceQuery>, headers: HeaderMap) -> Response {
+    let holder = match server.auth.authorize(&headers, Role::Operator, "end maintenance") {
+        Ok(holder) => holder,
+        Err(denied) => return denied,
+    };
+    let Some(ref am) = server.alert_manager else {
+        return (StatusCode::NOT_FOUND, "Alerting is not configured").into_response();
+    };
+    match am.end_maintenance(holder.as_deref().or(query.by.as_deref()).unwrap_or("web")).await {
+        Ok(()) => StatusCode::NO_CONTENT.into_response(),
+        Err(e) => (StatusCode::CONFLICT, e).into_response(),
+    }
+}
+
+async fn export_api(State(server): State<Arc<WebServer>>, Query(query): Query<ExportQuery>) -> Response {
+    let format = query.format.as_deref().unwrap_or("csv");
+    if format != "csv" {
+        return (StatusCode::BAD_REQUEST, format!("Unsupported export format: {}", format)).into_response();
+    }
+    let from = query.from.unwrap_or(DateTime::<Utc>::MIN_UTC);
+    let to = query.to.unwrap_or_else(Utc::now);
+    if from > to {
+        return (StatusCode::BAD_REQUEST, "from must be before to").into_response();
+    }
+
+    let comparisons: Vec<ComparisonSample> = match server.comparison_history {
+        Some(ref history) => history.read().await.iter()
+            .filter(|s| s.timestamp >= from && s.timestamp <= to)
+            .cloned()
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let mut volumes = HashMap::new();
+    for channel_name in server.router.get_all_channels() {
+        for stream_name in server.router.get_channel_streams(&channel_name).unwrap_or_default() {
+            let samples: Vec<_> = server.router.get_stream_volume_history(&stream_name).await.into_iter()
+                .filter(|s| s.timestamp >= from && s.timestamp <= to)
+                .collect();
+            volumes.insert(stream_name, samples);
+        }
+    }
+
+    let alerts = match server.alert_manager {
+        Some(ref am) => am.get_history(from, to).await,
+        None => Vec::new(),
+    };
+
+    let filename = format!("watchdog-export-{}.csv", to.format("%Y%m%d-%H%M%S"));
+    (
+        [
+            (header::CONTENT_TYPE, "text/csv; charset=utf-8".to_string()),
+            (header::CONTENT_DISPOSITION, format!("attachment; filename=\"{}\"", filename)),
+        ],
+        history_csv(&comparisons, &volumes, &alerts),
+    ).into_response()
+}
+
+async fn graphql_handler(State(server): State<Arc<WebServer>>, request: GraphQLRequest) -> GraphQLResponse {
+    server.graphql_schema.execute(request.into_inner()).await.into()
+}
+
+async fn graphiql_page(State(server): State<Arc<WebServer>>) -> impl IntoResponse {
+    Html(GraphiQLSource::build().endpoint(&format!("{}/graphql", server.base_path)).finish())
+}
+
+async fn status_api(State(server): State<Arc<WebServer>>) -> impl IntoResponse {
     let router = &server.router;
-    let channels = router.get_all_channels();
     let volume_metrics = router.get_all_stream_volumes().await;
-    let comparison_results = server.comparison_results.read().await.clone();
+    let mut streams = Vec::new();
 
-    let mut metrics = String::new();
+    for channel_name in router.get_all_channels() {
+        if let Some(stream_names) = router.get_channel_streams(&channel_name) {
+            for stream_name in stream_names {
+                if let Some((command_health, audio_health)) = router.get_stream_health(&stream_name).await {
+                    let uptime = router.get_stream_uptime(&stream_name).await;
+                    let volume = volume_metrics.get(&stream_name);
+                    let recent_stderr = router.get_stream_stderr(&stream_name).await.unwrap_or_default();
+                    let buffer = router.get_stream_buffer(&stream_name, server.min_buffer_size).await;
+                    streams.push(StreamStatus {
+                        name: stream_name.clone(),
+                        channel: channel_name.clone(),
+                        command_health,
+                        audio_health,
+                        uptime_seconds: uptime.map(|u| u.num_seconds()),
+                        mean_volume_db: volume.map(|v| v.mean_volume),
+                        max_volume_db: volume.map(|v| v.max_volume),
+                        recent_stderr,
+                        buffer,
+                        not_sampled: !router.is_stream_sampled(&stream_name),
+                        on_backup: router.is_stream_on_backup(&stream_name).await,
+                        discontinuities_last_hour: router.get_stream_discontinuities(&stream_name, chrono::Duration::hours(1)).await
+                            .map_or(0, |(recent, _)| recent),
+                        restarts_total: router.get_persisted_stats(&stream_name).map(|stats| stats.restarts),
+                        dropouts_last_hour: router.get_stream_dropouts(&stream_name, chrono::Duration::hours(1)).await
+                            .map_or(0, |(recent, _)| recent),
+                        startup_error: None,
+                    });
+                }
+            }
+        }
+    }
+    for (name, channel, error) in router.get_failed_streams() {
+        streams.push(StreamStatus {
+            name,
+            channel,
+            command_health: StreamHealth::Dead,
+            audio_health: AudioStreamHealth::Dead,
+            uptime_seconds: None,
+            mean_volume_db: None,
+            max_volume_db: None,
+            recent_stderr: Vec::new(),
+            buffer: None,
+            not_sampled: false,
+            on_backup: false,
+            discontinuities_last_hour: 0,
+            restarts_total: None,
+            dropouts_last_hour: 0,
+            startup_error: Some(error),
+        });
+    }
 
-    // Add header comments
-    metrics.push_str("# HELP watchdog_stream_health Stream health status (2=Running, 1=Stalled, 0=Dead)\n");
-    metrics.push_str("# TYPE watchdog_stream_health gauge\n");
+    let comparisons = server.comparison_results.read().await.clone();
+    let buffers: Vec<BufferStatus> = streams.iter().filter_map(|s| s.buffer).collect();
+    Json(StatusPayload {
+        generated_at: Utc::now(),
+        warming_up: is_warming_up(&buffers, &comparisons),
+        streams,
+        comparisons,
+    })
+}
 
-    metrics.push_str("# HELP watchdog_audio_health Audio stream health status (3=Running, 2=Degraded, 1=NoData, 0=Dead)\n");
-    metrics.push_str("# TYPE watchdog_audio_health gauge\n");
+/// What one live feed subscriber has already been told
+struct LiveFeed {
+    server: Arc<WebServer>,
+    health: HashMap<String, (StreamHealth, AudioStreamHealth)>,
+    comparisons: HashMap<(String, String), bool>,
+    alerts_since: DateTime<Utc>,
+    pending: VecDeque<LiveEvent>,
+    polled: bool,
+}
 
-    metrics.push_str("# HELP watchdog_stream_uptime_seconds Stream uptime in seconds\n");
-    metrics.push_str("# TYPE watchdog_stream_uptime_seconds gauge\n");
+impl LiveFeed {
+    /// Queues every change since the last poll; the first poll reports the current state of all streams and pairs
+    async fn poll(&mut self) {
+        let now = Utc::now();
 
-    metrics.push_str("# HELP watchdog_volume_mean_db Mean volume level in dB\n");
-    metrics.push_str("# TYPE watchdog_volume_mean_db gauge\n");
+        for (stream, command_health, audio_health) in self.server.router.get_all_streams().await {
+            let current = (command_health.clone(), audio_health.clone());
+            if self.health.get(&stream) != Some(&current) {
+                self.health.insert(stream.clone(), current);
+                self.pending.push_back(LiveEvent::Health { timestamp: now, stream, command_health, audio_health });
+            }
+        }
 
-    metrics.push_str("# HELP watchdog_volume_max_db Maximum volume level in dB\n");
-    metrics.push_str("# TYPE watchdog_volume_max_db gauge\n");
+        for result in self.server.comparison_results.read().await.iter() {
+            let pair = (result.stream1.clone(), result.stream2.clone());
+            if self.comparisons.insert(pair, result.is_error) != Some(result.is_error) {
+                self.pending.push_back(LiveEvent::Comparison { timestamp: now, result: result.clone() });
+            }
+        }
+
+        if let Some(ref am) = self.server.alert_manager {
+            for event in am.get_history(self.alerts_since, now).await {
+                if event.timestamp > self.alerts_since {
+                    self.alerts_since = event.timestamp;
+                    self.pending.push_back(LiveEvent::Alert(event));
+                }
+            }
+        }
+        self.polled = true;
+    }
+}
 
-    metrics.push_str("# HELP watchdog_comparison_similarity_percent Stream comparison similarity percentage\n");
-    metrics.push_str("# TYPE watchdog_comparison_similarity_percent gauge\n");
+/// Server-sent events of health transitions, comparison verdict changes and alerts, followed by `watchdog tail`
+async fn events_api(State(server): State<Arc<WebServer>>) -> Sse<impl Stream<Item = Result<Event, Infallible>>> {
+    let feed = LiveFeed {
+        server,
+        health: HashMap::new(),
+        comparisons: HashMap::new(),
+        alerts_since: Utc::now(),
+        pending: VecDeque::new(),
+        polled: false,
+    };
 
-    metrics.push_str("# HELP watchdog_comparison_is_error Comparison error status (1=error, 0=ok)\n");
-    metrics.push_str("# TYPE watchdog_comparison_is_error gauge\n");
+    let stream = futures_util::stream::unfold(feed, |mut feed| async move {
+        loop {
+            if let Some(event) = feed.pending.pop_front() {
+                let data = serde_json::to_string(&event).unwrap_or_default();
+                return Some((Ok(Event::default().data(data)), feed));
+            }
+            if feed.polled {
+                tokio::time::sleep(LIVE_POLL_INTERVAL).await;
+            }
+            feed.poll().await;
+        }
+    });
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+async fn metrics_endpoint(State(server): State<Arc<WebServer>>) -> impl IntoResponse {
+    (StatusCode::OK, [(header::CONTENT_TYPE, exposition::CONTENT_TYPE)], render_metrics(&server).await)
+}
+
+/// Prometheus text exposition of everything the watchdog tracks, scraped at /metrics or pushed to a Pushgateway
+async fn render_metrics(server: &WebServer) -> String {
+    let router = &server.router;
+    let channels = router.get_all_channels();
+    let volume_metrics = router.get_all_stream_volumes().await;
+    let dead_air = router.get_dead_air_stats().await;
+    let comparison_results = server.comparison_results.read().await.clone();
 
-    metrics.push_str("# HELP watchdog_comparison_offset_seconds Time offset between streams in seconds\n");
-    metrics.push_str("# TYPE watchdog_comparison_offset_seconds gauge\n");
+    let mut metrics = MetricsWriter::new();
+
+    metrics.gauge("watchdog_stream_health", "Stream health status (2=Running, 1=Stalled, 0=Dead)");
+    metrics.gauge("watchdog_audio_health", "Audio stream health status (3=Running, 2=Degraded, 1=NoData, 0=Dead)");
+    metrics.gauge("watchdog_stream_uptime_seconds", "Stream uptime in seconds");
+    metrics.counter("watchdog_stream_soft_eofs_total", "Transient EOFs restarted as soft events instead of deaths");
+    metrics.counter("watchdog_process_respawns_total", "Times a stream's process was respawned");
+    metrics.counter("watchdog_stream_uptime_seconds_total", "Time the stream has been up, kept across watchdog restarts in the stats database");
+    metrics.counter("watchdog_stream_discontinuities_total", "Jumps in a stream's audio timeline, e.g. origin encoder restarts");
+    metrics.counter("watchdog_stream_dropouts_total", "Short near-silent runs in a stream's audio, counted as it arrives");
+    metrics.gauge("watchdog_stream_dropouts_last_hour", "Short near-silent runs in the last hour");
+    metrics.gauge("watchdog_volume_mean_db", "Mean volume level in dB");
+    metrics.gauge("watchdog_volume_max_db", "Maximum volume level in dB");
+    metrics.gauge("watchdog_stereo_rms_db", "Per-channel RMS level in dBFS");
+    metrics.gauge("watchdog_stereo_correlation", "Left/right channel correlation (-1 to 1)");
+    metrics.gauge("watchdog_dead_air_today_seconds", "Silent time accumulated today (local time)");
+    metrics.counter("watchdog_dead_air_seconds_total", "Silent time accumulated since start");
+    metrics.gauge("watchdog_comparison_similarity_percent", "Stream comparison similarity percentage");
+    metrics.gauge("watchdog_comparison_is_error", "Comparison error status (1=error, 0=ok)");
+    metrics.gauge("watchdog_comparison_offset_seconds", "Time offset between streams in seconds");
+
+    // Every per-stream metric carries the same stream and channel labels
+    let mut stream_channels: HashMap<String, String> = HashMap::new();
 
     // Collect stream metrics
     for channel_name in channels {
         if let Some(stream_names) = router.get_channel_streams(&channel_name) {
             for stream_name in stream_names {
+                stream_channels.insert(stream_name.clone(), channel_name.clone());
                 if let Some((cmd_health, audio_health)) = router.get_stream_health(&stream_name).await {
-                    let labels = format!("stream=\"{}\",channel=\"{}\"", stream_name, channel_name);
+                    let labels = [("stream", stream_name.as_str()), ("channel", channel_name.as_str())];
 
                     // Stream health metric
                     let health_value = match cmd_health {
@@ -141,7 +950,7 @@ async fn metrics_endpoint(State(server): State<Arc<WebServer>>) -> impl IntoResp
                         StreamHealth::Stalled => 1,
                         StreamHealth::Dead => 0,
                     };
-                    metrics.push_str(&format!("watchdog_stream_health{{{}}} {}\n", labels, health_value));
+                    metrics.sample("watchdog_stream_health", &labels, health_value as f64);
 
                     // Audio health metric
                     let audio_health_value = match audio_health {
@@ -150,18 +959,47 @@ async fn metrics_endpoint(State(server): State<Arc<WebServer>>) -> impl IntoResp
                         AudioStreamHealth::NoData => 1,
                         AudioStreamHealth::Dead => 0,
                     };
-                    metrics.push_str(&format!("watchdog_audio_health{{{}}} {}\n", labels, audio_health_value));
+                    metrics.sample("watchdog_audio_health", &labels, audio_health_value as f64);
 
                     // Uptime metric
                     if let Some(uptime) = router.get_stream_uptime(&stream_name).await {
-                        let uptime_seconds = uptime.num_seconds();
-                        metrics.push_str(&format!("watchdog_stream_uptime_seconds{{{}}} {}\n", labels, uptime_seconds));
+                        metrics.sample("watchdog_stream_uptime_seconds", &labels, uptime.num_seconds() as f64);
+                    }
+
+                    if let Some(stats) = router.get_persisted_stats(&stream_name) {
+                        metrics.sample("watchdog_stream_uptime_seconds_total", &labels, stats.cumulative_uptime_seconds as f64);
+                    }
+
+                    if let Some(soft_eofs) = router.get_stream_soft_eofs(&stream_name).await {
+                        metrics.sample("watchdog_stream_soft_eofs_total", &labels, soft_eofs as f64);
+                    }
+
+                    if let Some(respawns) = router.get_stream_respawns(&stream_name).await {
+                        metrics.sample("watchdog_process_respawns_total", &labels, respawns as f64);
+                    }
+
+                    if let Some((_, total)) = router.get_stream_discontinuities(&stream_name, chrono::Duration::hours(1)).await {
+                        metrics.sample("watchdog_stream_discontinuities_total", &labels, total as f64);
+                    }
+
+                    if let Some((recent, total)) = router.get_stream_dropouts(&stream_name, chrono::Duration::hours(1)).await {
+                        metrics.sample("watchdog_stream_dropouts_total", &labels, total as f64);
+                        metrics.sample("watchdog_stream_dropouts_last_hour", &labels, recent as f64);
                     }
 
                     // Volume metrics
                     if let Some(volume) = volume_metrics.get(&stream_name) {
-                        metrics.push_str(&format!("watchdog_volume_mean_db{{{}}} {}\n", labels, volume.mean_volume));
-                        metrics.push_str(&format!("watchdog_volume_max_db{{{}}} {}\n", labels, volume.max_volume));
+                        metrics.sample("watchdog_volume_mean_db", &labels, volume.mean_volume as f64);
+                        metrics.sample("watchdog_volume_max_db", &labels, volume.max_volume as f64);
+                        metrics.sample("watchdog_stereo_rms_db", &[labels[0], labels[1], ("side", "left")], volume.stereo.left_rms as f64);
+                        metrics.sample("watchdog_stereo_rms_db", &[labels[0], labels[1], ("side", "right")], volume.stereo.right_rms as f64);
+                        metrics.sample("watchdog_stereo_correlation", &labels, volume.stereo.correlation as f64);
+                    }
+
+                    // Dead air counters
+                    if let Some(stats) = dead_air.get(&stream_name) {
+                        metrics.sample("watchdog_dead_air_today_seconds", &labels, stats.today_seconds);
+                        metrics.sample("watchdog_dead_air_seconds_total", &labels, stats.total_seconds);
                     }
                 }
             }
@@ -170,37 +1008,210 @@ async fn metrics_endpoint(State(server): State<Arc<WebServer>>) -> impl IntoResp
 
     // Comparison metrics
     for result in comparison_results {
-        let comparison_type = if result.is_within_channel { "within_channel" } else { "cross_channel" };
-        let labels = format!(
-            "stream1=\"{}\",stream2=\"{}\",comparison_type=\"{}\"",
-            result.stream1, result.stream2, comparison_type
-        );
+        let comparison_type = result.comparison_type();
+        let labels = [("stream1", result.stream1.as_str()), ("stream2", result.stream2.as_str()), ("comparison_type", comparison_type)];
 
-        metrics.push_str(&format!("watchdog_comparison_similarity_percent{{{}}} {}\n",
-            labels, result.similarity_percent));
+        metrics.sample("watchdog_comparison_similarity_percent", &labels, result.similarity_percent as f64);
+        metrics.sample("watchdog_comparison_is_error", &labels, if result.is_error { 1.0 } else { 0.0 });
+        if let Some(offset) = result.offset_seconds {
+            metrics.sample("watchdog_comparison_offset_seconds", &labels, offset as f64);
+        }
+    }
 
-        let error_value = if result.is_error { 1 } else { 0 };
-        metrics.push_str(&format!("watchdog_comparison_is_error{{{}}} {}\n", labels, error_value));
+    if let Some(ref counters) = server.comparison_counters {
+        metrics.counter("watchdog_comparisons_total", "Pairwise comparisons run, by type and outcome");
+        for (comparison_type, result, count) in counters.snapshot() {
+            metrics.sample("watchdog_comparisons_total", &[("comparison_type", comparison_type), ("result", result)], count as f64);
+        }
+        let (computed, cached) = counters.get_match_counts();
+        metrics.counter("watchdog_fingerprint_matches_total", "Fingerprint matches by whether they were run or reused because neither fingerprint changed");
+        metrics.sample("watchdog_fingerprint_matches_total", &[("result", "computed")], computed as f64);
+        metrics.sample("watchdog_fingerprint_matches_total", &[("result", "cached")], cached as f64);
+        let (fast, disagreements) = counters.get_fast_match_counts();
+        metrics.counter("watchdog_fast_matches_total", "Fingerprint matches run on the fast path instead of rusty_chromaprint");
+        metrics.sample("watchdog_fast_matches_total", &[], fast as f64);
+        metrics.counter("watchdog_fast_match_disagreements_total", "Fast matches checked against rusty_chromaprint that came out too different");
+        metrics.sample("watchdog_fast_match_disagreements_total", &[], disagreements as f64);
+    }
 
-        if let Some(offset) = result.offset_seconds {
-            metrics.push_str(&format!("watchdog_comparison_offset_seconds{{{}}} {}\n", labels, offset));
+    let channel_of = |stream_name: &str| stream_channels.get(stream_name).cloned().unwrap_or_default();
+
+    // HTTP origin probes
+    if let Some(ref prober) = server.prober {
+        metrics.gauge("watchdog_origin_up", "Web stream origin answered with a 2xx status (1=up, 0=down)");
+        metrics.gauge("watchdog_origin_http_status", "Last HTTP status code from the origin (0=no response)");
+
+        for (stream_name, result) in prober.get_results().await {
+            let channel = channel_of(&stream_name);
+            let labels = [("stream", stream_name.as_str()), ("channel", channel.as_str())];
+            metrics.sample("watchdog_origin_up", &labels, if result.is_up() { 1.0 } else { 0.0 });
+            metrics.sample("watchdog_origin_http_status", &labels, result.status_code.unwrap_or(0) as f64);
+        }
+    }
+
+    // DNS watch
+    if let Some(ref watcher) = server.dns_watcher {
+        metrics.counter("watchdog_dns_changes_total", "Times a stream origin's resolved addresses changed");
+        metrics.gauge("watchdog_dns_addresses", "Number of addresses a stream origin currently resolves to");
+
+        for (stream_name, record) in watcher.get_records().await {
+            let channel = channel_of(&stream_name);
+            let labels = [("stream", stream_name.as_str()), ("channel", channel.as_str()), ("host", record.host.as_str())];
+            metrics.sample("watchdog_dns_changes_total", &labels, record.changes as f64);
+            metrics.sample("watchdog_dns_addresses", &labels, record.addresses.len() as f64);
         }
     }
 
-    (StatusCode::OK, metrics)
+    // HD Radio decode artifacts
+    if let Some(ref detector) = server.artifact_detector {
+        metrics.gauge("watchdog_stream_artifacts_per_minute", "Decode artifacts per minute averaged over the configured window");
+        metrics.counter("watchdog_stream_artifacts_total", "Decode artifacts since start, by kind");
+
+        for (stream_name, stats) in detector.get_stats().await {
+            let channel = channel_of(&stream_name);
+            let labels = [("stream", stream_name.as_str()), ("channel", channel.as_str())];
+            metrics.sample("watchdog_stream_artifacts_per_minute", &labels, stats.rate_per_minute as f64);
+            metrics.sample("watchdog_stream_artifacts_total", &[labels[0], labels[1], ("kind", "repeated_frame")], stats.repeated_frames as f64);
+            metrics.sample("watchdog_stream_artifacts_total", &[labels[0], labels[1], ("kind", "click")], stats.clicks as f64);
+            metrics.sample("watchdog_stream_artifacts_total", &[labels[0], labels[1], ("kind", "mute")], stats.mutes as f64);
+        }
+    }
+
+    // Format verification
+    if let Some(ref checker) = server.format_checker {
+        metrics.gauge("watchdog_stream_format_mismatch", "Web stream codec parameters differ from config (1=mismatch, 0=ok)");
+        metrics.gauge("watchdog_stream_bitrate_kbps", "Declared bitrate of the web stream");
+
+        for (stream_name, format) in checker.get_results().await {
+            let channel = channel_of(&stream_name);
+            let labels = [("stream", stream_name.as_str()), ("channel", channel.as_str()), ("codec", format.codec.as_deref().unwrap_or("unknown"))];
+            metrics.sample("watchdog_stream_format_mismatch", &labels, if format.mismatches.is_empty() { 0.0 } else { 1.0 });
+            if let Some(kbps) = format.bitrate_kbps {
+                metrics.sample("watchdog_stream_bitrate_kbps", &labels, kbps as f64);
+            }
+        }
+    }
+
+    // Audio buffer memory
+    let budget = router.get_buffer_budget();
+    metrics.gauge("watchdog_audio_buffer_bytes", "Bytes held across all PCM and fingerprint buffers");
+    metrics.sample("watchdog_audio_buffer_bytes", &[], budget.held_bytes() as f64);
+    metrics.gauge("watchdog_audio_buffer_requested_bytes", "Bytes the buffers would hold at their configured durations");
+    metrics.sample("watchdog_audio_buffer_requested_bytes", &[], budget.requested_bytes() as f64);
+    if let Some(cap) = budget.cap_bytes() {
+        metrics.gauge("watchdog_audio_buffer_cap_bytes", "Configured cap on audio buffer memory");
+        metrics.sample("watchdog_audio_buffer_cap_bytes", &[], cap as f64);
+    }
+
+    // SDR IQ delivery
+    if !server.nrsc_managers.is_empty() {
+        metrics.gauge("watchdog_sdr_iq_drop_ratio", "Share of expected IQ samples missing over the last window");
+        metrics.counter("watchdog_sdr_iq_lagged_chunks_total", "IQ chunks nrsc5 decoders fell too far behind to receive");
+        metrics.counter("watchdog_sdr_nrsc5_resyncs_total", "Times an nrsc5 decoder on this SDR lost synchronization");
+
+        for (sdr_name, manager) in &server.nrsc_managers {
+            let stats = manager.get_iq_stats();
+            let labels = [("sdr", sdr_name.as_str())];
+            metrics.sample("watchdog_sdr_iq_drop_ratio", &labels, stats.drop_ratio as f64);
+            metrics.sample("watchdog_sdr_iq_lagged_chunks_total", &labels, stats.lagged_chunks as f64);
+            metrics.sample("watchdog_sdr_nrsc5_resyncs_total", &labels, stats.resyncs as f64);
+        }
+    }
+
+    // Slack Socket Mode link
+    if let Some(ref am) = server.alert_manager {
+        metrics.gauge("watchdog_alert_failing", "Currently failing alerts, labelled with their reason code");
+        for alert in am.get_active_alerts().await {
+            metrics.sample("watchdog_alert_failing", &[("alert", alert.alert.as_str()), ("reason", alert.reason.as_str())], 1.0);
+        }
+
+        metrics.counter("watchdog_alerts_fired_total", "Alerts announced after their grace period, by reason code");
+        for (reason, count) in am.get_fired_counts() {
+            metrics.sample("watchdog_alerts_fired_total", &[("reason", reason.as_str())], count as f64);
+        }
+    }
+
+    if let Some(ref slack) = server.slack {
+        let (sent, failed) = slack.get_counts();
+        metrics.counter("watchdog_slack_messages_sent_total", "Slack messages posted, by outcome");
+        metrics.sample("watchdog_slack_messages_sent_total", &[("result", "ok")], sent as f64);
+        metrics.sample("watchdog_slack_messages_sent_total", &[("result", "error")], failed as f64);
+    }
+
+    if let Some(ref delivery_stats) = server.delivery_stats {
+        let sinks = delivery_stats.get_sinks();
+        metrics.counter("watchdog_notification_deliveries_total", "Notifications delivered or given up on, by sink and outcome");
+        for (sink, counters) in &sinks {
+            metrics.sample("watchdog_notification_deliveries_total", &[("sink", sink.as_str()), ("result", "ok")], counters.get_delivered() as f64);
+            metrics.sample("watchdog_notification_deliveries_total", &[("sink", sink.as_str()), ("result", "error")], counters.get_failed() as f64);
+        }
+        metrics.counter("watchdog_notification_retries_total", "Delivery attempts repeated after a failure, by sink");
+        for (sink, counters) in &sinks {
+            metrics.sample("watchdog_notification_retries_total", &[("sink", sink.as_str())], counters.get_retries() as f64);
+        }
+        metrics.gauge("watchdog_notification_consecutive_failures", "Failed deliveries since the sink last succeeded");
+        for (sink, counters) in &sinks {
+            metrics.sample("watchdog_notification_consecutive_failures", &[("sink", sink.as_str())], counters.get_consecutive_failures() as f64);
+        }
+    }
+
+    if let Some(ref listener) = server.slack_listener {
+        metrics.gauge("watchdog_slack_listener_connected", "Slack Socket Mode connection state (1=connected, 0=disconnected)");
+        metrics.sample("watchdog_slack_listener_connected", &[], if listener.is_connected() { 1.0 } else { 0.0 });
+
+        metrics.counter("watchdog_slack_listener_reconnects_total", "Slack Socket Mode sessions that ended and were reconnected");
+        metrics.sample("watchdog_slack_listener_reconnects_total", &[], listener.reconnect_count() as f64);
+    }
+
+    metrics.finish()
+}
+
+async fn recordings_index(State(server): State<Arc<WebServer>>) -> Response {
+    match server.recorder {
+        Some(ref recorder) => Json(recorder.list_recordings().await).into_response(),
+        None => (StatusCode::NOT_FOUND, "Recording is not configured").into_response(),
+    }
+}
+
+async fn recording_file(
+    State(server): State<Arc<WebServer>>,
+    Path((stream, file)): Path<(String, String)>
+) -> Response {
+    let Some(ref recorder) = server.recorder else {
+        return (StatusCode::NOT_FOUND, "Recording is not configured").into_response();
+    };
+
+    match recorder.read_recording(&stream, &file).await {
+        Some(bytes) => ([(header::CONTENT_TYPE, "audio/wav")], bytes).into_response(),
+        None => (StatusCode::NOT_FOUND, "Recording not found").into_response(),
+    }
 }
 
 fn render_status_page(
-    channels: Vec<(String, Vec<(String, StreamHealth, AudioStreamHealth, Option<chrono::Duration>, Option<VolumeMetrics>)>)>,
-    comparison_results: Vec<ComparisonResult>
+    channels: Vec<(String, Vec<(String, StreamHealth, AudioStreamHealth, Option<chrono::Duration>, Option<VolumeMetrics>, Option<BufferStatus>, bool, bool)>)>,
+    comparison_results: Vec<ComparisonResult>,
+    alerts: Vec<ActiveAlert>,
+    recent_alerts: Vec<AlertEvent>,
+    min_buffer_size: usize,
+    clock_skew: Option<ClockOffset>,
+    maintenance: Option<MaintenanceStatus>,
+    branding: &Branding,
+    base_path: &str
 ) -> Markup {
+    let labels = &branding.labels;
+    let buffers: Vec<BufferStatus> = channels.iter()
+        .flat_map(|(_, streams)| streams.iter().filter_map(|s| s.5))
+        .collect();
+    let warming_up = is_warming_up(&buffers, &comparison_results);
+    let ready_in = buffers.iter().map(|b| b.ready_in_seconds).fold(0.0f32, f32::max);
+
     html! {
         (maud::DOCTYPE)
-        html {
+        html lang=(branding.language()) {
             head {
                 meta charset="utf-8";
                 meta name="viewport" content="width=device-width, initial-scale=1";
-                title { "Watchdog Status" }
+                title { (branding.title()) }
                 style {
                     r#"
                     body {
@@ -271,6 +1282,11 @@ fn render_status_page(
                         font-size: 0.9em;
                         margin-top: 10px;
                     }
+                    .logo {
+                        height: 1.2em;
+                        vertical-align: middle;
+                        margin-right: 10px;
+                    }
                     table {
                         width: 100%;
                         border-collapse: collapse;
@@ -307,18 +1323,124 @@ fn render_status_page(
                     .similarity.bad {
                         color: #ff6b6b;
                     }
+                    .alert-message {
+                        white-space: pre-line;
+                    }
+                    .banner {
+                        background: #2a3550;
+                        color: #9ec1ff;
+                        border: 1px solid #3d5a99;
+                        border-radius: 8px;
+                        padding: 15px 20px;
+                        margin: 20px 0;
+                    }
                     "#
                 }
             }
             body {
-                h1 { "🐕 Watchdog Status" }
-                p.timestamp { "Last updated: " (Utc::now().format("%Y-%m-%d %H:%M:%S UTC")) }
+                h1 {
+                    @if let Some(ref logo) = branding.logo_url {
+                        img.logo src=(logo) alt="";
+                    } @else {
+                        "🐕 "
+                    }
+                    (branding.title())
+                }
+                p.timestamp {
+                    (label(&labels.last_updated, "Last updated")) ": " (local_time(Utc::now(), "%Y-%m-%d %H:%M:%S")) " · "
+                    a #tz-toggle href="#" style="color: inherit;" { "Show times in my timezone" }
+                }
+
+                @if warming_up {
+                    div.banner {
+                        strong { "System warming up" }
+                        " — comparisons start once streams have buffered "
+                        (min_buffer_size) " fingerprint items, ready in ~" (format!("{:.0}", ready_in.ceil())) " s. "
+                        "An empty comparison table until then is expected."
+                    }
+                }
+
+                @if let Some(skew) = clock_skew {
+                    div.banner {
+                        strong { "System clock is off by " (format!("{:.1}", skew.offset_ms as f64 / 1000.0)) " s" }
+                        " (checked " (local_time(skew.checked_at, "%H:%M:%S")) ") — grace periods, uptimes and offsets may be wrong."
+                    }
+                }
+
+                @if let Some(ref maintenance) = maintenance {
+                    div.banner {
+                        strong { "Maintenance (" (maintenance.reason) ") until " (local_time(maintenance.until, "%H:%M")) }
+                        " — new alerts and reminders are held, alerts still failing when it ends are sent then."
+                    }
+                }
+
+                h2 { (label(&labels.active_alerts, "Active Alerts")) }
+
+                @if alerts.is_empty() {
+                    p style="color: #888;" { (label(&labels.no_active_alerts, "No active alerts")) }
+                } @else {
+                    table {
+                        thead {
+                            tr {
+                                th { "Severity" }
+                                th { "Alert" }
+                                th { "Age" }
+                                th { "State" }
+                            }
+                        }
+                        tbody {
+                            @for alert in &alerts {
+                                tr class=@if alert.severity == AlertSeverity::Critical { "error" } @else { "" } {
+                                    td {
+                                        @if alert.severity == AlertSeverity::Critical {
+                                            span.badge.dead { "Critical" }
+                                        } @else {
+                                            span.badge.stalled { "Warning" }
+                                        }
+                                    }
+                                    td {
+                                        div.alert-message { (alert.message) }
+                                        div style="color: #888; font-size: 0.85em; margin-top: 3px;" { (alert.alert) " · " (alert.reason.as_str()) }
+                                    }
+                                    td { (format_duration(Utc::now() - alert.failing_since)) }
+                                    td {
+                                        @if let Some(ref by) = alert.acknowledged_by {
+                                            span.badge.nodata { "Ack'd by " (by) }
+                                        } @else if alert.announced {
+                                            span.badge.dead { "Announced" }
+                                        } @else {
+                                            span.badge.stalled { "In grace period" }
+                                            @if let Some(announce_at) = alert.announce_at {
+                                                div style="color: #888; font-size: 0.85em; margin-top: 3px;" {
+                                                    "Alerts in " (format_duration((announce_at - Utc::now()).max(chrono::Duration::zero())))
+                                                }
+                                            }
+                                            // 204 keeps the browser on this page
+                                            form method="post" action=(format!("{}/api/alerts/{}/alert-now", base_path, alert.alert)) style="margin-top: 5px;" {
+                                                button type="submit" { "Alert now" }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                @if !recent_alerts.is_empty() {
+                    h3 { (label(&labels.recently_cleared, "Recently cleared")) }
+                    @for event in &recent_alerts {
+                        div style="color: #888; font-size: 0.9em; margin: 5px 0;" {
+                            (local_time(event.timestamp, "%H:%M:%S")) " — " (event.message)
+                        }
+                    }
+                }
 
-                h2 { "Cross-Comparison Results" }
+                h2 { (label(&labels.comparisons, "Cross-Comparison Results")) }
 
                 @if !comparison_results.is_empty() {
                     div.channel {
-                        h3 { "Within-Channel Comparisons" }
+                        h3 { (label(&labels.within_channel, "Within-Channel Comparisons")) }
                         table {
                             thead {
                                 tr {
@@ -356,7 +1478,7 @@ fn render_status_page(
                             }
                         }
 
-                        h3 style="margin-top: 30px;" { "Cross-Channel Comparisons" }
+                        h3 style="margin-top: 30px;" { (label(&labels.cross_channel, "Cross-Channel Comparisons")) }
                         table {
                             thead {
                                 tr {
@@ -367,7 +1489,7 @@ fn render_status_page(
                                 }
                             }
                             tbody {
-                                @for result in comparison_results.iter().filter(|r| !r.is_within_channel) {
+                                @for result in comparison_results.iter().filter(|r| !r.is_within_channel && !r.is_silence_check) {
                                     tr class=@if result.is_error { "error" } @else { "ok" } {
                                         td { (result.stream1) }
                                         td { (result.stream2) }
@@ -377,6 +1499,9 @@ fn render_status_page(
                                         td {
                                             @if result.is_error {
                                                 span.badge.dead { "⚠ Collision" }
+                                            } @else if let Some(ref note) = result.whitelisted {
+                                                span.badge.stalled title=(note) { "≈ Whitelisted" }
+                                                div style="color: #888; font-size: 0.85em; margin-top: 3px;" { (note) }
                                             } @else {
                                                 span.badge.running { "✓ Different" }
                                             }
@@ -390,30 +1515,75 @@ fn render_status_page(
                     p style="color: #888;" { "Waiting for comparison data..." }
                 }
 
-                h2 { "Stream Status" }
+                @if comparison_results.iter().any(|r| r.is_silence_check) {
+                    div.channel {
+                        h3 { (label(&labels.silence_checks, "Silence Checks")) }
+                        table {
+                            thead {
+                                tr {
+                                    th { "Stream" }
+                                    th { "Similarity to silence" }
+                                    th { "Status" }
+                                }
+                            }
+                            tbody {
+                                @for result in comparison_results.iter().filter(|r| r.is_silence_check) {
+                                    tr class=@if result.is_error { "error" } @else { "ok" } {
+                                        td { (if result.stream1 == "silence" { &result.stream2 } else { &result.stream1 }) }
+                                        td class=({format!("similarity {}", if result.is_error { "bad" } else { "good" })}) {
+                                            (format!("{:.1}%", result.similarity_percent))
+                                        }
+                                        td {
+                                            @if result.is_error {
+                                                span.badge.dead { "⚠ Silent" }
+                                            } @else {
+                                                span.badge.running { "✓ Audio" }
+                                            }
+                                        }
+                                    }
+                                }
+                            }
+                        }
+                    }
+                }
+
+                h2 { (label(&labels.stream_status, "Stream Status")) }
 
                 @for (channel_name, streams) in channels {
                     @if channel_name != "silence" {
                         div.channel {
-                            h2 { "Channel: " (channel_name) }
+                            h2 { (label(&labels.channel, "Channel")) ": " (channel_name) }
 
-                        @for (stream_name, cmd_health, audio_health, uptime, volume) in streams {
+                        @for (stream_name, cmd_health, audio_health, uptime, volume, buffer, sampled, on_backup) in streams {
                             div.stream {
                                 div {
-                                    div.stream-name { (stream_name) }
+                                    div.stream-name { a href=(format!("{}/stream/{}", base_path, stream_name)) style="color: inherit;" { (stream_name) } }
                                     @if let Some(uptime) = uptime {
                                         div style="color: #888; font-size: 0.85em; margin-top: 5px;" {
                                             "Uptime: " (format_duration(uptime))
                                         }
                                     }
+                                    @if let Some(buffer) = buffer.filter(|b| !b.is_ready()) {
+                                        div style="color: #9ec1ff; font-size: 0.85em; margin-top: 3px;" {
+                                            "Buffering " (buffer.items) "/" (buffer.items_needed) " items, ready in ~"
+                                            (format!("{:.0}", buffer.ready_in_seconds.ceil())) " s"
+                                        }
+                                    }
                                     @if let Some(vol) = volume {
                                         div style="color: #888; font-size: 0.85em; margin-top: 3px;" {
                                             "Mean: " (format!("{:.1}", vol.mean_volume)) " dB | "
-                                            "Max: " (format!("{:.1}", vol.max_volume)) " dB"
+                                            "Max: " (format!("{:.1}", vol.max_volume)) " dB | "
+                                            "L/R: " (format!("{:.1}/{:.1}", vol.stereo.left_rms, vol.stereo.right_rms)) " dB"
                                         }
                                     }
                                 }
                                 div.status {
+                                    @if !sampled {
+                                        span.badge.nodata { "Not sampled" }
+                                    }
+                                    @if on_backup {
+                                        span.badge.stalled { "On backup" }
+                                    }
                                     @match cmd_health {
                                         StreamHealth::Running => span.badge.running { "Running" },
                                         StreamHealth::Stalled => span.badge.stalled { "Stalled" },
@@ -431,6 +1601,144 @@ fn render_status_page(
                         }
                     }
                 }
+                script { (maud::PreEscaped(BROWSER_TIME_SCRIPT)) }
+            }
+        }
+    }
+}
+
+fn render_stream_page(
+    name: &str,
+    cmd_health: StreamHealth,
+    audio_health: AudioStreamHealth,
+    uptime: Option<chrono::Duration>,
+    persisted: Option<PersistedStats>,
+    volume: Option<VolumeMetrics>,
+    stderr: Vec<StderrSummary>,
+    branding: &Branding,
+    base_path: &str
+) -> Markup {
+    html! {
+        (maud::DOCTYPE)
+        html lang=(branding.language()) {
+            head {
+                meta charset="utf-8";
+                meta name="viewport" content="width=device-width, initial-scale=1";
+                title { (branding.title()) " - " (name) }
+                style {
+                    r#"
+                    body {
+                        font-family: sans-serif;
+                        max-width: 1200px;
+                        margin: 0 auto;
+                        padding: 20px;
+                        background: #1a1a1a;
+                        color: #e0e0e0;
+                    }
+                    a { color: #7fb3ff; }
+                    .channel {
+                        background: #2a2a2a;
+                        border-radius: 8px;
+                        padding: 20px;
+                        margin: 20px 0;
+                        border: 1px solid #444;
+                    }
+                    pre {
+                        white-space: pre-wrap;
+                        margin: 0;
+                    }
+                    .line { padding: 6px 0; border-bottom: 1px solid #333; }
+                    .line.error { color: #ff6b6b; }
+                    .line.warning { color: #ffa726; }
+                    .meta { color: #888; font-size: 0.85em; }
+                    "#
+                }
+            }
+            body {
+                p { a href=(if base_path.is_empty() { "/" } else { base_path }) { "← " (label(&branding.labels.back_to_status, "Back to status")) } }
+                h1 { "Stream: " (name) }
+                div.channel {
+                    p { "Command: " (format!("{:?}", cmd_health)) " | Audio: " (format!("{:?}", audio_health)) }
+                    @if let Some(uptime) = uptime {
+                        p.meta { "Uptime: " (format_duration(uptime)) }
+                    }
+                    @if let Some(stats) = persisted {
+                        p.meta {
+                            "Total uptime: " (format_duration(chrono::Duration::seconds(stats.cumulative_uptime_seconds)))
+                            " | Restarts: " (stats.restarts)
+                        }
+                    }
+                    @if let Some(vol) = volume {
+                        p.meta {
+                            "Mean: " (format!("{:.1}", vol.mean_volume)) " dB | "
+                            "Max: " (format!("{:.1}", vol.max_volume)) " dB"
+                        }
+                    }
+                }
+                h2 { "Recent stderr" }
+                div.channel {
+                    @if stderr.is_empty() {
+                        p.meta { "No stderr output." }
+                    }
+                    @for entry in stderr.iter().rev() {
+                        div class=(format!("line {}", format!("{:?}", entry.class).to_lowercase())) {
+                            pre { (entry.line) }
+                            span.meta { "×" (entry.count) " · last " (local_time(entry.last_seen, "%Y-%m-%d %H:%M:%S")) }
+                        }
+                    }
+                }
+                script { (maud::PreEscaped(BROWSER_TIME_SCRIPT)) }
+            }
+        }
+    }
+}
+
+fn render_login_page(error: Option<&str>, branding: &Branding, base_path: &str) -> Markup {
+    let log_in = label(&branding.labels.log_in, "Log in");
+    html! {
+        (maud::DOCTYPE)
+        html lang=(branding.language()) {
+            head {
+                meta charset="utf-8";
+                meta name="viewport" content="width=device-width, initial-scale=1";
+                title { (branding.title()) " - " (log_in) }
+                style {
+                    r#"
+                    body {
+                        font-family: sans-serif;
+                        max-width: 360px;
+                        margin: 80px auto;
+                        padding: 20px;
+                        background: #1a1a1a;
+                        color: #e0e0e0;
+                    }
+                    h1 {
+                        color: #fff;
+                        border-bottom: 2px solid #444;
+                        padding-bottom: 10px;
+                    }
+                    label { display: block; margin-top: 12px; }
+                    input { width: 100%; padding: 6px; margin-top: 4px; box-sizing: border-box; }
+                    button { margin-top: 16px; padding: 6px 16px; }
+                    .error { color: #f44336; }
+                    "#
+                }
+            }
+            body {
+                h1 {
+                    @if let Some(ref logo) = branding.logo_url {
+                        img src=(logo) alt="" style="height: 1.2em; vertical-align: middle; margin-right: 10px;";
+                    }
+                    (branding.title())
+                }
+                @if let Some(error) = error {
+                    p.error { (error) }
+                }
+                form method="post" action=(format!("{}/login", base_path)) {
+                    label { "Username" input type="text" name="username" autocomplete="username" autofocus; }
+                    label { "Password" input type="password" name="password" autocomplete="current-password"; }
+                    button type="submit" { (log_in) }
+                }
             }
         }
     }
@@ -0,0 +1,96 @@
+use chrono::{DateTime, Datelike, Duration, Local, NaiveDateTime, NaiveTime, Utc, Weekday};
+use schemars::JsonSchema;
+use serde::Deserialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, JsonSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum Day {
+    Mon,
+    Tue,
+    Wed,
+    Thu,
+    Fri,
+    Sat,
+    Sun,
+}
+
+impl Day {
+    fn weekday(self) -> Weekday {
+        match self {
+            Day::Mon => Weekday::Mon,
+            Day::Tue => Weekday::Tue,
+            Day::Wed => Weekday::Wed,
+            Day::Thu => Weekday::Thu,
+            Day::Fri => Weekday::Fri,
+            Day::Sat => Weekday::Sat,
+            Day::Sun => Weekday::Sun,
+        }
+    }
+}
+
+/// `local` in UTC; a time skipped by a DST change is taken an hour later
+pub fn to_utc(local: NaiveDateTime) -> DateTime<Utc> {
+    local.and_local_timezone(Local).earliest()
+        .or((local + Duration::hours(1)).and_local_timezone(Local).earliest())
+        .map(|time| time.with_timezone(&Utc))
+        .unwrap_or_else(Utc::now)
+}
+
+/// A stretch of local time recurring on some days of the week
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct WeeklyWindow {
+    /// days the window starts on, every day if unset
+    pub days: Option<Vec<Day>>,
+    /// "HH:MM" local time
+    pub start: String,
+    /// "HH:MM" local time, earlier than start to run past midnight; equal to start for the whole day
+    pub end: String,
+}
+
+impl WeeklyWindow {
+    pub fn describe(&self) -> String {
+        format!("{}-{}", self.start, self.end)
+    }
+
+    fn times(&self) -> Result<(NaiveTime, NaiveTime), String> {
+        let parse = |time: &str| NaiveTime::parse_from_str(time, "%H:%M")
+            .map_err(|e| format!("invalid time `{}`, use HH:MM: {}", time, e));
+        Ok((parse(&self.start)?, parse(&self.end)?))
+    }
+
+    pub fn validate(&self) -> Result<(), String> {
+        self.times()?;
+        if self.days.as_ref().is_some_and(|days| days.is_empty()) {
+            return Err("lists no days, it never applies".to_string());
+        }
+        Ok(())
+    }
+
+    fn starts_on(&self, day: Weekday) -> bool {
+        self.days.as_ref().map_or(true, |days| days.iter().any(|d| d.weekday() == day))
+    }
+
+    /// When the window covering `now` ends, `None` outside it; the part past midnight belongs to the day it started on
+    pub fn active_until(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        let (start, end) = self.times().ok()?;
+        let date = now.date();
+        let time = now.time();
+        let tomorrow = date + Duration::days(1);
+        let yesterday = (date - Duration::days(1)).weekday();
+        if start < end {
+            (self.starts_on(date.weekday()) && start <= time && time < end).then(|| date.and_time(end))
+        } else if start > end {
+            if self.starts_on(date.weekday()) && time >= start {
+                Some(tomorrow.and_time(end))
+            } else {
+                (self.starts_on(yesterday) && time < end).then(|| date.and_time(end))
+            }
+        } else {
+            self.starts_on(date.weekday()).then(|| tomorrow.and_time(start))
+        }
+    }
+
+    pub fn is_active(&self, now: NaiveDateTime) -> bool {
+        self.active_until(now).is_some()
+    }
+}